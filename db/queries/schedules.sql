@@ -62,6 +62,12 @@ UPDATE scheduled_events
 SET status = 'cancelled'
 WHERE id = $1 AND org_id = $2 AND project_id = $3 AND status = 'pending';
 
+-- name: UpdateScheduledEventByProject :one
+UPDATE scheduled_events
+SET topic = $4, data = $5, scheduled_for = $6
+WHERE id = $1 AND org_id = $2 AND project_id = $3 AND status = 'pending'
+RETURNING *;
+
 -- name: CountScheduledEventsByStatus :one
 SELECT
     COUNT(*) FILTER (WHERE status = 'pending') as pending,
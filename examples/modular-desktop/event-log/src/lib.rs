@@ -1,5 +1,5 @@
 use futures_util::StreamExt;
-use notifsh::{Notif, SubscribeOptions};
+use notifsh::{ConnectionState, Notif, SubscribeOptions};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter};
 
@@ -34,10 +34,24 @@ async fn start_subscription(app: AppHandle) -> Result<(), String> {
         })?;
 
     println!("[event-log] Subscribed! Waiting for frontend...");
-    // Wait a moment for frontend to be ready, then emit connected status
+    // Wait a moment for frontend to be ready, then mirror the connection state
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-    println!("[event-log] Emitting connected status...");
-    let _ = app.emit("status", "connected");
+    let mut state = stream.watch_state();
+    let status_app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            let status = match *state.borrow_and_update() {
+                ConnectionState::Connected => "connected",
+                ConnectionState::Reconnecting => "reconnecting",
+                _ => "disconnected",
+            };
+            println!("[event-log] Connection status: {}", status);
+            let _ = status_app.emit("status", status);
+            if state.changed().await.is_err() {
+                break;
+            }
+        }
+    });
 
     while let Some(result) = stream.next().await {
         match result {
@@ -60,7 +74,6 @@ async fn start_subscription(app: AppHandle) -> Result<(), String> {
         }
     }
 
-    let _ = app.emit("status", "disconnected");
     Ok(())
 }
 
@@ -483,6 +483,48 @@ func (q *Queries) ListScheduledEventsByStatus(ctx context.Context, arg ListSched
 	return items, nil
 }
 
+const updateScheduledEventByProject = `-- name: UpdateScheduledEventByProject :one
+UPDATE scheduled_events
+SET topic = $4, data = $5, scheduled_for = $6
+WHERE id = $1 AND org_id = $2 AND project_id = $3 AND status = 'pending'
+RETURNING id, org_id, topic, data, scheduled_for, status, api_key_id, error, created_at, executed_at, project_id
+`
+
+type UpdateScheduledEventByProjectParams struct {
+	ID           string             `json:"id"`
+	OrgID        string             `json:"org_id"`
+	ProjectID    pgtype.Text        `json:"project_id"`
+	Topic        string             `json:"topic"`
+	Data         []byte             `json:"data"`
+	ScheduledFor pgtype.Timestamptz `json:"scheduled_for"`
+}
+
+func (q *Queries) UpdateScheduledEventByProject(ctx context.Context, arg UpdateScheduledEventByProjectParams) (ScheduledEvent, error) {
+	row := q.db.QueryRow(ctx, updateScheduledEventByProject,
+		arg.ID,
+		arg.OrgID,
+		arg.ProjectID,
+		arg.Topic,
+		arg.Data,
+		arg.ScheduledFor,
+	)
+	var i ScheduledEvent
+	err := row.Scan(
+		&i.ID,
+		&i.OrgID,
+		&i.Topic,
+		&i.Data,
+		&i.ScheduledFor,
+		&i.Status,
+		&i.ApiKeyID,
+		&i.Error,
+		&i.CreatedAt,
+		&i.ExecutedAt,
+		&i.ProjectID,
+	)
+	return i, err
+}
+
 const updateScheduledEventStatus = `-- name: UpdateScheduledEventStatus :exec
 UPDATE scheduled_events
 SET status = $1::text,
@@ -2,19 +2,21 @@ package domain
 
 import (
 	"crypto/rand"
+	"crypto/sha256"
 	"encoding/hex"
 	"encoding/json"
 	"time"
 )
 
 type Event struct {
-	ID        string          `json:"id"`
-	Topic     string          `json:"topic"`
-	Data      json.RawMessage `json:"data"`
-	Timestamp time.Time       `json:"timestamp"`
-	OrgID     string          `json:"org_id,omitempty"`
-	ProjectID string          `json:"project_id,omitempty"`
-	Attempt   int             `json:"attempt,omitempty"`
+	ID        string            `json:"id"`
+	Topic     string            `json:"topic"`
+	Data      json.RawMessage   `json:"data"`
+	Headers   map[string]string `json:"headers,omitempty"`
+	Timestamp time.Time         `json:"timestamp"`
+	OrgID     string            `json:"org_id,omitempty"`
+	ProjectID string            `json:"project_id,omitempty"`
+	Attempt   int               `json:"attempt,omitempty"`
 }
 
 // NewEvent creates a new event with a generated ID.
@@ -35,15 +37,35 @@ func generateEventID() string {
 	return "evt_" + hex.EncodeToString(b)
 }
 
+// IdempotentEventID derives the event ID for a client-supplied idempotency
+// key, so a retried emit publishes under the same ID and JetStream drops
+// the duplicate. scope keeps keys of different projects apart.
+func IdempotentEventID(scope, key string) string {
+	sum := sha256.Sum256([]byte(scope + "\x00" + key))
+	return "evt_" + hex.EncodeToString(sum[:12])
+}
+
+// How far an emit must get before POST /emit answers.
+const (
+	AckLevelAccepted  = "accepted"
+	AckLevelStored    = "stored"
+	AckLevelDelivered = "delivered"
+)
+
 // EmitRequest is the request body for POST /emit.
 type EmitRequest struct {
-	Topic string          `json:"topic"`
-	Data  json.RawMessage `json:"data"`
+	Topic    string            `json:"topic"`
+	Data     json.RawMessage   `json:"data"`
+	Headers  map[string]string `json:"headers,omitempty"`
+	AckLevel string            `json:"ack_level,omitempty"` // Accepted when empty
 }
 
 // EmitResponse is the response body for POST /emit.
 type EmitResponse struct {
-	ID        string    `json:"id"`
-	Topic     string    `json:"topic"`
-	CreatedAt time.Time `json:"created_at"`
+	ID          string     `json:"id"`
+	Topic       string     `json:"topic"`
+	CreatedAt   time.Time  `json:"created_at"`
+	Seq         uint64     `json:"seq,omitempty"`          // Stream sequence
+	StoredAt    *time.Time `json:"stored_at,omitempty"`    // Stored or delivered ack level
+	DeliveredAt *time.Time `json:"delivered_at,omitempty"` // Delivered ack level, once received
 }
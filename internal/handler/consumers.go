@@ -0,0 +1,76 @@
+package handler
+
+import (
+	"errors"
+	"log/slog"
+	"net/http"
+	"time"
+
+	"github.com/filipexyz/notif/internal/middleware"
+	"github.com/filipexyz/notif/internal/nats"
+	"github.com/go-chi/chi/v5"
+)
+
+// ConsumersHandler handles consumer group endpoints.
+type ConsumersHandler struct {
+	consumers *nats.ConsumerManager
+}
+
+// NewConsumersHandler creates a new ConsumersHandler.
+func NewConsumersHandler(consumers *nats.ConsumerManager) *ConsumersHandler {
+	return &ConsumersHandler{consumers: consumers}
+}
+
+// ConsumerLagResponse is the response body for GET /consumers/:group/lag.
+type ConsumerLagResponse struct {
+	Group           string     `json:"group"`
+	Topics          []string   `json:"topics"`
+	Pending         uint64     `json:"pending"`
+	InFlight        uint64     `json:"in_flight"`
+	OldestPendingAt *time.Time `json:"oldest_pending_at,omitempty"`
+}
+
+// Lag handles GET /consumers/:group/lag?topic=...
+//
+// The topic parameters must be the topics the group subscribed with, since
+// each distinct set of topics gets its own consumer.
+func (h *ConsumersHandler) Lag(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil || authCtx.OrgID == "" {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	group := chi.URLParam(r, "group")
+	if group == "" {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "group is required"})
+		return
+	}
+	topics := r.URL.Query()["topic"]
+	if len(topics) == 0 {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "at least one topic is required"})
+		return
+	}
+
+	lag, err := h.consumers.Lag(r.Context(), authCtx.OrgID, authCtx.ProjectID, group, topics)
+	if errors.Is(err, nats.ErrConsumerNotFound) {
+		writeJSON(w, http.StatusNotFound, map[string]string{"error": "consumer group not found for these topics"})
+		return
+	}
+	if err != nil {
+		slog.Error("failed to get consumer lag", "error", err, "group", group)
+		writeJSON(w, http.StatusInternalServerError, map[string]string{"error": "failed to get consumer lag"})
+		return
+	}
+
+	resp := ConsumerLagResponse{
+		Group:    group,
+		Topics:   topics,
+		Pending:  lag.Pending,
+		InFlight: lag.InFlight,
+	}
+	if !lag.OldestPendingAt.IsZero() {
+		resp.OldestPendingAt = &lag.OldestPendingAt
+	}
+	writeJSON(w, http.StatusOK, resp)
+}
@@ -62,6 +62,9 @@ func (h *DLQHandler) List(w http.ResponseWriter, r *http.Request) {
 			"event_id":   entry.Message.ID,
 			"data":       entry.Message.Data,
 		}
+		if len(entry.Message.Headers) > 0 {
+			messages[i]["headers"] = entry.Message.Headers
+		}
 	}
 
 	writeJSON(w, http.StatusOK, map[string]any{
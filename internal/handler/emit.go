@@ -1,11 +1,14 @@
 package handler
 
 import (
+	"context"
 	"encoding/json"
+	"errors"
 	"fmt"
 	"log/slog"
 	"net/http"
 	"strings"
+	"time"
 
 	"github.com/filipexyz/notif/internal/audit"
 	"github.com/filipexyz/notif/internal/config"
@@ -65,6 +68,33 @@ func (h *EmitHandler) Emit(w http.ResponseWriter, r *http.Request) {
 		return
 	}
 
+	// Validate headers
+	if err := validateHeaders(req.Headers); err != nil {
+		writeJSON(w, http.StatusBadRequest, map[string]string{
+			"error": err.Error(),
+		})
+		return
+	}
+
+	// Validate ack level
+	switch req.AckLevel {
+	case "", domain.AckLevelAccepted, domain.AckLevelStored, domain.AckLevelDelivered:
+	default:
+		writeJSON(w, http.StatusBadRequest, map[string]string{
+			"error": fmt.Sprintf("invalid ack_level %q, expected accepted, stored or delivered", req.AckLevel),
+		})
+		return
+	}
+
+	// Retries of a request carry the same idempotency key
+	idempotencyKey := r.Header.Get(idempotencyKeyHeader)
+	if len(idempotencyKey) > maxIdempotencyKeyLen {
+		writeJSON(w, http.StatusBadRequest, map[string]string{
+			"error": fmt.Sprintf("%s too long, max %d chars", idempotencyKeyHeader, maxIdempotencyKeyLen),
+		})
+		return
+	}
+
 	// Schema validation (if registry is configured and we have project context)
 	authCtx := middleware.GetAuthContext(r.Context())
 	if h.schemaRegistry != nil && authCtx != nil && authCtx.ProjectID != "" {
@@ -109,13 +139,23 @@ func (h *EmitHandler) Emit(w http.ResponseWriter, r *http.Request) {
 
 	// Create event with org and project context
 	event := domain.NewEvent(req.Topic, req.Data)
+	event.Headers = req.Headers
 	if authCtx != nil {
 		event.OrgID = authCtx.OrgID
 		event.ProjectID = authCtx.ProjectID
 	}
+	if idempotencyKey != "" {
+		event.ID = domain.IdempotentEventID(event.OrgID+"."+event.ProjectID, idempotencyKey)
+	}
 
-	// Publish to NATS
-	if err := h.publisher.Publish(r.Context(), event); err != nil {
+	// Publish to NATS; a retry of an emit that went through is answered
+	// like the original without storing the event twice
+	seq, err := h.publisher.Publish(r.Context(), event)
+	if errors.Is(err, nats.ErrDuplicate) {
+		writeJSON(w, http.StatusOK, h.emitResponse(r.Context(), event, seq, req.AckLevel))
+		return
+	}
+	if err != nil {
 		slog.Error("failed to publish event", "error", err, "topic", req.Topic)
 		writeJSON(w, http.StatusInternalServerError, map[string]string{
 			"error": "failed to publish event",
@@ -163,11 +203,69 @@ func (h *EmitHandler) Emit(w http.ResponseWriter, r *http.Request) {
 		})
 	}
 
-	writeJSON(w, http.StatusOK, domain.EmitResponse{
+	writeJSON(w, http.StatusOK, h.emitResponse(r.Context(), event, seq, req.AckLevel))
+}
+
+// Longest an emit with the delivered ack level waits for a consumer,
+// short enough to answer within the SDKs' default 30s request timeout.
+const (
+	maxDeliveredWait      = 25 * time.Second
+	deliveredPollInterval = 100 * time.Millisecond
+)
+
+// emitResponse reports how far a published event got, waiting for its
+// first delivery under the delivered ack level.
+func (h *EmitHandler) emitResponse(ctx context.Context, event *domain.Event, seq uint64, ackLevel string) domain.EmitResponse {
+	resp := domain.EmitResponse{
 		ID:        event.ID,
 		Topic:     event.Topic,
 		CreatedAt: event.Timestamp,
-	})
+		Seq:       seq,
+	}
+	if ackLevel == "" || ackLevel == domain.AckLevelAccepted {
+		return resp
+	}
+
+	// Publish returns once JetStream stored the event on disk
+	storedAt := time.Now().UTC()
+	resp.StoredAt = &storedAt
+	if ackLevel == domain.AckLevelDelivered {
+		resp.DeliveredAt = h.waitDelivered(ctx, event.ID)
+	}
+	return resp
+}
+
+// waitDelivered polls the event's deliveries until a consumer or webhook
+// received it, returning nil if none did within maxDeliveredWait.
+func (h *EmitHandler) waitDelivered(ctx context.Context, eventID string) *time.Time {
+	if h.queries == nil {
+		return nil
+	}
+	ctx, cancel := context.WithTimeout(ctx, maxDeliveredWait)
+	defer cancel()
+	ticker := time.NewTicker(deliveredPollInterval)
+	defer ticker.Stop()
+
+	for {
+		deliveries, err := h.queries.GetEventDeliveries(ctx, eventID)
+		if err == nil {
+			var first *time.Time
+			for _, d := range deliveries {
+				if d.DeliveredAt.Valid && (first == nil || d.DeliveredAt.Time.Before(*first)) {
+					at := d.DeliveredAt.Time.UTC()
+					first = &at
+				}
+			}
+			if first != nil {
+				return first
+			}
+		}
+		select {
+		case <-ctx.Done():
+			return nil
+		case <-ticker.C:
+		}
+	}
 }
 
 func validateTopic(topic string) error {
@@ -189,6 +287,37 @@ func validateTopic(topic string) error {
 	return nil
 }
 
+// Header carrying a client-chosen key that deduplicates retried emits.
+const (
+	idempotencyKeyHeader = "Idempotency-Key"
+	maxIdempotencyKeyLen = 255
+)
+
+// Limits on the custom headers of an event.
+const (
+	maxHeaders        = 64
+	maxHeaderNameLen  = 128
+	maxHeaderValueLen = 4096
+)
+
+func validateHeaders(headers map[string]string) error {
+	if len(headers) > maxHeaders {
+		return &validationError{fmt.Sprintf("too many headers, max %d", maxHeaders)}
+	}
+	for name, value := range headers {
+		if name == "" {
+			return &validationError{"header name cannot be empty"}
+		}
+		if len(name) > maxHeaderNameLen {
+			return &validationError{fmt.Sprintf("header name too long, max %d chars", maxHeaderNameLen)}
+		}
+		if len(value) > maxHeaderValueLen {
+			return &validationError{fmt.Sprintf("header %s too long, max %d chars", name, maxHeaderValueLen)}
+		}
+	}
+	return nil
+}
+
 type validationError struct {
 	msg string
 }
@@ -4,6 +4,7 @@ import (
 	"crypto/rand"
 	"encoding/hex"
 	"encoding/json"
+	"errors"
 	"log/slog"
 	"net/http"
 	"strconv"
@@ -13,6 +14,7 @@ import (
 	"github.com/filipexyz/notif/internal/middleware"
 	"github.com/filipexyz/notif/internal/scheduler"
 	"github.com/go-chi/chi/v5"
+	"github.com/jackc/pgx/v5"
 	"github.com/jackc/pgx/v5/pgtype"
 )
 
@@ -38,6 +40,15 @@ type CreateScheduleRequest struct {
 	In           string          `json:"in,omitempty"`
 }
 
+// UpdateScheduleRequest is the request body for PATCH /schedules/:id.
+// Fields left out keep their current value.
+type UpdateScheduleRequest struct {
+	Topic        *string         `json:"topic,omitempty"`
+	Data         json.RawMessage `json:"data,omitempty"`
+	ScheduledFor *time.Time      `json:"scheduled_for,omitempty"`
+	In           string          `json:"in,omitempty"`
+}
+
 // CreateScheduleResponse is the response body for POST /schedules.
 type CreateScheduleResponse struct {
 	ID           string    `json:"id"`
@@ -234,6 +245,97 @@ func (h *SchedulesHandler) Get(w http.ResponseWriter, r *http.Request) {
 	writeJSON(w, http.StatusOK, scheduleToResponse(sch))
 }
 
+// Update handles PATCH /schedules/:id.
+func (h *SchedulesHandler) Update(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	id := chi.URLParam(r, "id")
+	if id == "" {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "id is required"})
+		return
+	}
+
+	var req UpdateScheduleRequest
+	if err := json.NewDecoder(r.Body).Decode(&req); err != nil {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "invalid JSON payload"})
+		return
+	}
+
+	projectID := pgtype.Text{String: authCtx.ProjectID, Valid: authCtx.ProjectID != ""}
+	sch, err := h.queries.GetScheduledEventByProject(r.Context(), db.GetScheduledEventByProjectParams{
+		ID:        id,
+		OrgID:     authCtx.OrgID,
+		ProjectID: projectID,
+	})
+	if err != nil {
+		writeJSON(w, http.StatusNotFound, map[string]string{"error": "scheduled event not found"})
+		return
+	}
+
+	// Start from the current schedule and apply what the request changes
+	topic := sch.Topic
+	if req.Topic != nil {
+		if err := validateTopic(*req.Topic); err != nil {
+			writeJSON(w, http.StatusBadRequest, map[string]string{"error": err.Error()})
+			return
+		}
+		topic = *req.Topic
+	}
+
+	data := sch.Data
+	if req.Data != nil {
+		data = req.Data
+	}
+
+	scheduledFor := sch.ScheduledFor.Time
+	if req.ScheduledFor != nil {
+		scheduledFor = *req.ScheduledFor
+	} else if req.In != "" {
+		duration, err := time.ParseDuration(req.In)
+		if err != nil {
+			writeJSON(w, http.StatusBadRequest, map[string]string{"error": "invalid duration format, use Go duration syntax (e.g. 5m, 1h30m)"})
+			return
+		}
+		scheduledFor = time.Now().Add(duration)
+	}
+	if (req.ScheduledFor != nil || req.In != "") && scheduledFor.Before(time.Now()) {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "scheduled_for must be in the future"})
+		return
+	}
+
+	// Only pending schedules change; one that ran or was cancelled meanwhile
+	// matches no row
+	updated, err := h.queries.UpdateScheduledEventByProject(r.Context(), db.UpdateScheduledEventByProjectParams{
+		ID:           id,
+		OrgID:        authCtx.OrgID,
+		ProjectID:    projectID,
+		Topic:        topic,
+		Data:         data,
+		ScheduledFor: pgtype.Timestamptz{Time: scheduledFor, Valid: true},
+	})
+	if errors.Is(err, pgx.ErrNoRows) {
+		writeJSON(w, http.StatusNotFound, map[string]string{"error": "scheduled event not found or already executed"})
+		return
+	}
+	if err != nil {
+		slog.Error("failed to update scheduled event", "error", err, "id", id)
+		writeJSON(w, http.StatusInternalServerError, map[string]string{"error": "failed to update scheduled event"})
+		return
+	}
+
+	slog.Info("scheduled event updated",
+		"id", updated.ID,
+		"topic", updated.Topic,
+		"scheduled_for", scheduledFor,
+	)
+
+	writeJSON(w, http.StatusOK, scheduleToResponse(updated))
+}
+
 // Cancel handles DELETE /schedules/:id.
 func (h *SchedulesHandler) Cancel(w http.ResponseWriter, r *http.Request) {
 	authCtx := middleware.GetAuthContext(r.Context())
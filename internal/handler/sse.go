@@ -0,0 +1,124 @@
+package handler
+
+import (
+	"context"
+	"crypto/rand"
+	"encoding/hex"
+	"io"
+	"log/slog"
+	"net/http"
+
+	"github.com/filipexyz/notif/internal/audit"
+	"github.com/filipexyz/notif/internal/config"
+	"github.com/filipexyz/notif/internal/db"
+	"github.com/filipexyz/notif/internal/middleware"
+	"github.com/filipexyz/notif/internal/nats"
+	"github.com/filipexyz/notif/internal/websocket"
+	"github.com/go-chi/chi/v5"
+	"github.com/google/uuid"
+)
+
+// Response header carrying the session that client messages are sent to.
+const sseSessionHeader = "X-Session-ID"
+
+// SSEHandler serves subscriptions as Server-Sent Events, for clients that
+// cannot open a WebSocket.
+type SSEHandler struct {
+	hub          *websocket.Hub
+	consumerMgr  *nats.ConsumerManager
+	dlqPublisher *nats.DLQPublisher
+	queries      *db.Queries
+	cfg          *config.Config
+	auditLog     *audit.Logger
+}
+
+// NewSSEHandler creates a new SSEHandler.
+func NewSSEHandler(hub *websocket.Hub, consumerMgr *nats.ConsumerManager, dlqPublisher *nats.DLQPublisher, queries *db.Queries, cfg *config.Config, auditLog *audit.Logger) *SSEHandler {
+	return &SSEHandler{
+		hub:          hub,
+		consumerMgr:  consumerMgr,
+		dlqPublisher: dlqPublisher,
+		queries:      queries,
+		cfg:          cfg,
+		auditLog:     auditLog,
+	}
+}
+
+// generateSSEClientID creates a unique session identifier.
+func generateSSEClientID() string {
+	b := make([]byte, 8)
+	rand.Read(b)
+	return "sse_" + hex.EncodeToString(b)
+}
+
+// Stream handles POST /sse. The body is a subscribe message; the response
+// streams the same server messages a WebSocket carries, and names the
+// session in the X-Session-ID header.
+func (h *SSEHandler) Stream(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil || authCtx.OrgID == "" {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	body, err := io.ReadAll(http.MaxBytesReader(w, r.Body, h.cfg.MaxPayloadSize))
+	if err != nil {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "invalid subscribe message"})
+		return
+	}
+
+	apiKeyID := ""
+	if apiKey := middleware.GetAPIKey(r.Context()); apiKey != nil {
+		apiKeyID = uuid.UUID(apiKey.ID.Bytes).String()
+	}
+
+	clientID := generateSSEClientID()
+	client := websocket.NewSSEClient(h.hub, apiKeyID, authCtx.OrgID, authCtx.ProjectID, h.dlqPublisher, h.queries, clientID, h.cfg.MaxPayloadSize)
+	h.hub.Register(client)
+
+	slog.Info("sse client connected", "client_id", clientID)
+
+	// Audit log
+	if h.auditLog != nil {
+		actor := auditActor(authCtx)
+		ctx := audit.WithIP(r.Context(), audit.IPFromRequest(r))
+		h.auditLog.Log(ctx, actor, "subscription.create", authCtx.OrgID, clientID, nil)
+	}
+
+	w.Header().Set("Content-Type", "text/event-stream")
+	w.Header().Set("Cache-Control", "no-cache")
+	w.Header().Set("X-Accel-Buffering", "no")
+	w.Header().Set(sseSessionHeader, clientID)
+	w.WriteHeader(http.StatusOK)
+	http.NewResponseController(w).Flush()
+
+	// The consumer outlives any single request, as for WebSockets
+	client.HandleFrame(context.Background(), body, h.consumerMgr)
+	client.StreamSSE(r.Context(), w)
+}
+
+// Send handles POST /sse/:session. The body is one client message, such as
+// an ack, nack or ping, for the session's stream.
+func (h *SSEHandler) Send(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil || authCtx.OrgID == "" {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	client := h.hub.Lookup(chi.URLParam(r, "session"))
+	if client == nil || !client.Owns(authCtx.OrgID, authCtx.ProjectID) {
+		writeJSON(w, http.StatusNotFound, map[string]string{"error": "event stream not found"})
+		return
+	}
+
+	body, err := io.ReadAll(http.MaxBytesReader(w, r.Body, h.cfg.MaxPayloadSize))
+	if err != nil {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "invalid message"})
+		return
+	}
+
+	// Answers and errors go out on the stream
+	client.HandleFrame(context.Background(), body, h.consumerMgr)
+	w.WriteHeader(http.StatusAccepted)
+}
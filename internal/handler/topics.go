@@ -0,0 +1,136 @@
+package handler
+
+import (
+	"log/slog"
+	"net/http"
+	"time"
+
+	"github.com/filipexyz/notif/internal/middleware"
+	"github.com/filipexyz/notif/internal/nats"
+	"github.com/go-chi/chi/v5"
+	"github.com/nats-io/nats.go/jetstream"
+)
+
+// TopicsHandler handles topic catalog endpoints.
+type TopicsHandler struct {
+	reader *nats.EventReader
+}
+
+// NewTopicsHandler creates a new TopicsHandler.
+func NewTopicsHandler(reader *nats.EventReader) *TopicsHandler {
+	return &TopicsHandler{reader: reader}
+}
+
+// TopicResponse describes the stored events of a topic.
+type TopicResponse struct {
+	Name         string             `json:"name"`
+	Messages     uint64             `json:"messages"`
+	FirstEventAt *time.Time         `json:"first_event_at,omitempty"`
+	LastEventAt  *time.Time         `json:"last_event_at,omitempty"`
+	Retention    *RetentionResponse `json:"retention,omitempty"`
+}
+
+// RetentionResponse holds the limits of the stream a topic is stored in.
+// Unlimited values are left out.
+type RetentionResponse struct {
+	MaxAgeSeconds *uint64 `json:"max_age_seconds,omitempty"`
+	MaxMessages   *uint64 `json:"max_messages,omitempty"`
+	MaxBytes      *uint64 `json:"max_bytes,omitempty"`
+}
+
+// List handles GET /topics.
+func (h *TopicsHandler) List(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil || authCtx.OrgID == "" {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	topics, err := h.reader.Topics(r.Context(), authCtx.OrgID, authCtx.ProjectID)
+	if err != nil {
+		slog.Error("failed to list topics", "error", err)
+		writeJSON(w, http.StatusInternalServerError, map[string]string{"error": "failed to list topics"})
+		return
+	}
+
+	retention := h.retention(r)
+	result := make([]TopicResponse, len(topics))
+	for i, topic := range topics {
+		result[i] = topicToResponse(topic, retention)
+	}
+
+	writeJSON(w, http.StatusOK, map[string]any{
+		"topics": result,
+		"count":  len(result),
+	})
+}
+
+// Get handles GET /topics/:name.
+func (h *TopicsHandler) Get(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil || authCtx.OrgID == "" {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	name := chi.URLParam(r, "name")
+	if err := validateTopic(name); err != nil {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": err.Error()})
+		return
+	}
+
+	topic, err := h.reader.Topic(r.Context(), authCtx.OrgID, authCtx.ProjectID, name)
+	if err != nil {
+		slog.Error("failed to get topic", "error", err, "topic", name)
+		writeJSON(w, http.StatusInternalServerError, map[string]string{"error": "failed to get topic"})
+		return
+	}
+	if topic == nil {
+		writeJSON(w, http.StatusNotFound, map[string]string{"error": "topic has no stored events"})
+		return
+	}
+
+	writeJSON(w, http.StatusOK, topicToResponse(*topic, h.retention(r)))
+}
+
+// retention reads the limits of the events stream, or nil if unavailable.
+func (h *TopicsHandler) retention(r *http.Request) *RetentionResponse {
+	info, err := h.reader.StreamInfo(r.Context())
+	if err != nil {
+		slog.Warn("failed to get stream info", "error", err)
+		return nil
+	}
+	return streamRetention(info.Config)
+}
+
+func streamRetention(cfg jetstream.StreamConfig) *RetentionResponse {
+	resp := &RetentionResponse{}
+	if cfg.MaxAge > 0 {
+		seconds := uint64(cfg.MaxAge / time.Second)
+		resp.MaxAgeSeconds = &seconds
+	}
+	if cfg.MaxMsgs > 0 {
+		messages := uint64(cfg.MaxMsgs)
+		resp.MaxMessages = &messages
+	}
+	if cfg.MaxBytes > 0 {
+		bytes := uint64(cfg.MaxBytes)
+		resp.MaxBytes = &bytes
+	}
+	return resp
+}
+
+func topicToResponse(topic nats.TopicInfo, retention *RetentionResponse) TopicResponse {
+	resp := TopicResponse{
+		Name:      topic.Topic,
+		Messages:  topic.Messages,
+		Retention: retention,
+	}
+	if !topic.FirstEventAt.IsZero() {
+		resp.FirstEventAt = &topic.FirstEventAt
+	}
+	if !topic.LastEventAt.IsZero() {
+		resp.LastEventAt = &topic.LastEventAt
+	}
+	return resp
+}
@@ -4,6 +4,7 @@ import (
 	"crypto/rand"
 	"encoding/hex"
 	"encoding/json"
+	"fmt"
 	"net/http"
 
 	"github.com/filipexyz/notif/internal/audit"
@@ -30,8 +31,15 @@ func NewWebhookHandler(queries *db.Queries, auditLog *audit.Logger) *WebhookHand
 type CreateWebhookRequest struct {
 	URL    string   `json:"url"`
 	Topics []string `json:"topics"`
+	Secret string   `json:"secret,omitempty"` // Generated when empty
 }
 
+// Bounds on a caller-chosen webhook secret.
+const (
+	minWebhookSecretLen = 16
+	maxWebhookSecretLen = 256
+)
+
 // WebhookResponse is the response for a webhook.
 type WebhookResponse struct {
 	ID        string   `json:"id"`
@@ -58,6 +66,12 @@ func (h *WebhookHandler) Create(w http.ResponseWriter, r *http.Request) {
 		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "at least one topic is required"})
 		return
 	}
+	if req.Secret != "" && (len(req.Secret) < minWebhookSecretLen || len(req.Secret) > maxWebhookSecretLen) {
+		writeJSON(w, http.StatusBadRequest, map[string]string{
+			"error": fmt.Sprintf("secret must be %d to %d chars", minWebhookSecretLen, maxWebhookSecretLen),
+		})
+		return
+	}
 
 	// Validate URL to prevent SSRF attacks
 	if err := security.ValidateWebhookURL(req.URL); err != nil {
@@ -71,8 +85,11 @@ func (h *WebhookHandler) Create(w http.ResponseWriter, r *http.Request) {
 		return
 	}
 
-	// Generate secret
-	secret := generateSecret()
+	// Use the caller's secret, or generate one
+	secret := req.Secret
+	if secret == "" {
+		secret = generateSecret()
+	}
 
 	webhook, err := h.queries.CreateWebhook(r.Context(), db.CreateWebhookParams{
 		OrgID:     pgtype.Text{String: authCtx.OrgID, Valid: true},
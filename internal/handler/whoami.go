@@ -0,0 +1,55 @@
+package handler
+
+import (
+	"net/http"
+
+	"github.com/filipexyz/notif/internal/db"
+	"github.com/filipexyz/notif/internal/middleware"
+	"github.com/google/uuid"
+)
+
+// WhoAmIHandler reports who the caller is authenticated as.
+type WhoAmIHandler struct {
+	queries *db.Queries
+}
+
+// NewWhoAmIHandler creates a new WhoAmIHandler.
+func NewWhoAmIHandler(queries *db.Queries) *WhoAmIHandler {
+	return &WhoAmIHandler{queries: queries}
+}
+
+// WhoAmIResponse is the response for the authenticated identity.
+type WhoAmIResponse struct {
+	OrgID       string `json:"org_id"`
+	ProjectID   string `json:"project_id"`
+	ProjectName string `json:"project_name,omitempty"`
+	KeyID       string `json:"key_id,omitempty"`
+	KeyName     string `json:"key_name,omitempty"`
+	KeyPrefix   string `json:"key_prefix,omitempty"`
+}
+
+// WhoAmI returns the organization, project and API key of the request.
+// It touches nothing but the project row, so clients can also use it to
+// check that their credentials work.
+func (h *WhoAmIHandler) WhoAmI(w http.ResponseWriter, r *http.Request) {
+	authCtx := middleware.GetAuthContext(r.Context())
+	if authCtx == nil {
+		writeJSON(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+		return
+	}
+
+	resp := WhoAmIResponse{
+		OrgID:     authCtx.OrgID,
+		ProjectID: authCtx.ProjectID,
+	}
+	if project, err := h.queries.GetProject(r.Context(), authCtx.ProjectID); err == nil {
+		resp.ProjectName = project.Name
+	}
+	if apiKey := middleware.GetAPIKey(r.Context()); apiKey != nil {
+		resp.KeyID = uuid.UUID(apiKey.ID.Bytes).String()
+		resp.KeyName = apiKey.Name.String
+		resp.KeyPrefix = apiKey.KeyPrefix
+	}
+
+	writeJSON(w, http.StatusOK, resp)
+}
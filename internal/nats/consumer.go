@@ -4,6 +4,7 @@ import (
 	"context"
 	"crypto/sha256"
 	"encoding/hex"
+	"errors"
 	"fmt"
 	"sort"
 	"strings"
@@ -118,6 +119,61 @@ func (cm *ConsumerManager) CreateConsumer(ctx context.Context, opts Subscription
 	return consumer, nil
 }
 
+// ErrConsumerNotFound is returned when a group has no consumer for the topics.
+var ErrConsumerNotFound = errors.New("consumer not found")
+
+// ConsumerLag is the backlog of a consumer group.
+type ConsumerLag struct {
+	Pending         uint64
+	InFlight        uint64
+	OldestPendingAt time.Time
+}
+
+// Lag returns the backlog of the consumer group that subscribed to topics.
+func (cm *ConsumerManager) Lag(ctx context.Context, orgID, projectID, group string, topics []string) (*ConsumerLag, error) {
+	if orgID == "" || projectID == "" {
+		return nil, fmt.Errorf("org_id and project_id are required for consumer queries")
+	}
+
+	consumer, err := cm.stream.Consumer(ctx, group+"-"+hashTopics(topics))
+	if errors.Is(err, jetstream.ErrConsumerNotFound) {
+		return nil, ErrConsumerNotFound
+	}
+	if err != nil {
+		return nil, fmt.Errorf("get consumer: %w", err)
+	}
+	info := consumer.CachedInfo()
+
+	// Durable names are not scoped to the org, so only report a consumer
+	// whose subjects all belong to the caller's project
+	subjects := info.Config.FilterSubjects
+	if info.Config.FilterSubject != "" {
+		subjects = append(subjects, info.Config.FilterSubject)
+	}
+	if len(subjects) == 0 {
+		return nil, ErrConsumerNotFound
+	}
+	prefix := "events." + orgID + "." + projectID + "."
+	for _, subject := range subjects {
+		if !strings.HasPrefix(subject, prefix) {
+			return nil, ErrConsumerNotFound
+		}
+	}
+
+	lag := &ConsumerLag{
+		Pending:  info.NumPending,
+		InFlight: uint64(info.NumAckPending),
+	}
+	if info.NumPending > 0 && len(subjects) == 1 {
+		// The next event the group gets is the oldest one still pending
+		msg, err := cm.stream.GetMsg(ctx, info.Delivered.Stream+1, jetstream.WithGetMsgSubject(subjects[0]))
+		if err == nil {
+			lag.OldestPendingAt = msg.Time
+		}
+	}
+	return lag, nil
+}
+
 // hashTopics returns a short hash of the sorted topics for consumer naming.
 func hashTopics(topics []string) string {
 	sorted := make([]string, len(topics))
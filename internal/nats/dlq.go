@@ -11,16 +11,17 @@ import (
 
 // DLQMessage represents a message in the dead letter queue.
 type DLQMessage struct {
-	ID            string          `json:"id"`
-	OrgID         string          `json:"org_id"`
-	ProjectID     string          `json:"project_id"`
-	OriginalTopic string          `json:"original_topic"`
-	Data          json.RawMessage `json:"data"`
-	Timestamp     time.Time       `json:"timestamp"`
-	FailedAt      time.Time       `json:"failed_at"`
-	Attempts      int             `json:"attempts"`
-	LastError     string          `json:"last_error,omitempty"`
-	ConsumerGroup string          `json:"consumer_group,omitempty"`
+	ID            string            `json:"id"`
+	OrgID         string            `json:"org_id"`
+	ProjectID     string            `json:"project_id"`
+	OriginalTopic string            `json:"original_topic"`
+	Data          json.RawMessage   `json:"data"`
+	Headers       map[string]string `json:"headers,omitempty"`
+	Timestamp     time.Time         `json:"timestamp"`
+	FailedAt      time.Time         `json:"failed_at"`
+	Attempts      int               `json:"attempts"`
+	LastError     string            `json:"last_error,omitempty"`
+	ConsumerGroup string            `json:"consumer_group,omitempty"`
 }
 
 // DLQPublisher publishes failed messages to the dead letter queue.
@@ -223,19 +224,21 @@ func (r *DLQReader) Replay(ctx context.Context, seq uint64, publisher *Publisher
 
 	// Republish to original topic with org and project isolation
 	event := struct {
-		ID        string          `json:"id"`
-		OrgID     string          `json:"org_id"`
-		ProjectID string          `json:"project_id"`
-		Topic     string          `json:"topic"`
-		Data      json.RawMessage `json:"data"`
-		Timestamp time.Time       `json:"timestamp"`
-		Attempt   int             `json:"attempt"`
+		ID        string            `json:"id"`
+		OrgID     string            `json:"org_id"`
+		ProjectID string            `json:"project_id"`
+		Topic     string            `json:"topic"`
+		Data      json.RawMessage   `json:"data"`
+		Headers   map[string]string `json:"headers,omitempty"`
+		Timestamp time.Time         `json:"timestamp"`
+		Attempt   int               `json:"attempt"`
 	}{
 		ID:        entry.Message.ID,
 		OrgID:     entry.Message.OrgID,
 		ProjectID: entry.Message.ProjectID,
 		Topic:     entry.Message.OriginalTopic,
 		Data:      entry.Message.Data,
+		Headers:   entry.Message.Headers,
 		Timestamp: entry.Message.Timestamp,
 		Attempt:   1, // Reset attempt count
 	}
@@ -3,6 +3,7 @@ package nats
 import (
 	"context"
 	"encoding/json"
+	"errors"
 	"fmt"
 	"log/slog"
 
@@ -10,6 +11,10 @@ import (
 	"github.com/nats-io/nats.go/jetstream"
 )
 
+// ErrDuplicate is returned by Publish when JetStream already stored an
+// event with the same ID within its duplicate window.
+var ErrDuplicate = errors.New("duplicate event")
+
 // Publisher publishes events to JetStream.
 type Publisher struct {
 	js jetstream.JetStream
@@ -20,14 +25,15 @@ func NewPublisher(js jetstream.JetStream) *Publisher {
 	return &Publisher{js: js}
 }
 
-// Publish sends an event to JetStream.
-func (p *Publisher) Publish(ctx context.Context, event *domain.Event) error {
+// Publish sends an event to JetStream and returns its stream sequence.
+// A duplicate returns the sequence of the original along with ErrDuplicate.
+func (p *Publisher) Publish(ctx context.Context, event *domain.Event) (uint64, error) {
 	// Strict org_id and project_id enforcement - no anonymous events allowed
 	if event.OrgID == "" {
-		return fmt.Errorf("org_id is required for publishing events")
+		return 0, fmt.Errorf("org_id is required for publishing events")
 	}
 	if event.ProjectID == "" {
-		return fmt.Errorf("project_id is required for publishing events")
+		return 0, fmt.Errorf("project_id is required for publishing events")
 	}
 
 	// Subject format: events.{org_id}.{project_id}.{topic}
@@ -35,7 +41,7 @@ func (p *Publisher) Publish(ctx context.Context, event *domain.Event) error {
 
 	data, err := json.Marshal(event)
 	if err != nil {
-		return fmt.Errorf("marshal event: %w", err)
+		return 0, fmt.Errorf("marshal event: %w", err)
 	}
 
 	// Synchronous publish with ack from JetStream
@@ -43,7 +49,10 @@ func (p *Publisher) Publish(ctx context.Context, event *domain.Event) error {
 		jetstream.WithMsgID(event.ID), // Deduplication
 	)
 	if err != nil {
-		return fmt.Errorf("publish to JetStream: %w", err)
+		return 0, fmt.Errorf("publish to JetStream: %w", err)
+	}
+	if ack.Duplicate {
+		return ack.Sequence, ErrDuplicate
 	}
 
 	slog.Debug("event published",
@@ -55,5 +64,5 @@ func (p *Publisher) Publish(ctx context.Context, event *domain.Event) error {
 		"seq", ack.Sequence,
 	)
 
-	return nil
+	return ack.Sequence, nil
 }
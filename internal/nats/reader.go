@@ -3,7 +3,10 @@ package nats
 import (
 	"context"
 	"encoding/json"
+	"errors"
 	"fmt"
+	"sort"
+	"strings"
 	"time"
 
 	"github.com/filipexyz/notif/internal/domain"
@@ -141,3 +144,83 @@ func (r *EventReader) GetBySeq(ctx context.Context, seq uint64) (*StoredEvent, e
 func (r *EventReader) StreamInfo(ctx context.Context) (*jetstream.StreamInfo, error) {
 	return r.stream.Info(ctx)
 }
+
+// TopicInfo describes the events stored for one topic.
+type TopicInfo struct {
+	Topic        string
+	Messages     uint64
+	FirstEventAt time.Time
+	LastEventAt  time.Time
+}
+
+// Topics lists the topics of a project that have stored events, by name.
+func (r *EventReader) Topics(ctx context.Context, orgID, projectID string) ([]TopicInfo, error) {
+	if orgID == "" || projectID == "" {
+		return nil, fmt.Errorf("org_id and project_id are required for topic queries")
+	}
+
+	prefix := "events." + orgID + "." + projectID + "."
+	info, err := r.stream.Info(ctx, jetstream.WithSubjectFilter(prefix+">"))
+	if err != nil {
+		return nil, err
+	}
+
+	topics := make([]TopicInfo, 0, len(info.State.Subjects))
+	for subject, messages := range info.State.Subjects {
+		topic, err := r.topicInfo(ctx, subject, strings.TrimPrefix(subject, prefix), messages)
+		if err != nil {
+			return nil, err
+		}
+		topics = append(topics, topic)
+	}
+	sort.Slice(topics, func(i, j int) bool { return topics[i].Topic < topics[j].Topic })
+	return topics, nil
+}
+
+// Topic describes a single topic of a project, or returns nil if it has no
+// stored events.
+func (r *EventReader) Topic(ctx context.Context, orgID, projectID, topic string) (*TopicInfo, error) {
+	if orgID == "" || projectID == "" {
+		return nil, fmt.Errorf("org_id and project_id are required for topic queries")
+	}
+
+	subject := "events." + orgID + "." + projectID + "." + topic
+	info, err := r.stream.Info(ctx, jetstream.WithSubjectFilter(subject))
+	if err != nil {
+		return nil, err
+	}
+
+	messages, ok := info.State.Subjects[subject]
+	if !ok {
+		return nil, nil
+	}
+	result, err := r.topicInfo(ctx, subject, topic, messages)
+	if err != nil {
+		return nil, err
+	}
+	return &result, nil
+}
+
+// topicInfo looks up the oldest and newest stored event on subject.
+func (r *EventReader) topicInfo(ctx context.Context, subject, topic string, messages uint64) (TopicInfo, error) {
+	result := TopicInfo{Topic: topic, Messages: messages}
+
+	// Events can age out between the stream info and these lookups
+	first, err := r.stream.GetMsg(ctx, 1, jetstream.WithGetMsgSubject(subject))
+	if err != nil && !errors.Is(err, jetstream.ErrMsgNotFound) {
+		return result, err
+	}
+	if first != nil {
+		result.FirstEventAt = first.Time
+	}
+
+	last, err := r.stream.GetLastMsgForSubject(ctx, subject)
+	if err != nil && !errors.Is(err, jetstream.ErrMsgNotFound) {
+		return result, err
+	}
+	if last != nil {
+		result.LastEventAt = last.Time
+	}
+
+	return result, nil
+}
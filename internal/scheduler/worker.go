@@ -73,7 +73,7 @@ func (w *Worker) executeScheduled(ctx context.Context, sch db.ScheduledEvent) {
 	event.OrgID = sch.OrgID
 
 	// Publish to NATS
-	if err := w.publisher.Publish(ctx, event); err != nil {
+	if _, err := w.publisher.Publish(ctx, event); err != nil {
 		slog.Error("failed to publish scheduled event",
 			"scheduled_id", sch.ID,
 			"topic", sch.Topic,
@@ -124,7 +124,7 @@ func (w *Worker) ExecuteNow(ctx context.Context, orgID, scheduleID string) (stri
 	event.OrgID = sch.OrgID
 
 	// Publish to NATS
-	if err := w.publisher.Publish(ctx, event); err != nil {
+	if _, err := w.publisher.Publish(ctx, event); err != nil {
 		w.queries.UpdateScheduledEventStatus(ctx, db.UpdateScheduledEventStatusParams{
 			ID:     sch.ID,
 			Status: "failed",
@@ -30,9 +30,9 @@ func (s *Server) routes() http.Handler {
 	// CORS
 	r.Use(cors.Handler(cors.Options{
 		AllowedOrigins:   s.cfg.CORSOrigins,
-		AllowedMethods:   []string{"GET", "POST", "PUT", "DELETE", "OPTIONS"},
-		AllowedHeaders:   []string{"Accept", "Authorization", "Content-Type", "X-Project-ID"},
-		ExposedHeaders:   []string{"Link"},
+		AllowedMethods:   []string{"GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"},
+		AllowedHeaders:   []string{"Accept", "Authorization", "Content-Type", "Idempotency-Key", "X-Project-ID"},
+		ExposedHeaders:   []string{"Link", "X-Session-ID"},
 		AllowCredentials: true,
 		MaxAge:           300,
 	}))
@@ -126,6 +126,10 @@ func (s *Server) routesMultiAccount(r chi.Router, queries *db.Queries) {
 		r.Use(middleware.RateLimit(s.rateLimiter))
 		r.Use(middleware.UnifiedAuth(queries, s.cfg))
 
+		// Identity of the caller
+		whoamiHandler := handler.NewWhoAmIHandler(queries)
+		r.Get("/whoami", whoamiHandler.WhoAmI)
+
 		// Events — resolve orgID → pool.Get(orgID)
 		r.Post("/emit", func(w http.ResponseWriter, r *http.Request) {
 			authCtx := middleware.GetAuthContext(r.Context())
@@ -210,6 +214,86 @@ func (s *Server) routesMultiAccount(r chi.Router, queries *db.Queries) {
 			eventsHandler.Deliveries(w, r)
 		})
 
+		// Topics — resolve orgID → pool.Get(orgID)
+		r.Get("/topics", func(w http.ResponseWriter, r *http.Request) {
+			authCtx := middleware.GetAuthContext(r.Context())
+			if authCtx == nil || authCtx.OrgID == "" {
+				handler.WriteJSONPublic(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+				return
+			}
+			orgClient, err := s.pool.Get(authCtx.OrgID)
+			if err != nil {
+				handler.WriteJSONPublic(w, http.StatusServiceUnavailable, map[string]string{"error": "org not connected"})
+				return
+			}
+			topicsHandler := handler.NewTopicsHandler(nats.NewEventReader(orgClient.Stream()))
+			topicsHandler.List(w, r)
+		})
+		r.Get("/topics/{name}", func(w http.ResponseWriter, r *http.Request) {
+			authCtx := middleware.GetAuthContext(r.Context())
+			if authCtx == nil || authCtx.OrgID == "" {
+				handler.WriteJSONPublic(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+				return
+			}
+			orgClient, err := s.pool.Get(authCtx.OrgID)
+			if err != nil {
+				handler.WriteJSONPublic(w, http.StatusServiceUnavailable, map[string]string{"error": "org not connected"})
+				return
+			}
+			topicsHandler := handler.NewTopicsHandler(nats.NewEventReader(orgClient.Stream()))
+			topicsHandler.Get(w, r)
+		})
+
+		// Server-Sent Events subscriptions — resolve orgID → pool.Get(orgID)
+		r.Post("/sse", func(w http.ResponseWriter, r *http.Request) {
+			authCtx := middleware.GetAuthContext(r.Context())
+			if authCtx == nil || authCtx.OrgID == "" {
+				handler.WriteJSONPublic(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+				return
+			}
+			orgClient, err := s.pool.Get(authCtx.OrgID)
+			if err != nil {
+				handler.WriteJSONPublic(w, http.StatusServiceUnavailable, map[string]string{"error": "org not connected"})
+				return
+			}
+			consumerMgr := nats.NewConsumerManager(orgClient.Stream())
+			dlqPublisher := nats.NewDLQPublisher(orgClient.JetStream())
+			sseHandler := handler.NewSSEHandler(s.hub, consumerMgr, dlqPublisher, queries, s.cfg, s.auditLog)
+			sseHandler.Stream(w, r)
+		})
+		r.Post("/sse/{session}", func(w http.ResponseWriter, r *http.Request) {
+			authCtx := middleware.GetAuthContext(r.Context())
+			if authCtx == nil || authCtx.OrgID == "" {
+				handler.WriteJSONPublic(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+				return
+			}
+			orgClient, err := s.pool.Get(authCtx.OrgID)
+			if err != nil {
+				handler.WriteJSONPublic(w, http.StatusServiceUnavailable, map[string]string{"error": "org not connected"})
+				return
+			}
+			consumerMgr := nats.NewConsumerManager(orgClient.Stream())
+			dlqPublisher := nats.NewDLQPublisher(orgClient.JetStream())
+			sseHandler := handler.NewSSEHandler(s.hub, consumerMgr, dlqPublisher, queries, s.cfg, s.auditLog)
+			sseHandler.Send(w, r)
+		})
+
+		// Consumer groups — resolve orgID → pool.Get(orgID)
+		r.Get("/consumers/{group}/lag", func(w http.ResponseWriter, r *http.Request) {
+			authCtx := middleware.GetAuthContext(r.Context())
+			if authCtx == nil || authCtx.OrgID == "" {
+				handler.WriteJSONPublic(w, http.StatusUnauthorized, map[string]string{"error": "unauthorized"})
+				return
+			}
+			orgClient, err := s.pool.Get(authCtx.OrgID)
+			if err != nil {
+				handler.WriteJSONPublic(w, http.StatusServiceUnavailable, map[string]string{"error": "org not connected"})
+				return
+			}
+			consumersHandler := handler.NewConsumersHandler(nats.NewConsumerManager(orgClient.Stream()))
+			consumersHandler.Lag(w, r)
+		})
+
 		// Webhooks
 		webhookHandler := handler.NewWebhookHandler(queries, s.auditLog)
 		r.Post("/webhooks", webhookHandler.Create)
@@ -352,6 +436,7 @@ func (s *Server) routesMultiAccount(r chi.Router, queries *db.Queries) {
 		r.Post("/schedules", http.HandlerFunc(notImplemented))
 		r.Get("/schedules", http.HandlerFunc(notImplemented))
 		r.Get("/schedules/{id}", http.HandlerFunc(notImplemented))
+		r.Patch("/schedules/{id}", http.HandlerFunc(notImplemented))
 		r.Delete("/schedules/{id}", http.HandlerFunc(notImplemented))
 		r.Post("/schedules/{id}/run", http.HandlerFunc(notImplemented))
 		r.Get("/stats/schedules", http.HandlerFunc(notImplemented))
@@ -484,12 +569,15 @@ func (s *Server) routesLegacy(r chi.Router, queries *db.Queries) {
 	consumerMgr := nats.NewConsumerManager(s.nats.Stream())
 	dlqPublisher := nats.NewDLQPublisher(s.nats.JetStream())
 	subscribeHandler := handler.NewSubscribeHandler(s.hub, consumerMgr, dlqPublisher, queries, s.cfg, s.auditLog)
+	sseHandler := handler.NewSSEHandler(s.hub, consumerMgr, dlqPublisher, queries, s.cfg, s.auditLog)
 
 	dlqReader, _ := nats.NewDLQReader(s.nats.JetStream())
 	dlqHandler := handler.NewDLQHandler(dlqReader, publisher)
 
 	eventReader := nats.NewEventReader(s.nats.Stream())
 	eventsHandler := handler.NewEventsHandler(eventReader, queries)
+	topicsHandler := handler.NewTopicsHandler(eventReader)
+	consumersHandler := handler.NewConsumersHandler(consumerMgr)
 
 	webhookHandler := handler.NewWebhookHandler(queries, s.auditLog)
 	apiKeyHandler := handler.NewAPIKeyHandler(queries)
@@ -499,6 +587,7 @@ func (s *Server) routesLegacy(r chi.Router, queries *db.Queries) {
 
 	schemaHandler := handler.NewSchemaHandler(schemaRegistry)
 	auditHandler := handler.NewAuditHandler(queries)
+	whoamiHandler := handler.NewWhoAmIHandler(queries)
 
 	r.Group(func(r chi.Router) {
 		r.Use(middleware.UnifiedAuth(queries, s.cfg))
@@ -518,12 +607,22 @@ func (s *Server) routesLegacy(r chi.Router, queries *db.Queries) {
 		r.Use(middleware.RateLimit(s.rateLimiter))
 		r.Use(middleware.UnifiedAuth(queries, s.cfg))
 
+		r.Get("/whoami", whoamiHandler.WhoAmI)
+
 		r.Post("/emit", emitHandler.Emit)
 		r.Get("/events", eventsHandler.List)
 		r.Get("/events/stats", eventsHandler.Stats)
 		r.Get("/events/{seq}", eventsHandler.Get)
 		r.Get("/events/{id}/deliveries", eventsHandler.Deliveries)
 
+		r.Get("/topics", topicsHandler.List)
+		r.Get("/topics/{name}", topicsHandler.Get)
+
+		r.Get("/consumers/{group}/lag", consumersHandler.Lag)
+
+		r.Post("/sse", sseHandler.Stream)
+		r.Post("/sse/{session}", sseHandler.Send)
+
 		r.Post("/webhooks", webhookHandler.Create)
 		r.Get("/webhooks", webhookHandler.List)
 		r.Get("/webhooks/{id}", webhookHandler.Get)
@@ -541,6 +640,7 @@ func (s *Server) routesLegacy(r chi.Router, queries *db.Queries) {
 		r.Post("/schedules", schedulesHandler.Create)
 		r.Get("/schedules", schedulesHandler.List)
 		r.Get("/schedules/{id}", schedulesHandler.Get)
+		r.Patch("/schedules/{id}", schedulesHandler.Update)
 		r.Delete("/schedules/{id}", schedulesHandler.Cancel)
 		r.Post("/schedules/{id}/run", schedulesHandler.Run)
 
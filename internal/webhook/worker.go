@@ -42,15 +42,16 @@ var retryDelays = []time.Duration{
 // Note: Secret and URL are fetched from the database at retry time
 // instead of being stored in the message queue.
 type RetryJob struct {
-	WebhookID  string          `json:"webhook_id"`
-	EventID    string          `json:"event_id"`
-	OrgID      string          `json:"org_id"`
-	Topic      string          `json:"topic"`
-	Data       json.RawMessage `json:"data"`
-	Timestamp  time.Time       `json:"timestamp"`
-	Attempt    int             `json:"attempt"`
-	LastError  string          `json:"last_error"`
-	DeliveryID string          `json:"delivery_id"`
+	WebhookID  string            `json:"webhook_id"`
+	EventID    string            `json:"event_id"`
+	OrgID      string            `json:"org_id"`
+	Topic      string            `json:"topic"`
+	Data       json.RawMessage   `json:"data"`
+	Headers    map[string]string `json:"headers,omitempty"`
+	Timestamp  time.Time         `json:"timestamp"`
+	Attempt    int               `json:"attempt"`
+	LastError  string            `json:"last_error"`
+	DeliveryID string            `json:"delivery_id"`
 }
 
 // Worker handles webhook deliveries.
@@ -226,6 +227,7 @@ func (w *Worker) processRetry(ctx context.Context, msg jetstream.Msg) {
 		OrgID:     job.OrgID,
 		Topic:     job.Topic,
 		Data:      job.Data,
+		Headers:   job.Headers,
 		Timestamp: job.Timestamp,
 	}
 
@@ -269,6 +271,7 @@ func (w *Worker) deliver(ctx context.Context, wh *db.Webhook, event *domain.Even
 		ID:        event.ID,
 		Topic:     event.Topic,
 		Data:      event.Data,
+		Headers:   event.Headers,
 		Timestamp: event.Timestamp,
 	}
 
@@ -312,6 +315,7 @@ func (w *Worker) scheduleRetry(ctx context.Context, wh *db.Webhook, event *domai
 		OrgID:      event.OrgID,
 		Topic:      event.Topic,
 		Data:       event.Data,
+		Headers:    event.Headers,
 		Timestamp:  event.Timestamp,
 		Attempt:    attempt + 1,
 		LastError:  lastError,
@@ -360,6 +364,7 @@ func (w *Worker) moveToDLQ(ctx context.Context, job *RetryJob, lastError string)
 		OrgID:         job.OrgID,
 		OriginalTopic: job.Topic,
 		Data:          job.Data,
+		Headers:       job.Headers,
 		Timestamp:     job.Timestamp,
 		FailedAt:      time.Now(),
 		Attempts:      job.Attempt,
@@ -413,10 +418,11 @@ func (w *Worker) recordEventDelivery(ctx context.Context, webhookID pgtype.UUID,
 
 // WebhookPayload is the payload sent to webhook endpoints.
 type WebhookPayload struct {
-	ID        string          `json:"id"`
-	Topic     string          `json:"topic"`
-	Data      json.RawMessage `json:"data"`
-	Timestamp time.Time       `json:"timestamp"`
+	ID        string            `json:"id"`
+	Topic     string            `json:"topic"`
+	Data      json.RawMessage   `json:"data"`
+	Headers   map[string]string `json:"headers,omitempty"`
+	Timestamp time.Time         `json:"timestamp"`
 }
 
 // sign creates an HMAC-SHA256 signature.
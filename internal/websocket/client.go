@@ -51,6 +51,7 @@ type Client struct {
 	maxRetries      int
 	group           string
 	dlqPublisher    *nats.DLQPublisher
+	closed          bool // send is closed; set by the hub on unregister
 }
 
 // NewClient creates a new WebSocket client.
@@ -256,8 +257,10 @@ func (c *Client) deliverMessage(msg jetstream.Msg) {
 	// Get metadata for attempt count
 	meta, _ := msg.Metadata()
 	attempt := 1
+	var seq uint64
 	if meta != nil {
 		attempt = int(meta.NumDelivered)
+		seq = meta.Sequence.Stream
 	}
 
 	c.mu.RLock()
@@ -288,7 +291,7 @@ func (c *Client) deliverMessage(msg jetstream.Msg) {
 	}
 
 	// Send to client
-	eventMsg := NewEventMessage(event.ID, event.Topic, event.Data, event.Timestamp, attempt, maxRetries)
+	eventMsg := NewEventMessage(event.ID, event.Topic, event.Data, event.Headers, event.Timestamp, attempt, maxRetries, seq)
 	c.sendJSON(eventMsg)
 
 	if autoAck {
@@ -451,6 +454,7 @@ func (c *Client) moveToDLQ(pending *pendingMsg, group, reason string) {
 		ProjectID:     c.projectID,
 		OriginalTopic: pending.event.Topic,
 		Data:          pending.event.Data,
+		Headers:       pending.event.Headers,
 		Timestamp:     pending.event.Timestamp,
 		FailedAt:      time.Now().UTC(),
 		Attempts:      pending.attempt,
@@ -473,6 +477,14 @@ func (c *Client) sendJSON(v any) {
 		return
 	}
 
+	// Messages can still be produced after unregistering, such as answers
+	// to frames an SSE client POSTed meanwhile
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	if c.closed {
+		return
+	}
+
 	select {
 	case c.send <- data:
 	default:
@@ -480,6 +492,14 @@ func (c *Client) sendJSON(v any) {
 	}
 }
 
+// closeSend closes the outgoing message channel.
+func (c *Client) closeSend() {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.closed = true
+	close(c.send)
+}
+
 func (c *Client) sendError(code, message string) {
 	c.sendJSON(NewErrorMessage(code, message))
 }
@@ -9,6 +9,7 @@ import (
 type Hub struct {
 	mu         sync.RWMutex
 	clients    map[*Client]bool
+	byID       map[string]*Client
 	register   chan *Client
 	unregister chan *Client
 }
@@ -17,6 +18,7 @@ type Hub struct {
 func NewHub() *Hub {
 	return &Hub{
 		clients:    make(map[*Client]bool),
+		byID:       make(map[string]*Client),
 		register:   make(chan *Client),
 		unregister: make(chan *Client),
 	}
@@ -29,6 +31,7 @@ func (h *Hub) Run() {
 		case client := <-h.register:
 			h.mu.Lock()
 			h.clients[client] = true
+			h.byID[client.clientID] = client
 			h.mu.Unlock()
 			slog.Debug("client registered", "total", len(h.clients))
 
@@ -36,7 +39,8 @@ func (h *Hub) Run() {
 			h.mu.Lock()
 			if _, ok := h.clients[client]; ok {
 				delete(h.clients, client)
-				close(client.send)
+				delete(h.byID, client.clientID)
+				client.closeSend()
 			}
 			h.mu.Unlock()
 			slog.Debug("client unregistered", "total", len(h.clients))
@@ -49,6 +53,13 @@ func (h *Hub) Register(client *Client) {
 	h.register <- client
 }
 
+// Lookup returns the connected client with the given ID, or nil.
+func (h *Hub) Lookup(clientID string) *Client {
+	h.mu.RLock()
+	defer h.mu.RUnlock()
+	return h.byID[clientID]
+}
+
 // ClientCount returns the number of connected clients.
 func (h *Hub) ClientCount() int {
 	h.mu.RLock()
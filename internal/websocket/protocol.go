@@ -43,13 +43,15 @@ type ServerMessage struct {
 }
 
 type EventMessage struct {
-	Type        string          `json:"type"`
-	ID          string          `json:"id"`
-	Topic       string          `json:"topic"`
-	Data        json.RawMessage `json:"data"`
-	Timestamp   time.Time       `json:"timestamp"`
-	Attempt     int             `json:"attempt,omitempty"`
-	MaxAttempts int             `json:"max_attempts,omitempty"`
+	Type        string            `json:"type"`
+	ID          string            `json:"id"`
+	Topic       string            `json:"topic"`
+	Data        json.RawMessage   `json:"data"`
+	Headers     map[string]string `json:"headers,omitempty"`
+	Timestamp   time.Time         `json:"timestamp"`
+	Attempt     int               `json:"attempt,omitempty"`
+	MaxAttempts int               `json:"max_attempts,omitempty"`
+	Seq         uint64            `json:"seq,omitempty"` // Stream sequence
 }
 
 type SubscribedMessage struct {
@@ -69,15 +71,17 @@ type PongMessage struct {
 }
 
 // NewEventMessage creates an event message from domain event.
-func NewEventMessage(id, topic string, data json.RawMessage, timestamp time.Time, attempt, maxAttempts int) *EventMessage {
+func NewEventMessage(id, topic string, data json.RawMessage, headers map[string]string, timestamp time.Time, attempt, maxAttempts int, seq uint64) *EventMessage {
 	return &EventMessage{
 		Type:        "event",
 		ID:          id,
 		Topic:       topic,
 		Data:        data,
+		Headers:     headers,
 		Timestamp:   timestamp,
 		Attempt:     attempt,
 		MaxAttempts: maxAttempts,
+		Seq:         seq,
 	}
 }
 
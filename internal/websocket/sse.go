@@ -0,0 +1,72 @@
+package websocket
+
+import (
+	"context"
+	"fmt"
+	"io"
+	"net/http"
+	"time"
+
+	"github.com/filipexyz/notif/internal/db"
+	"github.com/filipexyz/notif/internal/nats"
+)
+
+// NewSSEClient creates a client whose server messages are streamed as
+// Server-Sent Events. Its client messages arrive as separate requests and
+// are passed to HandleFrame.
+func NewSSEClient(hub *Hub, apiKeyID, orgID, projectID string, dlqPublisher *nats.DLQPublisher, queries *db.Queries, clientID string, maxMessageSize int64) *Client {
+	return NewClient(hub, nil, apiKeyID, orgID, projectID, dlqPublisher, queries, clientID, maxMessageSize)
+}
+
+// Owns reports whether the client was opened for the given org and project.
+func (c *Client) Owns(orgID, projectID string) bool {
+	return c.orgID == orgID && c.projectID == projectID
+}
+
+// HandleFrame handles one client message, like a WebSocket text frame.
+func (c *Client) HandleFrame(ctx context.Context, data []byte, consumerMgr *nats.ConsumerManager) {
+	c.handleMessage(ctx, data, consumerMgr)
+}
+
+// StreamSSE writes server messages to w, one per event data field, until
+// the request ends. The subscription is then released like on a WebSocket
+// disconnect.
+func (c *Client) StreamSSE(ctx context.Context, w http.ResponseWriter) {
+	defer func() {
+		c.cleanup()
+		c.hub.unregister <- c
+	}()
+
+	rc := http.NewResponseController(w)
+	ticker := time.NewTicker(pingPeriod)
+	defer ticker.Stop()
+
+	for {
+		select {
+		case <-ctx.Done():
+			return
+
+		case message, ok := <-c.send:
+			if !ok {
+				return
+			}
+			rc.SetWriteDeadline(time.Now().Add(writeWait))
+			if _, err := fmt.Fprintf(w, "data: %s\n\n", message); err != nil {
+				return
+			}
+			if err := rc.Flush(); err != nil {
+				return
+			}
+
+		case <-ticker.C:
+			// Keeps proxies from closing an idle stream
+			rc.SetWriteDeadline(time.Now().Add(writeWait))
+			if _, err := io.WriteString(w, ": keep-alive\n\n"); err != nil {
+				return
+			}
+			if err := rc.Flush(); err != nil {
+				return
+			}
+		}
+	}
+}
@@ -0,0 +1,195 @@
+#!/usr/bin/env python3
+"""Generate the endpoint list of the Rust SDK's raw module from routes.go.
+
+Every route the server serves must have a name below; routes that are gone
+from routes.go drop out of the SDK. Routes served by only one server mode
+say so in their docs.
+
+Usage: python3 scripts/genraw.py [--check]
+"""
+
+import re
+import sys
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+ROUTES = ROOT / "internal/server/routes.go"
+RAW = ROOT / "sdk/rust/src/raw.rs"
+
+# Streaming routes, reached through the SDK's subscribe API instead
+SKIP = {
+    "GET /ws",
+    "GET /ws/terminal",
+    "POST /api/v1/sse",
+    "POST /api/v1/sse/{session}",
+}
+
+# Function name and doc of each route
+NAMES = {
+    "GET /health": ("health", "Whether the server is up."),
+    "GET /ready": ("ready", "Whether the server is ready to serve requests."),
+    "GET /healthz": ("healthz", "Health of the server and its dependencies."),
+    "GET /api/v1/bootstrap/status": ("bootstrap_status", "Whether the server was bootstrapped."),
+    "POST /api/v1/bootstrap": ("bootstrap", "Create the first organization and project of a self-hosted server."),
+    "GET /api/v1/whoami": ("whoami", "The organization, project and key of the caller."),
+    "POST /api/v1/orgs": ("create_org", "Create an organization; requires a dashboard session."),
+    "GET /api/v1/orgs": ("list_orgs", "List organizations; requires a dashboard session."),
+    "DELETE /api/v1/orgs/{id}": ("delete_org", "Delete an organization; requires a dashboard session."),
+    "GET /api/v1/orgs/{id}/limits": ("get_org_limits", "Limits of an organization; requires a dashboard session."),
+    "PUT /api/v1/orgs/{id}/limits": ("update_org_limits", "Change the limits of an organization; requires a dashboard session."),
+    "POST /api/v1/emit": ("emit", "Emit an event."),
+    "GET /api/v1/events": ("list_events", "List stored events."),
+    "GET /api/v1/events/stats": ("event_stats", "Number of events the project emitted."),
+    "GET /api/v1/events/{seq}": ("get_event", "A stored event by sequence number."),
+    "GET /api/v1/events/{id}/deliveries": ("list_event_deliveries", "Deliveries of an event to consumers and webhooks."),
+    "GET /api/v1/topics": ("list_topics", "Topics with stored events."),
+    "GET /api/v1/topics/{name}": ("get_topic", "A topic with stored events."),
+    "GET /api/v1/consumers/{group}/lag": ("consumer_lag", "Backlog of a consumer group."),
+    "POST /api/v1/webhooks": ("create_webhook", "Register a webhook."),
+    "GET /api/v1/webhooks": ("list_webhooks", "List webhooks."),
+    "GET /api/v1/webhooks/{id}": ("get_webhook", "A webhook."),
+    "PUT /api/v1/webhooks/{id}": ("update_webhook", "Change a webhook."),
+    "DELETE /api/v1/webhooks/{id}": ("delete_webhook", "Delete a webhook."),
+    "GET /api/v1/webhooks/{id}/deliveries": ("list_webhook_deliveries", "Deliveries of a webhook."),
+    "GET /api/v1/dlq": ("list_dlq", "List the dead letter queue."),
+    "GET /api/v1/dlq/{seq}": ("get_dlq", "An event of the dead letter queue."),
+    "POST /api/v1/dlq/{seq}/replay": ("replay_dlq", "Emit an event of the dead letter queue again."),
+    "DELETE /api/v1/dlq/{seq}": ("delete_dlq", "Remove an event from the dead letter queue."),
+    "POST /api/v1/dlq/replay-all": ("replay_all_dlq", "Emit every event of the dead letter queue again."),
+    "DELETE /api/v1/dlq/purge": ("purge_dlq", "Empty the dead letter queue."),
+    "POST /api/v1/schedules": ("create_schedule", "Schedule an event."),
+    "GET /api/v1/schedules": ("list_schedules", "List scheduled events."),
+    "GET /api/v1/schedules/{id}": ("get_schedule", "A scheduled event."),
+    "PATCH /api/v1/schedules/{id}": ("update_schedule", "Change a pending scheduled event."),
+    "DELETE /api/v1/schedules/{id}": ("cancel_schedule", "Cancel a scheduled event."),
+    "POST /api/v1/schedules/{id}/run": ("run_schedule", "Emit a scheduled event now."),
+    "POST /api/v1/schemas": ("create_schema", "Create a schema."),
+    "GET /api/v1/schemas": ("list_schemas", "List schemas."),
+    "GET /api/v1/schemas/for-topic/{topic}": ("get_schema_for_topic", "The schema that applies to a topic."),
+    "GET /api/v1/schemas/{name}": ("get_schema", "A schema."),
+    "PUT /api/v1/schemas/{name}": ("update_schema", "Change a schema."),
+    "DELETE /api/v1/schemas/{name}": ("delete_schema", "Delete a schema."),
+    "POST /api/v1/schemas/{name}/versions": ("create_schema_version", "Add a version to a schema."),
+    "GET /api/v1/schemas/{name}/versions": ("list_schema_versions", "List the versions of a schema."),
+    "GET /api/v1/schemas/{name}/versions/{version}": ("get_schema_version", "A version of a schema."),
+    "POST /api/v1/schemas/{name}/validate": ("validate_schema", "Validate a payload against a schema."),
+    "GET /api/v1/audit": ("list_audit", "List the audit log."),
+    "GET /api/v1/stats/overview": ("stats_overview", "Overview of the project's activity."),
+    "GET /api/v1/stats/events": ("stats_events", "Event counts of the organization, in total and recently."),
+    "GET /api/v1/stats/webhooks": ("stats_webhooks", "Webhook delivery statistics."),
+    "GET /api/v1/stats/dlq": ("stats_dlq", "Dead letter queue statistics."),
+    "GET /api/v1/stats/schedules": ("stats_schedules", "Scheduled event statistics."),
+    "POST /api/v1/api-keys": ("create_api_key", "Create an API key; requires a dashboard session."),
+    "GET /api/v1/api-keys": ("list_api_keys", "List API keys; requires a dashboard session."),
+    "DELETE /api/v1/api-keys/{id}": ("revoke_api_key", "Revoke an API key; requires a dashboard session."),
+    "POST /api/v1/projects": ("create_project", "Create a project; requires a dashboard session."),
+    "GET /api/v1/projects": ("list_projects", "List projects; requires a dashboard session."),
+    "GET /api/v1/projects/{id}": ("get_project", "A project; requires a dashboard session."),
+    "PUT /api/v1/projects/{id}": ("update_project", "Change a project; requires a dashboard session."),
+    "DELETE /api/v1/projects/{id}": ("delete_project", "Delete a project; requires a dashboard session."),
+}
+
+MODES = {"routesMultiAccount": "multi", "routesLegacy": "legacy"}
+
+FUNC = re.compile(r"^func \(s \*Server\) (\w+)\(")
+ROUTE = re.compile(r'^(\s*)r\.Route\("([^"]*)"')
+HANDLE = re.compile(r'^\s*r\.(Get|Post|Put|Patch|Delete)\("([^"]*)",\s*(.*)$')
+CLOSE = re.compile(r"^(\s*)\}\)")
+
+
+def parse():
+    """Return the routes in order, each with the modes serving it."""
+    order = []  # (key, modes) in order of registration
+    served = {}
+    func = None
+    modes = {"multi", "legacy"}
+    prefixes = []  # (indent, prefix) of enclosing r.Route calls
+
+    for line in ROUTES.read_text().splitlines():
+        m = FUNC.match(line)
+        if m:
+            func = m.group(1)
+            modes = {MODES[func]} if func in MODES else {"multi", "legacy"}
+            prefixes = []
+            continue
+        # The health checks of routes() branch on the mode
+        if func == "routes":
+            if "if s.pool != nil {" in line:
+                modes = {"multi"}
+            elif line.strip() == "} else {":
+                modes = {"legacy"}
+            elif line.startswith("\t}") and modes != {"multi", "legacy"}:
+                modes = {"multi", "legacy"}
+
+        m = ROUTE.match(line)
+        if m:
+            prefixes.append((m.group(1), m.group(2)))
+            continue
+        m = CLOSE.match(line)
+        if m and prefixes and prefixes[-1][0] == m.group(1):
+            prefixes.pop()
+            continue
+
+        m = HANDLE.match(line)
+        if not m:
+            continue
+        method, path, handler = m.groups()
+        path = "".join(p for _, p in prefixes) + path
+        if len(path) > 1:
+            path = path.rstrip("/")
+        key = f"{method.upper()} {path}"
+        if key not in served:
+            served[key] = set()
+            order.append(key)
+        # Registered so the route exists, but not served in this mode
+        if "notImplemented" not in handler:
+            served[key] |= modes
+
+    return [(key, served[key]) for key in order if served[key]]
+
+
+def generate(routes):
+    missing = [key for key, _ in routes if key not in NAMES and key not in SKIP]
+    if missing:
+        sys.exit("routes without a name in scripts/genraw.py:\n  " + "\n  ".join(missing))
+
+    lines = []
+    group = None
+    for key, modes in routes:
+        if key in SKIP:
+            continue
+        method, path = key.split(" ", 1)
+        name, doc = NAMES[key]
+        if modes == {"multi"}:
+            doc += " Multi-account servers only."
+        elif modes == {"legacy"}:
+            doc += " Single-account servers only."
+
+        segments = path.strip("/").split("/")
+        key_group = segments[2] if segments[:2] == ["api", "v1"] else ""
+        if group is not None and key_group != group:
+            lines.append("")
+        group = key_group
+
+        params = ", ".join(re.findall(r"\{(\w+)\}", path))
+        lines.append(f"    /// {doc}")
+        lines.append(f'    {name}({params}) => {method} "{path}";')
+    return "\n".join(lines) + "\n"
+
+
+def main():
+    body = generate(parse())
+    source = RAW.read_text()
+    start = source.index("endpoints! {\n") + len("endpoints! {\n")
+    end = source.rindex("}\n")
+    updated = source[:start] + body + source[end:]
+
+    if "--check" in sys.argv:
+        if updated != source:
+            sys.exit(f"{RAW.relative_to(ROOT)} is out of date; run python3 scripts/genraw.py")
+        return
+    RAW.write_text(updated)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,91 @@
+//! Derive macro for `notifsh::NotifEvent`.
+//!
+//! Use it through the `derive` feature of `notifsh`, which re-exports it
+//! next to the trait.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, LitStr};
+
+/// Maximum topic length accepted by the server.
+const MAX_TOPIC_LEN: usize = 255;
+
+/// Bind a type to the topic given in `#[notif(topic = "...")]`.
+///
+/// The topic is checked at compile time: it must be a concrete topic
+/// name, without wildcards.
+#[proc_macro_derive(NotifEvent, attributes(notif))]
+pub fn derive_notif_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let topic = topic(input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::notifsh::NotifEvent for #name #type_generics #where_clause {
+            const TOPIC: &'static str = #topic;
+        }
+    })
+}
+
+/// The validated `topic` of the `#[notif(...)]` attribute.
+fn topic(input: &DeriveInput) -> syn::Result<LitStr> {
+    let mut topic = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("notif"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("topic") {
+                topic = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `topic = \"...\"`"))
+            }
+        })?;
+    }
+    let topic = topic.ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "missing #[notif(topic = \"...\")] attribute")
+    })?;
+    if let Err(reason) = check_topic(&topic.value()) {
+        return Err(syn::Error::new_spanned(
+            &topic,
+            format!("invalid topic: {}", reason),
+        ));
+    }
+    Ok(topic)
+}
+
+/// Apply the rules of `notifsh::Topic::name`.
+fn check_topic(topic: &str) -> Result<(), String> {
+    if topic.is_empty() {
+        return Err("topic is required".to_string());
+    }
+    if topic.len() > MAX_TOPIC_LEN {
+        return Err(format!("topic too long, max {} chars", MAX_TOPIC_LEN));
+    }
+    if topic.starts_with('$') {
+        return Err("topic cannot start with $".to_string());
+    }
+    for segment in topic.split('.') {
+        if segment.is_empty() {
+            return Err(
+                "empty segment (topics cannot start or end with '.' or contain '..')".to_string(),
+            );
+        }
+        if segment.chars().any(char::is_whitespace) {
+            return Err("topic cannot contain whitespace".to_string());
+        }
+        if segment.contains(['*', '>']) {
+            return Err("wildcards are not allowed in topic names".to_string());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+//! Receiving events pushed by webhooks in an axum service (`axum` feature).
+//!
+//! The server POSTs each event as JSON, signed with the webhook secret in
+//! the `X-Notif-Signature` header (`sha256=<hex HMAC-SHA256 of the body>`).
+//! [`handler`] turns a callback into a route that verifies the signature,
+//! decodes the [`Event`] and answers with a status the server understands:
+//! 2xx settles the delivery, anything else makes the server retry it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use axum::Router;
+//!
+//! # fn example(secret: String) {
+//! let app: Router = Router::new().route(
+//!     "/hooks/notif",
+//!     notifsh::axum::handler(secret, |event| async move {
+//!         println!("{}: {}", event.topic, event.data);
+//!         Ok::<_, std::convert::Infallible>(())
+//!     }),
+//! );
+//! # }
+//! ```
+//!
+//! Handlers that need other extractors can take [`Push`] instead, with the
+//! secret provided as [`PushSecret`] state:
+//!
+//! ```no_run
+//! use axum::http::StatusCode;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use notifsh::axum::{Push, PushSecret};
+//!
+//! async fn receive(Push(event): Push) -> StatusCode {
+//!     println!("{}", event.id);
+//!     StatusCode::NO_CONTENT
+//! }
+//!
+//! # fn example(secret: String) {
+//! let app: Router = Router::new()
+//!     .route("/hooks/notif", post(receive))
+//!     .with_state(PushSecret::new(secret));
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use ::axum::body::Bytes;
+use ::axum::extract::{FromRef, FromRequest, Request};
+use ::axum::http::{HeaderMap, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use ::axum::routing::{post, MethodRouter};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::types::Event;
+
+/// Header carrying the delivery signature.
+pub const SIGNATURE_HEADER: &str = "x-notif-signature";
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// Body of a webhook delivery.
+#[derive(Deserialize)]
+struct Payload {
+    id: String,
+    topic: String,
+    #[serde(default)]
+    data: serde_json::Value,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    timestamp: DateTime<Utc>,
+}
+
+/// Check a delivery signature against the webhook secret in constant time.
+///
+/// `signature` is the `X-Notif-Signature` header value.
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(digest) = signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(decode_hex)
+    else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&digest).is_ok()
+}
+
+/// A route that verifies pushed events and hands them to `callback`.
+///
+/// Responds `204 No Content` when the callback returns `Ok` and
+/// `500 Internal Server Error` when it returns `Err`, so the server
+/// retries the delivery. Requests with a missing or wrong signature get
+/// `401 Unauthorized` and malformed bodies `400 Bad Request`.
+pub fn handler<S, F, Fut, E>(secret: impl Into<String>, callback: F) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+    F: Fn(Event) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), E>> + Send,
+    E: fmt::Display,
+{
+    let secret = PushSecret::new(secret);
+    post(move |headers: HeaderMap, body: Bytes| async move {
+        let event = match decode(&secret, &headers, &body) {
+            Ok(event) => event,
+            Err(rejection) => return rejection.into_response(),
+        };
+        match callback(event).await {
+            Ok(()) => StatusCode::NO_CONTENT.into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    })
+}
+
+/// Webhook secret that the [`Push`] extractor verifies signatures with.
+///
+/// Use it as router state, or derive `FromRef` for it from your own state.
+#[derive(Clone)]
+pub struct PushSecret(Arc<str>);
+
+impl PushSecret {
+    /// Wrap the secret returned by `Notif::create_webhook`.
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(Arc::from(secret.into()))
+    }
+}
+
+impl fmt::Debug for PushSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PushSecret(..)")
+    }
+}
+
+/// Extractor for a verified pushed event.
+///
+/// The event is not tied to a subscription: acking it does nothing, and
+/// the response status settles the delivery instead.
+#[derive(Debug, Clone)]
+pub struct Push(pub Event);
+
+impl<S> FromRequest<S> for Push
+where
+    S: Send + Sync,
+    PushSecret: FromRef<S>,
+{
+    type Rejection = PushRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let secret = PushSecret::from_ref(state);
+        let headers = req.headers().clone();
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| PushRejection::InvalidBody(e.body_text()))?;
+        decode(&secret, &headers, &body).map(Push)
+    }
+}
+
+/// Why a pushed request was rejected.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PushRejection {
+    /// The request has no `X-Notif-Signature` header.
+    MissingSignature,
+    /// The signature does not match the body and secret.
+    InvalidSignature,
+    /// The body could not be read or is not an event.
+    InvalidBody(String),
+}
+
+impl fmt::Display for PushRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushRejection::MissingSignature => f.write_str("missing webhook signature"),
+            PushRejection::InvalidSignature => f.write_str("invalid webhook signature"),
+            PushRejection::InvalidBody(reason) => write!(f, "invalid webhook body: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PushRejection {}
+
+impl IntoResponse for PushRejection {
+    fn into_response(self) -> Response {
+        let status = match self {
+            PushRejection::MissingSignature | PushRejection::InvalidSignature => {
+                StatusCode::UNAUTHORIZED
+            }
+            PushRejection::InvalidBody(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+fn decode(secret: &PushSecret, headers: &HeaderMap, body: &[u8]) -> Result<Event, PushRejection> {
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(PushRejection::MissingSignature)?;
+    if !verify_signature(&secret.0, body, signature) {
+        return Err(PushRejection::InvalidSignature);
+    }
+
+    let payload: Payload =
+        serde_json::from_slice(body).map_err(|e| PushRejection::InvalidBody(e.to_string()))?;
+    Ok(Event {
+        id: payload.id,
+        topic: payload.topic,
+        data: payload.data,
+        headers: payload.headers,
+        timestamp: payload.timestamp,
+        attempt: 1,
+        max_attempts: 1,
+        seq: None,
+        ack_tx: None,
+    })
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,62 @@
+//! Binary payloads carried as base64 in the JSON event data.
+//!
+//! The payload is base64-encoded into a JSON string and marked with the
+//! `notif-encoding` header, next to its `content-type`, so subscribing SDK
+//! clients can hand back the original bytes.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+
+use crate::types::{EmitOptions, Event};
+
+pub(crate) const ENCODING_HEADER: &str = "notif-encoding";
+pub(crate) const CONTENT_TYPE_HEADER: &str = "content-type";
+pub(crate) const BASE64: &str = "base64";
+
+/// Encode `data` for emitting, marking `options` as carrying bytes.
+pub(crate) fn encode(
+    data: &[u8],
+    content_type: &str,
+    options: EmitOptions,
+) -> (String, EmitOptions) {
+    let options = options
+        .header(ENCODING_HEADER, BASE64)
+        .header(CONTENT_TYPE_HEADER, content_type);
+    (STANDARD.encode(data), options)
+}
+
+impl Event {
+    /// The payload bytes of an event emitted with `Notif::emit_bytes`.
+    ///
+    /// Returns `None` for JSON events and for data that is not valid
+    /// base64.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn example(event: notifsh::Event) {
+    /// if let Some(png) = event.data_bytes() {
+    ///     std::fs::write("thumbnail.png", png).unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub fn data_bytes(&self) -> Option<Vec<u8>> {
+        if !self.is_binary() {
+            return None;
+        }
+        match &self.data {
+            Value::String(encoded) => STANDARD.decode(encoded).ok(),
+            _ => None,
+        }
+    }
+
+    /// Media type of the payload, from the `content-type` header.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get(CONTENT_TYPE_HEADER).map(String::as_str)
+    }
+
+    pub(crate) fn is_binary(&self) -> bool {
+        self.headers.get(ENCODING_HEADER).map(String::as_str) == Some(BASE64)
+    }
+}
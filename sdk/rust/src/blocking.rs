@@ -0,0 +1,512 @@
+//! Blocking client for use outside of an async runtime.
+//!
+//! Wraps the async [`crate::Notif`] in an internal Tokio runtime, so CLI
+//! tools and build scripts can emit and subscribe without going async.
+//!
+//! The blocking client must not be used from within an async runtime.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::blocking::Notif;
+//! use serde_json::json;
+//!
+//! fn main() -> notifsh::Result<()> {
+//!     let client = Notif::from_env()?;
+//!     client.emit("builds.finished", json!({"ok": true}))?;
+//!
+//!     for event in client.subscribe(&["builds.*"])? {
+//!         let event = event?;
+//!         println!("{}: {}", event.topic, event.data);
+//!         event.ack()?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::Serialize;
+use tokio::runtime::Runtime;
+
+use crate::error::{NotifError, Result};
+use crate::interceptor::Interceptor;
+use crate::retry::RetryPolicy;
+use crate::types::{
+    CreateScheduleResponse, EmitOptions, EmitResponse, Identity, ListSchedulesResponse,
+    RunScheduleResponse, Schedule, ScheduleFilter, SubscribeOptions, UpdateSchedule, Webhook,
+};
+
+/// Builder for creating a blocking Notif client.
+#[derive(Debug, Clone)]
+pub struct NotifBuilder {
+    inner: crate::NotifBuilder,
+}
+
+impl NotifBuilder {
+    /// Create a new builder with the given API key.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            inner: crate::NotifBuilder::new(api_key),
+        }
+    }
+
+    /// Create a builder configured from the environment.
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            inner: crate::NotifBuilder::from_env()?,
+        })
+    }
+
+    /// Create a builder configured from a profile in the profile file.
+    pub fn from_profile(name: &str) -> Result<Self> {
+        Ok(Self {
+            inner: crate::NotifBuilder::from_profile(name)?,
+        })
+    }
+
+    /// Create a new builder taking API keys from `provider`.
+    pub fn with_credentials(provider: impl crate::CredentialsProvider) -> Self {
+        Self {
+            inner: crate::NotifBuilder::with_credentials(provider),
+        }
+    }
+
+    /// Set the server URL.
+    pub fn server(mut self, server: impl Into<String>) -> Self {
+        self.inner = self.inner.server(server);
+        self
+    }
+
+    /// Set the request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.inner = self.inner.timeout(timeout);
+        self
+    }
+
+    /// Set the retry policy for HTTP operations.
+    pub fn retries(mut self, retry: RetryPolicy) -> Self {
+        self.inner = self.inner.retries(retry);
+        self
+    }
+
+    /// Add an interceptor that observes or modifies emits and received events.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        self.inner = self.inner.with_interceptor(interceptor);
+        self
+    }
+
+    /// Set the largest emit request body, in bytes (default: 256 KiB).
+    pub fn max_payload_size(mut self, bytes: usize) -> Self {
+        self.inner = self.inner.max_payload_size(bytes);
+        self
+    }
+
+    /// Split emits above the maximum payload size into parts (default: off).
+    pub fn chunking(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.chunking(enabled);
+        self
+    }
+
+    /// Exchange payloads as MessagePack when the server supports it (default: off).
+    #[cfg(feature = "msgpack")]
+    pub fn msgpack(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.msgpack(enabled);
+        self
+    }
+
+    /// Delay emits to stay under a rate instead of running into 429s (default: off).
+    pub fn throttle_emits(mut self, throttle: crate::Throttle) -> Self {
+        self.inner = self.inner.throttle_emits(throttle);
+        self
+    }
+
+    /// Restrict the operations the client may perform (default: read-write).
+    pub fn mode(mut self, mode: crate::ClientMode) -> Self {
+        self.inner = self.inner.mode(mode);
+        self
+    }
+
+    /// Add a header to every HTTP request and WebSocket handshake.
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.inner = self.inner.default_header(name, value);
+        self
+    }
+
+    /// Send HTTP requests and WebSocket connections through a proxy.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.inner = self.inner.proxy(url);
+        self
+    }
+
+    /// Connect directly, ignoring the proxy environment variables.
+    pub fn no_proxy(mut self) -> Self {
+        self.inner = self.inner.no_proxy();
+        self
+    }
+
+    /// Trust a PEM-encoded root certificate in addition to the system ones.
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.inner = self.inner.add_root_certificate(pem);
+        self
+    }
+
+    /// Present a client certificate (PEM chain and PKCS#8 PEM key).
+    pub fn client_certificate(
+        mut self,
+        certificate: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.inner = self.inner.client_certificate(certificate, key);
+        self
+    }
+
+    /// Build the blocking Notif client.
+    pub fn build(self) -> Result<Notif> {
+        Notif::wrap(self.inner.build()?)
+    }
+
+    /// Build the blocking client and check the key against the server.
+    pub fn connect(self) -> Result<Notif> {
+        let client = self.build()?;
+        client.ping()?;
+        Ok(client)
+    }
+}
+
+/// A blocking notif.sh client.
+#[derive(Clone)]
+pub struct Notif {
+    inner: crate::Notif,
+    rt: Arc<Runtime>,
+}
+
+impl Notif {
+    /// Create a new client from environment variables.
+    ///
+    /// Reads the same variables and profiles as `crate::Notif::from_env`.
+    pub fn from_env() -> Result<Self> {
+        Self::wrap(crate::Notif::from_env()?)
+    }
+
+    /// Create a new client from a named profile.
+    pub fn from_profile(name: &str) -> Result<Self> {
+        Self::wrap(crate::Notif::from_profile(name)?)
+    }
+
+    /// Create a new builder with the given API key.
+    pub fn builder(api_key: impl Into<String>) -> NotifBuilder {
+        NotifBuilder::new(api_key)
+    }
+
+    fn wrap(inner: crate::Notif) -> Result<Self> {
+        // A worker thread keeps subscriptions and acks flowing between calls.
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("notifsh-blocking")
+            .enable_all()
+            .build()
+            .map_err(|e| NotifError::connection(format!("failed to start runtime: {}", e)))?;
+
+        Ok(Self {
+            inner,
+            rt: Arc::new(rt),
+        })
+    }
+
+    /// Get the configured server URL.
+    pub fn server_url(&self) -> &str {
+        self.inner.server_url()
+    }
+
+    /// Check that the server is reachable and accepts the API key.
+    pub fn ping(&self) -> Result<Duration> {
+        self.rt.block_on(self.inner.ping())
+    }
+
+    /// Look up the organization, project and key the API key belongs to.
+    pub fn whoami(&self) -> Result<Identity> {
+        self.rt.block_on(self.inner.whoami())
+    }
+
+    /// The rate limit the server last reported, if any response carried one.
+    pub fn rate_limit_status(&self) -> Option<crate::RateLimitStatus> {
+        self.inner.rate_limit_status()
+    }
+
+    /// Get the underlying async client.
+    pub fn as_async(&self) -> &crate::Notif {
+        &self.inner
+    }
+
+    /// Emit an event to a topic.
+    pub fn emit<T: Serialize>(&self, topic: &str, data: T) -> Result<EmitResponse> {
+        self.rt.block_on(self.inner.emit(topic, data))
+    }
+
+    /// Emit an event with custom options such as headers.
+    pub fn emit_with_options<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        self.rt
+            .block_on(self.inner.emit_with_options(topic, data, options))
+    }
+
+    /// Emit a binary payload tagged with `content_type`.
+    pub fn emit_bytes(
+        &self,
+        topic: &str,
+        data: impl AsRef<[u8]>,
+        content_type: &str,
+    ) -> Result<EmitResponse> {
+        self.rt
+            .block_on(self.inner.emit_bytes(topic, data, content_type))
+    }
+
+    /// Emit a binary payload with custom options such as headers.
+    pub fn emit_bytes_with_options(
+        &self,
+        topic: &str,
+        data: impl AsRef<[u8]>,
+        content_type: &str,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        self.rt.block_on(
+            self.inner
+                .emit_bytes_with_options(topic, data, content_type, options),
+        )
+    }
+
+    /// Emit a JSON Patch against a document (see [`crate::Notif::emit_patch`]).
+    pub fn emit_patch(&self, topic: &str, patch: &crate::JsonPatch) -> Result<EmitResponse> {
+        self.rt.block_on(self.inner.emit_patch(topic, patch))
+    }
+
+    /// Subscribe to one or more topics.
+    ///
+    /// Returns an iterator of events.
+    pub fn subscribe(&self, topics: &[&str]) -> Result<EventStream> {
+        self.subscribe_with_options(topics, SubscribeOptions::new())
+    }
+
+    /// Subscribe to topics with custom options.
+    pub fn subscribe_with_options(
+        &self,
+        topics: &[&str],
+        options: SubscribeOptions,
+    ) -> Result<EventStream> {
+        let inner = self
+            .rt
+            .block_on(self.inner.subscribe_with_options(topics, options))?;
+        Ok(EventStream {
+            inner,
+            rt: self.rt.clone(),
+        })
+    }
+
+    /// Schedule an event to be emitted at a future time.
+    pub fn schedule<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        scheduled_for: Option<DateTime<Utc>>,
+        in_duration: Option<&str>,
+    ) -> Result<CreateScheduleResponse> {
+        self.rt
+            .block_on(self.inner.schedule(topic, data, scheduled_for, in_duration))
+    }
+
+    /// List scheduled events.
+    pub fn list_schedules(
+        &self,
+        status: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<ListSchedulesResponse> {
+        self.rt
+            .block_on(self.inner.list_schedules(status, limit, offset))
+    }
+
+    /// Walk every scheduled event matching `filter`, fetching pages as needed.
+    pub fn schedules(&self, filter: ScheduleFilter) -> impl Iterator<Item = Result<Schedule>> {
+        let rt = self.rt.clone();
+        let mut stream = Box::pin(self.inner.schedules(filter));
+        std::iter::from_fn(move || rt.block_on(stream.next()))
+    }
+
+    /// Get a specific scheduled event.
+    pub fn get_schedule(&self, id: &str) -> Result<Schedule> {
+        self.rt.block_on(self.inner.get_schedule(id))
+    }
+
+    /// Change a pending scheduled event.
+    pub fn update_schedule(&self, id: &str, update: UpdateSchedule) -> Result<Schedule> {
+        self.rt.block_on(self.inner.update_schedule(id, update))
+    }
+
+    /// Cancel a pending scheduled event.
+    pub fn cancel_schedule(&self, id: &str) -> Result<()> {
+        self.rt.block_on(self.inner.cancel_schedule(id))
+    }
+
+    /// Execute a scheduled event immediately.
+    pub fn run_schedule(&self, id: &str) -> Result<RunScheduleResponse> {
+        self.rt.block_on(self.inner.run_schedule(id))
+    }
+
+    /// Register an HTTP endpoint that the server pushes matching events to.
+    pub fn create_webhook(
+        &self,
+        topics: &[&str],
+        url: &str,
+        secret: Option<&str>,
+    ) -> Result<Webhook> {
+        self.rt
+            .block_on(self.inner.create_webhook(topics, url, secret))
+    }
+
+    /// List the webhooks registered for the project.
+    pub fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.rt.block_on(self.inner.list_webhooks())
+    }
+
+    /// Delete a webhook.
+    pub fn delete_webhook(&self, id: &str) -> Result<()> {
+        self.rt.block_on(self.inner.delete_webhook(id))
+    }
+}
+
+/// A blocking iterator of events from a subscription.
+pub struct EventStream {
+    inner: crate::EventStream,
+    rt: Arc<Runtime>,
+}
+
+impl EventStream {
+    /// Close the subscription cleanly (see [`crate::EventStream::close`]).
+    pub fn close(self) -> Result<()> {
+        self.rt.block_on(self.inner.close())
+    }
+
+    /// Close after in-flight events are settled
+    /// (see [`crate::EventStream::close_gracefully`]).
+    pub fn close_gracefully(self, grace: Duration) -> Result<()> {
+        self.rt.block_on(self.inner.close_gracefully(grace))
+    }
+
+    /// ID of the last event this stream yielded.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.inner.last_event_id()
+    }
+
+    /// Position of the last event this stream yielded
+    /// (see [`crate::EventStream::cursor`]).
+    pub fn cursor(&self) -> Option<&crate::EventCursor> {
+        self.inner.cursor()
+    }
+
+    /// Current health of the subscription's connection.
+    pub fn state(&self) -> crate::ConnectionState {
+        self.inner.state()
+    }
+
+    /// Collect events until none arrives for `idle`
+    /// (see [`crate::EventStream::drain`]).
+    pub fn drain(&mut self, idle: Duration) -> Result<Vec<Event>> {
+        let rt = self.rt.clone();
+        let batch = rt.block_on(self.inner.drain(idle))?;
+        Ok(batch
+            .into_iter()
+            .map(|inner| Event {
+                inner,
+                rt: rt.clone(),
+            })
+            .collect())
+    }
+
+    /// Subscribe to more topics (see [`crate::EventStream::add_topics`]).
+    pub fn add_topics(&self, topics: &[&str]) -> Result<()> {
+        self.rt.block_on(self.inner.add_topics(topics))
+    }
+
+    /// Unsubscribe from some topics (see [`crate::EventStream::remove_topics`]).
+    pub fn remove_topics(&self, topics: &[&str]) -> Result<()> {
+        self.rt.block_on(self.inner.remove_topics(topics))
+    }
+
+    /// Stop reading events, keeping the subscription
+    /// (see [`crate::EventStream::pause`]).
+    pub fn pause(&mut self) {
+        self.inner.pause();
+    }
+
+    /// Start reading events again (see [`crate::EventStream::resume`]).
+    pub fn resume(&mut self) {
+        self.inner.resume();
+    }
+
+    /// Counters of the subscription (see [`crate::EventStream::stats`]).
+    pub fn stats(&self) -> crate::StreamStats {
+        self.inner.stats()
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rt = self.rt.clone();
+        let item = rt.block_on(self.inner.next())?;
+        Some(item.map(|inner| Event { inner, rt }))
+    }
+}
+
+/// An event received from a blocking subscription.
+///
+/// Derefs to [`crate::Event`] for access to the event fields.
+#[derive(Debug, Clone)]
+pub struct Event {
+    inner: crate::Event,
+    rt: Arc<Runtime>,
+}
+
+impl Event {
+    /// Acknowledge the event.
+    ///
+    /// This is a no-op if auto_ack is enabled.
+    pub fn ack(&self) -> Result<()> {
+        self.rt.block_on(self.inner.ack())
+    }
+
+    /// Negatively acknowledge the event.
+    ///
+    /// This is a no-op if auto_ack is enabled.
+    pub fn nack(&self, retry_in: Option<Duration>) -> Result<()> {
+        self.rt.block_on(self.inner.nack(retry_in))
+    }
+
+    /// Negatively acknowledge the event with a delay such as `"5m"`.
+    pub fn nack_str(&self, retry_in: &str) -> Result<()> {
+        self.rt.block_on(self.inner.nack_str(retry_in))
+    }
+
+    /// Take the underlying async event.
+    pub fn into_inner(self) -> crate::Event {
+        self.inner
+    }
+}
+
+impl Deref for Event {
+    type Target = crate::Event;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
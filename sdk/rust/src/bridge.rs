@@ -0,0 +1,169 @@
+//! Gluing in-process tokio channels to topics.
+//!
+//! Code that already passes messages over `tokio::sync::mpsc` or
+//! `broadcast` channels can be spread across machines by forwarding a
+//! channel into a topic with [`channel_to_topic`] and feeding a topic back
+//! into a channel with [`topic_to_channel`] or [`topic_to_broadcast`].
+//!
+//! Bridging the same topic both ways through one channel loops every
+//! message back into it; use separate channels or topics per direction.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::bridge;
+//! use notifsh::Notif;
+//! use serde::{Deserialize, Serialize};
+//! use tokio::sync::mpsc;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct JobDone {
+//!     id: u64,
+//! }
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! // Machine A: what used to be an in-process channel now goes to the hub
+//! let (tx, rx) = mpsc::channel::<JobDone>(64);
+//! bridge::channel_to_topic(&client, rx, "jobs.done");
+//! tx.send(JobDone { id: 1 }).await.ok();
+//!
+//! // Machine B: receive it as if it were local
+//! let mut done = bridge::topic_to_channel::<JobDone>(&client, "jobs.done", 64).await?;
+//! while let Some(job) = done.recv().await {
+//!     println!("job {} done", job.id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+use crate::client::Notif;
+use crate::error::Result;
+
+/// The receiving half of a tokio channel, as taken by [`channel_to_topic`].
+///
+/// Implemented for `mpsc` bounded and unbounded receivers, and for
+/// `broadcast` receivers, which skip messages they lagged behind on.
+pub trait ChannelReceiver<T>: Send + 'static {
+    /// The next message, or `None` once every sender is gone.
+    fn recv(&mut self) -> BoxFuture<'_, Option<T>>;
+}
+
+impl<T: Send + 'static> ChannelReceiver<T> for mpsc::Receiver<T> {
+    fn recv(&mut self) -> BoxFuture<'_, Option<T>> {
+        Box::pin(mpsc::Receiver::recv(self))
+    }
+}
+
+impl<T: Send + 'static> ChannelReceiver<T> for mpsc::UnboundedReceiver<T> {
+    fn recv(&mut self) -> BoxFuture<'_, Option<T>> {
+        Box::pin(mpsc::UnboundedReceiver::recv(self))
+    }
+}
+
+impl<T: Clone + Send + 'static> ChannelReceiver<T> for broadcast::Receiver<T> {
+    fn recv(&mut self) -> BoxFuture<'_, Option<T>> {
+        Box::pin(async move {
+            loop {
+                match broadcast::Receiver::recv(self).await {
+                    Ok(message) => return Some(message),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+/// Emit every message received on `receiver` to `topic`.
+///
+/// Runs until the channel closes, or until an emit fails after the
+/// client's retries; the task returns that error. Messages are emitted one
+/// at a time, in order.
+pub fn channel_to_topic<T, R>(
+    client: &Notif,
+    mut receiver: R,
+    topic: &str,
+) -> JoinHandle<Result<()>>
+where
+    T: Serialize + Send + 'static,
+    R: ChannelReceiver<T>,
+{
+    let client = client.clone();
+    let topic = topic.to_string();
+    tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            client.emit(&topic, message).await?;
+        }
+        Ok(())
+    })
+}
+
+/// Subscribe to `pattern` and deliver each payload, decoded into `T`,
+/// to the returned channel of `capacity` messages.
+///
+/// The subscription applies backpressure when the channel is full and
+/// closes once the receiver is dropped. Payloads that do not decode into
+/// `T` are skipped and reported to the client's drop handler.
+pub async fn topic_to_channel<T>(
+    client: &Notif,
+    pattern: &str,
+    capacity: usize,
+) -> Result<mpsc::Receiver<T>>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let mut stream = client.subscribe_typed::<T>(&[pattern]).await?;
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            let Ok(event) = event else {
+                continue;
+            };
+            if tx.send(event.data).await.is_err() {
+                break;
+            }
+        }
+        let _ = stream.close().await;
+    });
+    Ok(rx)
+}
+
+/// Subscribe to `pattern` and publish each payload, decoded into `T`, on
+/// a broadcast channel of `capacity` messages.
+///
+/// Call `subscribe` on the returned sender for as many receivers as
+/// needed. Keep the sender alive: the subscription closes once every
+/// clone of it is dropped.
+pub async fn topic_to_broadcast<T>(
+    client: &Notif,
+    pattern: &str,
+    capacity: usize,
+) -> Result<broadcast::Sender<T>>
+where
+    T: DeserializeOwned + Clone + Send + 'static,
+{
+    let mut stream = client.subscribe_typed::<T>(&[pattern]).await?;
+    let (tx, _) = broadcast::channel(capacity.max(1));
+    let sender = tx.downgrade();
+    tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            let Ok(event) = event else {
+                continue;
+            };
+            let Some(tx) = sender.upgrade() else {
+                break;
+            };
+            // No receivers right now is not a reason to stop
+            let _ = tx.send(event.data);
+        }
+        let _ = stream.close().await;
+    });
+    Ok(tx)
+}
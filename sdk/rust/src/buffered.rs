@@ -0,0 +1,420 @@
+//! Emits that are queued while the server is unreachable.
+//!
+//! A [`BufferedEmitter`] accepts emits right away and sends them in order
+//! from a background task, retrying transient failures until connectivity
+//! returns. With [`Buffering::persist`] the queue is also kept in an
+//! append-only journal file, so events survive a restart of the process.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Notify};
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::{Backoff, BackpressurePolicy, ConnectionState, EmitOptions};
+
+/// Settled entries the journal may hold before it is rewritten.
+const COMPACT_AFTER: usize = 1024;
+
+/// Settings for [`Notif::buffered`].
+#[derive(Debug, Clone)]
+pub struct Buffering {
+    /// Most events queued at once.
+    pub capacity: usize,
+    /// What an emit does when the queue is full.
+    pub overflow: BackpressurePolicy,
+    /// Delay between attempts while sending fails.
+    pub retry_backoff: Backoff,
+    path: Option<PathBuf>,
+}
+
+impl Buffering {
+    /// Queue up to `capacity` events (at least one) in memory.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            overflow: BackpressurePolicy::DropOldest,
+            retry_backoff: Backoff::new(Duration::from_millis(500), Duration::from_secs(30)),
+            path: None,
+        }
+    }
+
+    /// Set what an emit does when the queue is full (default: drop the oldest).
+    ///
+    /// `Block` waits for room, `DropOldest` and `DropNewest` discard an
+    /// event, and `Error` fails the emit with `NotifError::BufferOverflow`.
+    pub fn overflow(mut self, policy: BackpressurePolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Set the delay between attempts (default: 500ms doubling to 30s).
+    ///
+    /// `max_retries` is ignored: queued events are retried until they are
+    /// sent or the server rejects them.
+    pub fn retry_backoff(mut self, backoff: Backoff) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Keep the queue in a journal file, reloading it on creation.
+    pub fn persist(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+/// One line of the journal.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Emit(Entry),
+    Done { seq: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    seq: u64,
+    topic: String,
+    data: serde_json::Value,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    headers: HashMap<String, String>,
+}
+
+/// Append-only file mirroring the queue.
+struct Journal {
+    path: PathBuf,
+    file: File,
+    settled: usize,
+}
+
+impl Journal {
+    /// Open `path`, returning the entries still pending in it.
+    fn open(path: &Path) -> Result<(Self, VecDeque<Entry>)> {
+        let mut pending: VecDeque<Entry> = VecDeque::new();
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    // A line cut short by a crash is the last one; skip it
+                    match serde_json::from_str(&line?) {
+                        Ok(Record::Emit(entry)) => pending.push_back(entry),
+                        Ok(Record::Done { seq }) => pending.retain(|entry| entry.seq != seq),
+                        Err(_) => continue,
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut journal = Self {
+            path: path.to_path_buf(),
+            file: File::create(path)?,
+            settled: 0,
+        };
+        journal.rewrite(&pending)?;
+        Ok((journal, pending))
+    }
+
+    fn append(&mut self, record: &Record) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Record `seq` as settled, compacting once enough have piled up.
+    fn settle(&mut self, seq: u64, pending: &VecDeque<Entry>) -> Result<()> {
+        self.settled += 1;
+        if pending.is_empty() || self.settled >= COMPACT_AFTER {
+            return self.rewrite(pending);
+        }
+        self.append(&Record::Done { seq })
+    }
+
+    /// Replace the file with just the pending entries.
+    fn rewrite(&mut self, pending: &VecDeque<Entry>) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        for entry in pending {
+            let mut line = serde_json::to_vec(&Record::Emit(entry.clone()))?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.settled = 0;
+        Ok(())
+    }
+}
+
+struct Queue {
+    pending: VecDeque<Entry>,
+    next_seq: u64,
+    dropped: u64,
+    journal: Option<Journal>,
+    closed: bool,
+}
+
+impl Queue {
+    /// Remove the entry `seq` if still queued.
+    fn settle(&mut self, seq: u64) {
+        let Some(at) = self.pending.iter().position(|entry| entry.seq == seq) else {
+            return;
+        };
+        self.pending.remove(at);
+        if let Some(journal) = &mut self.journal {
+            // The event is out either way; at worst it is resent after a restart
+            let _ = journal.settle(seq, &self.pending);
+        }
+    }
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    /// Wakes the sender when an event is queued or the emitter is dropped.
+    wake: Notify,
+    /// Wakes emits waiting for room and `flush` callers.
+    progress: Notify,
+}
+
+/// Queues emits and sends them in order in the background.
+///
+/// Created with [`Notif::buffered`]. `emit` returns once the event is
+/// queued; a background task sends queued events one at a time, in
+/// order, retrying with backoff while sending fails with a retryable
+/// error and right away once a subscription of the client reconnects.
+/// Events the server rejects for good (e.g. an invalid payload) are
+/// discarded and counted in `dropped`.
+///
+/// Dropping the emitter stops the background task; events still queued
+/// are lost unless the queue is persisted.
+///
+/// # Example
+///
+/// ```no_run
+/// # use notifsh::{Buffering, Notif};
+/// # use serde_json::json;
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let emitter = client.buffered(Buffering::new(10_000).persist("notif-queue.jsonl"))?;
+///
+/// // Succeeds even while offline
+/// emitter.emit("desktop.notification", json!({"title": "Build finished"})).await?;
+///
+/// // Wait for everything queued to reach the server, e.g. before exiting
+/// emitter.flush().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BufferedEmitter {
+    client: Notif,
+    shared: Arc<Shared>,
+    capacity: usize,
+    overflow: BackpressurePolicy,
+}
+
+impl Notif {
+    /// Queue emits through a [`BufferedEmitter`] that rides out outages.
+    ///
+    /// Fails if the journal of a persisted queue cannot be read or written.
+    /// Must be called within a Tokio runtime.
+    pub fn buffered(&self, buffering: Buffering) -> Result<BufferedEmitter> {
+        let (journal, pending) = match &buffering.path {
+            Some(path) => {
+                let (journal, pending) = Journal::open(path)?;
+                (Some(journal), pending)
+            }
+            None => (None, VecDeque::new()),
+        };
+        let next_seq = pending.back().map_or(0, |entry| entry.seq + 1);
+
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                pending,
+                next_seq,
+                dropped: 0,
+                journal,
+                closed: false,
+            }),
+            wake: Notify::new(),
+            progress: Notify::new(),
+        });
+        tokio::spawn(send_queued(
+            self.clone(),
+            shared.clone(),
+            buffering.retry_backoff,
+        ));
+
+        Ok(BufferedEmitter {
+            client: self.clone(),
+            shared,
+            capacity: buffering.capacity,
+            overflow: buffering.overflow,
+        })
+    }
+}
+
+impl BufferedEmitter {
+    /// Queue an event for `topic`.
+    ///
+    /// Fails right away for an invalid topic, a payload that does not
+    /// serialize, a read-only client, or a full queue under
+    /// `BackpressurePolicy::Error`.
+    pub async fn emit<T: Serialize>(&self, topic: &str, data: T) -> Result<()> {
+        self.emit_with_options(topic, data, EmitOptions::new())
+            .await
+    }
+
+    /// Queue an event with custom options such as headers.
+    pub async fn emit_with_options<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        options: EmitOptions,
+    ) -> Result<()> {
+        self.client.check_writable("emit")?;
+        let topic = Topic::name(topic)?.to_string();
+        let data = serde_json::to_value(data)?;
+
+        loop {
+            let progress = self.shared.progress.notified();
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+                let must_wait = queue.pending.len() >= self.capacity
+                    && match self.overflow {
+                        BackpressurePolicy::DropNewest => {
+                            queue.dropped += 1;
+                            return Ok(());
+                        }
+                        BackpressurePolicy::Error => {
+                            return Err(NotifError::BufferOverflow {
+                                capacity: self.capacity,
+                            });
+                        }
+                        BackpressurePolicy::DropOldest => {
+                            if let Some(oldest) = queue.pending.front().map(|entry| entry.seq) {
+                                queue.settle(oldest);
+                                queue.dropped += 1;
+                            }
+                            false
+                        }
+                        BackpressurePolicy::Block => true,
+                    };
+
+                if !must_wait {
+                    let entry = Entry {
+                        seq: queue.next_seq,
+                        topic,
+                        data,
+                        headers: options.headers,
+                    };
+                    if let Some(journal) = &mut queue.journal {
+                        journal.append(&Record::Emit(entry.clone()))?;
+                    }
+                    queue.next_seq += 1;
+                    queue.pending.push_back(entry);
+                    drop(queue);
+                    self.shared.wake.notify_one();
+                    return Ok(());
+                }
+            }
+            progress.await;
+        }
+    }
+
+    /// Number of events waiting to be sent.
+    pub fn pending(&self) -> usize {
+        self.shared.queue.lock().unwrap().pending.len()
+    }
+
+    /// Number of events discarded because the queue was full or the server
+    /// rejected them.
+    pub fn dropped(&self) -> u64 {
+        self.shared.queue.lock().unwrap().dropped
+    }
+
+    /// Wait until every queued event has been sent or discarded.
+    pub async fn flush(&self) {
+        loop {
+            let progress = self.shared.progress.notified();
+            if self.shared.queue.lock().unwrap().pending.is_empty() {
+                return;
+            }
+            progress.await;
+        }
+    }
+}
+
+impl Drop for BufferedEmitter {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().closed = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+/// Send queued events in order until the emitter is dropped.
+async fn send_queued(client: Notif, shared: Arc<Shared>, backoff: Backoff) {
+    let mut connection = client.watch_connection_state();
+    let mut attempt = 0;
+
+    loop {
+        let wake = shared.wake.notified();
+        let next = {
+            let queue = shared.queue.lock().unwrap();
+            if queue.closed {
+                return;
+            }
+            queue.pending.front().cloned()
+        };
+        let Some(entry) = next else {
+            wake.await;
+            continue;
+        };
+
+        let options = EmitOptions::new().headers(entry.headers);
+        match client
+            .emit_with_options(&entry.topic, entry.data, options)
+            .await
+        {
+            Err(e) if e.is_retryable() => {
+                let delay = backoff.delay(attempt);
+                attempt += 1;
+                connection.borrow_and_update();
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    // A subscription coming back is a good sign the server is too
+                    _ = reconnected(&mut connection) => {}
+                }
+                continue;
+            }
+            result => {
+                attempt = 0;
+                let mut queue = shared.queue.lock().unwrap();
+                if result.is_err() {
+                    queue.dropped += 1;
+                }
+                queue.settle(entry.seq);
+            }
+        }
+        shared.progress.notify_waiters();
+    }
+}
+
+/// Wait for the next change to `ConnectionState::Connected`.
+async fn reconnected(connection: &mut watch::Receiver<ConnectionState>) {
+    while connection.changed().await.is_ok() {
+        if *connection.borrow() == ConnectionState::Connected {
+            return;
+        }
+    }
+    std::future::pending().await
+}
@@ -0,0 +1,46 @@
+//! Topic catalog queries over HTTP.
+
+use reqwest::Method;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::topic::Topic;
+use crate::types::{ListTopicsResponse, TopicInfo};
+
+impl Notif {
+    /// List the topics that have stored events, with their metadata.
+    ///
+    /// Topics whose events all aged out of retention are not listed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    ///
+    /// for topic in client.topics().await? {
+    ///     println!("{}: {} events", topic.name, topic.messages);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn topics(&self) -> Result<Vec<TopicInfo>> {
+        let response = self
+            .execute(self.http(Method::GET, "/api/v1/topics"))
+            .await?;
+        let list: ListTopicsResponse = response.json().await?;
+        Ok(list.topics)
+    }
+
+    /// Get the metadata of a single topic.
+    ///
+    /// `name` must be a concrete topic, not a pattern. Fails with
+    /// `NotifError::NotFound` if the topic has no stored events.
+    pub async fn topic_info(&self, name: &str) -> Result<TopicInfo> {
+        let topic = Topic::name(name)?;
+        let path = format!("/api/v1/topics/{}", topic);
+        let response = self.execute(self.http(Method::GET, &path)).await?;
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,210 @@
+//! Chunking protocol for payloads above the server's size limit.
+//!
+//! A large payload is serialized, base64-encoded and split into parts that
+//! are emitted to the same topic as string payloads. Each part carries the
+//! producer's headers plus:
+//!
+//! - `notif-chunk-id`: correlation ID shared by all parts
+//! - `notif-chunk-index`: zero-based position of the part
+//! - `notif-chunk-count`: total number of parts
+//!
+//! Subscribing clients buffer parts until every index has arrived, then
+//! yield a single event with the original payload. It is yielded under the
+//! ID of the part that completed it and keeps that part's chunk headers, so
+//! consumers can tell a reassembled event apart and how many parts it took.
+//!
+//! Both sides refuse sets of more than a configured number of parts, so a
+//! forged count cannot make a subscriber allocate without bound.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use tokio::time::Instant;
+
+use crate::error::{NotifError, Result};
+use crate::interceptor::OutgoingEvent;
+use crate::types::{EmitRequest, Event};
+
+/// Header of a chunked event holding the correlation ID of its parts.
+pub const CHUNK_ID_HEADER: &str = "notif-chunk-id";
+
+/// Header of a chunked event holding the zero-based index of its part.
+pub const CHUNK_INDEX_HEADER: &str = "notif-chunk-index";
+
+/// Header of a chunked event holding the number of parts it was split into.
+pub const CHUNK_COUNT_HEADER: &str = "notif-chunk-count";
+
+/// Room left in each part for the chunk headers.
+const HEADER_RESERVE: usize = 128;
+
+/// How long an incomplete set of parts is kept before being discarded.
+const PARTIAL_TTL: Duration = Duration::from_secs(600);
+
+/// Size of `event` once wrapped in an emit request body.
+pub(crate) fn request_size(event: &OutgoingEvent) -> Result<usize> {
+    let request = EmitRequest {
+        topic: &event.topic,
+        data: &event.data,
+        headers: &event.headers,
+        ack_level: event.ack_level,
+    };
+    Ok(serde_json::to_vec(&request)?.len())
+}
+
+/// Split `event` into at most `max_parts` parts whose request bodies fit in
+/// `max` bytes.
+pub(crate) fn split(
+    event: &OutgoingEvent,
+    max: usize,
+    max_parts: usize,
+) -> Result<Vec<OutgoingEvent>> {
+    let payload = serde_json::to_vec(&event.data)?;
+    let too_large = || NotifError::PayloadTooLarge {
+        size: payload.len(),
+        max,
+    };
+
+    let envelope = request_size(&OutgoingEvent {
+        topic: event.topic.clone(),
+        data: serde_json::Value::String(String::new()),
+        headers: event.headers.clone(),
+        ack_level: event.ack_level,
+    })?;
+    // Base64 encodes every 3 raw bytes as 4 characters
+    let part_size = max.saturating_sub(envelope + HEADER_RESERVE) / 4 * 3;
+    if part_size == 0 {
+        return Err(too_large());
+    }
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let count = payload.len().div_ceil(part_size);
+    if count > max_parts {
+        return Err(NotifError::PayloadTooLarge {
+            size: payload.len(),
+            max: part_size * max_parts,
+        });
+    }
+    Ok(payload
+        .chunks(part_size)
+        .enumerate()
+        .map(|(index, part)| {
+            let mut headers = event.headers.clone();
+            headers.insert(CHUNK_ID_HEADER.to_string(), id.clone());
+            headers.insert(CHUNK_INDEX_HEADER.to_string(), index.to_string());
+            headers.insert(CHUNK_COUNT_HEADER.to_string(), count.to_string());
+            OutgoingEvent {
+                topic: event.topic.clone(),
+                data: serde_json::Value::String(STANDARD.encode(part)),
+                headers,
+                ack_level: event.ack_level,
+            }
+        })
+        .collect())
+}
+
+/// An event ready to be handed to the consumer.
+pub(crate) struct Assembled {
+    pub(crate) event: Event,
+    /// IDs of the other parts, settled together with `event`.
+    pub(crate) part_ids: Vec<String>,
+}
+
+/// Buffers chunked events until all of their parts have arrived.
+pub(crate) struct Reassembler {
+    partial: HashMap<String, Partial>,
+    max_parts: usize,
+}
+
+struct Partial {
+    parts: Vec<Option<(String, String)>>,
+    started: Instant,
+}
+
+impl Reassembler {
+    /// A reassembler rejecting sets of more than `max_parts` parts.
+    pub(crate) fn new(max_parts: usize) -> Self {
+        Self {
+            partial: HashMap::new(),
+            max_parts,
+        }
+    }
+
+    /// Accept a received event, returning it once it is complete.
+    ///
+    /// Events without chunk headers pass straight through.
+    pub(crate) fn accept(&mut self, mut event: Event) -> Result<Option<Assembled>> {
+        let Some((chunk_id, index, count)) = chunk_headers(&event) else {
+            return Ok(Some(Assembled {
+                event,
+                part_ids: Vec::new(),
+            }));
+        };
+        if count > self.max_parts {
+            return Err(NotifError::websocket(format!(
+                "event {} has {} parts, more than the limit of {}",
+                chunk_id, count, self.max_parts
+            )));
+        }
+
+        self.partial
+            .retain(|_, partial| partial.started.elapsed() < PARTIAL_TTL);
+        let partial = self
+            .partial
+            .entry(chunk_id.clone())
+            .or_insert_with(|| Partial {
+                parts: vec![None; count],
+                started: Instant::now(),
+            });
+        if partial.parts.len() != count {
+            return Err(NotifError::websocket(format!(
+                "chunk {} of event {} has an inconsistent part count",
+                index, chunk_id
+            )));
+        }
+
+        let data = match event.data.as_str() {
+            Some(data) => data.to_string(),
+            None => return Err(decode_error(&event, "chunk payload is not a string")),
+        };
+        partial.parts[index] = Some((event.id.clone(), data));
+        if partial.parts.iter().any(Option::is_none) {
+            return Ok(None);
+        }
+
+        let parts = self.partial.remove(&chunk_id).map(|p| p.parts);
+        let mut payload = Vec::new();
+        let mut part_ids = Vec::new();
+        for (id, data) in parts.into_iter().flatten().flatten() {
+            let bytes = STANDARD.decode(data).map_err(|e| decode_error(&event, e))?;
+            payload.extend_from_slice(&bytes);
+            if id != event.id {
+                part_ids.push(id);
+            }
+        }
+
+        event.data = serde_json::from_slice(&payload).map_err(|source| NotifError::Decode {
+            id: event.id.clone(),
+            topic: event.topic.clone(),
+            source,
+        })?;
+        Ok(Some(Assembled { event, part_ids }))
+    }
+}
+
+/// Chunk ID, index and count, if the event is a valid part.
+fn chunk_headers(event: &Event) -> Option<(String, usize, usize)> {
+    let id = event.headers.get(CHUNK_ID_HEADER)?;
+    let index = event.headers.get(CHUNK_INDEX_HEADER)?.parse().ok()?;
+    let count = event.headers.get(CHUNK_COUNT_HEADER)?.parse().ok()?;
+    (index < count).then(|| (id.clone(), index, count))
+}
+
+fn decode_error(event: &Event, reason: impl std::fmt::Display) -> NotifError {
+    NotifError::Decode {
+        id: event.id.clone(),
+        topic: event.topic.clone(),
+        source: serde::de::Error::custom(reason),
+    }
+}
@@ -0,0 +1,206 @@
+//! Conversions between events and the CloudEvents 1.0 JSON format.
+//!
+//! Notif carries CloudEvents attributes in `ce-` headers, like the
+//! CloudEvents HTTP binary mode: the payload stays the event data, the
+//! event ID and timestamp are the CloudEvents `id` and `time`, and the
+//! topic is the `type`. `EmitOptions::cloudevents` marks an event as a
+//! CloudEvent when emitting it.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::binary;
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::Event;
+
+/// CloudEvents specification version produced and accepted.
+pub(crate) const SPEC_VERSION: &str = "1.0";
+
+/// Header prefix for CloudEvents attributes.
+pub(crate) const HEADER_PREFIX: &str = "ce-";
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Attribute carrying binary data in the JSON format.
+const DATA_BASE64: &str = "data_base64";
+
+/// An event in the CloudEvents 1.0 JSON format.
+///
+/// Serializes to and from the structured-mode JSON envelope expected by
+/// Knative, EventBridge and other CloudEvents consumers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudEvent {
+    /// Always `"1.0"`.
+    pub specversion: String,
+    /// Event ID, unique within `source`.
+    pub id: String,
+    /// Context in which the event happened, as a URI reference.
+    pub source: String,
+    /// Kind of event; the topic in notif.
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// What the event is about within `source`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// When the event happened.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Utc>>,
+    /// Media type of `data`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datacontenttype: Option<String>,
+    /// Schema that `data` adheres to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataschema: Option<String>,
+    /// Event payload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    /// Extension attributes, and `data_base64` for binary data.
+    #[serde(flatten)]
+    pub extensions: HashMap<String, Value>,
+}
+
+impl Event {
+    /// Convert into a CloudEvent.
+    ///
+    /// Attributes come from the event's `ce-` headers, defaulting to the
+    /// event ID, the topic as `type`, `/topics/<topic>` as `source`, the
+    /// event timestamp as `time` and `application/json` content. Other
+    /// `ce-` headers become extension attributes; headers without the
+    /// prefix are left out.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn example(event: notifsh::Event) -> notifsh::Result<()> {
+    /// let body = serde_json::to_string(&event.to_cloudevent())?;
+    /// // POST `body` with `Content-Type: application/cloudevents+json`
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_cloudevent(&self) -> CloudEvent {
+        let mut attributes: HashMap<&str, &str> = self
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.strip_prefix(HEADER_PREFIX)?;
+                Some((name, value.as_str()))
+            })
+            .collect();
+        let mut take = |name: &str| attributes.remove(name).map(String::from);
+
+        // Only 1.0 exists; the header just marks the event as a CloudEvent
+        take("specversion");
+        take("id");
+        let source = take("source").unwrap_or_else(|| format!("/topics/{}", self.topic));
+        let event_type = take("type").unwrap_or_else(|| self.topic.clone());
+        let subject = take("subject");
+        let time = take("time")
+            .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+            .map_or(self.timestamp, |time| time.with_timezone(&Utc));
+        let datacontenttype = take("datacontenttype")
+            .or_else(|| self.content_type().map(String::from))
+            .unwrap_or_else(|| JSON_CONTENT_TYPE.into());
+        let dataschema = take("dataschema");
+
+        let mut extensions: HashMap<String, Value> = attributes
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), Value::from(value)))
+            .collect();
+        // Bytes from `Notif::emit_bytes` are already base64
+        let data = if self.is_binary() {
+            extensions.insert(DATA_BASE64.to_string(), self.data.clone());
+            None
+        } else {
+            (!self.data.is_null()).then(|| self.data.clone())
+        };
+
+        CloudEvent {
+            specversion: SPEC_VERSION.to_string(),
+            id: self.id.clone(),
+            source,
+            event_type,
+            subject,
+            time: Some(time),
+            datacontenttype: Some(datacontenttype),
+            dataschema,
+            data,
+            extensions,
+        }
+    }
+
+    /// Build an event from a CloudEvent, e.g. one received from Knative.
+    ///
+    /// The `type` becomes the topic and the other attributes `ce-` headers,
+    /// so `to_cloudevent` gives the same CloudEvent back. The event is not
+    /// tied to a subscription; acking it does nothing.
+    ///
+    /// Fails with `NotifError::InvalidTopic` if the `type` is not a valid
+    /// topic name, or `NotifError::InvalidCloudEvent` for a spec version
+    /// other than 1.0.
+    pub fn from_cloudevent(cloudevent: CloudEvent) -> Result<Event> {
+        if cloudevent.specversion != SPEC_VERSION {
+            return Err(NotifError::InvalidCloudEvent(format!(
+                "unsupported specversion '{}'",
+                cloudevent.specversion
+            )));
+        }
+        let topic = Topic::name(&cloudevent.event_type)?.to_string();
+
+        let mut headers = HashMap::new();
+        let mut extensions = cloudevent.extensions;
+        let data = match extensions.remove(DATA_BASE64) {
+            Some(encoded) => {
+                headers.insert(
+                    binary::ENCODING_HEADER.to_string(),
+                    binary::BASE64.to_string(),
+                );
+                if let Some(content_type) = &cloudevent.datacontenttype {
+                    headers.insert(
+                        binary::CONTENT_TYPE_HEADER.to_string(),
+                        content_type.clone(),
+                    );
+                }
+                encoded
+            }
+            None => cloudevent.data.unwrap_or(Value::Null),
+        };
+        let mut set = |name: &str, value: String| {
+            headers.insert(format!("{}{}", HEADER_PREFIX, name), value);
+        };
+        set("specversion", cloudevent.specversion);
+        set("source", cloudevent.source);
+        let optional = [
+            ("subject", cloudevent.subject),
+            ("datacontenttype", cloudevent.datacontenttype),
+            ("dataschema", cloudevent.dataschema),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                set(name, value);
+            }
+        }
+        for (name, value) in extensions {
+            let value = match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            set(&name, value);
+        }
+
+        Ok(Event {
+            id: cloudevent.id,
+            topic,
+            data,
+            headers,
+            timestamp: cloudevent.time.unwrap_or_else(Utc::now),
+            attempt: 1,
+            max_attempts: 1,
+            seq: None,
+            ack_tx: None,
+        })
+    }
+}
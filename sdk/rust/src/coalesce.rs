@@ -0,0 +1,224 @@
+//! Coalescing bursts of events: debouncing per key and throttling.
+//!
+//! Both adapters of [`CoalesceExt`] keep only the latest of events that
+//! arrive too close together. Events dropped that way are superseded: they
+//! are acked right away, so a subscription with manual acks does not get
+//! them redelivered, while the event that replaces them is left to the
+//! consumer to settle as usual.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use notifsh::{CoalesceExt, Notif};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! // The latest status of each agent, once it settles for 300ms
+//! let mut statuses = client
+//!     .subscribe(&["agents.*.status"])
+//!     .await?
+//!     .debounce_by_key("topic", Duration::from_millis(300));
+//!
+//! // At most one redraw of the output every 100ms
+//! let mut output = client
+//!     .subscribe(&["agents.*.session.output"])
+//!     .await?
+//!     .throttle(Duration::from_millis(100));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::stream::{self, BoxStream};
+use futures_util::{Stream, StreamExt};
+use tokio::time::Instant;
+
+use crate::error::Result;
+use crate::filter::FieldPath;
+use crate::types::Event;
+
+/// Adds [`debounce_by_key`](CoalesceExt::debounce_by_key) and
+/// [`throttle`](CoalesceExt::throttle) to every stream of events.
+pub trait CoalesceExt: Stream<Item = Result<Event>> + Sized + Unpin + Send + 'static {
+    /// Hold each event until no other event with the same value of the
+    /// field `key` (such as `topic` or `data.session_id`) arrived for
+    /// `window`, then yield the latest one.
+    ///
+    /// Events without the field are yielded right away, and held events
+    /// when the stream ends. A key that keeps receiving events more often
+    /// than `window` yields nothing until it pauses; use
+    /// [`throttle`](Self::throttle) for those.
+    ///
+    /// An invalid key path is yielded as `NotifError::InvalidKeyPath`
+    /// before the stream ends.
+    fn debounce_by_key(self, key: &str, window: Duration) -> Coalesced {
+        let key = match FieldPath::parse(key) {
+            Ok(key) => key,
+            Err(e) => return Coalesced::new(stream::once(async { Err(e) })),
+        };
+        let state = Debounce {
+            source: Some(self),
+            key,
+            window,
+            held: HashMap::new(),
+        };
+        Coalesced::new(stream::unfold(state, Debounce::next))
+    }
+
+    /// Yield at most one event per `interval`: the first of a burst right
+    /// away, then the latest of those that arrived meanwhile once the
+    /// interval is over.
+    fn throttle(self, interval: Duration) -> Coalesced {
+        let state = Throttle {
+            source: Some(self),
+            interval,
+            next_slot: Instant::now(),
+            pending: None,
+        };
+        Coalesced::new(stream::unfold(state, Throttle::next))
+    }
+}
+
+impl<S> CoalesceExt for S where S: Stream<Item = Result<Event>> + Unpin + Send + 'static {}
+
+/// A stream of events with bursts coalesced, from [`CoalesceExt`].
+///
+/// Implements `futures::Stream<Item = Result<Event>>`; errors of the
+/// underlying stream are passed through.
+pub struct Coalesced {
+    inner: BoxStream<'static, Result<Event>>,
+}
+
+impl Coalesced {
+    fn new(inner: impl Stream<Item = Result<Event>> + Send + 'static) -> Self {
+        Self {
+            inner: inner.boxed(),
+        }
+    }
+}
+
+impl Stream for Coalesced {
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for Coalesced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coalesced").finish_non_exhaustive()
+    }
+}
+
+/// Ack `event`, which a later one replaces.
+async fn supersede(event: Event) {
+    let _ = event.ack().await;
+}
+
+struct Debounce<S> {
+    /// `None` once the stream has ended.
+    source: Option<S>,
+    key: FieldPath,
+    window: Duration,
+    /// Latest event of each key and when to yield it.
+    held: HashMap<String, (Instant, Event)>,
+}
+
+impl<S> Debounce<S>
+where
+    S: Stream<Item = Result<Event>> + Unpin,
+{
+    async fn next(mut self) -> Option<(Result<Event>, Self)> {
+        loop {
+            let due = self
+                .held
+                .iter()
+                .min_by_key(|(_, (deadline, _))| *deadline)
+                .map(|(key, (deadline, _))| (key.clone(), *deadline));
+            let Some(source) = self.source.as_mut() else {
+                // Ended: flush what is held, oldest deadline first
+                let (key, _) = due?;
+                let (_, event) = self.held.remove(&key)?;
+                return Some((Ok(event), self));
+            };
+
+            let deadline = due.as_ref().map(|(_, deadline)| *deadline);
+            tokio::select! {
+                item = source.next() => match item {
+                    Some(Ok(event)) => {
+                        let Some(key) = self.key.key(&event) else {
+                            return Some((Ok(event), self));
+                        };
+                        let deadline = Instant::now() + self.window;
+                        if let Some((_, superseded)) = self.held.insert(key, (deadline, event)) {
+                            supersede(superseded).await;
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), self)),
+                    None => self.source = None,
+                },
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    let (key, _) = due.expect("deadline is set");
+                    if let Some((_, event)) = self.held.remove(&key) {
+                        return Some((Ok(event), self));
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Throttle<S> {
+    /// `None` once the stream has ended.
+    source: Option<S>,
+    interval: Duration,
+    /// No event is yielded before this.
+    next_slot: Instant,
+    /// Latest event that arrived too early.
+    pending: Option<Event>,
+}
+
+impl<S> Throttle<S>
+where
+    S: Stream<Item = Result<Event>> + Unpin,
+{
+    async fn next(mut self) -> Option<(Result<Event>, Self)> {
+        loop {
+            let Some(source) = self.source.as_mut() else {
+                let event = self.pending.take()?;
+                return Some((Ok(event), self));
+            };
+
+            let waiting = self.pending.is_some();
+            tokio::select! {
+                item = source.next() => match item {
+                    Some(Ok(event)) => {
+                        let now = Instant::now();
+                        if !waiting && now >= self.next_slot {
+                            self.next_slot = now + self.interval;
+                            return Some((Ok(event), self));
+                        }
+                        if let Some(superseded) = self.pending.replace(event) {
+                            supersede(superseded).await;
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), self)),
+                    None => self.source = None,
+                },
+                _ = tokio::time::sleep_until(self.next_slot), if waiting => {
+                    self.next_slot = Instant::now() + self.interval;
+                    let event = self.pending.take().expect("waiting on an event");
+                    return Some((Ok(event), self));
+                }
+            }
+        }
+    }
+}
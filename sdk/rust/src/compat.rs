@@ -0,0 +1,125 @@
+//! A Tokio bridge for calling the SDK from async-std, smol or other
+//! executors.
+//!
+//! This is not a runtime abstraction: the SDK's HTTP and WebSocket stacks
+//! are built on Tokio, and Tokio is compiled in and running whichever
+//! executor the application uses. Polling the first [`Compat`] starts a Tokio
+//! runtime with one worker thread, `notifsh-compat`, that lives for the
+//! rest of the process. Wrapped futures and streams are still polled by the
+//! application's executor, but with that runtime entered, so their timers
+//! and sockets are driven by its worker thread. Tasks the SDK spawns, such
+//! as subscription workers, run on that thread too.
+//!
+//! What this saves is driving a Tokio runtime by hand, not depending on
+//! Tokio.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use notifsh::compat::CompatExt;
+//! use notifsh::Notif;
+//! use serde_json::json;
+//!
+//! fn main() -> notifsh::Result<()> {
+//!     // Any executor will do, e.g. smol::block_on or async_std::task::block_on
+//!     futures::executor::block_on(async {
+//!         let client = Notif::from_env()?;
+//!         client.emit("builds.finished", json!({"ok": true})).compat().await?;
+//!
+//!         let mut stream = client.subscribe(&["builds.*"]).compat().await?.compat();
+//!         while let Some(event) = stream.next().await {
+//!             println!("{}", event?.topic);
+//!         }
+//!         Ok(())
+//!     })
+//! }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use tokio::runtime::{Builder, Runtime};
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("notifsh-compat")
+            .enable_all()
+            .build()
+            .expect("failed to start the notifsh compat runtime")
+    })
+}
+
+/// A future or stream polled, and dropped, with the bridge's Tokio runtime
+/// entered.
+///
+/// Created with [`Compat::new`] or [`CompatExt::compat`].
+pub struct Compat<T> {
+    inner: Option<Pin<Box<T>>>,
+}
+
+impl<T> Compat<T> {
+    /// Wrap a future or stream of the SDK.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Some(Box::pin(inner)),
+        }
+    }
+
+    fn inner(&mut self) -> Pin<&mut T> {
+        self.inner
+            .as_mut()
+            .expect("compat value used after drop")
+            .as_mut()
+    }
+}
+
+impl<F: Future> Future for Compat<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _guard = runtime().enter();
+        self.get_mut().inner().poll(cx)
+    }
+}
+
+impl<S: Stream> Stream for Compat<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let _guard = runtime().enter();
+        self.get_mut().inner().poll_next(cx)
+    }
+}
+
+impl<T> Drop for Compat<T> {
+    fn drop(&mut self) {
+        // Cleanup such as aborting tasks needs the runtime too
+        let _guard = runtime().enter();
+        self.inner.take();
+    }
+}
+
+impl<T> fmt::Debug for Compat<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compat").finish_non_exhaustive()
+    }
+}
+
+/// Adds [`compat`](CompatExt::compat) to every future and stream.
+pub trait CompatExt: Sized {
+    /// Poll this future or stream with the bridge's Tokio runtime entered.
+    fn compat(self) -> Compat<Self> {
+        Compat::new(self)
+    }
+}
+
+impl<T> CompatExt for T {}
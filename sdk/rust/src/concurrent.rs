@@ -0,0 +1,284 @@
+//! Bounded-concurrency event processing.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::error::Result;
+use crate::guard::{Circuit, HandlerGuard};
+use crate::parking::ParkingPolicy;
+use crate::subscribe::EventStream;
+use crate::types::{self, AckMessage, AckRequest, Backoff, Event};
+
+type KeyFn = dyn Fn(&Event) -> Option<String> + Send + Sync;
+
+/// Settings for `EventStream::for_each_concurrent_with`.
+#[derive(Clone)]
+pub struct Concurrency {
+    /// Most events held at once, running or waiting for their key.
+    pub limit: usize,
+    /// Delay before a failed event is redelivered, by delivery attempt.
+    pub retry_backoff: Backoff,
+    key: Option<Arc<KeyFn>>,
+    guard: Option<HandlerGuard>,
+    parking: Option<ParkingPolicy>,
+}
+
+impl Concurrency {
+    /// Process up to `limit` events at once (at least one).
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            retry_backoff: Backoff::new(Duration::from_secs(1), Duration::from_secs(300)),
+            key: None,
+            guard: None,
+            parking: None,
+        }
+    }
+
+    /// Process events with the same key one at a time, in arrival order.
+    ///
+    /// Events for which `key` returns `None` are not ordered.
+    pub fn key<F>(mut self, key: F) -> Self
+    where
+        F: Fn(&Event) -> Option<String> + Send + Sync + 'static,
+    {
+        self.key = Some(Arc::new(key));
+        self
+    }
+
+    /// Set the redelivery delay for failed events (default: 1s doubling to 5m).
+    pub fn retry_backoff(mut self, backoff: Backoff) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Pause consumption while the handler keeps failing (see
+    /// [`HandlerGuard`]).
+    pub fn guard(mut self, guard: HandlerGuard) -> Self {
+        self.guard = Some(guard);
+        self
+    }
+
+    /// Park events whose last attempts fail instead of nacking them (see
+    /// [`ParkingPolicy`]).
+    pub fn park(mut self, policy: ParkingPolicy) -> Self {
+        self.parking = Some(policy);
+        self
+    }
+}
+
+impl fmt::Debug for Concurrency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Concurrency")
+            .field("limit", &self.limit)
+            .field("retry_backoff", &self.retry_backoff)
+            .field("key", &self.key.is_some())
+            .field("guard", &self.guard)
+            .field("parking", &self.parking)
+            .finish()
+    }
+}
+
+/// What is left of an event once the handler owns it.
+struct Receipt {
+    id: String,
+    attempt: u32,
+    key: Option<String>,
+    ack_tx: Option<mpsc::Sender<AckRequest>>,
+    /// Copy of the event to park if the handler fails.
+    parkable: Option<Event>,
+}
+
+impl EventStream {
+    /// Run `handler` on every event, at most `limit` at a time.
+    ///
+    /// Events are acked when the handler returns `Ok` and nacked with a
+    /// growing delay when it returns `Err`, so the handler must not settle
+    /// them itself. Needs `auto_ack(false)` to have any effect on
+    /// redelivery.
+    ///
+    /// Errors yielded by the stream do not stop processing. Returns once the
+    /// stream ends and every handler has finished, with the stream's last
+    /// error if it ended right after one (e.g. reconnection gave up).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SubscribeOptions};
+    /// # async fn send_email(data: &serde_json::Value) -> Result<(), String> { Ok(()) }
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let stream = client
+    ///     .subscribe_with_options(&["emails.send"], SubscribeOptions::new().auto_ack(false))
+    ///     .await?;
+    ///
+    /// stream
+    ///     .for_each_concurrent(8, |event| async move { send_email(&event.data).await })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn for_each_concurrent<F, Fut, E>(self, limit: usize, handler: F) -> Result<()>
+    where
+        F: FnMut(Event) -> Fut,
+        Fut: Future<Output = std::result::Result<(), E>>,
+    {
+        self.for_each_concurrent_with(Concurrency::new(limit), handler)
+            .await
+    }
+
+    /// Run `handler` on every event with custom concurrency settings.
+    ///
+    /// With `Concurrency::key`, events sharing a key are handled one after
+    /// another while other keys proceed in parallel.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Concurrency, Notif, SubscribeOptions};
+    /// # async fn apply(event: notifsh::Event) -> Result<(), String> { Ok(()) }
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let stream = client
+    ///     .subscribe_with_options(&["orders.>"], SubscribeOptions::new().auto_ack(false))
+    ///     .await?;
+    ///
+    /// let concurrency = Concurrency::new(16)
+    ///     .key(|event| event.data["order_id"].as_str().map(String::from));
+    /// stream.for_each_concurrent_with(concurrency, apply).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn for_each_concurrent_with<F, Fut, E>(
+        mut self,
+        concurrency: Concurrency,
+        mut handler: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Event) -> Fut,
+        Fut: Future<Output = std::result::Result<(), E>>,
+    {
+        let mut running = FuturesUnordered::new();
+        // Keys with an event running, and the events queued behind it
+        let mut busy: HashSet<String> = HashSet::new();
+        let mut waiting: HashMap<String, VecDeque<Event>> = HashMap::new();
+        let mut held = 0;
+        let mut last_error = None;
+        let mut ended = false;
+        let mut circuit = concurrency.guard.clone().map(Circuit::new);
+
+        let mut start = |event: Event, key: Option<String>| {
+            let receipt = Receipt {
+                id: event.id.clone(),
+                attempt: event.attempt,
+                key,
+                ack_tx: event.ack_tx.clone(),
+                parkable: concurrency
+                    .parking
+                    .as_ref()
+                    .filter(|policy| policy.applies(&event))
+                    .map(|_| event.clone()),
+            };
+            let work = handler(event);
+            async move { (receipt, work.await.is_ok()) }
+        };
+
+        loop {
+            if circuit.as_mut().is_some_and(Circuit::cooled_down) {
+                self.resume();
+            }
+            let reading = !ended
+                && circuit
+                    .as_ref()
+                    .map_or(held < concurrency.limit, |circuit| {
+                        circuit.admits(held, concurrency.limit)
+                    });
+            let open_until = circuit.as_ref().and_then(Circuit::open_until);
+            tokio::select! {
+                item = self.next(), if reading => match item {
+                    Some(Ok(event)) => {
+                        last_error = None;
+                        if let Some(circuit) = &mut circuit {
+                            // Buffered before the subscription paused
+                            if let Some(retry_in) = circuit.open_for() {
+                                let _ = event.nack(Some(retry_in)).await;
+                                continue;
+                            }
+                            circuit.started(&event.id);
+                        }
+                        held += 1;
+                        let key = concurrency.key.as_ref().and_then(|key| key(&event));
+                        match key {
+                            Some(key) if busy.contains(&key) => {
+                                waiting.entry(key).or_default().push_back(event);
+                            }
+                            Some(key) => {
+                                busy.insert(key.clone());
+                                running.push(start(event, Some(key)));
+                            }
+                            None => running.push(start(event, None)),
+                        }
+                    }
+                    Some(Err(e)) => last_error = Some(e),
+                    None => ended = true,
+                },
+                _ = tokio::time::sleep_until(open_until.unwrap_or_else(Instant::now)), if open_until.is_some() && !ended => {}
+                Some((receipt, ok)) = running.next() => {
+                    held -= 1;
+                    let parked = match (&concurrency.parking, receipt.parkable) {
+                        (Some(policy), Some(event)) if !ok => policy.park(event).await.is_ok(),
+                        _ => false,
+                    };
+                    let msg = if ok || parked {
+                        AckMessage::Ack { id: receipt.id.clone() }
+                    } else {
+                        let delay = concurrency
+                            .retry_backoff
+                            .delay(receipt.attempt.saturating_sub(1));
+                        AckMessage::Nack { id: receipt.id.clone(), retry_in: Some(delay) }
+                    };
+                    // The handler may have settled it already; nothing to do then
+                    let _ = types::settle(&receipt.ack_tx, msg).await;
+
+                    let opened = match &mut circuit {
+                        Some(circuit) => circuit.record(&receipt.id, ok).await,
+                        None => false,
+                    };
+                    if opened {
+                        self.pause();
+                        // Hand back what waits behind busy keys; running events finish
+                        let retry_in = circuit.as_ref().and_then(Circuit::open_for);
+                        for event in waiting.drain().flat_map(|(_, queue)| queue) {
+                            held -= 1;
+                            let _ = event.nack(retry_in).await;
+                        }
+                    }
+
+                    if let Some(key) = receipt.key {
+                        match waiting.get_mut(&key).and_then(VecDeque::pop_front) {
+                            Some(next) => running.push(start(next, Some(key))),
+                            None => {
+                                waiting.remove(&key);
+                                busy.remove(&key);
+                            }
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
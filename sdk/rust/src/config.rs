@@ -0,0 +1,150 @@
+//! Client settings from environment variables and profile files.
+//!
+//! Profiles are tables in `~/.config/notif/config.toml` (or
+//! `$XDG_CONFIG_HOME/notif/config.toml`, or the file named by
+//! `NOTIF_CONFIG`):
+//!
+//! ```toml
+//! [default]
+//! api_key = "nsh_live_..."
+//!
+//! [staging]
+//! api_key = "nsh_test_..."
+//! server = "https://notif.staging.example.com"
+//! timeout = "60s"
+//! ```
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::client::NotifBuilder;
+use crate::duration;
+use crate::error::{NotifError, Result};
+
+const API_KEY_VAR: &str = "NOTIF_API_KEY";
+const SERVER_VAR: &str = "NOTIF_SERVER";
+const TIMEOUT_VAR: &str = "NOTIF_TIMEOUT";
+const PROFILE_VAR: &str = "NOTIF_PROFILE";
+const CONFIG_VAR: &str = "NOTIF_CONFIG";
+const DEFAULT_PROFILE: &str = "default";
+
+/// Connection settings, from a profile or the environment.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Settings {
+    api_key: Option<String>,
+    server: Option<String>,
+    timeout: Option<Timeout>,
+}
+
+/// A timeout given as seconds or as a duration string like `"90s"`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Timeout {
+    Seconds(u64),
+    Text(String),
+}
+
+impl Timeout {
+    fn duration(&self) -> Result<Duration> {
+        match self {
+            Timeout::Seconds(secs) => Ok(Duration::from_secs(*secs)),
+            Timeout::Text(text) => match text.parse() {
+                Ok(secs) => Ok(Duration::from_secs(secs)),
+                Err(_) => duration::parse(text),
+            },
+        }
+    }
+}
+
+impl Settings {
+    /// Settings of the profile `name`, which must exist.
+    pub(crate) fn profile(name: &str) -> Result<Self> {
+        let path = config_path()
+            .ok_or_else(|| NotifError::Config("no home directory for the profile file".into()))?;
+        let mut profiles = read_profiles(&path)?
+            .ok_or_else(|| NotifError::Config(format!("{} not found", path.display())))?;
+        profiles.remove(name).ok_or_else(|| {
+            NotifError::Config(format!("no profile '{}' in {}", name, path.display()))
+        })
+    }
+
+    /// Settings from the environment, on top of the `NOTIF_PROFILE`
+    /// profile or else the `default` profile when there is one.
+    pub(crate) fn from_env() -> Result<Self> {
+        let mut settings = match env::var(PROFILE_VAR) {
+            Ok(name) => Self::profile(&name)?,
+            Err(_) => match config_path() {
+                Some(path) => read_profiles(&path)?
+                    .and_then(|mut profiles| profiles.remove(DEFAULT_PROFILE))
+                    .unwrap_or_default(),
+                None => Self::default(),
+            },
+        };
+
+        if let Ok(api_key) = env::var(API_KEY_VAR) {
+            settings.api_key = Some(api_key);
+        }
+        if let Ok(server) = env::var(SERVER_VAR) {
+            settings.server = Some(server);
+        }
+        if let Ok(timeout) = env::var(TIMEOUT_VAR) {
+            settings.timeout = Some(Timeout::Text(timeout));
+        }
+        Ok(settings)
+    }
+
+    /// A builder with these settings applied.
+    pub(crate) fn builder(self) -> Result<NotifBuilder> {
+        let api_key = self.api_key.ok_or_else(|| {
+            NotifError::auth(format!("{} environment variable not set", API_KEY_VAR))
+        })?;
+        let mut builder = NotifBuilder::new(api_key);
+        if let Some(server) = self.server {
+            builder = builder.server(server);
+        }
+        if let Some(timeout) = self.timeout {
+            let timeout = timeout
+                .duration()
+                .map_err(|e| NotifError::Config(format!("invalid timeout: {}", e)))?;
+            builder = builder.timeout(timeout);
+        }
+        Ok(builder)
+    }
+}
+
+/// Where profiles are read from.
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_VAR) {
+        return Some(path.into());
+    }
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?)
+            .join(".config"),
+    };
+    Some(dir.join("notif").join("config.toml"))
+}
+
+/// Read every profile in `path`, or `None` if the file does not exist.
+fn read_profiles(path: &PathBuf) -> Result<Option<HashMap<String, Settings>>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(NotifError::Config(format!(
+                "cannot read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    toml::from_str(&text)
+        .map(Some)
+        .map_err(|e| NotifError::Config(format!("invalid {}: {}", path.display(), e)))
+}
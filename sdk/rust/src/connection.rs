@@ -0,0 +1,95 @@
+//! Connection health of a client's subscriptions.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tokio::sync::watch;
+
+use crate::client::Notif;
+use crate::types::ConnectionState;
+
+/// State of every open subscription of a client, folded into one.
+#[derive(Debug)]
+pub(crate) struct Connections {
+    overall: watch::Sender<ConnectionState>,
+    streams: Mutex<HashMap<u64, ConnectionState>>,
+    next_id: AtomicU64,
+}
+
+impl Default for Connections {
+    fn default() -> Self {
+        Self {
+            overall: watch::Sender::new(ConnectionState::Disconnected),
+            streams: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Connections {
+    /// Start tracking a new subscription.
+    pub(crate) fn register(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Record the state of subscription `id`; `Disconnected` stops tracking it.
+    pub(crate) fn update(&self, id: u64, state: ConnectionState) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        if state == ConnectionState::Disconnected {
+            streams.remove(&id);
+        } else {
+            streams.insert(id, state);
+        }
+
+        let overall = if streams.is_empty() {
+            ConnectionState::Disconnected
+        } else if streams
+            .values()
+            .any(|state| *state == ConnectionState::Reconnecting)
+        {
+            ConnectionState::Reconnecting
+        } else {
+            ConnectionState::Connected
+        };
+        self.overall.send_if_modified(|current| {
+            let changed = *current != overall;
+            *current = overall;
+            changed
+        });
+    }
+}
+
+impl Notif {
+    /// Health of the client's subscriptions.
+    ///
+    /// `Connected` when every open subscription is connected,
+    /// `Reconnecting` while any of them is re-establishing its connection,
+    /// and `Disconnected` when none is open. Emits and other HTTP calls do
+    /// not affect it.
+    pub fn connection_state(&self) -> ConnectionState {
+        *self.inner.connections.overall.borrow()
+    }
+
+    /// Watch `connection_state` change, e.g. to drive a status indicator.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let mut state = client.watch_connection_state();
+    ///
+    /// tokio::spawn(async move {
+    ///     while state.changed().await.is_ok() {
+    ///         println!("connection: {:?}", *state.borrow());
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watch_connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.inner.connections.overall.subscribe()
+    }
+}
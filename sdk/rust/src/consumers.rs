@@ -0,0 +1,42 @@
+//! Consumer group queries over HTTP.
+
+use reqwest::Method;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::raw;
+use crate::topic::Topic;
+use crate::types::ConsumerLag;
+
+impl Notif {
+    /// Get how far a consumer group is behind.
+    ///
+    /// `topics` must be the topics the group subscribes with: each distinct
+    /// set of topics is a separate consumer on the server. Fails with
+    /// `NotifError::NotFound` if the group never subscribed to them. Useful
+    /// for scaling workers on the backlog.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    ///
+    /// let lag = client.consumer_lag("workers", &["orders.created"]).await?;
+    /// println!("{} pending, {} in flight", lag.pending, lag.in_flight);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn consumer_lag(&self, group: &str, topics: &[&str]) -> Result<ConsumerLag> {
+        // Normalized like subscription patterns, which name the consumer
+        let mut query = Vec::with_capacity(topics.len());
+        for topic in topics {
+            query.push(("topic", String::from(Topic::parse(topic)?)));
+        }
+        let path = format!("/api/v1/consumers/{}/lag", raw::segment(group));
+        let request = self.http(Method::GET, &path).query(&query);
+        let response = self.execute(request).await?;
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,189 @@
+//! Electing one leader among the instances of a worker.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::client::Notif;
+use crate::coordination::lease::{Lease, LeaseState};
+use crate::error::Result;
+
+/// Default lease TTL of an election.
+const DEFAULT_TTL: Duration = Duration::from_secs(15);
+
+type ElectedFn = dyn Fn(u64) + Send + Sync;
+type RevokedFn = dyn Fn() + Send + Sync;
+
+/// Elects one leader among the instances running the same election.
+///
+/// Instances compete for a lease on the `_lease.<name>` topic. The
+/// leader renews it every third of the TTL; when it stops renewing,
+/// because it crashed, lost its connection or resigned, another instance
+/// takes over once the TTL runs out (right away after a resignation).
+/// All instances of an election must use the same TTL.
+///
+/// `on_elected` receives a fencing token that grows with each new leader;
+/// pass it along to storage that can reject writes from an older leader.
+/// A leader whose renewals stop getting through is revoked one TTL after
+/// its last successful one, before any other instance can be elected.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::coordination::LeaderElection;
+/// use notifsh::Notif;
+/// use std::time::Duration;
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// let election = LeaderElection::new(&client, "billing-cron")
+///     .ttl(Duration::from_secs(10))
+///     .on_elected(|token| println!("leading with token {token}"))
+///     .on_revoked(|| println!("no longer leading"))
+///     .start()
+///     .await?;
+///
+/// // Later, on shutdown
+/// election.resign().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct LeaderElection {
+    client: Notif,
+    name: String,
+    ttl: Duration,
+    on_elected: Option<Arc<ElectedFn>>,
+    on_revoked: Option<Arc<RevokedFn>>,
+}
+
+impl LeaderElection {
+    /// Create an election named `name`, a single topic segment.
+    pub fn new(client: &Notif, name: impl Into<String>) -> Self {
+        Self {
+            client: client.clone(),
+            name: name.into(),
+            ttl: DEFAULT_TTL,
+            on_elected: None,
+            on_revoked: None,
+        }
+    }
+
+    /// Set how long leadership outlives the leader's last renewal
+    /// (default: 15s, at least 1s).
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Call `callback` with the fencing token when this instance becomes
+    /// the leader.
+    pub fn on_elected<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        self.on_elected = Some(Arc::new(callback));
+        self
+    }
+
+    /// Call `callback` when this instance stops being the leader.
+    pub fn on_revoked<F>(mut self, callback: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_revoked = Some(Arc::new(callback));
+        self
+    }
+
+    /// Start competing for leadership.
+    ///
+    /// Fails with `NotifError::InvalidTopic` if the name is not a valid
+    /// topic segment.
+    pub async fn start(self) -> Result<Election> {
+        let lease = Lease::start(&self.client, &self.name, self.ttl, true).await?;
+        let mut state_rx = lease.watch_state();
+        let (on_elected, on_revoked) = (self.on_elected, self.on_revoked);
+        let callbacks = tokio::spawn(async move {
+            let mut leading = false;
+            while state_rx.changed().await.is_ok() {
+                let state = *state_rx.borrow_and_update();
+                match state {
+                    LeaseState::Held { token } if !leading => {
+                        leading = true;
+                        if let Some(on_elected) = &on_elected {
+                            on_elected(token);
+                        }
+                    }
+                    LeaseState::Waiting | LeaseState::Lost | LeaseState::Stopped if leading => {
+                        leading = false;
+                        if let Some(on_revoked) = &on_revoked {
+                            on_revoked();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        Ok(Election {
+            name: self.name,
+            lease,
+            callbacks,
+        })
+    }
+}
+
+impl fmt::Debug for LeaderElection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeaderElection")
+            .field("name", &self.name)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A running election started by [`LeaderElection::start`].
+///
+/// Dropping it resigns in the background.
+pub struct Election {
+    name: String,
+    lease: Lease,
+    callbacks: JoinHandle<()>,
+}
+
+impl Election {
+    /// Whether this instance is the leader.
+    pub fn is_leader(&self) -> bool {
+        self.token().is_some()
+    }
+
+    /// The fencing token of this instance's leadership, if it leads.
+    pub fn token(&self) -> Option<u64> {
+        match self.lease.state() {
+            LeaseState::Held { token } => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Stop competing, handing leadership over right away if held.
+    ///
+    /// `on_revoked` is called if this instance was leading.
+    pub async fn resign(self) -> Result<()> {
+        let Self {
+            lease, callbacks, ..
+        } = self;
+        let result = lease.release().await;
+        // The callbacks task ends once it has seen the last state
+        let _ = callbacks.await;
+        result
+    }
+}
+
+impl fmt::Debug for Election {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Election")
+            .field("name", &self.name)
+            .field("token", &self.token())
+            .finish_non_exhaustive()
+    }
+}
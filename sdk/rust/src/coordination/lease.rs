@@ -0,0 +1,379 @@
+//! Leases held through an ordered log of events on one topic.
+//!
+//! Participants emit `acquire`, `renew` and `release` operations on
+//! `_lease.<name>` and fold every operation, in delivery order, into the
+//! same view of who holds the lease. An `acquire` is granted when the
+//! lease is free at its server timestamp: nobody holds it, or the holder's
+//! last grant or renewal is older than its TTL. The fencing token of a
+//! grant is its timestamp in microseconds, so later grants get larger
+//! tokens. A holder considers the lease lost one TTL after it *sent* its
+//! last applied operation, which is never later than others see it
+//! expire.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Instant;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::subscribe::EventStream;
+use crate::topic::Topic;
+use crate::types::{Event, SubscribeOptions};
+
+/// Prefix for the topics leases are coordinated on.
+const LEASE_TOPIC_PREFIX: &str = "_lease";
+
+/// Shortest TTL a lease may have.
+const MIN_TTL: Duration = Duration::from_secs(1);
+
+/// An operation on a lease, as emitted on its topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LeaseOp {
+    Acquire {
+        holder: String,
+        ttl_ms: u64,
+    },
+    Renew {
+        holder: String,
+        token: u64,
+        ttl_ms: u64,
+    },
+    Release {
+        holder: String,
+        token: u64,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Grant {
+    holder: String,
+    token: u64,
+    expires_at: DateTime<Utc>,
+}
+
+/// Who holds the lease, from the operations folded so far.
+#[derive(Debug, Default)]
+struct LeaseLog {
+    grant: Option<Grant>,
+}
+
+impl LeaseLog {
+    fn apply(&mut self, op: &LeaseOp, at: DateTime<Utc>) {
+        let live = self.grant.as_ref().filter(|grant| at < grant.expires_at);
+        match op {
+            LeaseOp::Acquire { holder, ttl_ms } => match live {
+                Some(grant) if grant.holder != *holder => {}
+                Some(grant) => {
+                    let token = grant.token;
+                    self.grant = Some(granted(holder, token, at, *ttl_ms));
+                }
+                None => {
+                    let token = u64::try_from(at.timestamp_micros()).unwrap_or_default();
+                    self.grant = Some(granted(holder, token, at, *ttl_ms));
+                }
+            },
+            LeaseOp::Renew {
+                holder,
+                token,
+                ttl_ms,
+            } => {
+                if live.is_some_and(|grant| grant.holder == *holder && grant.token == *token) {
+                    self.grant = Some(granted(holder, *token, at, *ttl_ms));
+                }
+            }
+            LeaseOp::Release { holder, token } => {
+                let held = self
+                    .grant
+                    .as_ref()
+                    .is_some_and(|grant| grant.holder == *holder && grant.token == *token);
+                if held {
+                    self.grant = None;
+                }
+            }
+        }
+    }
+}
+
+fn granted(holder: &str, token: u64, at: DateTime<Utc>, ttl_ms: u64) -> Grant {
+    let ttl = i64::try_from(ttl_ms).unwrap_or(i64::MAX);
+    Grant {
+        holder: holder.to_string(),
+        token,
+        expires_at: at + chrono::Duration::milliseconds(ttl),
+    }
+}
+
+/// Where a participant stands on the lease.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LeaseState {
+    /// Waiting for the lease to be granted.
+    Waiting,
+    /// Holding the lease under a fencing token.
+    Held { token: u64 },
+    /// Lost the lease and stopped competing, if not rejoining.
+    Lost,
+    /// Stopped: released, or the subscription ended.
+    Stopped,
+}
+
+enum Command {
+    Release(oneshot::Sender<Result<()>>),
+}
+
+/// One participant competing for a lease in a background task.
+///
+/// After losing the lease it competes again if it rejoins, and stops
+/// otherwise. Dropping it releases the lease, if held, from the
+/// background.
+pub(crate) struct Lease {
+    state_rx: watch::Receiver<LeaseState>,
+    command_tx: mpsc::UnboundedSender<Command>,
+}
+
+impl Lease {
+    /// Start competing for the lease `name`.
+    pub(crate) async fn start(
+        client: &Notif,
+        name: &str,
+        ttl: Duration,
+        rejoin: bool,
+    ) -> Result<Self> {
+        let ttl = ttl.max(MIN_TTL);
+        let topic = Topic::name(&format!("{}.{}", LEASE_TOPIC_PREFIX, name))?;
+        // Live leases were granted or renewed within one TTL
+        let lookback = chrono::Duration::from_std(ttl * 2)
+            .map_err(|_| NotifError::Config("lease TTL is too long".to_string()))?;
+        let stream = client
+            .subscribe_with_options(
+                &[topic.as_str()],
+                SubscribeOptions::new().from((Utc::now() - lookback).to_rfc3339()),
+            )
+            .await?;
+
+        let (state_tx, state_rx) = watch::channel(LeaseState::Waiting);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let driver = Driver {
+            client: client.clone(),
+            topic: topic.as_str().to_string(),
+            holder: uuid::Uuid::new_v4().simple().to_string(),
+            ttl,
+            rejoin,
+            log: LeaseLog::default(),
+            state_tx,
+            acquire_sent: None,
+            retry_at: Instant::now(),
+            renews_sent: VecDeque::new(),
+            renew_at: Instant::now(),
+            deadline: Instant::now(),
+        };
+        tokio::spawn(driver.run(stream, command_rx));
+
+        Ok(Self {
+            state_rx,
+            command_tx,
+        })
+    }
+
+    /// Watch the participant's state change.
+    pub(crate) fn watch_state(&self) -> watch::Receiver<LeaseState> {
+        self.state_rx.clone()
+    }
+
+    /// Current state.
+    pub(crate) fn state(&self) -> LeaseState {
+        *self.state_rx.borrow()
+    }
+
+    /// Stop competing and release the lease if held.
+    pub(crate) async fn release(self) -> Result<()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.command_tx.send(Command::Release(done_tx)).is_err() {
+            return Ok(());
+        }
+        done_rx.await.unwrap_or(Ok(()))
+    }
+}
+
+struct Driver {
+    client: Notif,
+    topic: String,
+    holder: String,
+    ttl: Duration,
+    /// Whether to compete again after losing the lease.
+    rejoin: bool,
+    log: LeaseLog,
+    state_tx: watch::Sender<LeaseState>,
+    /// When our unanswered `acquire` was sent.
+    acquire_sent: Option<Instant>,
+    /// When to ask for the lease again while waiting.
+    retry_at: Instant,
+    /// When our `renew`s not yet seen in the log were sent, oldest first.
+    renews_sent: VecDeque<Instant>,
+    /// When to renew next while holding.
+    renew_at: Instant,
+    /// When the lease is lost while holding, unless renewed.
+    deadline: Instant,
+}
+
+impl Driver {
+    async fn run(
+        mut self,
+        mut stream: EventStream,
+        mut command_rx: mpsc::UnboundedReceiver<Command>,
+    ) {
+        loop {
+            let wake = match *self.state_tx.borrow() {
+                LeaseState::Held { .. } => self.renew_at.min(self.deadline),
+                _ => self.retry_at,
+            };
+            tokio::select! {
+                item = stream.next() => match item {
+                    Some(Ok(event)) => self.observe(event),
+                    Some(Err(_)) => {}
+                    None => {
+                        self.state_tx.send_replace(LeaseState::Stopped);
+                        return;
+                    }
+                },
+                command = command_rx.recv() => {
+                    let result = self.release().await;
+                    if let Some(Command::Release(done_tx)) = command {
+                        let _ = done_tx.send(result);
+                    }
+                    return;
+                }
+                _ = tokio::time::sleep_until(wake) => self.tick().await,
+            }
+            if *self.state_tx.borrow() == LeaseState::Lost {
+                return;
+            }
+        }
+    }
+
+    fn ttl_ms(&self) -> u64 {
+        u64::try_from(self.ttl.as_millis()).unwrap_or(u64::MAX)
+    }
+
+    fn held_token(&self) -> Option<u64> {
+        match *self.state_tx.borrow() {
+            LeaseState::Held { token } => Some(token),
+            _ => None,
+        }
+    }
+
+    fn observe(&mut self, event: Event) {
+        let Ok(op) = event.parse::<LeaseOp>() else {
+            return;
+        };
+        self.log.apply(&op, event.timestamp);
+        let now = Instant::now();
+        let ours = match &op {
+            LeaseOp::Acquire { holder, .. }
+            | LeaseOp::Renew { holder, .. }
+            | LeaseOp::Release { holder, .. } => *holder == self.holder,
+        };
+        let grant = self.log.grant.clone();
+        let granted_to_us = grant.as_ref().filter(|grant| grant.holder == self.holder);
+
+        if let Some(token) = self.held_token() {
+            match granted_to_us {
+                Some(grant) if grant.token == token => {
+                    if ours && matches!(op, LeaseOp::Renew { .. }) {
+                        if let Some(sent) = self.renews_sent.pop_front() {
+                            self.deadline = sent + self.ttl;
+                        }
+                    }
+                }
+                _ => self.lose(now),
+            }
+            return;
+        }
+
+        if ours && matches!(op, LeaseOp::Acquire { .. }) {
+            let sent = self.acquire_sent.take().unwrap_or(now);
+            if let Some(grant) = granted_to_us {
+                self.deadline = sent + self.ttl;
+                self.renew_at = now + self.ttl / 3;
+                self.renews_sent.clear();
+                self.state_tx
+                    .send_replace(LeaseState::Held { token: grant.token });
+                return;
+            }
+        }
+
+        // Someone else holds it: ask again around when it expires
+        if self.acquire_sent.is_none() {
+            self.retry_at = match &grant {
+                Some(grant) => {
+                    let left = (grant.expires_at - Utc::now()).to_std().unwrap_or_default();
+                    now + left
+                }
+                None => now,
+            };
+        }
+    }
+
+    fn lose(&mut self, now: Instant) {
+        self.renews_sent.clear();
+        self.acquire_sent = None;
+        self.retry_at = now;
+        let state = if self.rejoin {
+            LeaseState::Waiting
+        } else {
+            LeaseState::Lost
+        };
+        self.state_tx.send_replace(state);
+    }
+
+    async fn tick(&mut self) {
+        let now = Instant::now();
+        if let Some(token) = self.held_token() {
+            if now >= self.deadline {
+                self.lose(now);
+                return;
+            }
+            let op = LeaseOp::Renew {
+                holder: self.holder.clone(),
+                token,
+                ttl_ms: self.ttl_ms(),
+            };
+            if self.client.emit(&self.topic, op).await.is_ok() {
+                self.renews_sent.push_back(now);
+            }
+            self.renew_at = now + self.ttl / 3;
+            return;
+        }
+
+        let op = LeaseOp::Acquire {
+            holder: self.holder.clone(),
+            ttl_ms: self.ttl_ms(),
+        };
+        match self.client.emit(&self.topic, op).await {
+            Ok(_) => {
+                self.acquire_sent = Some(now);
+                // In case our acquire never comes back
+                self.retry_at = now + self.ttl;
+            }
+            Err(_) => self.retry_at = now + self.ttl / 3,
+        }
+    }
+
+    async fn release(&mut self) -> Result<()> {
+        let token = self.held_token();
+        self.state_tx.send_replace(LeaseState::Stopped);
+        let Some(token) = token else {
+            return Ok(());
+        };
+        let op = LeaseOp::Release {
+            holder: self.holder.clone(),
+            token,
+        };
+        self.client.emit(&self.topic, op).await.map(|_| ())
+    }
+}
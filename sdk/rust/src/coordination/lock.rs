@@ -0,0 +1,111 @@
+//! Distributed locks over lease events.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::client::Notif;
+use crate::coordination::lease::{Lease, LeaseState};
+use crate::error::{NotifError, Result};
+
+/// A held lock, returned by [`Notif::lock`].
+///
+/// The lease behind it is renewed in the background every third of its
+/// TTL. If renewals stop getting through for a whole TTL the lock is lost,
+/// before anyone else can acquire it; [`LockGuard::lost`] resolves then.
+/// Dropping the guard releases the lock from the background.
+pub struct LockGuard {
+    name: String,
+    token: u64,
+    lease: Lease,
+}
+
+impl Notif {
+    /// Acquire the lock `name`, waiting for as long as someone else holds it.
+    ///
+    /// Locks are leases on the `_lease.<name>` topic, so they are shared by
+    /// every client of the project; all holders of a lock must use the same
+    /// `ttl` (at least 1s). Wrap the call in `tokio::time::timeout` to give
+    /// up waiting. Fails with `NotifError::InvalidTopic` if the name is not a
+    /// valid topic segment.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use std::time::Duration;
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let lock = client.lock("desktop-hub", Duration::from_secs(10)).await?;
+    /// println!("holding with fencing token {}", lock.token());
+    ///
+    /// tokio::select! {
+    ///     _ = lock.lost() => eprintln!("lost the lock, stopping"),
+    ///     _ = tokio::time::sleep(Duration::from_secs(60)) => {}
+    /// }
+    /// lock.release().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn lock(&self, name: &str, ttl: Duration) -> Result<LockGuard> {
+        let lease = Lease::start(self, name, ttl, false).await?;
+        let mut state_rx = lease.watch_state();
+        loop {
+            match *state_rx.borrow_and_update() {
+                LeaseState::Held { token } => {
+                    return Ok(LockGuard {
+                        name: name.to_string(),
+                        token,
+                        lease,
+                    })
+                }
+                LeaseState::Waiting => {}
+                LeaseState::Lost | LeaseState::Stopped => break,
+            }
+            if state_rx.changed().await.is_err() {
+                break;
+            }
+        }
+        Err(NotifError::connection(format!(
+            "lock '{}' subscription closed",
+            name
+        )))
+    }
+}
+
+impl LockGuard {
+    /// The fencing token of this acquisition.
+    ///
+    /// Tokens grow with each acquisition of the lock; pass it along to
+    /// storage that can reject writes from an older holder.
+    pub fn token(&self) -> u64 {
+        self.token
+    }
+
+    /// Whether the lock is still held.
+    pub fn is_held(&self) -> bool {
+        self.lease.state() == LeaseState::Held { token: self.token }
+    }
+
+    /// Wait until the lock is lost.
+    ///
+    /// Resolves right away if it already is.
+    pub async fn lost(&self) {
+        let mut state_rx = self.lease.watch_state();
+        let held = LeaseState::Held { token: self.token };
+        let _ = state_rx.wait_for(|state| *state != held).await;
+    }
+
+    /// Release the lock.
+    pub async fn release(self) -> Result<()> {
+        self.lease.release().await
+    }
+}
+
+impl fmt::Debug for LockGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockGuard")
+            .field("name", &self.name)
+            .field("token", &self.token)
+            .field("held", &self.is_held())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,9 @@
+//! Coordinating instances of a horizontally scaled service over topics:
+//! leader election and distributed locks.
+
+mod election;
+mod lease;
+mod lock;
+
+pub use election::{Election, LeaderElection};
+pub use lock::LockGuard;
@@ -0,0 +1,90 @@
+//! API keys that change over the lifetime of a client.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+
+use crate::error::Result;
+
+/// Supplies the API key for each request and connection.
+///
+/// Register with `NotifBuilder::with_credentials` to use short-lived keys
+/// from a secrets manager without rebuilding the client. `api_key` is
+/// called before every HTTP request and WebSocket connection, so it
+/// should return a cached key and only fetch a new one when it expires
+/// or `invalidate` is called.
+///
+/// When the server rejects a key, the client calls `invalidate` with it
+/// and retries once with a fresh key, both for HTTP requests and when
+/// (re)connecting subscriptions.
+///
+/// Async closures returning `Result<String>` implement this trait.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{Notif, NotifBuilder};
+///
+/// # async fn fetch_from_vault() -> notifsh::Result<String> { unimplemented!() }
+/// let client = NotifBuilder::with_credentials(|| async { fetch_from_vault().await }).build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+pub trait CredentialsProvider: Send + Sync + 'static {
+    /// Return the API key to authenticate with.
+    fn api_key(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// Called when the server rejected `api_key`, before asking for a new one.
+    fn invalidate(&self, api_key: &str) {
+        let _ = api_key;
+    }
+}
+
+impl<F, Fut> CredentialsProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String>> + Send + 'static,
+{
+    fn api_key(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(self())
+    }
+}
+
+/// Where the client gets its API key from.
+#[derive(Clone)]
+pub(crate) enum Credentials {
+    Static(String),
+    Provider(Arc<dyn CredentialsProvider>),
+}
+
+impl Credentials {
+    /// The API key to send now.
+    pub(crate) async fn api_key(&self) -> Result<String> {
+        match self {
+            Credentials::Static(api_key) => Ok(api_key.clone()),
+            Credentials::Provider(provider) => provider.api_key().await,
+        }
+    }
+
+    /// Whether a rejected key may be replaced by asking again.
+    pub(crate) fn refreshes(&self) -> bool {
+        matches!(self, Credentials::Provider(_))
+    }
+
+    /// Report that the server rejected `api_key`.
+    pub(crate) fn invalidate(&self, api_key: &str) {
+        if let Credentials::Provider(provider) = self {
+            provider.invalidate(api_key);
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::Static(_) => f.write_str("Static(..)"),
+            Credentials::Provider(_) => f.write_str("Provider(..)"),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Suppression of redelivered events, for `SubscribeOptions::dedupe_window`.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Event IDs seen within the window, oldest first.
+#[derive(Debug)]
+pub(crate) struct Dedupe {
+    window: Duration,
+    seen: HashMap<String, Instant>,
+    order: VecDeque<(Instant, String)>,
+}
+
+impl Dedupe {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Whether `id` was already seen within the window; remembers it if not.
+    pub(crate) fn is_duplicate(&mut self, id: &str) -> bool {
+        let now = Instant::now();
+        while let Some((at, _)) = self.order.front() {
+            if now.duration_since(*at) < self.window {
+                break;
+            }
+            if let Some((at, id)) = self.order.pop_front() {
+                // Only drop the entry if it was not forgotten and seen again since
+                if self.seen.get(&id) == Some(&at) {
+                    self.seen.remove(&id);
+                }
+            }
+        }
+
+        if self.seen.contains_key(id) {
+            return true;
+        }
+        self.seen.insert(id.to_string(), now);
+        self.order.push_back((now, id.to_string()));
+        false
+    }
+
+    /// Let the next delivery of `id` through, e.g. the retry of a nack.
+    pub(crate) fn forget(&mut self, id: &str) {
+        self.seen.remove(id);
+    }
+}
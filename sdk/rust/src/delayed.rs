@@ -0,0 +1,97 @@
+//! Emitting events later, through the schedule API.
+
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::client::Notif;
+use crate::duration;
+use crate::error::Result;
+use crate::types::CreateScheduleResponse;
+
+/// An event scheduled with [`Notif::emit_in`] or [`Notif::emit_at`].
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct ScheduledEmit {
+    /// Schedule ID, for the other schedule methods.
+    pub id: String,
+    /// Topic the event will be published to.
+    pub topic: String,
+    /// When the event will be emitted.
+    pub scheduled_for: DateTime<Utc>,
+    /// When the schedule was created.
+    pub created_at: DateTime<Utc>,
+    client: Notif,
+}
+
+impl ScheduledEmit {
+    fn new(client: &Notif, response: CreateScheduleResponse) -> Self {
+        Self {
+            id: response.id,
+            topic: response.topic,
+            scheduled_for: response.scheduled_for,
+            created_at: response.created_at,
+            client: client.clone(),
+        }
+    }
+
+    /// Cancel the event if it was not emitted yet.
+    pub async fn cancel(&self) -> Result<()> {
+        self.client.cancel_schedule(&self.id).await
+    }
+}
+
+impl fmt::Debug for ScheduledEmit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScheduledEmit")
+            .field("id", &self.id)
+            .field("topic", &self.topic)
+            .field("scheduled_for", &self.scheduled_for)
+            .field("created_at", &self.created_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Notif {
+    /// Emit an event after `delay`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use serde_json::json;
+    /// # use std::time::Duration;
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let reminder = client
+    ///     .emit_in("orders.reminder", json!({"order_id": "123"}), Duration::from_secs(1800))
+    ///     .await?;
+    ///
+    /// // The order was paid in the meantime
+    /// reminder.cancel().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn emit_in<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        delay: Duration,
+    ) -> Result<ScheduledEmit> {
+        let delay = duration::format(delay);
+        let response = self.schedule(topic, data, None, Some(&delay)).await?;
+        Ok(ScheduledEmit::new(self, response))
+    }
+
+    /// Emit an event at `at`.
+    pub async fn emit_at<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        at: DateTime<Utc>,
+    ) -> Result<ScheduledEmit> {
+        let response = self.schedule(topic, data, Some(at), None).await?;
+        Ok(ScheduledEmit::new(self, response))
+    }
+}
@@ -0,0 +1,80 @@
+//! Delivery tracking and acknowledged emits.
+
+use std::time::Duration;
+
+use reqwest::Method;
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::types::{DeliveriesResponse, Delivery};
+
+/// First delay between delivery checks in `emit_and_wait`.
+const POLL_INITIAL: Duration = Duration::from_millis(100);
+/// Longest delay between delivery checks in `emit_and_wait`.
+const POLL_MAX: Duration = Duration::from_secs(2);
+
+impl Notif {
+    /// List the deliveries of an event to subscribers and webhooks.
+    pub async fn deliveries(&self, event_id: &str) -> Result<Vec<Delivery>> {
+        let path = format!("/api/v1/events/{}/deliveries", event_id);
+        let response = self.execute(self.http(Method::GET, &path)).await?;
+        let list: DeliveriesResponse = response.json().await?;
+        Ok(list.deliveries)
+    }
+
+    /// Emit an event and wait until at least one receiver acknowledges it.
+    ///
+    /// Resolves with the first acknowledged delivery, or fails with
+    /// `NotifError::Timeout` if nothing acknowledges the event within
+    /// `timeout`. The event stays published either way.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, NotifError};
+    /// # use serde_json::json;
+    /// # use std::time::Duration;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    ///
+    /// match client
+    ///     .emit_and_wait("permissions.request", json!({"tool": "bash"}), Duration::from_secs(5))
+    ///     .await
+    /// {
+    ///     Ok(delivery) => println!("acked by {:?}", delivery.consumer_name),
+    ///     Err(NotifError::Timeout(_)) => println!("nobody is listening"),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn emit_and_wait<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        timeout: Duration,
+    ) -> Result<Delivery> {
+        let deadline = Instant::now() + timeout;
+        let event = self.emit(topic, data).await?;
+
+        let mut delay = POLL_INITIAL;
+        loop {
+            let deliveries = self.deliveries(&event.id).await?;
+            if let Some(delivery) = deliveries.into_iter().find(Delivery::is_acked) {
+                return Ok(delivery);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(NotifError::timeout(format!(
+                    "no receiver acknowledged event {} within {:?}",
+                    event.id, timeout
+                )));
+            }
+            tokio::time::sleep(delay.min(deadline - now)).await;
+            delay = (delay * 2).min(POLL_MAX);
+        }
+    }
+}
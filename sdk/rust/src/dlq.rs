@@ -0,0 +1,242 @@
+//! Replaying the dead letter queue with a transformation hook.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::types::EmitOptions;
+
+/// Header of a replayed event holding its sequence number in the DLQ.
+pub const DLQ_SEQ_HEADER: &str = "notif-dlq-seq";
+
+/// Header of a replayed event holding the ID it had before failing.
+pub const DLQ_ORIGINAL_ID_HEADER: &str = "notif-dlq-original-id";
+
+/// Header of a replayed event holding the error it failed with.
+pub const DLQ_ERROR_HEADER: &str = "notif-dlq-error";
+
+/// Entries `replay_dlq` lists at once by default.
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// An event in the dead letter queue.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct DlqEvent {
+    /// Position in the DLQ.
+    pub seq: u64,
+    /// ID the event had when it failed.
+    #[serde(rename = "event_id")]
+    pub id: String,
+    /// Topic it was published to.
+    pub topic: String,
+    /// Payload.
+    pub data: Value,
+    /// Custom headers it was emitted with.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Error of the last failed attempt, if the consumer reported one.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Delivery attempts made.
+    #[serde(default)]
+    pub attempts: u32,
+    /// When it was moved to the DLQ.
+    #[serde(rename = "created_at")]
+    pub failed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DlqListResponse {
+    #[serde(default)]
+    messages: Vec<DlqEvent>,
+}
+
+/// What `Notif::replay_dlq` does with a DLQ event.
+#[derive(Debug, Clone)]
+pub enum Transform {
+    /// Emit this payload to the original topic and remove the event from
+    /// the DLQ.
+    Replay(Value),
+    /// Leave the event in the DLQ.
+    Skip,
+    /// Remove the event from the DLQ without emitting it.
+    Discard,
+}
+
+/// Options for `Notif::replay_dlq_with`.
+#[derive(Debug, Clone)]
+pub struct DlqReplayOptions {
+    /// Only handle events after this DLQ position (see `DlqReplay::cursor`).
+    pub after: Option<u64>,
+    /// Events listed per request (default: 100).
+    pub page_size: u32,
+}
+
+impl DlqReplayOptions {
+    /// Create options starting at the beginning of the DLQ.
+    pub fn new() -> Self {
+        Self {
+            after: None,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Resume after the DLQ position `cursor` of an earlier replay.
+    pub fn after(mut self, cursor: u64) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    /// List `size` events per request.
+    pub fn page_size(mut self, size: u32) -> Self {
+        self.page_size = size.max(1);
+        self
+    }
+}
+
+impl Default for DlqReplayOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of `Notif::replay_dlq`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DlqReplay {
+    /// Events emitted again.
+    pub replayed: u64,
+    /// Events left in the DLQ.
+    pub skipped: u64,
+    /// Events removed without being emitted.
+    pub discarded: u64,
+    /// DLQ position of the last event handled; pass it to
+    /// `DlqReplayOptions::after` to continue from there.
+    pub cursor: Option<u64>,
+}
+
+impl Notif {
+    /// Replay the dead letter queue of `topic`, oldest first, letting
+    /// `transform` patch each payload or leave the event out.
+    ///
+    /// See [`replay_dlq_with`](Self::replay_dlq_with).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, Transform};
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// // `customer` was renamed to `customer_id` after these events failed
+    /// let report = client
+    ///     .replay_dlq("orders.*", |mut event| {
+    ///         let Some(data) = event.data.as_object_mut() else {
+    ///             return Transform::Skip;
+    ///         };
+    ///         if let Some(customer) = data.remove("customer") {
+    ///             data.insert("customer_id".to_string(), customer);
+    ///         }
+    ///         Transform::Replay(event.data)
+    ///     })
+    ///     .await?;
+    /// println!("replayed {}, skipped {}", report.replayed, report.skipped);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn replay_dlq<F>(&self, topic: &str, transform: F) -> Result<DlqReplay>
+    where
+        F: FnMut(DlqEvent) -> Transform,
+    {
+        self.replay_dlq_with(topic, DlqReplayOptions::new(), transform)
+            .await
+    }
+
+    /// Replay the dead letter queue of `topic` with custom options.
+    ///
+    /// `topic` may be a pattern such as `orders.*`. Replayed events are
+    /// emitted to their original topic with their headers plus
+    /// [`DLQ_SEQ_HEADER`], [`DLQ_ORIGINAL_ID_HEADER`] and
+    /// [`DLQ_ERROR_HEADER`], then removed from the DLQ, so an interrupted
+    /// replay picks up where it stopped when run again. Skipped events stay; resume after them with
+    /// `DlqReplay::cursor`.
+    ///
+    /// Stops at the first failed request. An event whose emit succeeded
+    /// but whose removal failed is replayed again by the next run;
+    /// consumers can spot the duplicate by its `DLQ_SEQ_HEADER`.
+    pub async fn replay_dlq_with<F>(
+        &self,
+        topic: &str,
+        options: DlqReplayOptions,
+        mut transform: F,
+    ) -> Result<DlqReplay>
+    where
+        F: FnMut(DlqEvent) -> Transform,
+    {
+        let mut report = DlqReplay {
+            cursor: options.after,
+            ..DlqReplay::default()
+        };
+        let mut limit = options.page_size;
+        loop {
+            let query = [("topic", topic.to_string()), ("limit", limit.to_string())];
+            let response = self
+                .execute(self.http(Method::GET, "/api/v1/dlq").query(&query))
+                .await?;
+            let list: DlqListResponse = response.json().await?;
+            let listed = list.messages.len();
+
+            // The DLQ is listed from its start, including what was skipped
+            let mut events: Vec<DlqEvent> = list
+                .messages
+                .into_iter()
+                .filter(|event| report.cursor.is_none_or(|cursor| event.seq > cursor))
+                .collect();
+            events.sort_by_key(|event| event.seq);
+            if events.is_empty() {
+                if listed < limit as usize {
+                    return Ok(report);
+                }
+                // A full page of skipped events; look further
+                limit = limit.saturating_mul(2);
+                continue;
+            }
+
+            for event in events {
+                let (seq, id, original) = (event.seq, event.id.clone(), event.topic.clone());
+                let error = event.error.clone().unwrap_or_default();
+                let headers = event.headers.clone();
+                match transform(event) {
+                    Transform::Replay(data) => {
+                        let options = EmitOptions::new()
+                            .headers(&headers)
+                            .header(DLQ_SEQ_HEADER, seq.to_string())
+                            .header(DLQ_ORIGINAL_ID_HEADER, id)
+                            .header(DLQ_ERROR_HEADER, error.replace(['\r', '\n'], " "));
+                        self.emit_with_options(&original, data, options).await?;
+                        self.remove_from_dlq(seq).await?;
+                        report.replayed += 1;
+                    }
+                    Transform::Skip => report.skipped += 1,
+                    Transform::Discard => {
+                        self.remove_from_dlq(seq).await?;
+                        report.discarded += 1;
+                    }
+                }
+                report.cursor = Some(seq);
+            }
+            if listed < limit as usize {
+                return Ok(report);
+            }
+        }
+    }
+
+    async fn remove_from_dlq(&self, seq: u64) -> Result<()> {
+        let path = format!("/api/v1/dlq/{seq}");
+        self.execute(self.http(Method::DELETE, &path)).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Reporting what subscriptions drop.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::types::Event;
+
+/// Why a subscription dropped something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DropReason {
+    /// A frame from the server could not be decoded into a message, or an
+    /// event in it was incomplete.
+    Malformed,
+    /// The event buffer was full under `BackpressurePolicy::DropOldest` or
+    /// `DropNewest`. The event is left unsettled for redelivery.
+    BufferFull,
+    /// The payload did not match the type of a typed stream.
+    Undecodable,
+}
+
+/// Something a subscription dropped, as passed to a [`DroppedEventHandler`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DroppedEvent {
+    /// Why it was dropped.
+    pub reason: DropReason,
+    /// The event, when the frame was decoded far enough to have one.
+    pub event: Option<Event>,
+    /// The text frame as received, when it could not be made into an event.
+    pub frame: Option<String>,
+    /// What went wrong decoding it, if anything.
+    pub error: Option<String>,
+}
+
+impl DroppedEvent {
+    pub(crate) fn new(reason: DropReason) -> Self {
+        Self {
+            reason,
+            event: None,
+            frame: None,
+            error: None,
+        }
+    }
+
+    pub(crate) fn event(mut self, event: Event) -> Self {
+        self.event = Some(event);
+        self
+    }
+
+    pub(crate) fn frame(mut self, frame: Option<&str>) -> Self {
+        self.frame = frame.map(String::from);
+        self
+    }
+
+    pub(crate) fn error(mut self, error: impl fmt::Display) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+}
+
+/// Called whenever a subscription drops a frame or event.
+///
+/// Register with `NotifBuilder::on_drop`. It runs on the subscription's
+/// background task, so it should return quickly, e.g. by logging. Closures
+/// taking a `&DroppedEvent` implement it.
+///
+/// Malformed frames and undecodable payloads are still yielded as errors on
+/// the stream; the handler additionally sees the raw frame or the event.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::Notif;
+///
+/// let client = Notif::builder("nsh_your_api_key")
+///     .on_drop(|dropped: &notifsh::DroppedEvent| {
+///         eprintln!("dropped ({:?}): {:?}", dropped.reason, dropped.frame);
+///     })
+///     .build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+pub trait DroppedEventHandler: Send + Sync + 'static {
+    /// Called with what was dropped.
+    fn on_drop(&self, dropped: &DroppedEvent);
+}
+
+impl<F> DroppedEventHandler for F
+where
+    F: Fn(&DroppedEvent) + Send + Sync + 'static,
+{
+    fn on_drop(&self, dropped: &DroppedEvent) {
+        self(dropped)
+    }
+}
+
+/// The handler registered with `NotifBuilder::on_drop`, if any.
+#[derive(Clone, Default)]
+pub(crate) struct DropHandler(Option<Arc<dyn DroppedEventHandler>>);
+
+impl DropHandler {
+    pub(crate) fn new(handler: impl DroppedEventHandler) -> Self {
+        Self(Some(Arc::new(handler)))
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Report a drop, building it only when a handler is registered.
+    pub(crate) fn report(&self, dropped: impl FnOnce() -> DroppedEvent) {
+        if let Some(handler) = &self.0 {
+            handler.on_drop(&dropped());
+        }
+    }
+}
+
+impl fmt::Debug for DropHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("DropHandler(Some(..))"),
+            None => f.write_str("DropHandler(None)"),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Conversion between `Duration` and the server's duration strings.
+//!
+//! The server parses durations with Go's `time.ParseDuration`: a sequence
+//! of decimal numbers, each with an optional fraction and a unit suffix
+//! (`ns`, `us`, `µs`, `ms`, `s`, `m`, `h`), such as `"90s"` or `"1h30m"`.
+
+use std::time::Duration;
+
+use crate::error::{NotifError, Result};
+
+/// Format `duration` so the server parses it back exactly.
+pub(crate) fn format(duration: Duration) -> String {
+    if !duration.subsec_nanos().is_multiple_of(1_000_000) {
+        return format!("{}ns", duration.as_nanos());
+    }
+    if duration < Duration::from_secs(1) {
+        return format!("{}ms", duration.as_millis());
+    }
+
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    let millis = duration.subsec_millis();
+    let mut out = String::new();
+    if hours > 0 {
+        out.push_str(&format!("{}h", hours));
+    }
+    if minutes > 0 {
+        out.push_str(&format!("{}m", minutes));
+    }
+    if seconds > 0 || millis > 0 {
+        out.push_str(&seconds.to_string());
+        if millis > 0 {
+            out.push_str(format!(".{:03}", millis).trim_end_matches('0'));
+        }
+        out.push('s');
+    }
+    out
+}
+
+/// Parse a server duration string, rejecting what the server would ignore.
+pub(crate) fn parse(value: &str) -> Result<Duration> {
+    let invalid = |reason: &str| NotifError::InvalidDuration {
+        value: value.to_string(),
+        reason: reason.to_string(),
+    };
+
+    if value == "0" {
+        return Ok(Duration::ZERO);
+    }
+    if value.is_empty() {
+        return Err(invalid("empty duration"));
+    }
+    if value.starts_with('-') {
+        return Err(invalid("negative durations are not allowed"));
+    }
+
+    let mut rest = value.strip_prefix('+').unwrap_or(value);
+    let mut total = 0f64;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len]
+            .parse()
+            .map_err(|_| invalid("expected a number"))?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let nanos_per_unit = match &rest[..unit_len] {
+            "ns" => 1.0,
+            "us" | "µs" | "μs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            "" => return Err(invalid("missing unit")),
+            _ => return Err(invalid("unknown unit, expected ns, us, ms, s, m or h")),
+        };
+        rest = &rest[unit_len..];
+        total += number * nanos_per_unit;
+    }
+
+    Ok(Duration::from_nanos(total.round() as u64))
+}
@@ -0,0 +1,347 @@
+//! Client-side envelope encryption of payloads (`encryption` feature).
+//!
+//! Each payload is encrypted with AES-256-GCM under a data key, and the
+//! data key is wrapped by a [`KeyProvider`], such as a KMS or
+//! [`LocalKeys`]. The payload is replaced with an envelope holding the
+//! ciphertext, its nonce, the wrapped key and the ID of the key that
+//! wrapped it, so notif.sh only ever stores ciphertext:
+//!
+//! ```json
+//! {
+//!   "alg": "aes-256-gcm",
+//!   "key_id": "2026-10",
+//!   "wrapped_key": "<base64>",
+//!   "nonce": "<base64>",
+//!   "ciphertext": "<base64>"
+//! }
+//! ```
+//!
+//! The ciphertext is bound to the event's topic and the key ID, so it
+//! cannot be replayed under another topic. A data key is reused for an
+//! hour of emits, and unwrapped keys are cached, so the provider is not
+//! called for every event.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures_util::future::BoxFuture;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::error::{NotifError, Result};
+use crate::interceptor::OutgoingEvent;
+use crate::types::Event;
+
+const AES_256_GCM_NAME: &str = "aes-256-gcm";
+
+/// How long emits reuse a data key before wrapping a new one.
+const DATA_KEY_TTL: Duration = Duration::from_secs(3600);
+
+/// Unwrapped data keys kept for decrypting.
+const UNWRAPPED_CACHE_SIZE: usize = 256;
+
+/// A data key wrapped by a [`KeyProvider`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct WrappedKey {
+    /// ID of the key that wrapped it, e.g. a KMS key ARN.
+    pub key_id: String,
+    /// The wrapped key bytes.
+    pub wrapped: Vec<u8>,
+}
+
+impl WrappedKey {
+    /// A data key wrapped by `key_id`.
+    pub fn new(key_id: impl Into<String>, wrapped: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key_id: key_id.into(),
+            wrapped: wrapped.into(),
+        }
+    }
+}
+
+/// Wraps and unwraps the data keys payloads are encrypted with.
+///
+/// Register with `NotifBuilder::encryption`. Implement it on top of a
+/// KMS to keep the master keys out of the process, or use
+/// [`LocalKeys`] with keys from your own secret store.
+pub trait KeyProvider: Send + Sync + 'static {
+    /// Wrap a freshly generated 32-byte data key with the current master
+    /// key.
+    fn wrap_key(&self, data_key: Vec<u8>) -> BoxFuture<'_, Result<WrappedKey>>;
+
+    /// Unwrap a data key wrapped by `wrap_key`, possibly with a master key
+    /// that has since been rotated out.
+    fn unwrap_key(&self, key: WrappedKey) -> BoxFuture<'_, Result<Vec<u8>>>;
+}
+
+/// A [`KeyProvider`] holding 256-bit master keys in memory.
+///
+/// New data keys are wrapped with the key given to `new`; keys added with
+/// `retired` still unwrap older payloads after a rotation.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{LocalKeys, Notif};
+///
+/// # fn load_key(id: &str) -> [u8; 32] { unimplemented!() }
+/// let keys = LocalKeys::new("2026-10", load_key("2026-10")).retired("2026-04", load_key("2026-04"));
+/// let client = Notif::builder("nsh_...").encryption(keys).build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+#[derive(Clone)]
+pub struct LocalKeys {
+    current: String,
+    keys: HashMap<String, Arc<LessSafeKey>>,
+    rng: SystemRandom,
+}
+
+impl LocalKeys {
+    /// Wrap data keys with `key`, identified as `key_id`.
+    pub fn new(key_id: impl Into<String>, key: [u8; 32]) -> Self {
+        let current = key_id.into();
+        let keys = HashMap::from([(current.clone(), Arc::new(aead_key(&key)))]);
+        Self {
+            current,
+            keys,
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Also unwrap data keys wrapped by an older `key`.
+    pub fn retired(mut self, key_id: impl Into<String>, key: [u8; 32]) -> Self {
+        self.keys
+            .entry(key_id.into())
+            .or_insert_with(|| Arc::new(aead_key(&key)));
+        self
+    }
+}
+
+impl KeyProvider for LocalKeys {
+    fn wrap_key(&self, data_key: Vec<u8>) -> BoxFuture<'_, Result<WrappedKey>> {
+        let key = &self.keys[&self.current];
+        let sealed = seal(key, &self.rng, self.current.as_bytes(), data_key);
+        Box::pin(async move { Ok(WrappedKey::new(self.current.clone(), sealed?)) })
+    }
+
+    fn unwrap_key(&self, key: WrappedKey) -> BoxFuture<'_, Result<Vec<u8>>> {
+        let opened = match self.keys.get(&key.key_id) {
+            Some(master) => open(master, key.key_id.as_bytes(), key.wrapped),
+            None => Err(NotifError::Encryption(format!(
+                "unknown key '{}'",
+                key.key_id
+            ))),
+        };
+        Box::pin(async move { opened })
+    }
+}
+
+impl fmt::Debug for LocalKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKeys")
+            .field("current", &self.current)
+            .field("keys", &self.keys.len())
+            .finish_non_exhaustive()
+    }
+}
+
+struct DataKey {
+    key: Arc<LessSafeKey>,
+    wrapped: WrappedKey,
+    created: Instant,
+}
+
+/// Encrypts emitted payloads and decrypts received ones.
+pub(crate) struct Encryption {
+    provider: Arc<dyn KeyProvider>,
+    rng: SystemRandom,
+    /// Data key new payloads are encrypted with.
+    current: tokio::sync::Mutex<Option<DataKey>>,
+    /// Unwrapped data keys by wrapped key.
+    unwrapped: Mutex<HashMap<WrappedKey, Arc<LessSafeKey>>>,
+}
+
+impl Encryption {
+    pub(crate) fn new(provider: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            provider,
+            rng: SystemRandom::new(),
+            current: tokio::sync::Mutex::new(None),
+            unwrapped: Mutex::default(),
+        }
+    }
+
+    /// Replace the payload of `event` with its envelope.
+    pub(crate) async fn seal(&self, event: &OutgoingEvent) -> Result<OutgoingEvent> {
+        let (key, wrapped) = self.data_key().await?;
+        let plaintext = serde_json::to_vec(&event.data)?;
+        let aad = aad(&event.topic, &wrapped.key_id);
+        let mut nonce = seal(&key, &self.rng, &aad, plaintext)?;
+        let ciphertext = nonce.split_off(NONCE_LEN);
+
+        let envelope = Envelope {
+            alg: AES_256_GCM_NAME.to_string(),
+            key_id: wrapped.key_id,
+            wrapped_key: STANDARD.encode(wrapped.wrapped),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        Ok(OutgoingEvent {
+            topic: event.topic.clone(),
+            data: serde_json::to_value(envelope)?,
+            headers: event.headers.clone(),
+            ack_level: event.ack_level,
+        })
+    }
+
+    /// Restore the payload of `event` from its envelope.
+    ///
+    /// Fails for any payload that is not an envelope, so a producer
+    /// without the key cannot slip plaintext past consumers.
+    pub(crate) async fn open(&self, mut event: Event) -> Result<Event> {
+        let envelope = Envelope::deserialize(&event.data)
+            .map_err(|_| NotifError::Encryption("payload is not encrypted".to_string()))?;
+        if envelope.alg != AES_256_GCM_NAME {
+            return Err(NotifError::Encryption(format!(
+                "unsupported algorithm '{}'",
+                envelope.alg
+            )));
+        }
+        let nonce = decode(&envelope.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(NotifError::Encryption("invalid nonce".to_string()));
+        }
+        let wrapped = WrappedKey::new(envelope.key_id, decode(&envelope.wrapped_key)?);
+        let mut sealed = nonce;
+        sealed.extend(decode(&envelope.ciphertext)?);
+
+        let key = self.unwrap(&wrapped).await?;
+        let plaintext = open(&key, &aad(&event.topic, &wrapped.key_id), sealed)?;
+        event.data = serde_json::from_slice(&plaintext)?;
+        Ok(event)
+    }
+
+    /// The current data key, wrapping a new one when it has expired.
+    async fn data_key(&self) -> Result<(Arc<LessSafeKey>, WrappedKey)> {
+        let mut current = self.current.lock().await;
+        if let Some(data_key) = current
+            .as_ref()
+            .filter(|data_key| data_key.created.elapsed() < DATA_KEY_TTL)
+        {
+            return Ok((data_key.key.clone(), data_key.wrapped.clone()));
+        }
+
+        let mut bytes = vec![0; 32];
+        self.rng
+            .fill(&mut bytes)
+            .map_err(|_| NotifError::Encryption("no randomness available".to_string()))?;
+        let key = Arc::new(aead_key(&bytes));
+        let wrapped = self.provider.wrap_key(bytes).await?;
+        *current = Some(DataKey {
+            key: key.clone(),
+            wrapped: wrapped.clone(),
+            created: Instant::now(),
+        });
+        Ok((key, wrapped))
+    }
+
+    async fn unwrap(&self, wrapped: &WrappedKey) -> Result<Arc<LessSafeKey>> {
+        if let Some(key) = self.unwrapped.lock().unwrap().get(wrapped) {
+            return Ok(key.clone());
+        }
+        let bytes = self.provider.unwrap_key(wrapped.clone()).await?;
+        if bytes.len() != 32 {
+            return Err(NotifError::Encryption(
+                "unwrapped data key is not 32 bytes".to_string(),
+            ));
+        }
+        let key = Arc::new(aead_key(&bytes));
+        let mut unwrapped = self.unwrapped.lock().unwrap();
+        if unwrapped.len() >= UNWRAPPED_CACHE_SIZE {
+            unwrapped.clear();
+        }
+        unwrapped.insert(wrapped.clone(), key.clone());
+        Ok(key)
+    }
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encryption").finish_non_exhaustive()
+    }
+}
+
+/// An encrypted payload, as it is emitted.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Envelope {
+    alg: String,
+    key_id: String,
+    wrapped_key: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// The associated data a payload is sealed with: the algorithm, topic and
+/// key ID. Topics cannot contain newlines, so the fields cannot run into
+/// each other.
+fn aad(topic: &str, key_id: &str) -> Vec<u8> {
+    format!("{AES_256_GCM_NAME}\n{topic}\n{key_id}").into_bytes()
+}
+
+/// An AES-256-GCM key; `bytes` must be 32 bytes long.
+fn aead_key(bytes: &[u8]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, bytes).expect("AES-256 keys are 32 bytes"))
+}
+
+/// Encrypt `plaintext` under a random nonce, returning the nonce followed
+/// by the ciphertext and tag.
+fn seal(key: &LessSafeKey, rng: &SystemRandom, aad: &[u8], plaintext: Vec<u8>) -> Result<Vec<u8>> {
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill(&mut nonce)
+        .map_err(|_| NotifError::Encryption("no randomness available".to_string()))?;
+    let mut sealed = plaintext;
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(aad),
+        &mut sealed,
+    )
+    .map_err(|_| NotifError::Encryption("encryption failed".to_string()))?;
+    sealed.splice(0..0, nonce);
+    Ok(sealed)
+}
+
+/// Decrypt the output of `seal`.
+fn open(key: &LessSafeKey, aad: &[u8], sealed: Vec<u8>) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(NotifError::Encryption("ciphertext too short".to_string()));
+    }
+    let mut ciphertext = sealed;
+    let nonce: [u8; NONCE_LEN] = ciphertext[..NONCE_LEN].try_into().unwrap();
+    let plaintext_len = key
+        .open_within(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut ciphertext,
+            NONCE_LEN..,
+        )
+        .map_err(|_| {
+            NotifError::Encryption("decryption failed: wrong key or tampered payload".to_string())
+        })?
+        .len();
+    ciphertext.truncate(plaintext_len);
+    Ok(ciphertext)
+}
+
+fn decode(encoded: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| NotifError::Encryption(format!("invalid base64: {e}")))
+}
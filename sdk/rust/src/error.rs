@@ -1,5 +1,8 @@
 //! Error types for the notif.sh SDK.
 
+use std::time::Duration;
+
+use serde::Deserialize;
 use thiserror::Error;
 
 /// Result type alias using NotifError.
@@ -12,10 +15,23 @@ pub enum NotifError {
     #[error("authentication error: {0}")]
     Auth(String),
 
-    /// API error with HTTP status code.
+    /// API error with HTTP status code, for statuses without a variant of
+    /// their own.
     #[error("API error ({status}): {message}")]
     Api { status: u16, message: String },
 
+    /// The server throttled the request (429).
+    #[error("rate limited: {message}")]
+    RateLimited {
+        /// How long the server asked to wait, from `Retry-After`.
+        retry_after: Option<Duration>,
+        message: String,
+    },
+
+    /// The requested resource does not exist (404).
+    #[error("not found: {0}")]
+    NotFound(String),
+
     /// Connection error (network, WebSocket).
     #[error("connection error: {0}")]
     Connection(String),
@@ -32,6 +48,134 @@ pub enum NotifError {
     #[error("WebSocket error: {0}")]
     WebSocket(String),
 
+    /// An event payload could not be decoded into the requested type.
+    #[error("failed to decode event {id} on '{topic}': {source}")]
+    Decode {
+        id: String,
+        topic: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A topic name or pattern is malformed.
+    #[error("invalid topic '{topic}': {reason}")]
+    InvalidTopic { topic: String, reason: String },
+
+    /// A payload exceeds the maximum size, checked before sending when
+    /// chunking is disabled or reported by the server (413).
+    #[error("payload of {size} bytes exceeds the {max} byte limit")]
+    PayloadTooLarge { size: usize, max: usize },
+
+    /// A chunked emit failed after some of its parts were emitted.
+    ///
+    /// Subscribers discard the parts already emitted once they give up
+    /// waiting for the rest; emitting the event again splits it under a new
+    /// chunk ID.
+    #[error("chunked event {chunk_id} failed after {sent} of {count} parts: {source}")]
+    PartialEmit {
+        /// Correlation ID of the parts, from the `notif-chunk-id` header.
+        chunk_id: String,
+        /// Number of parts emitted before the failure.
+        sent: usize,
+        /// Number of parts the event was split into.
+        count: usize,
+        #[source]
+        source: Box<NotifError>,
+    },
+
+    /// A subscription buffer filled up under `BackpressurePolicy::Error`.
+    #[error("subscription buffer of {capacity} events overflowed")]
+    BufferOverflow { capacity: usize },
+
+    /// The server stopped answering heartbeats on a subscription.
+    #[error("connection stale: no response to {missed} heartbeats")]
+    ConnectionStale { missed: u32 },
+
+    /// A duration string is not in the server's format.
+    #[error("invalid duration '{value}': {reason}")]
+    InvalidDuration { value: String, reason: String },
+
+    /// A subscription filter expression is malformed.
+    #[error("invalid filter '{expr}': {reason}")]
+    InvalidFilter { expr: String, reason: String },
+
+    /// An ordering key path is malformed.
+    #[error("invalid key path '{path}': {reason}")]
+    InvalidKeyPath { path: String, reason: String },
+
+    /// A JSON Patch could not be applied: an operation's path does not
+    /// exist or a `test` operation failed.
+    #[error("JSON patch failed at '{path}': {reason}")]
+    PatchFailed { path: String, reason: String },
+
+    /// An event cursor string is malformed.
+    #[error("invalid event cursor '{0}'")]
+    InvalidCursor(String),
+
+    /// A CloudEvent cannot be represented as a notif event.
+    #[error("invalid CloudEvent: {0}")]
+    InvalidCloudEvent(String),
+
+    /// The operation is not allowed, by the client's mode or by the
+    /// server for this key (403).
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
+    /// A conditional update lost to a concurrent change, or the request
+    /// clashes with existing state (409, 412).
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    /// No client is registered for a tenant in a `NotifRegistry`.
+    #[error("unknown tenant '{0}'")]
+    UnknownTenant(String),
+
+    /// A payload does not match the JSON Schema registered for its topic.
+    ///
+    /// `path` locates the first failing value: a JSON pointer such as
+    /// `/items/0` when checked locally, the server's field path otherwise.
+    #[error("schema violation at '{path}': {message}")]
+    SchemaViolation { path: String, message: String },
+
+    /// An operation did not complete in time.
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    /// A profile file or configuration variable is missing or malformed.
+    #[error("configuration error: {0}")]
+    Config(String),
+
+    /// Reading or writing a local file failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A payload could not be encrypted or decrypted, or its data key
+    /// could not be wrapped or unwrapped.
+    #[cfg(feature = "encryption")]
+    #[error("encryption error: {0}")]
+    Encryption(String),
+
+    /// An event is unsigned, signed by an untrusted key, or was changed
+    /// after it was signed.
+    #[cfg(feature = "signing")]
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+
+    /// A database query of the transactional outbox or SQLite sink failed.
+    #[cfg(any(feature = "sqlx", feature = "sqlite"))]
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    /// A Redis command failed (`redis` feature).
+    #[cfg(feature = "redis")]
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+
+    /// An SMTP server rejected a command (`smtp` feature).
+    #[cfg(feature = "smtp")]
+    #[error("SMTP error: {0}")]
+    Smtp(String),
+
     /// URL parsing error.
     #[error("invalid URL: {0}")]
     Url(#[from] url::ParseError),
@@ -56,8 +200,136 @@ impl NotifError {
         Self::Connection(msg.into())
     }
 
+    /// Create a timeout error.
+    pub fn timeout(msg: impl Into<String>) -> Self {
+        Self::Timeout(msg.into())
+    }
+
     /// Create a WebSocket error.
     pub fn websocket(msg: impl Into<String>) -> Self {
         Self::WebSocket(msg.into())
     }
+
+    /// Whether the operation may succeed if tried again later.
+    ///
+    /// True for rate limiting, server errors (5xx but 501), timeouts and lost
+    /// connections; false for errors that need a different request, such
+    /// as bad input, missing resources or rejected credentials.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use serde_json::json;
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// loop {
+    ///     match client.emit("orders.created", json!({"id": 1})).await {
+    ///         Err(e) if e.is_retryable() => {
+    ///             let wait = e.retry_after().unwrap_or(std::time::Duration::from_secs(1));
+    ///             tokio::time::sleep(wait).await;
+    ///         }
+    ///         result => break result.map(drop),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. }
+            | Self::Connection(_)
+            | Self::WebSocket(_)
+            | Self::ConnectionStale { .. }
+            | Self::Timeout(_) => true,
+            Self::Api { status, .. } => *status >= 500 && *status != 501,
+            Self::PartialEmit { source, .. } => source.is_retryable(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Http(e) => e.is_timeout() || e.is_connect(),
+            // Fetch does not tell connection failures apart
+            #[cfg(target_arch = "wasm32")]
+            Self::Http(e) => e.is_timeout(),
+            #[cfg(feature = "redis")]
+            Self::Redis(e) => e.is_io_error() || e.is_connection_dropped(),
+            _ => false,
+        }
+    }
+
+    /// How long the server asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Classify an error response from the server.
+    ///
+    /// `body` is the raw response body, usually `{"error": "..."}`, and
+    /// `sent` the size of the rejected request body, if known.
+    pub(crate) fn from_response(
+        status: u16,
+        body: &str,
+        retry_after: Option<Duration>,
+        sent: Option<usize>,
+    ) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            #[serde(alias = "message")]
+            error: String,
+            #[serde(default)]
+            code: Option<String>,
+            #[serde(default)]
+            validation_errors: Vec<FieldError>,
+        }
+
+        #[derive(Deserialize)]
+        struct FieldError {
+            #[serde(default)]
+            field: String,
+            message: String,
+        }
+
+        let (message, code) = match serde_json::from_str::<ErrorBody>(body) {
+            Ok(mut parsed) if !parsed.validation_errors.is_empty() => {
+                let first = parsed.validation_errors.swap_remove(0);
+                return Self::SchemaViolation {
+                    path: first.field,
+                    message: first.message,
+                };
+            }
+            Ok(parsed) => (parsed.error, parsed.code),
+            Err(_) => (body.trim().to_string(), None),
+        };
+        // Machine-readable codes win over the status where the server sends them
+        match (code.as_deref(), status) {
+            (Some("unauthorized"), _) | (None, 401) => Self::Auth(message),
+            (Some("forbidden"), _) | (None, 403) => Self::Forbidden(message),
+            (Some("not_found"), _) | (None, 404) => Self::NotFound(message),
+            (Some("conflict"), _) | (None, 409 | 412) => Self::Conflict(message),
+            (Some("payload_too_large"), _) | (None, 413) => Self::PayloadTooLarge {
+                size: sent.unwrap_or_default(),
+                max: max_size(&message).unwrap_or_default(),
+            },
+            (Some("rate_limited"), _) | (None, 429) => Self::RateLimited {
+                retry_after,
+                message,
+            },
+            _ => Self::Api { status, message },
+        }
+    }
+}
+
+/// The limit in a message like `payload too large, max 256KB`.
+fn max_size(message: &str) -> Option<usize> {
+    let limit = message.split("max ").nth(1)?;
+    let digits: String = limit.chars().take_while(char::is_ascii_digit).collect();
+    let value: usize = digits.parse().ok()?;
+    let unit = limit[digits.len()..].trim_start();
+    let scale = if unit.starts_with("MB") {
+        1024 * 1024
+    } else if unit.starts_with("KB") {
+        1024
+    } else {
+        1
+    };
+    Some(value * scale)
 }
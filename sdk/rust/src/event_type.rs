@@ -0,0 +1,101 @@
+//! Event types bound to a topic at compile time.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::subscribe::TypedEventStream;
+use crate::types::{EmitOptions, EmitResponse, SubscribeOptions};
+
+/// A payload type that always travels on the same topic.
+///
+/// Emitting with `Notif::emit_event` and subscribing with
+/// `Notif::subscribe_events` take the topic from the type, so a payload
+/// cannot be sent to, or decoded from, the wrong topic by mistake. With
+/// the `derive` feature, `#[derive(NotifEvent)]` implements it and checks
+/// the topic at compile time.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use notifsh::NotifEvent;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, NotifEvent)]
+/// #[notif(topic = "orders.created")]
+/// struct OrderCreated {
+///     order_id: String,
+/// }
+///
+/// assert_eq!(OrderCreated::TOPIC, "orders.created");
+/// # }
+/// ```
+pub trait NotifEvent: Serialize + DeserializeOwned {
+    /// The topic events of this type are emitted to.
+    const TOPIC: &'static str;
+}
+
+impl Notif {
+    /// Emit `event` to the topic of its type.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, NotifEvent};
+    /// # #[derive(serde::Serialize, serde::Deserialize)]
+    /// # struct OrderCreated { order_id: String }
+    /// # impl NotifEvent for OrderCreated { const TOPIC: &'static str = "orders.created"; }
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// client
+    ///     .emit_event(&OrderCreated { order_id: "123".into() })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn emit_event<E: NotifEvent>(&self, event: &E) -> Result<EmitResponse> {
+        self.emit(E::TOPIC, event).await
+    }
+
+    /// Emit `event` to the topic of its type with custom options.
+    pub async fn emit_event_with_options<E: NotifEvent>(
+        &self,
+        event: &E,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        self.emit_with_options(E::TOPIC, event, options).await
+    }
+
+    /// Subscribe to the topic of `E`, decoding every payload into it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, NotifEvent};
+    /// # use futures::StreamExt;
+    /// # #[derive(serde::Serialize, serde::Deserialize)]
+    /// # struct OrderCreated { order_id: String }
+    /// # impl NotifEvent for OrderCreated { const TOPIC: &'static str = "orders.created"; }
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let mut orders = client.subscribe_events::<OrderCreated>().await?;
+    /// while let Some(order) = orders.next().await {
+    ///     println!("order {}", order?.data.order_id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_events<E: NotifEvent>(&self) -> Result<TypedEventStream<E>> {
+        self.subscribe_typed(&[E::TOPIC]).await
+    }
+
+    /// Subscribe to the topic of `E` with custom options.
+    pub async fn subscribe_events_with_options<E: NotifEvent>(
+        &self,
+        options: SubscribeOptions,
+    ) -> Result<TypedEventStream<E>> {
+        self.subscribe_typed_with_options(&[E::TOPIC], options)
+            .await
+    }
+}
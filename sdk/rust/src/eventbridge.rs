@@ -0,0 +1,451 @@
+//! Relaying events to an AWS EventBridge bus (`eventbridge` feature).
+//!
+//! An [`EventBridgeForwarder`] subscribes to some topics and sends their
+//! events to a bus with `PutEvents`, in batches of up to ten entries:
+//! the topic becomes the entry's `detail-type` and the payload its
+//! `detail`, so EventBridge rules can route them to Lambdas, Step
+//! Functions or any other target. Requests are signed with AWS
+//! Signature Version 4 by [`aws_sigv4`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::eventbridge::{AwsCredentials, EventBridgeForwarder};
+//! use notifsh::Notif;
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! EventBridgeForwarder::new(&client, &["orders.*"], "orders-bus")
+//!     .region("eu-west-1")
+//!     .credentials(AwsCredentials::from_env()?)
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! An EventBridge rule matching the forwarded events:
+//!
+//! ```json
+//! { "source": ["notif.sh"], "detail-type": ["orders.created"] }
+//! ```
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use aws_credential_types::Credentials;
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningSettings};
+use aws_sigv4::sign::v4;
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::time::MissedTickBehavior;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::retry::{self, RetryPolicy};
+use crate::types::{Event, SubscribeOptions};
+
+/// Most entries `PutEvents` accepts per request.
+const MAX_BATCH: usize = 10;
+
+const TARGET: &str = "AWSEvents.PutEvents";
+const CONTENT: &str = "application/x-amz-json-1.1";
+
+/// AWS credentials requests are signed with.
+#[derive(Clone)]
+pub struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Long-term credentials of an IAM user.
+    pub fn new(access_key_id: impl Into<String>, secret_access_key: impl Into<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+        }
+    }
+
+    /// Add the session token of temporary credentials.
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    /// Read `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and, if set,
+    /// `AWS_SESSION_TOKEN`.
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| NotifError::Config(format!("{name} is not set")))
+        };
+        let mut credentials = Self::new(var("AWS_ACCESS_KEY_ID")?, var("AWS_SECRET_ACCESS_KEY")?);
+        credentials.session_token = std::env::var("AWS_SESSION_TOKEN").ok();
+        Ok(credentials)
+    }
+}
+
+impl fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Forwards the events of some topics to an EventBridge bus.
+///
+/// Created with [`EventBridgeForwarder::new`] and started with
+/// [`run`](EventBridgeForwarder::run). Events are acked once EventBridge
+/// accepted them. Entries it throttles or fails internally are retried
+/// per the retry policy; events that still fail are nacked, so notif.sh
+/// redelivers them and moves them to the dead-letter queue once they run
+/// out of attempts.
+pub struct EventBridgeForwarder {
+    client: Notif,
+    topics: Vec<String>,
+    bus: String,
+    source: String,
+    region: Option<String>,
+    endpoint: Option<String>,
+    credentials: Option<AwsCredentials>,
+    options: SubscribeOptions,
+    retry: RetryPolicy,
+    batch_size: usize,
+    flush_interval: Duration,
+    http: reqwest::Client,
+}
+
+impl EventBridgeForwarder {
+    /// Forward the events of `topics` to the bus named `bus`.
+    ///
+    /// The region defaults to `AWS_REGION` and the credentials to
+    /// `AwsCredentials::from_env`.
+    pub fn new(client: &Notif, topics: &[&str], bus: impl Into<String>) -> Self {
+        Self {
+            client: client.clone(),
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+            bus: bus.into(),
+            source: "notif.sh".to_string(),
+            region: None,
+            endpoint: None,
+            credentials: None,
+            options: SubscribeOptions::new(),
+            retry: RetryPolicy::exponential(3),
+            batch_size: MAX_BATCH,
+            flush_interval: Duration::from_secs(1),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Set the AWS region of the bus.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Send requests to `endpoint` instead of the region's public
+    /// endpoint, e.g. a VPC endpoint or a local emulator.
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Sign requests with `credentials`.
+    pub fn credentials(mut self, credentials: AwsCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Set the `source` of the entries (default: `notif.sh`).
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// Subscribe with `options`, e.g. a filter or a consumer group shared
+    /// by several forwarders. Acks are always manual.
+    pub fn options(mut self, options: SubscribeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set how failed requests and entries are retried (default: 3
+    /// retries with exponential backoff).
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the most events per request (default and maximum: 10).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_BATCH);
+        self
+    }
+
+    /// Set how long a partial batch waits for more events (default: 1s).
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Forward events until the subscription ends.
+    ///
+    /// Fails if EventBridge rejects a request for good, e.g. for invalid
+    /// credentials or a missing bus; the events of that batch are left
+    /// unacked and redelivered later.
+    pub async fn run(self) -> Result<()> {
+        let region = match &self.region {
+            Some(region) => region.clone(),
+            None => std::env::var("AWS_REGION")
+                .map_err(|_| NotifError::Config("no AWS region configured".to_string()))?,
+        };
+        let credentials = match &self.credentials {
+            Some(credentials) => credentials.clone(),
+            None => AwsCredentials::from_env()?,
+        };
+        let target = Target {
+            endpoint: self
+                .endpoint
+                .clone()
+                .unwrap_or_else(|| format!("https://events.{region}.amazonaws.com/")),
+            region,
+            credentials,
+        };
+
+        let topics: Vec<&str> = self.topics.iter().map(String::as_str).collect();
+        let stream = self
+            .client
+            .subscribe_with_options(&topics, self.options.clone().auto_ack(false))
+            .await?;
+        self.forward(stream, &target).await
+    }
+
+    /// Batch the events of `stream` and send each batch.
+    async fn forward<S>(&self, mut stream: S, target: &Target) -> Result<()>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut flush = tokio::time::interval(self.flush_interval);
+        flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                event = stream.next() => match event {
+                    Some(event) => {
+                        batch.push(event?);
+                        if batch.len() < self.batch_size {
+                            continue;
+                        }
+                    }
+                    None => break,
+                },
+                _ = flush.tick() => {
+                    if batch.is_empty() {
+                        continue;
+                    }
+                }
+            }
+            self.send(&batch, target).await?;
+            batch.clear();
+        }
+        if !batch.is_empty() {
+            self.send(&batch, target).await?;
+        }
+        Ok(())
+    }
+
+    /// Put `batch`, retrying what fails, then settle every event.
+    async fn send(&self, batch: &[Event], target: &Target) -> Result<()> {
+        let mut pending: Vec<&Event> = batch.iter().collect();
+        let mut attempt = 0;
+        loop {
+            let failed = match self.put(&pending, target).await {
+                Ok(failed) => failed,
+                Err(e) if e.is_retryable() && attempt < self.retry.max_retries() => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let mut retry = Vec::new();
+            for (event, failure) in pending.into_iter().zip(failed) {
+                match failure {
+                    None => {
+                        let _ = event.ack().await;
+                    }
+                    Some(code)
+                        if is_retryable_code(&code) && attempt < self.retry.max_retries() =>
+                    {
+                        retry.push(event);
+                    }
+                    Some(_) => {
+                        let _ = event.nack(None).await;
+                    }
+                }
+            }
+            if retry.is_empty() {
+                return Ok(());
+            }
+            tokio::time::sleep(self.retry.delay(attempt)).await;
+            attempt += 1;
+            pending = retry;
+        }
+    }
+
+    /// Send one `PutEvents` request, returning the error code of every
+    /// entry that failed, in order.
+    async fn put(&self, events: &[&Event], target: &Target) -> Result<Vec<Option<String>>> {
+        let entries: Vec<Value> = events
+            .iter()
+            .map(|event| {
+                // The detail must be a JSON object
+                let detail = match &event.data {
+                    Value::Object(_) => event.data.clone(),
+                    other => json!({ "data": other }),
+                };
+                json!({
+                    "EventBusName": self.bus,
+                    "Source": self.source,
+                    "DetailType": event.topic,
+                    "Detail": detail.to_string(),
+                    "Time": event.timestamp.timestamp(),
+                })
+            })
+            .collect();
+        let body = serde_json::to_vec(&json!({ "Entries": entries }))?;
+
+        let request = target.sign(&self.http, body, SystemTime::now())?;
+        let response = request.send().await.map_err(|e| {
+            if retry::is_retryable_error(&e) {
+                NotifError::connection(format!("EventBridge: {e}"))
+            } else {
+                NotifError::Http(e)
+            }
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            if body.contains("ThrottlingException") {
+                return Err(NotifError::RateLimited {
+                    retry_after: None,
+                    message: format!("EventBridge: {body}"),
+                });
+            }
+            return Err(NotifError::Api {
+                status: status.as_u16(),
+                message: format!("EventBridge: {body}"),
+            });
+        }
+
+        let result: PutEventsResponse = response.json().await?;
+        Ok(result
+            .entries
+            .into_iter()
+            .map(|entry| entry.error_code)
+            .chain(std::iter::repeat(Some("Missing".to_string())))
+            .take(events.len())
+            .collect())
+    }
+}
+
+impl fmt::Debug for EventBridgeForwarder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBridgeForwarder")
+            .field("topics", &self.topics)
+            .field("bus", &self.bus)
+            .field("source", &self.source)
+            .field("region", &self.region)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PutEventsResponse {
+    #[serde(default)]
+    entries: Vec<PutEventsEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PutEventsEntry {
+    error_code: Option<String>,
+}
+
+/// Whether an entry failed for a reason that may pass on retry.
+fn is_retryable_code(code: &str) -> bool {
+    matches!(
+        code,
+        "ThrottlingException" | "InternalFailure" | "InternalException" | "Missing"
+    )
+}
+
+/// Where and as whom requests are sent.
+struct Target {
+    endpoint: String,
+    region: String,
+    credentials: AwsCredentials,
+}
+
+impl Target {
+    /// A `PutEvents` request carrying `body`, signed with Signature
+    /// Version 4 as of `now`.
+    fn sign(
+        &self,
+        http: &reqwest::Client,
+        body: Vec<u8>,
+        now: SystemTime,
+    ) -> Result<reqwest::RequestBuilder> {
+        let url = url::Url::parse(&self.endpoint)?;
+        if url.host_str().is_none() {
+            return Err(NotifError::Config(format!(
+                "invalid EventBridge endpoint '{}'",
+                self.endpoint
+            )));
+        }
+        let signing =
+            |e: &dyn fmt::Display| NotifError::Config(format!("EventBridge signing: {e}"));
+
+        let credentials = &self.credentials;
+        let identity = Credentials::new(
+            &credentials.access_key_id,
+            &credentials.secret_access_key,
+            credentials.session_token.clone(),
+            None,
+            "notifsh",
+        )
+        .into();
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name("events")
+            .time(now)
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| signing(&e))?
+            .into();
+
+        let headers = [("content-type", CONTENT), ("x-amz-target", TARGET)];
+        let signable = SignableRequest::new(
+            "POST",
+            url.as_str(),
+            headers.into_iter(),
+            SignableBody::Bytes(&body),
+        )
+        .map_err(|e| signing(&e))?;
+        let (instructions, _) = sign(signable, &params)
+            .map_err(|e| signing(&e))?
+            .into_parts();
+
+        let mut request = http.post(url);
+        for (name, value) in headers.into_iter().chain(instructions.headers()) {
+            request = request.header(name, value);
+        }
+        Ok(request.body(body))
+    }
+}
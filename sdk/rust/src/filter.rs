@@ -0,0 +1,412 @@
+//! Filter expressions over event fields, for `SubscribeOptions::filter`.
+//!
+//! An expression compares event fields with literals and combines the
+//! comparisons with boolean operators:
+//!
+//! ```text
+//! data.level == 'error' && (data.retries >= 3 || headers.tenant != "acme")
+//! ```
+//!
+//! Fields are `id`, `topic`, `attempt`, `headers.<name>` and
+//! `data.<path>`, where the path walks object keys and array indices
+//! (`data.items.0.sku`). Literals are single- or double-quoted strings,
+//! numbers, `true`, `false` and `null`. Operators are `==`, `!=`, `<`,
+//! `<=`, `>`, `>=`, `&&` (`and`), `||` (`or`) and `!` (`not`). A field on
+//! its own tests whether it is truthy. Missing fields are `null`, and
+//! ordering comparisons between different types are false.
+
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+use crate::error::{NotifError, Result};
+use crate::types::Event;
+
+/// A parsed filter expression.
+#[derive(Debug, Clone)]
+pub(crate) struct Filter {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Truthy(Operand),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Field(Field),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Id,
+    Topic,
+    Attempt,
+    Header(String),
+    Data(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Path(String),
+    Literal(Value),
+    Compare(CompareOp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Filter {
+    /// Parse an expression, reporting where it is malformed.
+    pub(crate) fn parse(source: &str) -> Result<Self> {
+        let invalid = |reason: String| NotifError::InvalidFilter {
+            expr: source.to_string(),
+            reason,
+        };
+
+        let tokens = tokenize(source).map_err(invalid)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or().map_err(invalid)?;
+        if parser.pos < parser.tokens.len() {
+            return Err(invalid(format!(
+                "unexpected {} after the expression",
+                describe(&parser.tokens[parser.pos])
+            )));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether `event` satisfies the expression.
+    pub(crate) fn matches(&self, event: &Event) -> bool {
+        self.expr.eval(event)
+    }
+}
+
+impl Expr {
+    fn eval(&self, event: &Event) -> bool {
+        match self {
+            Expr::Or(left, right) => left.eval(event) || right.eval(event),
+            Expr::And(left, right) => left.eval(event) && right.eval(event),
+            Expr::Not(inner) => !inner.eval(event),
+            Expr::Compare(left, op, right) => {
+                compare(&left.resolve(event), *op, &right.resolve(event))
+            }
+            Expr::Truthy(operand) => truthy(&operand.resolve(event)),
+        }
+    }
+}
+
+impl Operand {
+    fn resolve(&self, event: &Event) -> Value {
+        match self {
+            Operand::Literal(value) => value.clone(),
+            Operand::Field(field) => field.resolve(event),
+        }
+    }
+}
+
+impl Field {
+    fn resolve(&self, event: &Event) -> Value {
+        match self {
+            Field::Id => Value::from(event.id.as_str()),
+            Field::Topic => Value::from(event.topic.as_str()),
+            Field::Attempt => Value::from(event.attempt),
+            Field::Header(name) => event
+                .headers
+                .get(name)
+                .map_or(Value::Null, |value| Value::from(value.as_str())),
+            Field::Data(path) => {
+                let mut value = &event.data;
+                for key in path {
+                    let next = match value {
+                        Value::Object(map) => map.get(key),
+                        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                        _ => None,
+                    };
+                    match next {
+                        Some(next) => value = next,
+                        None => return Value::Null,
+                    }
+                }
+                value.clone()
+            }
+        }
+    }
+}
+
+/// A single event field, named the way filter expressions name it.
+#[derive(Debug, Clone)]
+pub(crate) struct FieldPath {
+    field: Field,
+}
+
+impl FieldPath {
+    /// Parse a field such as `data.order_id` or `headers.tenant`.
+    pub(crate) fn parse(path: &str) -> Result<Self> {
+        field(path.trim())
+            .map(|field| Self { field })
+            .map_err(|reason| NotifError::InvalidKeyPath {
+                path: path.to_string(),
+                reason,
+            })
+    }
+
+    /// The field's value on `event`, as a string; `None` if it is missing
+    /// or null.
+    pub(crate) fn key(&self, event: &Event) -> Option<String> {
+        match self.field.resolve(event) {
+            Value::Null => None,
+            Value::String(s) => Some(s),
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        CompareOp::Eq => ordering == Some(Ordering::Equal) || (ordering.is_none() && left == right),
+        CompareOp::Ne => !compare(left, CompareOp::Eq, right),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let token = match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' if next == Some('=') => Token::Compare(CompareOp::Eq),
+            '!' if next == Some('=') => Token::Compare(CompareOp::Ne),
+            '<' if next == Some('=') => Token::Compare(CompareOp::Le),
+            '>' if next == Some('=') => Token::Compare(CompareOp::Ge),
+            '&' if next == Some('&') => Token::And,
+            '|' if next == Some('|') => Token::Or,
+            '<' => Token::Compare(CompareOp::Lt),
+            '>' => Token::Compare(CompareOp::Gt),
+            '!' => Token::Not,
+            '\'' | '"' => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => return Err(format!("unterminated string at {}", i)),
+                        Some('\\') if j + 1 < chars.len() => {
+                            value.push(chars[j + 1]);
+                            j += 2;
+                        }
+                        Some(&q) if q == c => break,
+                        Some(&other) => {
+                            value.push(other);
+                            j += 1;
+                        }
+                    }
+                }
+                tokens.push(Token::Literal(Value::String(value)));
+                i = j + 1;
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let start = i;
+                i += 1;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit()
+                        || matches!(chars[i], '.' | 'e' | 'E' | '+' | '-'))
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number: serde_json::Number = text
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", text))?;
+                tokens.push(Token::Literal(Value::Number(number)));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | '.'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Path(word),
+                });
+                continue;
+            }
+            other => return Err(format!("unexpected '{}' at {}", other, i)),
+        };
+
+        // Two-character operators
+        i += match token {
+            Token::Compare(CompareOp::Lt | CompareOp::Gt)
+            | Token::Not
+            | Token::Open
+            | Token::Close => 1,
+            _ => 2,
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let expr = self.or()?;
+            return match self.advance() {
+                Some(Token::Close) => Ok(expr),
+                _ => Err("missing ')'".to_string()),
+            };
+        }
+
+        let left = self.operand()?;
+        if let Some(&Token::Compare(op)) = self.peek() {
+            self.pos += 1;
+            let right = self.operand()?;
+            return Ok(Expr::Compare(left, op, right));
+        }
+        Ok(Expr::Truthy(left))
+    }
+
+    fn operand(&mut self) -> ParseResult<Operand> {
+        match self.advance() {
+            Some(Token::Literal(value)) => Ok(Operand::Literal(value)),
+            Some(Token::Path(path)) => field(&path).map(Operand::Field),
+            Some(token) => Err(format!(
+                "expected a field or value, found {}",
+                describe(&token)
+            )),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn field(path: &str) -> ParseResult<Field> {
+    let mut segments = path.split('.');
+    let root = segments.next().unwrap_or_default();
+    let rest: Vec<String> = segments.map(String::from).collect();
+    if rest.iter().any(|segment| segment.is_empty()) {
+        return Err(format!("empty segment in '{}'", path));
+    }
+
+    match (root, rest.len()) {
+        ("id", 0) => Ok(Field::Id),
+        ("topic", 0) => Ok(Field::Topic),
+        ("attempt", 0) => Ok(Field::Attempt),
+        ("headers", 1) => Ok(Field::Header(rest[0].clone())),
+        ("data", _) => Ok(Field::Data(rest)),
+        _ => Err(format!(
+            "unknown field '{}'; expected id, topic, attempt, headers.<name> or data.<path>",
+            path
+        )),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Path(path) => format!("'{}'", path),
+        Token::Literal(value) => value.to_string(),
+        Token::Compare(_) => "comparison".to_string(),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
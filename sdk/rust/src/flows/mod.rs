@@ -0,0 +1,7 @@
+//! Ready-made protocols built on topics.
+//!
+//! Each module implements one convention that several apps share, so both
+//! sides agree on topics and payloads without copying them around.
+
+pub mod permissions;
+pub mod presence;
@@ -0,0 +1,344 @@
+//! Permission prompts for coding agents, as sent by Claude Code hooks.
+//!
+//! An agent's `PermissionRequest` hook emits the tool it wants to run on
+//! [`REQUEST_TOPIC`] and waits for a decision on [`RESPONSE_TOPIC`], e.g.
+//! with `notif emit claude.permission.request --reply-to
+//! claude.permission.response --raw`. A [`PermissionListener`] yields those
+//! prompts to an app that asks the user, and [`request_permission`] is the
+//! agent side for Rust programs.
+//!
+//! Responses carry the `hookSpecificOutput` the hook prints back to the
+//! agent, plus the session ID of the request so that agents sharing the
+//! topic can pick out their own (`--filter '.session_id ==
+//! $input.session_id'`).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use notifsh::flows::permissions::{Decision, PermissionListener};
+//! use notifsh::Notif;
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let mut prompts = PermissionListener::subscribe(&client).await?;
+//! while let Some(request) = prompts.next().await {
+//!     let request = request?;
+//!     let decision = if request.permission.tool_name == "Read" {
+//!         Decision::Allow
+//!     } else {
+//!         Decision::deny("Only reads are allowed")
+//!     };
+//!     request.respond(decision).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::subscribe::EventStream;
+use crate::types::{EmitResponse, SubscribeOptions};
+
+/// Topic agents emit permission prompts on.
+pub const REQUEST_TOPIC: &str = "claude.permission.request";
+
+/// Topic decisions are emitted on.
+pub const RESPONSE_TOPIC: &str = "claude.permission.response";
+
+/// Hook event name the agent expects in a response.
+const HOOK_EVENT_NAME: &str = "PermissionRequest";
+
+/// Message of a denial that did not give one.
+const DEFAULT_DENY_MESSAGE: &str = "Denied by user";
+
+/// What an agent asks permission for: the payload of a permission prompt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Permission {
+    /// Tool the agent wants to run, e.g. `Bash` or `Edit`.
+    #[serde(default)]
+    pub tool_name: String,
+    /// Arguments of the tool call.
+    #[serde(default)]
+    pub tool_input: Value,
+    /// Session of the agent asking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Working directory of the agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Other fields of the hook payload, e.g. `transcript_path`.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Permission {
+    /// Ask to run `tool_name` with `tool_input`.
+    pub fn new(tool_name: impl Into<String>, tool_input: Value) -> Self {
+        Self {
+            tool_name: tool_name.into(),
+            tool_input,
+            session_id: None,
+            cwd: None,
+            extra: Map::new(),
+        }
+    }
+
+    /// Set the session asking, which decisions are matched against.
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Set the working directory of the agent.
+    pub fn cwd(mut self, cwd: impl Into<String>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+}
+
+/// The answer to a permission prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Let the agent run the tool.
+    Allow,
+    /// Refuse, telling the agent why.
+    Deny {
+        /// Reason shown to the agent.
+        message: String,
+    },
+}
+
+impl Decision {
+    /// Refuse with `message`.
+    pub fn deny(message: impl Into<String>) -> Self {
+        Self::Deny {
+            message: message.into(),
+        }
+    }
+
+    /// Whether the tool may run.
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allow)
+    }
+}
+
+/// A permission prompt received by a [`PermissionListener`].
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct PermissionRequest {
+    /// ID of the prompt event.
+    pub event_id: String,
+    /// What the agent asks for.
+    pub permission: Permission,
+    client: Notif,
+}
+
+impl PermissionRequest {
+    /// Send `decision` back to the agent on [`RESPONSE_TOPIC`].
+    pub async fn respond(&self, decision: Decision) -> Result<EmitResponse> {
+        let response = Response {
+            session_id: self.permission.session_id.clone(),
+            output: HookOutput {
+                hook_event_name: HOOK_EVENT_NAME.to_string(),
+                decision: decision.into(),
+            },
+        };
+        self.client.emit(RESPONSE_TOPIC, response).await
+    }
+}
+
+/// Stream of permission prompts from [`REQUEST_TOPIC`].
+///
+/// Yields `NotifError::Decode` for prompts whose payload is not an object,
+/// and keeps going.
+pub struct PermissionListener {
+    inner: EventStream,
+    client: Notif,
+}
+
+impl PermissionListener {
+    /// Listen for prompts emitted from now on.
+    pub async fn subscribe(client: &Notif) -> Result<Self> {
+        Self::subscribe_with_options(client, SubscribeOptions::new().from("latest")).await
+    }
+
+    /// Listen for prompts with custom subscription options, e.g. a consumer
+    /// group so that one of several apps answers each prompt.
+    pub async fn subscribe_with_options(client: &Notif, options: SubscribeOptions) -> Result<Self> {
+        let inner = client
+            .subscribe_with_options(&[REQUEST_TOPIC], options)
+            .await?;
+        Ok(Self {
+            inner,
+            client: client.clone(),
+        })
+    }
+
+    /// Close the subscription (see `EventStream::close`).
+    pub async fn close(self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+impl Stream for PermissionListener {
+    type Item = Result<PermissionRequest>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let client = self.client.clone();
+        Pin::new(&mut self.inner).poll_next(cx).map(|item| {
+            item.map(|event| {
+                let event = event?;
+                Ok(PermissionRequest {
+                    permission: event.parse()?,
+                    event_id: event.id,
+                    client,
+                })
+            })
+        })
+    }
+}
+
+impl std::fmt::Debug for PermissionRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PermissionRequest")
+            .field("event_id", &self.event_id)
+            .field("permission", &self.permission)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for PermissionListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PermissionListener").finish_non_exhaustive()
+    }
+}
+
+/// Prompt for `permission` and wait for the decision.
+///
+/// The first response on [`RESPONSE_TOPIC`] is taken, skipping ones for
+/// another session when both sides name theirs. Fails with
+/// `NotifError::Timeout` if nobody answers within `timeout`.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::flows::permissions::{self, Permission};
+/// use notifsh::Notif;
+/// use serde_json::json;
+/// use std::time::Duration;
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// let permission = Permission::new("Bash", json!({"command": "cargo publish"}))
+///     .session_id("release-bot");
+/// let decision =
+///     permissions::request_permission(&client, &permission, Duration::from_secs(60)).await?;
+/// if decision.is_allowed() {
+///     // Run the tool...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn request_permission(
+    client: &Notif,
+    permission: &Permission,
+    timeout: Duration,
+) -> Result<Decision> {
+    // Subscribe before emitting so the decision cannot be missed.
+    let mut responses = client
+        .subscribe_with_options(&[RESPONSE_TOPIC], SubscribeOptions::new().from("latest"))
+        .await?;
+    client.emit(REQUEST_TOPIC, permission).await?;
+
+    let wait = async {
+        while let Some(event) = responses.next().await {
+            let response: Response = match event?.parse() {
+                Ok(response) => response,
+                Err(_) => continue,
+            };
+            let other_session = matches!(
+                (&permission.session_id, &response.session_id),
+                (Some(ours), Some(theirs)) if ours != theirs
+            );
+            if !other_session {
+                return Ok(response.output.decision.into());
+            }
+        }
+        Err(NotifError::connection(
+            "permission response subscription closed",
+        ))
+    };
+
+    tokio::time::timeout(timeout, wait).await.map_err(|_| {
+        NotifError::timeout(format!(
+            "no permission decision for '{}'",
+            permission.tool_name
+        ))
+    })?
+}
+
+/// Payload of a response, as printed back to the agent by its hook.
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    #[serde(rename = "hookSpecificOutput")]
+    output: HookOutput,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HookOutput {
+    #[serde(rename = "hookEventName")]
+    hook_event_name: String,
+    decision: WireDecision,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WireDecision {
+    behavior: Behavior,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Behavior {
+    Allow,
+    Deny,
+}
+
+impl From<Decision> for WireDecision {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Allow => Self {
+                behavior: Behavior::Allow,
+                message: None,
+            },
+            Decision::Deny { message } => Self {
+                behavior: Behavior::Deny,
+                message: Some(message),
+            },
+        }
+    }
+}
+
+impl From<WireDecision> for Decision {
+    fn from(decision: WireDecision) -> Self {
+        match decision.behavior {
+            Behavior::Allow => Self::Allow,
+            Behavior::Deny => Self::Deny {
+                message: decision
+                    .message
+                    .unwrap_or_else(|| DEFAULT_DENY_MESSAGE.to_string()),
+            },
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Agent presence: announcing agents and discovering who is online.
+//!
+//! Agents announce themselves on [`AVAILABLE_TOPIC`] when they start,
+//! again every third of their TTL as a heartbeat, and whenever anyone
+//! emits on [`DISCOVER_TOPIC`]. Each announcement carries its TTL in
+//! `ttl_secs`; a watcher considers an agent gone once that long passes
+//! without hearing from it, or as soon as it announces the `offline`
+//! status on leaving.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use notifsh::flows::presence::{AgentInfo, Presence, PresenceChange};
+//! use notifsh::Notif;
+//! use std::time::Duration;
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let presence = Presence::new(&client);
+//!
+//! // Agent side
+//! let announcer = presence
+//!     .announce(AgentInfo::new("reviewer").tag("rust"), Duration::from_secs(30))
+//!     .await?;
+//!
+//! // Dashboard side
+//! let mut changes = presence.watch().await?;
+//! while let Some(change) = changes.next().await {
+//!     match change? {
+//!         PresenceChange::Joined(agent) => println!("{} joined", agent.name),
+//!         PresenceChange::Left(agent) | PresenceChange::Expired(agent) => {
+//!             println!("{} left", agent.name)
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//!
+//! announcer.leave().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::types::{EmitResponse, SubscribeOptions};
+
+/// Topic agents announce themselves on.
+pub const AVAILABLE_TOPIC: &str = "agents.available";
+
+/// Topic that asks every agent to announce itself right away.
+pub const DISCOVER_TOPIC: &str = "agents.discover";
+
+/// TTL assumed for announcements that carry none.
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Shortest TTL an agent may announce.
+const MIN_TTL: Duration = Duration::from_secs(1);
+
+/// What an agent is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    /// Ready for work.
+    Idle,
+    /// Working on something.
+    Busy,
+    /// Gone; watchers drop the agent.
+    Offline,
+}
+
+/// How an agent describes itself in announcements.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AgentInfo {
+    /// Name the agent is known by, unique among agents.
+    pub name: String,
+    /// What the agent does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Machine the agent runs on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Free-form labels, e.g. capabilities.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// What the agent is doing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<AgentStatus>,
+    /// Other fields of the announcement.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl AgentInfo {
+    /// Describe an agent named `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            hostname: None,
+            tags: Vec::new(),
+            status: None,
+            extra: Map::new(),
+        }
+    }
+
+    /// Set what the agent does.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the machine the agent runs on.
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Add a label.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Set what the agent is doing.
+    pub fn status(mut self, status: AgentStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Add another field to the announcement.
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A change seen by a [`PresenceWatch`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PresenceChange {
+    /// An agent announced itself for the first time.
+    Joined(AgentInfo),
+    /// A known agent announced different info.
+    Updated(AgentInfo),
+    /// An agent announced that it is going offline.
+    Left(AgentInfo),
+    /// An agent stopped announcing itself for longer than its TTL.
+    Expired(AgentInfo),
+}
+
+/// Payload of an announcement.
+#[derive(Debug, Serialize, Deserialize)]
+struct Announcement {
+    #[serde(flatten)]
+    info: AgentInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ttl_secs: Option<u64>,
+}
+
+/// Announces agents and watches them come and go.
+#[derive(Clone)]
+pub struct Presence {
+    client: Notif,
+    default_ttl: Duration,
+}
+
+impl Presence {
+    /// Use `client` for announcements and watches.
+    pub fn new(client: &Notif) -> Self {
+        Self {
+            client: client.clone(),
+            default_ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// Set the TTL watchers assume for announcements without one, e.g.
+    /// from agents that predate TTLs (default: 60s).
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Announce `info` now, then keep it fresh until the returned
+    /// [`Announcer`] leaves or is dropped.
+    ///
+    /// Watchers expire the agent `ttl` (at least a second) after they last
+    /// heard from it. Failed heartbeats are retried on the next one.
+    pub async fn announce(&self, info: AgentInfo, ttl: Duration) -> Result<Announcer> {
+        let ttl = ttl.max(MIN_TTL);
+        let mut discover = self
+            .client
+            .subscribe_with_options(&[DISCOVER_TOPIC], SubscribeOptions::new().from("latest"))
+            .await?;
+        announce(&self.client, &info, ttl).await?;
+
+        let info = Arc::new(Mutex::new(info));
+        let client = self.client.clone();
+        let current = info.clone();
+        let task = tokio::spawn(async move {
+            let period = ttl / 3;
+            let mut heartbeat = tokio::time::interval_at(Instant::now() + period, period);
+            heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut discovering = true;
+            loop {
+                tokio::select! {
+                    _ = heartbeat.tick() => {}
+                    request = discover.next(), if discovering => {
+                        if request.is_none() {
+                            discovering = false;
+                            continue;
+                        }
+                    }
+                }
+                let info = current.lock().unwrap().clone();
+                let _ = announce(&client, &info, ttl).await;
+            }
+        });
+
+        Ok(Announcer {
+            client: self.client.clone(),
+            info,
+            ttl,
+            task,
+        })
+    }
+
+    /// Watch agents join, change and leave.
+    ///
+    /// Asks every agent to announce itself, so agents that were already
+    /// online show up as `Joined` shortly after the watch starts.
+    pub async fn watch(&self) -> Result<PresenceWatch> {
+        let mut stream = self
+            .client
+            .subscribe_with_options(&[AVAILABLE_TOPIC], SubscribeOptions::new().from("latest"))
+            .await?;
+        self.client.emit(DISCOVER_TOPIC, json!({})).await?;
+
+        let agents: Arc<Mutex<HashMap<String, Tracked>>> = Arc::default();
+        let (changes_tx, changes_rx) = mpsc::unbounded_channel();
+        let default_ttl = self.default_ttl;
+        let tracked = agents.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let next_expiry = tracked
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .map(|agent| agent.expires_at)
+                    .min();
+                let expiry = async {
+                    match next_expiry {
+                        Some(at) => tokio::time::sleep_until(at).await,
+                        None => std::future::pending().await,
+                    }
+                };
+                let changes = tokio::select! {
+                    item = stream.next() => match item {
+                        Some(Ok(event)) => match event.parse::<Announcement>() {
+                            Ok(announcement) => {
+                                observe(&tracked, announcement, default_ttl).into_iter().map(Ok).collect()
+                            }
+                            Err(_) => Vec::new(),
+                        },
+                        Some(Err(e)) => vec![Err(e)],
+                        None => return,
+                    },
+                    _ = expiry => expire(&tracked),
+                };
+                for change in changes {
+                    if changes_tx.send(change).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(PresenceWatch {
+            agents,
+            changes_rx,
+            task,
+        })
+    }
+}
+
+impl fmt::Debug for Presence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Presence")
+            .field("default_ttl", &self.default_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+async fn announce(client: &Notif, info: &AgentInfo, ttl: Duration) -> Result<EmitResponse> {
+    let announcement = Announcement {
+        info: info.clone(),
+        ttl_secs: Some(ttl.as_secs()),
+    };
+    client.emit(AVAILABLE_TOPIC, announcement).await
+}
+
+/// A running announcement started by [`Presence::announce`].
+///
+/// Dropping it stops the heartbeats without saying goodbye, so watchers
+/// see the agent expire.
+pub struct Announcer {
+    client: Notif,
+    info: Arc<Mutex<AgentInfo>>,
+    ttl: Duration,
+    task: JoinHandle<()>,
+}
+
+impl Announcer {
+    /// The info being announced.
+    pub fn info(&self) -> AgentInfo {
+        self.info.lock().unwrap().clone()
+    }
+
+    /// Announce `info` instead, right away.
+    pub async fn update(&self, info: AgentInfo) -> Result<EmitResponse> {
+        *self.info.lock().unwrap() = info.clone();
+        announce(&self.client, &info, self.ttl).await
+    }
+
+    /// Stop the heartbeats and announce the agent offline.
+    pub async fn leave(self) -> Result<EmitResponse> {
+        self.task.abort();
+        let mut info = self.info();
+        info.status = Some(AgentStatus::Offline);
+        announce(&self.client, &info, self.ttl).await
+    }
+}
+
+impl Drop for Announcer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for Announcer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Announcer")
+            .field("info", &*self.info.lock().unwrap())
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Tracked {
+    info: AgentInfo,
+    expires_at: Instant,
+}
+
+/// Record an announcement, returning the change it makes.
+fn observe(
+    agents: &Mutex<HashMap<String, Tracked>>,
+    announcement: Announcement,
+    default_ttl: Duration,
+) -> Option<PresenceChange> {
+    let info = announcement.info;
+    let mut agents = agents.lock().unwrap();
+    if info.status == Some(AgentStatus::Offline) {
+        return agents
+            .remove(&info.name)
+            .map(|_| PresenceChange::Left(info));
+    }
+
+    let ttl = announcement
+        .ttl_secs
+        .map(Duration::from_secs)
+        .unwrap_or(default_ttl);
+    let expires_at = Instant::now() + ttl;
+    let change = match agents.get(&info.name) {
+        None => Some(PresenceChange::Joined(info.clone())),
+        Some(known) if known.info != info => Some(PresenceChange::Updated(info.clone())),
+        Some(_) => None,
+    };
+    agents.insert(info.name.clone(), Tracked { info, expires_at });
+    change
+}
+
+/// Drop agents whose TTL ran out, returning them as changes.
+fn expire(agents: &Mutex<HashMap<String, Tracked>>) -> Vec<Result<PresenceChange>> {
+    let now = Instant::now();
+    let mut agents = agents.lock().unwrap();
+    let expired: Vec<String> = agents
+        .iter()
+        .filter(|(_, agent)| agent.expires_at <= now)
+        .map(|(name, _)| name.clone())
+        .collect();
+    expired
+        .into_iter()
+        .filter_map(|name| agents.remove(&name))
+        .map(|agent| Ok(PresenceChange::Expired(agent.info)))
+        .collect()
+}
+
+/// Stream of [`PresenceChange`]s started by [`Presence::watch`].
+///
+/// Yields subscription errors as they happen and keeps going;
+/// announcements that do not decode are skipped.
+pub struct PresenceWatch {
+    agents: Arc<Mutex<HashMap<String, Tracked>>>,
+    changes_rx: mpsc::UnboundedReceiver<Result<PresenceChange>>,
+    task: JoinHandle<()>,
+}
+
+impl PresenceWatch {
+    /// Agents currently online.
+    pub fn agents(&self) -> Vec<AgentInfo> {
+        let agents = self.agents.lock().unwrap();
+        agents.values().map(|agent| agent.info.clone()).collect()
+    }
+}
+
+impl Stream for PresenceWatch {
+    type Item = Result<PresenceChange>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.changes_rx.poll_recv(cx)
+    }
+}
+
+impl Drop for PresenceWatch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for PresenceWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let agents = self.agents.lock().unwrap();
+        let names: Vec<&String> = agents.keys().collect();
+        f.debug_struct("PresenceWatch")
+            .field("agents", &names)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,168 @@
+//! Copying subscription frames for debugging.
+
+use std::fmt;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+/// Stands in for the API key in copied frames.
+const REDACTED: &str = "[redacted]";
+
+/// Which way a frame travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FrameDirection {
+    /// Received from the server.
+    Inbound,
+    /// Sent to the server.
+    Outbound,
+}
+
+/// A copy of a subscription frame, as passed to a [`FrameSink`].
+///
+/// The API key is replaced with `[redacted]` wherever it appears, so
+/// frames can be pasted into bug reports as they are.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DebugFrame {
+    /// Which way the frame travelled.
+    pub direction: FrameDirection,
+    /// Address of the connection, e.g. `wss://api.notif.sh/ws?token=[redacted]`.
+    pub url: String,
+    /// The frame's JSON. MessagePack frames are shown as the JSON they
+    /// decode to.
+    pub payload: String,
+    /// When the frame was sent or received.
+    pub at: DateTime<Utc>,
+}
+
+impl fmt::Display for DebugFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self.direction {
+            FrameDirection::Inbound => "<<",
+            FrameDirection::Outbound => ">>",
+        };
+        write!(
+            f,
+            "{} {} {} {}",
+            self.at.to_rfc3339(),
+            arrow,
+            self.url,
+            self.payload
+        )
+    }
+}
+
+/// Receives a copy of every frame subscriptions send and receive.
+///
+/// Register with `NotifBuilder::debug_frames`. It runs on the subscription's
+/// background task, so it should return quickly. Closures taking a
+/// `&DebugFrame` implement it, and so does any `Mutex<impl Write>`, which
+/// writes one line per frame.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Mutex;
+/// use notifsh::Notif;
+///
+/// let client = Notif::builder("nsh_your_api_key")
+///     .debug_frames(Mutex::new(std::io::stderr()))
+///     .build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+pub trait FrameSink: Send + Sync + 'static {
+    /// Called with each frame.
+    fn on_frame(&self, frame: &DebugFrame);
+}
+
+impl<F> FrameSink for F
+where
+    F: Fn(&DebugFrame) + Send + Sync + 'static,
+{
+    fn on_frame(&self, frame: &DebugFrame) {
+        self(frame)
+    }
+}
+
+impl<W: Write + Send + 'static> FrameSink for Mutex<W> {
+    fn on_frame(&self, frame: &DebugFrame) {
+        let mut out = self.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out, "{}", frame);
+    }
+}
+
+/// The sink registered with `NotifBuilder::debug_frames`, if any.
+#[derive(Clone, Default)]
+pub(crate) struct FrameSinks(Option<Arc<dyn FrameSink>>);
+
+impl FrameSinks {
+    pub(crate) fn new(sink: impl FrameSink) -> Self {
+        Self(Some(Arc::new(sink)))
+    }
+
+    /// Start copying the frames of a connection to `url` authenticated with
+    /// `api_key`.
+    pub(crate) fn tap(&self, url: &str, api_key: &str) -> Tap {
+        Tap(self.0.as_ref().map(|sink| {
+            Arc::new(TapInner {
+                sink: sink.clone(),
+                url: redact(url, api_key),
+                api_key: api_key.to_string(),
+            })
+        }))
+    }
+}
+
+impl fmt::Debug for FrameSinks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("FrameSinks(Some(..))"),
+            None => f.write_str("FrameSinks(None)"),
+        }
+    }
+}
+
+/// Copies the frames of one connection, when a sink is registered.
+#[derive(Clone, Default)]
+pub(crate) struct Tap(Option<Arc<TapInner>>);
+
+struct TapInner {
+    sink: Arc<dyn FrameSink>,
+    url: String,
+    api_key: String,
+}
+
+impl Tap {
+    /// Copy a text frame.
+    pub(crate) fn text(&self, direction: FrameDirection, text: &str) {
+        if let Some(tap) = &self.0 {
+            tap.sink.on_frame(&DebugFrame {
+                direction,
+                url: tap.url.clone(),
+                payload: redact(text, &tap.api_key),
+                at: Utc::now(),
+            });
+        }
+    }
+
+    /// Copy a MessagePack frame.
+    #[cfg(feature = "msgpack")]
+    pub(crate) fn binary(&self, direction: FrameDirection, bytes: &[u8]) {
+        if self.0.is_some() {
+            let text = match crate::msgpack::decode::<serde_json::Value>(bytes) {
+                Ok(value) => value.to_string(),
+                Err(_) => format!("<{} bytes of invalid MessagePack>", bytes.len()),
+            };
+            self.text(direction, &text);
+        }
+    }
+}
+
+fn redact(text: &str, api_key: &str) -> String {
+    if api_key.is_empty() {
+        return text.to_string();
+    }
+    text.replace(api_key, REDACTED)
+}
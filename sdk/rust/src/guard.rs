@@ -0,0 +1,235 @@
+//! Circuit breaking around failing handlers, for `Concurrency::guard`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::time::Instant;
+
+use crate::client::Notif;
+
+/// Stops feeding events to a handler whose error rate spikes.
+///
+/// When at least `error_rate` of the last handled events failed, the
+/// circuit opens: the subscription is paused, events still buffered and
+/// those waiting for their key are nacked to be redelivered after the
+/// cooldown, and a diagnostic event is emitted if configured. Once the
+/// cooldown is over, consumption resumes with a single trial event: if it
+/// succeeds the circuit closes, otherwise it opens again right away.
+///
+/// This keeps a poison message, or a downstream that is down, from being
+/// hammered by every redelivery at full speed.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use notifsh::{Concurrency, HandlerGuard, Notif, SubscribeOptions};
+/// # async fn charge(event: notifsh::Event) -> Result<(), String> { Ok(()) }
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let stream = client
+///     .subscribe_with_options(&["payments.>"], SubscribeOptions::new().auto_ack(false))
+///     .await?;
+///
+/// let guard = HandlerGuard::new()
+///     .error_rate(0.5, 20)
+///     .cooldown(Duration::from_secs(60))
+///     .diagnostics(&client, "workers.payments.circuit");
+/// stream
+///     .for_each_concurrent_with(Concurrency::new(8).guard(guard), charge)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct HandlerGuard {
+    error_rate: f64,
+    window: usize,
+    cooldown: Duration,
+    diagnostics: Option<(Notif, String)>,
+}
+
+impl HandlerGuard {
+    /// Open when half of the last 20 events failed, for 30s.
+    pub fn new() -> Self {
+        Self {
+            error_rate: 0.5,
+            window: 20,
+            cooldown: Duration::from_secs(30),
+            diagnostics: None,
+        }
+    }
+
+    /// Open once at least `rate` (between `0.0` and `1.0`) of the last
+    /// `window` handled events failed (default: 0.5 of 20).
+    ///
+    /// Nothing opens the circuit before `window` events were handled.
+    pub fn error_rate(mut self, rate: f64, window: usize) -> Self {
+        self.error_rate = rate.clamp(0.0, 1.0);
+        self.window = window.max(1);
+        self
+    }
+
+    /// Keep the circuit open for `cooldown` (default: 30s).
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Emit an event to `topic` whenever the circuit opens or closes.
+    ///
+    /// Its payload has the new `state` (`"open"` or `"closed"`); when
+    /// opening, also the `failures` among the `handled` events, the
+    /// `error_rate`, the `cooldown_ms` and the `event_id` of the last
+    /// failure.
+    pub fn diagnostics(mut self, client: &Notif, topic: impl Into<String>) -> Self {
+        self.diagnostics = Some((client.clone(), topic.into()));
+        self
+    }
+}
+
+impl Default for HandlerGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for HandlerGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandlerGuard")
+            .field("error_rate", &self.error_rate)
+            .field("window", &self.window)
+            .field("cooldown", &self.cooldown)
+            .field(
+                "diagnostics",
+                &self.diagnostics.as_ref().map(|(_, topic)| topic),
+            )
+            .finish()
+    }
+}
+
+/// State of a [`HandlerGuard`] while events are processed.
+pub(crate) struct Circuit {
+    guard: HandlerGuard,
+    /// Latest outcomes, oldest first, while closed.
+    outcomes: VecDeque<bool>,
+    open_until: Option<Instant>,
+    /// Half-open after a cooldown; holds the trial event's ID once started.
+    trial: Option<Option<String>>,
+}
+
+impl Circuit {
+    pub(crate) fn new(guard: HandlerGuard) -> Self {
+        Self {
+            guard,
+            outcomes: VecDeque::new(),
+            open_until: None,
+            trial: None,
+        }
+    }
+
+    /// When the cooldown ends, if the circuit is open.
+    pub(crate) fn open_until(&self) -> Option<Instant> {
+        self.open_until
+    }
+
+    /// How long events should wait before being redelivered, if the
+    /// circuit is open.
+    pub(crate) fn open_for(&self) -> Option<Duration> {
+        // Never zero, which would nack with the server's default delay
+        self.open_until.map(|until| {
+            until
+                .saturating_duration_since(Instant::now())
+                .max(Duration::from_millis(1))
+        })
+    }
+
+    /// Whether another event may be read with `held` of at most `limit`
+    /// events in hand.
+    pub(crate) fn admits(&self, held: usize, limit: usize) -> bool {
+        match &self.trial {
+            _ if self.open_until.is_some() => true,
+            Some(trial) => trial.is_none(),
+            None => held < limit,
+        }
+    }
+
+    /// End the cooldown if it is over, to wait for a trial event; true if
+    /// it just ended.
+    pub(crate) fn cooled_down(&mut self) -> bool {
+        if self.open_until.is_none_or(|until| until > Instant::now()) {
+            return false;
+        }
+        self.open_until = None;
+        self.trial = Some(None);
+        true
+    }
+
+    /// Note that event `id` was handed to the handler.
+    pub(crate) fn started(&mut self, id: &str) {
+        if let Some(trial @ None) = &mut self.trial {
+            *trial = Some(id.to_string());
+        }
+    }
+
+    /// Record how handling event `id` went; true if the circuit opened.
+    pub(crate) async fn record(&mut self, id: &str, ok: bool) -> bool {
+        if self.open_until.is_some() {
+            // Started before the circuit opened
+            return false;
+        }
+        if let Some(trial) = &self.trial {
+            if trial.as_deref() != Some(id) {
+                return false;
+            }
+            self.trial = None;
+            if ok {
+                self.report(json!({ "state": "closed" })).await;
+                return false;
+            }
+            self.outcomes.clear();
+            self.outcomes.push_back(false);
+            self.open(id).await;
+            return true;
+        }
+
+        self.outcomes.push_back(ok);
+        if self.outcomes.len() > self.guard.window {
+            self.outcomes.pop_front();
+        }
+        let failures = self.outcomes.iter().filter(|ok| !**ok).count();
+        if ok
+            || self.outcomes.len() < self.guard.window
+            || (failures as f64) < self.guard.error_rate * self.outcomes.len() as f64
+        {
+            return false;
+        }
+        self.open(id).await;
+        true
+    }
+
+    async fn open(&mut self, id: &str) {
+        self.open_until = Some(Instant::now() + self.guard.cooldown);
+        let handled = self.outcomes.len();
+        let failures = self.outcomes.iter().filter(|ok| !**ok).count();
+        self.outcomes.clear();
+        self.report(json!({
+            "state": "open",
+            "failures": failures,
+            "handled": handled,
+            "error_rate": failures as f64 / handled as f64,
+            "cooldown_ms": self.guard.cooldown.as_millis() as u64,
+            "event_id": id,
+        }))
+        .await;
+    }
+
+    async fn report(&self, data: serde_json::Value) {
+        if let Some((client, topic)) = &self.guard.diagnostics {
+            // Best effort: a failing emit must not stop processing
+            let _ = client.emit(topic, data).await;
+        }
+    }
+}
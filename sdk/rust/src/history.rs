@@ -0,0 +1,164 @@
+//! Event history queries over HTTP.
+
+use std::future::Future;
+
+use reqwest::Method;
+use tokio::time::Instant;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::types::{
+    HistoryEvent, HistoryOptions, HistoryPage, ListEventsResponse, ReplayOptions, ReplaySpeed,
+};
+
+/// Page size the server uses when no limit is given.
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Page size `replay` fetches history with (the server maximum).
+const REPLAY_PAGE_SIZE: u32 = 1000;
+
+impl Notif {
+    /// Query past events on a topic without opening a WebSocket.
+    ///
+    /// Pass `HistoryPage::next_cursor` back in `HistoryOptions::cursor` to
+    /// fetch the following page.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{HistoryOptions, Notif};
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    ///
+    /// let mut options = HistoryOptions::new().limit(500);
+    /// loop {
+    ///     let page = client.history("orders.created", options.clone()).await?;
+    ///     for event in &page.events {
+    ///         println!("#{} {}: {}", event.seq, event.topic, event.data);
+    ///     }
+    ///     match page.next_cursor {
+    ///         Some(cursor) => options = options.cursor(cursor),
+    ///         None => break,
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn history(&self, topic: &str, options: HistoryOptions) -> Result<HistoryPage> {
+        let mut query: Vec<(&str, String)> = vec![("topic", topic.to_string())];
+        if let Some(since) = options.since {
+            query.push(("from", since.to_rfc3339()));
+        }
+        if let Some(until) = options.until {
+            query.push(("to", until.to_rfc3339()));
+        }
+        if let Some(limit) = options.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(cursor) = options.cursor {
+            query.push(("cursor", cursor.to_string()));
+        }
+
+        let response = self
+            .execute(self.http(Method::GET, "/api/v1/events").query(&query))
+            .await?;
+        let list: ListEventsResponse = response.json().await?;
+        let received = list.events.len();
+
+        // Skip anything at or before the cursor in case the server ignores it.
+        let events: Vec<HistoryEvent> = list
+            .events
+            .into_iter()
+            .filter(|stored| options.cursor.is_none_or(|cursor| stored.seq > cursor))
+            .map(|stored| HistoryEvent {
+                seq: stored.seq,
+                id: stored.event.id,
+                topic: stored.event.topic,
+                data: stored.event.data,
+                headers: stored.event.headers,
+                timestamp: stored.event.timestamp,
+            })
+            .collect();
+
+        let page_size = options
+            .limit
+            .map_or(DEFAULT_PAGE_SIZE, |limit| limit as usize);
+        let next_cursor = if received >= page_size {
+            events.last().map(|event| event.seq)
+        } else {
+            None
+        };
+
+        Ok(HistoryPage {
+            events,
+            next_cursor,
+        })
+    }
+
+    /// Redeliver past events on a topic to `handler`, oldest first.
+    ///
+    /// Pages through `history` within the `from`/`until` bounds and calls
+    /// `handler` once per event, waiting for it to finish before the next
+    /// one. With `ReplaySpeed::RealTime` events are spaced by their
+    /// original gaps; a slow handler delays later events but the
+    /// remaining schedule is kept. Returns the number of events replayed,
+    /// or the first error of `handler` or a history query.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, ReplayOptions, ReplaySpeed};
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let since = chrono::Utc::now() - chrono::Duration::hours(1);
+    /// let options = ReplayOptions::new().from(since).speed(ReplaySpeed::RealTime);
+    ///
+    /// let replayed = client
+    ///     .replay("agent.session.events", options, |event| async move {
+    ///         println!("{} {}: {}", event.timestamp, event.topic, event.data);
+    ///         Ok(())
+    ///     })
+    ///     .await?;
+    /// println!("replayed {replayed} events");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn replay<F, Fut>(
+        &self,
+        topic: &str,
+        options: ReplayOptions,
+        mut handler: F,
+    ) -> Result<u64>
+    where
+        F: FnMut(HistoryEvent) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut query = HistoryOptions {
+            since: options.from,
+            until: options.until,
+            limit: Some(REPLAY_PAGE_SIZE),
+            cursor: None,
+        };
+        // Replay start, and the timestamp of the first event it maps to
+        let mut origin = None;
+        let mut replayed = 0;
+
+        loop {
+            let page = self.history(topic, query.clone()).await?;
+            for event in page.events {
+                if options.speed == ReplaySpeed::RealTime {
+                    let first = event.timestamp;
+                    let (started, first) = *origin.get_or_insert((Instant::now(), first));
+                    if let Ok(offset) = (event.timestamp - first).to_std() {
+                        tokio::time::sleep_until(started + offset).await;
+                    }
+                }
+                handler(event).await?;
+                replayed += 1;
+            }
+            match page.next_cursor {
+                Some(cursor) => query = query.cursor(cursor),
+                None => return Ok(replayed),
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Hooks for observing and modifying traffic through the client.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::{NotifError, Result};
+use crate::types::{AckLevel, EmitResponse, Event};
+
+/// An event about to be emitted, as seen by an [`Interceptor`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct OutgoingEvent {
+    /// Topic the event will be published to.
+    pub topic: String,
+    /// Event payload.
+    pub data: serde_json::Value,
+    /// Custom headers sent with the event.
+    pub headers: HashMap<String, String>,
+    /// How far the event must get before the emit resolves.
+    pub ack_level: AckLevel,
+}
+
+/// Observes or modifies outgoing emits and incoming events.
+///
+/// Register with `NotifBuilder::with_interceptor`. Interceptors run in
+/// registration order; returning an error from `on_emit` aborts the emit,
+/// and from `on_event` yields the error in place of the event.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{Event, Interceptor, Notif, OutgoingEvent};
+///
+/// struct TenantTagger;
+///
+/// impl Interceptor for TenantTagger {
+///     fn on_emit(&self, event: &mut OutgoingEvent) -> notifsh::Result<()> {
+///         event.headers.insert("tenant".into(), "acme".into());
+///         Ok(())
+///     }
+///
+///     fn on_event(&self, event: &mut Event) -> notifsh::Result<()> {
+///         println!("received {} on {}", event.id, event.topic);
+///         Ok(())
+///     }
+/// }
+///
+/// let client = Notif::builder("nsh_your_api_key")
+///     .with_interceptor(TenantTagger)
+///     .build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+pub trait Interceptor: Send + Sync + 'static {
+    /// Called before an event is emitted.
+    fn on_emit(&self, event: &mut OutgoingEvent) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    /// Called after an emit completes, successfully or not.
+    fn on_emit_result(
+        &self,
+        event: &OutgoingEvent,
+        result: std::result::Result<&EmitResponse, &NotifError>,
+    ) {
+        let _ = (event, result);
+    }
+
+    /// Called for each received event before it is yielded.
+    fn on_event(&self, event: &mut Event) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    /// Called for each error yielded by a subscription.
+    fn on_error(&self, error: &NotifError) {
+        let _ = error;
+    }
+}
+
+/// Registered interceptors, in order.
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors(Vec<Arc<dyn Interceptor>>);
+
+impl Interceptors {
+    pub(crate) fn push(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.0.push(interceptor);
+    }
+
+    pub(crate) fn on_emit(&self, event: &mut OutgoingEvent) -> Result<()> {
+        self.0.iter().try_for_each(|i| i.on_emit(event))
+    }
+
+    pub(crate) fn on_emit_result(&self, event: &OutgoingEvent, result: &Result<EmitResponse>) {
+        for interceptor in &self.0 {
+            interceptor.on_emit_result(event, result.as_ref());
+        }
+    }
+
+    /// Run incoming hooks, turning a rejected event into an error.
+    pub(crate) fn on_event(&self, event: Result<Event>) -> Result<Event> {
+        let result = event.and_then(|mut event| {
+            self.0.iter().try_for_each(|i| i.on_event(&mut event))?;
+            Ok(event)
+        });
+        if let Err(err) = &result {
+            for interceptor in &self.0 {
+                interceptor.on_error(err);
+            }
+        }
+        result
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interceptors({})", self.0.len())
+    }
+}
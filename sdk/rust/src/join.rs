@@ -0,0 +1,355 @@
+//! Pairing the events of two streams by a correlation key.
+//!
+//! [`JoinExt::join`] pairs each event of one stream with the event of the
+//! other that has the same key, such as `orders.created` and
+//! `payments.settled` by `data.order_id`, as long as the second arrives
+//! within the join window of the first. Events are paired one to one, in
+//! arrival order; those still unmatched when their window runs out, or
+//! without a key at all, are yielded on their own so they can be handled
+//! too, e.g. to flag orders that were never paid.
+//!
+//! The join does not settle events: with manual acks, ack the events of
+//! each item once it is handled.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use notifsh::{JoinExt, Joined, Notif};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let orders = client.subscribe(&["orders.created"]).await?;
+//! let payments = client.subscribe(&["payments.settled"]).await?;
+//!
+//! let mut joined = orders
+//!     .join(payments, "data.id")
+//!     .other_key("data.order_id")
+//!     .within(Duration::from_secs(15 * 60))
+//!     .build();
+//!
+//! while let Some(item) = joined.next().await {
+//!     match item? {
+//!         Joined::Pair { left, right } => println!("order {} paid by {}", left.id, right.id),
+//!         Joined::Left(order) => println!("order {} not paid in time", order.id),
+//!         Joined::Right(payment) => println!("payment {} for no known order", payment.id),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::stream::{self, BoxStream};
+use futures_util::{Stream, StreamExt};
+use tokio::time::Instant;
+
+use crate::error::Result;
+use crate::filter::FieldPath;
+use crate::types::Event;
+
+/// Default join window.
+const DEFAULT_WITHIN: Duration = Duration::from_secs(300);
+
+/// An item of a [`Join`].
+#[derive(Debug, Clone)]
+pub enum Joined {
+    /// Events of both streams with the same key.
+    Pair {
+        /// The event of the stream `join` was called on.
+        left: Event,
+        /// The event of the other stream.
+        right: Event,
+    },
+    /// An event of the left stream that nothing matched in time, or that
+    /// has no key.
+    Left(Event),
+    /// An event of the right stream that nothing matched in time, or that
+    /// has no key.
+    Right(Event),
+}
+
+/// Adds [`join`](JoinExt::join) to every stream of events.
+pub trait JoinExt: Stream<Item = Result<Event>> + Sized {
+    /// Pair events of this stream and `other` whose field `key` (such as
+    /// `data.order_id` or `headers.correlation-id`) is equal.
+    ///
+    /// Configure the join on the returned builder, then call
+    /// [`JoinBuilder::build`] to get the stream of pairs.
+    fn join<R>(self, other: R, key: &str) -> JoinBuilder<Self, R>
+    where
+        R: Stream<Item = Result<Event>>,
+    {
+        JoinBuilder {
+            left: self,
+            right: other,
+            left_key: key.to_string(),
+            right_key: key.to_string(),
+            within: DEFAULT_WITHIN,
+        }
+    }
+}
+
+impl<S: Stream<Item = Result<Event>>> JoinExt for S {}
+
+/// Configuration of a join, from [`JoinExt::join`].
+pub struct JoinBuilder<L, R> {
+    left: L,
+    right: R,
+    left_key: String,
+    right_key: String,
+    within: Duration,
+}
+
+impl<L, R> JoinBuilder<L, R> {
+    /// Read the key of the other stream's events from `key` instead.
+    pub fn other_key(mut self, key: &str) -> Self {
+        self.right_key = key.to_string();
+        self
+    }
+
+    /// Pair events arriving up to `within` apart (default: 5m).
+    pub fn within(mut self, within: Duration) -> Self {
+        self.within = within;
+        self
+    }
+}
+
+impl<L, R> JoinBuilder<L, R>
+where
+    L: Stream<Item = Result<Event>> + Unpin + Send + 'static,
+    R: Stream<Item = Result<Event>> + Unpin + Send + 'static,
+{
+    /// Start joining the two streams.
+    pub fn build(self) -> Join {
+        let left_key = self.left_key.clone();
+        let right_key = self.right_key.clone();
+        let within = self.within;
+        let inner = match State::new(self) {
+            Ok(state) => stream::unfold(state, State::next).boxed(),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        };
+        Join {
+            left_key,
+            right_key,
+            within,
+            inner,
+        }
+    }
+}
+
+impl<L, R> fmt::Debug for JoinBuilder<L, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinBuilder")
+            .field("left_key", &self.left_key)
+            .field("right_key", &self.right_key)
+            .field("within", &self.within)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Two streams of events joined by key, from [`JoinBuilder::build`].
+///
+/// Implements `futures::Stream<Item = Result<Joined>>`; errors of either
+/// stream are passed through, and an invalid key path is yielded as
+/// `NotifError::InvalidKeyPath` before the stream ends.
+pub struct Join {
+    left_key: String,
+    right_key: String,
+    within: Duration,
+    inner: BoxStream<'static, Result<Joined>>,
+}
+
+impl Stream for Join {
+    type Item = Result<Joined>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for Join {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Join")
+            .field("left_key", &self.left_key)
+            .field("right_key", &self.right_key)
+            .field("within", &self.within)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Events of one side waiting for a match, by key, oldest first.
+type Waiting = HashMap<String, VecDeque<Event>>;
+
+struct State<L, R> {
+    left: Option<L>,
+    right: Option<R>,
+    left_key: FieldPath,
+    right_key: FieldPath,
+    within: Duration,
+    waiting: [Waiting; 2],
+    /// Every waiting event as side, key and ID, in deadline order.
+    deadlines: VecDeque<(Instant, Side, String, String)>,
+    ready: VecDeque<Joined>,
+}
+
+impl<L, R> State<L, R>
+where
+    L: Stream<Item = Result<Event>> + Unpin,
+    R: Stream<Item = Result<Event>> + Unpin,
+{
+    fn new(config: JoinBuilder<L, R>) -> Result<Self> {
+        Ok(Self {
+            left: Some(config.left),
+            right: Some(config.right),
+            left_key: FieldPath::parse(&config.left_key)?,
+            right_key: FieldPath::parse(&config.right_key)?,
+            within: config.within,
+            waiting: [HashMap::new(), HashMap::new()],
+            deadlines: VecDeque::new(),
+            ready: VecDeque::new(),
+        })
+    }
+
+    async fn next(mut self) -> Option<(Result<Joined>, Self)> {
+        loop {
+            if let Some(joined) = self.ready.pop_front() {
+                return Some((Ok(joined), self));
+            }
+            if self.left.is_none() && self.right.is_none() {
+                // Nothing can match what is still waiting
+                let waiting = self.deadlines.drain(..).collect::<Vec<_>>();
+                for (_, side, key, id) in waiting {
+                    if let Some(event) = take(&mut self.waiting[side as usize], &key, &id) {
+                        self.ready.push_back(unmatched(side, event));
+                    }
+                }
+                if self.ready.is_empty() {
+                    return None;
+                }
+                continue;
+            }
+
+            let deadline = self.deadlines.front().map(|(deadline, ..)| *deadline);
+            let (side, item) = tokio::select! {
+                item = next_of(&mut self.left) => (Side::Left, item),
+                item = next_of(&mut self.right) => (Side::Right, item),
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    self.expire();
+                    continue;
+                }
+            };
+            match item {
+                Some(Ok(event)) => self.add(side, event),
+                Some(Err(e)) => return Some((Err(e), self)),
+                None if side == Side::Left => self.left = None,
+                None => self.right = None,
+            }
+        }
+    }
+
+    /// Pair `event` with the oldest waiting event of the other side with
+    /// its key, or make it wait for one.
+    fn add(&mut self, side: Side, event: Event) {
+        let path = match side {
+            Side::Left => &self.left_key,
+            Side::Right => &self.right_key,
+        };
+        let Some(key) = path.key(&event) else {
+            self.ready.push_back(unmatched(side, event));
+            return;
+        };
+        let other = match side {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        };
+        let matched = self.waiting[other as usize]
+            .get_mut(&key)
+            .and_then(VecDeque::pop_front);
+        match matched {
+            Some(matched) => {
+                if self.waiting[other as usize]
+                    .get(&key)
+                    .is_some_and(VecDeque::is_empty)
+                {
+                    self.waiting[other as usize].remove(&key);
+                }
+                let (left, right) = match side {
+                    Side::Left => (event, matched),
+                    Side::Right => (matched, event),
+                };
+                self.ready.push_back(Joined::Pair { left, right });
+            }
+            None => {
+                self.deadlines.push_back((
+                    Instant::now() + self.within,
+                    side,
+                    key.clone(),
+                    event.id.clone(),
+                ));
+                self.waiting[side as usize]
+                    .entry(key)
+                    .or_default()
+                    .push_back(event);
+            }
+        }
+    }
+
+    /// Yield the waiting events whose window ran out.
+    fn expire(&mut self) {
+        let now = Instant::now();
+        while let Some((deadline, ..)) = self.deadlines.front() {
+            if *deadline > now {
+                break;
+            }
+            let (_, side, key, id) = self.deadlines.pop_front().expect("checked above");
+            // Already paired if it is gone
+            if let Some(event) = take(&mut self.waiting[side as usize], &key, &id) {
+                self.ready.push_back(unmatched(side, event));
+            }
+        }
+    }
+}
+
+/// The next item of `stream`, or never once it has ended.
+async fn next_of<S>(stream: &mut Option<S>) -> Option<Result<Event>>
+where
+    S: Stream<Item = Result<Event>> + Unpin,
+{
+    match stream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Remove the waiting event `id` with `key`.
+fn take(waiting: &mut Waiting, key: &str, id: &str) -> Option<Event> {
+    let queue = waiting.get_mut(key)?;
+    let event = queue
+        .iter()
+        .position(|event| event.id == id)
+        .and_then(|at| queue.remove(at));
+    if queue.is_empty() {
+        waiting.remove(key);
+    }
+    event
+}
+
+fn unmatched(side: Side, event: Event) -> Joined {
+    match side {
+        Side::Left => Joined::Left(event),
+        Side::Right => Joined::Right(event),
+    }
+}
@@ -0,0 +1,368 @@
+//! A local, size-capped on-disk log of received events.
+//!
+//! Attach an [`EventJournal`] to a stream with `EventStream::journal` and
+//! every event it yields is appended to disk, so an app can rebuild its
+//! view of recent history after a restart with [`EventJournal::replay`].
+//!
+//! Each record is a little-endian `u32` length followed by the event
+//! serialized with serde. Payloads are arbitrary JSON, so records use
+//! JSON rather than a schema-driven format like bincode, which cannot
+//! read self-describing values back.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::error::Result;
+use crate::types::Event;
+
+/// Default cap on the journal's size on disk.
+const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Largest record read back; anything bigger is a corrupt length.
+const MAX_RECORD: usize = 64 * 1024 * 1024;
+
+/// One event as stored in the journal.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    id: String,
+    topic: String,
+    data: serde_json::Value,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    headers: HashMap<String, String>,
+    timestamp: DateTime<Utc>,
+    attempt: u32,
+    max_attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seq: Option<u64>,
+}
+
+impl From<&Event> for Record {
+    fn from(event: &Event) -> Self {
+        Self {
+            id: event.id.clone(),
+            topic: event.topic.clone(),
+            data: event.data.clone(),
+            headers: event.headers.clone(),
+            timestamp: event.timestamp,
+            attempt: event.attempt,
+            max_attempts: event.max_attempts,
+            seq: event.seq,
+        }
+    }
+}
+
+impl From<Record> for Event {
+    fn from(record: Record) -> Self {
+        Event {
+            id: record.id,
+            topic: record.topic,
+            data: record.data,
+            headers: record.headers,
+            timestamp: record.timestamp,
+            attempt: record.attempt,
+            max_attempts: record.max_attempts,
+            seq: record.seq,
+            ack_tx: None,
+        }
+    }
+}
+
+/// The open segment events are appended to.
+struct Writer {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    max_bytes: u64,
+}
+
+impl Writer {
+    fn append(&mut self, record: &[u8]) -> Result<()> {
+        let size = 4 + record.len() as u64;
+        if self.len > 0 && self.len + size > self.max_bytes / 2 {
+            self.rotate()?;
+        }
+        let mut frame = Vec::with_capacity(size as usize);
+        frame.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        frame.extend_from_slice(record);
+        self.file.write_all(&frame)?;
+        self.len += size;
+        Ok(())
+    }
+
+    /// Move the current segment aside, dropping the one moved aside before.
+    fn rotate(&mut self) -> Result<()> {
+        fs::rename(&self.path, previous(&self.path))?;
+        self.file = File::create(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// Appends received events to a file, keeping at most a configured number
+/// of bytes of the most recent ones.
+///
+/// The journal is split into two segments, `path` and `path.1`. Once
+/// `path` reaches half the cap it replaces `path.1`, so the oldest half
+/// is dropped at once and the total stays under the cap. A record cut
+/// short by a crash is discarded when the journal is reopened.
+///
+/// Cheap to clone; clones append to the same files.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use notifsh::{EventJournal, Notif};
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// let journal = EventJournal::open("events.journal")?.max_bytes(16 * 1024 * 1024);
+///
+/// // After a restart: show what was received before
+/// let mut history = journal.replay()?;
+/// while let Some(event) = history.next().await {
+///     let event = event?;
+///     println!("{} {}: {}", event.timestamp, event.topic, event.data);
+/// }
+///
+/// // Then keep recording
+/// let mut stream = client.subscribe(&["agent.>"]).await?.journal(journal);
+/// while let Some(event) = stream.next().await {
+///     let event = event?;
+///     println!("{}: {}", event.topic, event.data);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct EventJournal {
+    writer: Arc<Mutex<Writer>>,
+}
+
+impl EventJournal {
+    /// Open the journal at `path`, creating it if needed (default cap: 64 MiB).
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let len = complete_len(&mut file)?;
+        if len < file.metadata()?.len() {
+            file.set_len(len)?;
+        }
+        Ok(Self {
+            writer: Arc::new(Mutex::new(Writer {
+                path,
+                file,
+                len,
+                max_bytes: DEFAULT_MAX_BYTES,
+            })),
+        })
+    }
+
+    /// Keep at most `bytes` on disk, across both segments.
+    ///
+    /// Must leave room for the largest event: a record bigger than half
+    /// the cap still gets a segment to itself.
+    pub fn max_bytes(self, bytes: u64) -> Self {
+        self.writer.lock().unwrap().max_bytes = bytes;
+        self
+    }
+
+    /// Append `event` to the journal.
+    ///
+    /// Writes to disk before returning; streams the journal is attached to
+    /// append from a background task instead. Call it directly to record
+    /// events from elsewhere.
+    pub fn append(&self, event: &Event) -> Result<()> {
+        self.writer.lock().unwrap().append(&encode(event)?)
+    }
+
+    /// Start a task appending the records sent to it, in order, without
+    /// blocking the runtime on file I/O.
+    pub(crate) fn spawn_writer(&self) -> mpsc::UnboundedSender<Vec<u8>> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let writer = self.writer.clone();
+        tokio::spawn(async move {
+            while let Some(record) = rx.recv().await {
+                let mut batch = vec![record];
+                while let Ok(record) = rx.try_recv() {
+                    batch.push(record);
+                }
+                let writer = writer.clone();
+                let _ = tokio::task::spawn_blocking(move || {
+                    let mut writer = writer.lock().unwrap();
+                    for record in &batch {
+                        let _ = writer.append(record);
+                    }
+                })
+                .await;
+            }
+        });
+        tx
+    }
+
+    /// Read the journaled events back, oldest first.
+    ///
+    /// The replay covers the events appended before this call. Its
+    /// events cannot be acked: they were settled when first received.
+    pub fn replay(&self) -> Result<JournalReplay> {
+        let writer = self.writer.lock().unwrap();
+        // Open both segments now, so a rotation while replaying moves
+        // files out from under the names but not the open handles
+        let mut segments = Vec::new();
+        match File::open(previous(&writer.path)) {
+            Ok(file) => {
+                let len = file.metadata()?.len();
+                segments.push(BufReader::new(file).take(len));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        segments.push(BufReader::new(File::open(&writer.path)?).take(writer.len));
+        segments.reverse();
+        Ok(JournalReplay { segments })
+    }
+
+    /// Bytes the journal takes on disk.
+    pub fn size(&self) -> u64 {
+        let writer = self.writer.lock().unwrap();
+        let previous = fs::metadata(previous(&writer.path)).map_or(0, |m| m.len());
+        previous + writer.len
+    }
+
+    /// Remove every journaled event.
+    pub fn clear(&self) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = fs::remove_file(previous(&writer.path)) {
+            if e.kind() != io::ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        writer.file.set_len(0)?;
+        writer.len = 0;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for EventJournal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let writer = self.writer.lock().unwrap();
+        f.debug_struct("EventJournal")
+            .field("path", &writer.path)
+            .field("max_bytes", &writer.max_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Events read back from an [`EventJournal`], oldest first.
+///
+/// Implements `futures::Stream<Item = Result<Event>>`, like an
+/// `EventStream`. A record that fails to decode is yielded as an
+/// error and ends the replay.
+pub struct JournalReplay {
+    /// Segments still to read, up to their length at replay time, the
+    /// oldest last.
+    segments: Vec<io::Take<BufReader<File>>>,
+}
+
+impl JournalReplay {
+    fn read_next(&mut self) -> Result<Option<Event>> {
+        while let Some(reader) = self.segments.last_mut() {
+            match read_record(reader)? {
+                Some(bytes) => {
+                    let record: Record = serde_json::from_slice(&bytes)?;
+                    return Ok(Some(record.into()));
+                }
+                None => {
+                    self.segments.pop();
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Stream for JournalReplay {
+    type Item = Result<Event>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        Poll::Ready(match this.read_next() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                this.segments.clear();
+                Some(Err(e))
+            }
+        })
+    }
+}
+
+impl fmt::Debug for JournalReplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JournalReplay")
+            .field("segments", &self.segments.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Serialize `event` as a journal record.
+pub(crate) fn encode(event: &Event) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&Record::from(event))?)
+}
+
+/// Where the older segment of the journal at `path` lives.
+fn previous(path: &Path) -> PathBuf {
+    let mut previous = path.as_os_str().to_owned();
+    previous.push(".1");
+    PathBuf::from(previous)
+}
+
+/// The next record of `reader`, or `None` at its end or at a record cut
+/// short.
+fn read_record(reader: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    if !read_full(reader, &mut len)? {
+        return Ok(None);
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_RECORD {
+        return Ok(None);
+    }
+    let mut record = vec![0; len];
+    if !read_full(reader, &mut record)? {
+        return Ok(None);
+    }
+    Ok(Some(record))
+}
+
+/// Fill `buf`, returning false if `reader` ends first.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Length of the complete records at the start of `file`.
+fn complete_len(file: &mut File) -> Result<u64> {
+    let mut reader = BufReader::new(&*file);
+    let mut len = 0;
+    while let Some(record) = read_record(&mut reader)? {
+        len += 4 + record.len() as u64;
+    }
+    Ok(len)
+}
@@ -53,6 +53,7 @@
 //! ```no_run
 //! use notifsh::{Notif, SubscribeOptions};
 //! use futures::StreamExt;
+//! use std::time::Duration;
 //!
 //! # async fn example() -> notifsh::Result<()> {
 //! let client = Notif::from_env()?;
@@ -71,21 +72,179 @@
 //!     let event = event?;
 //!     // Process event...
 //!     event.ack().await?;
-//!     // Or: event.nack(Some("5m")).await?;
+//!     // Or: event.nack(Some(Duration::from_secs(300))).await?;
 //! }
 //! # Ok(())
 //! # }
 //! ```
 
-mod client;
+// The browser client leaves most of the shared wire types unused
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
+
+// Everything but the wire types needs the native runtime; see `wasm` for
+// the browser client.
+macro_rules! native {
+    ($($item:item)*) => {
+        $(
+            #[cfg(not(target_arch = "wasm32"))]
+            $item
+        )*
+    };
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("building for wasm32 requires the `wasm` feature");
+
+mod binary;
+mod cloudevents;
+mod duration;
 mod error;
-mod subscribe;
+mod patch;
+mod topic;
 mod types;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod wasm;
+
+native! {
+    #[cfg(feature = "axum")]
+    pub mod axum;
+    #[cfg(feature = "blocking")]
+    pub mod blocking;
+    pub mod bridge;
+    mod buffered;
+    mod catalog;
+    mod chunk;
+    mod client;
+    mod coalesce;
+    #[cfg(feature = "compat")]
+    pub mod compat;
+    mod concurrent;
+    mod consumers;
+    mod config;
+    mod credentials;
+    mod connection;
+    pub mod coordination;
+    mod dedupe;
+    mod delayed;
+    mod deliveries;
+    mod dlq;
+    mod dropped;
+    #[cfg(feature = "encryption")]
+    mod encryption;
+    #[cfg(feature = "eventbridge")]
+    pub mod eventbridge;
+    mod event_type;
+    mod filter;
+    mod frames;
+    pub mod flows;
+    mod guard;
+    mod history;
+    mod interceptor;
+    mod join;
+    mod journal;
+    mod merge;
+    mod namespace;
+    #[cfg(feature = "nats")]
+    pub mod nats;
+    #[cfg(feature = "msgpack")]
+    mod msgpack;
+    mod ordering;
+    #[cfg(feature = "sqlx")]
+    pub mod outbox;
+    mod parking;
+    mod projector;
+    mod ratelimit;
+    pub mod raw;
+    #[cfg(feature = "redis")]
+    pub mod redis;
+    mod registry;
+    mod retry;
+    mod router;
+    mod rpc;
+    mod rules;
+    mod sample;
+    mod schema;
+    mod sequence;
+    mod session;
+    #[cfg(feature = "signing")]
+    mod signing;
+    mod shared;
+    mod sink;
+    pub mod sinks;
+    mod sse;
+    mod state_cache;
+    mod stats;
+    mod subscribe;
+    mod telemetry;
+    #[cfg(feature = "testing")]
+    pub mod testing;
+    mod transport;
+    #[cfg(feature = "jsonschema")]
+    mod validation;
+    mod webhooks;
+    mod window;
+
+    pub use buffered::{BufferedEmitter, Buffering};
+    pub use chunk::{CHUNK_COUNT_HEADER, CHUNK_ID_HEADER, CHUNK_INDEX_HEADER};
+    pub use client::{Notif, NotifBuilder};
+    pub use coalesce::{CoalesceExt, Coalesced};
+    pub use concurrent::Concurrency;
+    pub use credentials::CredentialsProvider;
+    pub use delayed::ScheduledEmit;
+    pub use dlq::{
+        DlqEvent, DlqReplay, DlqReplayOptions, Transform, DLQ_ERROR_HEADER,
+        DLQ_ORIGINAL_ID_HEADER, DLQ_SEQ_HEADER,
+    };
+    pub use dropped::{DropReason, DroppedEvent, DroppedEventHandler};
+    #[cfg(feature = "encryption")]
+    pub use encryption::{KeyProvider, LocalKeys, WrappedKey};
+    pub use event_type::NotifEvent;
+    #[cfg(feature = "derive")]
+    pub use notifsh_derive::NotifEvent;
+    pub use frames::{DebugFrame, FrameDirection, FrameSink};
+    pub use guard::HandlerGuard;
+    pub use interceptor::{Interceptor, OutgoingEvent};
+    pub use join::{Join, JoinBuilder, JoinExt, Joined};
+    pub use journal::{EventJournal, JournalReplay};
+    pub use merge::{merge_prioritized, Prioritized};
+    pub use namespace::Namespaced;
+    pub use parking::{
+        ParkingPolicy, PARKED_AT_HEADER, PARKED_ATTEMPTS_HEADER, PARKED_ID_HEADER, PARKED_SUFFIX,
+        PARKED_TOPIC_HEADER,
+    };
+    pub use projector::{
+        FileSnapshotStore, MemorySnapshotStore, Projection, Projector, Snapshot, SnapshotStore,
+    };
+    pub use ratelimit::{RateLimitStatus, Throttle};
+    pub use registry::NotifRegistry;
+    pub use retry::RetryPolicy;
+    pub use router::{EventRouter, RouteFailure};
+    pub use rules::{Condition, Rules, DERIVED_FROM_HEADER};
+    pub use sequence::SequenceWatch;
+    pub use session::{Session, SessionEvent};
+    pub use shared::SharedSubscription;
+    pub use sink::EmitSink;
+    #[cfg(feature = "signing")]
+    pub use signing::{SigningKey, TrustedKeys, KEYS_TOPIC};
+    pub use state_cache::{StateCache, StateCacheBuilder};
+    pub use stats::StreamStats;
+    pub use subscribe::{ControlEvents, EventStream, TypedEventStream};
+    #[cfg(feature = "jsonschema")]
+    pub use validation::SchemaValidation;
+    pub use window::{Window, WindowExt, WindowStream, Windows};
+}
 
-pub use client::{Notif, NotifBuilder};
+pub use cloudevents::CloudEvent;
 pub use error::{NotifError, Result};
-pub use subscribe::EventStream;
+pub use patch::{JsonPatch, PatchOperation};
+pub use topic::{Topic, TopicBuilder};
 pub use types::{
-    CreateScheduleResponse, EmitResponse, Event, ListSchedulesResponse, RunScheduleResponse,
-    Schedule, SubscribeOptions,
+    AckLevel, Backoff, BackpressurePolicy, ClientMode, ConnectionState, ConsumerLag, ControlEvent,
+    CreateScheduleResponse, Delivery, EmitOptions, EmitResponse, Event, EventCursor,
+    FilteredPolicy, HistoryEvent, HistoryOptions, HistoryPage, Identity, ListSchedulesResponse,
+    ReplayOptions, ReplaySpeed, RunScheduleResponse, Schedule, ScheduleFilter, Schema,
+    SchemaVersion, SubscribeOptions, SubscribeTransport, TopicInfo, TopicRetention, TypedEvent,
+    UpdateSchedule, Webhook,
 };
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub use wasm::{EventStream, Notif, NotifBuilder};
@@ -0,0 +1,268 @@
+//! Several subscriptions, each with its own options, merged into one
+//! event stream.
+
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::stream::{self, SelectAll};
+use futures_util::{Stream, StreamExt};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_stream::wrappers::WatchStream;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::stats::StreamStats;
+use crate::subscribe::{Command, ControlEvents, EventStream, CONTROL_CHANNEL_SIZE};
+use crate::types::{ConnectionState, ControlEvent, Event, SubscribeOptions};
+
+impl Notif {
+    /// Subscribe to several topic patterns, each with its own options, and
+    /// merge their events into one stream.
+    ///
+    /// Each pattern gets its own subscription, so one can join a consumer
+    /// group with manual acks while another replays history with auto-ack.
+    /// Events keep their `topic` and are settled on the subscription they
+    /// came from. Closing or pausing the merged stream closes or pauses
+    /// them all; its state is `Reconnecting` while any of them reconnects.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SubscribeOptions};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let mut stream = client
+    ///     .subscribe_many(vec![
+    ///         ("orders.*", SubscribeOptions::new().group("fulfillment").auto_ack(false)),
+    ///         ("billing.>", SubscribeOptions::new().from("beginning")),
+    ///     ])
+    ///     .await?;
+    ///
+    /// while let Some(event) = stream.next().await {
+    ///     let event = event?;
+    ///     println!("{}: {:?}", event.topic, event.data);
+    ///     event.ack().await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_many<I, T>(&self, subscriptions: I) -> Result<EventStream>
+    where
+        I: IntoIterator<Item = (T, SubscribeOptions)>,
+        T: AsRef<str>,
+    {
+        let mut sources = Vec::new();
+        for (topic, options) in subscriptions {
+            sources.push(
+                self.subscribe_with_options(&[topic.as_ref()], options)
+                    .await?,
+            );
+        }
+        if sources.is_empty() {
+            return Err(NotifError::InvalidTopic {
+                topic: String::new(),
+                reason: "no topics to subscribe to".to_string(),
+            });
+        }
+
+        let stats: Vec<_> = sources.iter().map(EventStream::stats).collect();
+        let stats = StreamStats::merged(&stats);
+        let states: Vec<_> = sources.iter().map(EventStream::watch_state).collect();
+        let control = stream::select_all(sources.iter_mut().map(EventStream::control_events));
+
+        let (event_tx, event_rx) = mpsc::channel(1);
+        // Events are settled on their own subscription, never through here
+        let (ack_tx, _) = mpsc::channel(1);
+        let (control_tx, control_rx) = broadcast::channel(CONTROL_CHANNEL_SIZE);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(fold(&states));
+
+        let merger = Merger {
+            sources: stream::select_all(sources),
+            control,
+            changes: stream::select_all(states.iter().cloned().map(WatchStream::new)),
+            states,
+            next: None,
+            event_tx,
+            control_tx: control_tx.clone(),
+            command_rx,
+            state_tx,
+        };
+        tokio::spawn(merger.run());
+
+        Ok(EventStream::from_parts(
+            event_rx, ack_tx, control_tx, control_rx, command_tx, state_rx, stats,
+        ))
+    }
+}
+
+/// Background task feeding a merged stream.
+struct Merger {
+    sources: SelectAll<EventStream>,
+    control: SelectAll<ControlEvents>,
+    /// Wakes on any source's state change.
+    changes: SelectAll<WatchStream<ConnectionState>>,
+    states: Vec<watch::Receiver<ConnectionState>>,
+    /// Item waiting for room in the merged stream.
+    next: Option<Result<Event>>,
+    event_tx: mpsc::Sender<Result<Event>>,
+    control_tx: broadcast::Sender<ControlEvent>,
+    command_rx: mpsc::UnboundedReceiver<Command>,
+    state_tx: watch::Sender<ConnectionState>,
+}
+
+impl Merger {
+    async fn run(mut self) {
+        loop {
+            tokio::select! {
+                item = self.sources.next(), if self.next.is_none() => match item {
+                    Some(item) => self.next = Some(item),
+                    None => break,
+                },
+                permit = self.event_tx.reserve(), if self.next.is_some() => {
+                    let Ok(permit) = permit else { break };
+                    if let Some(item) = self.next.take() {
+                        permit.send(item);
+                    }
+                }
+                Some(change) = self.control.next() => {
+                    let _ = self.control_tx.send(change);
+                }
+                Some(_) = self.changes.next() => {
+                    self.state_tx.send_replace(fold(&self.states));
+                }
+                command = self.command_rx.recv() => match command {
+                    Some(Command::Pause(paused)) => {
+                        for source in self.sources.iter_mut() {
+                            if paused {
+                                source.pause();
+                            } else {
+                                source.resume();
+                            }
+                        }
+                    }
+                    Some(Command::ChangeTopics { done, .. }) => {
+                        let _ = done.send(Err(NotifError::Forbidden(
+                            "topics of a merged stream are fixed; change them on a single subscription".to_string(),
+                        )));
+                    }
+                    Some(Command::Close { grace, done }) => {
+                        let closing = self.sources.into_iter().map(|source| source.shutdown(grace));
+                        futures_util::future::join_all(closing).await;
+                        if let Some(done) = done {
+                            let _ = done.send(());
+                        }
+                        self.state_tx.send_replace(ConnectionState::Disconnected);
+                        return;
+                    }
+                    None => break,
+                },
+            }
+        }
+        // Dropping the sources closes them
+        self.state_tx.send_replace(ConnectionState::Disconnected);
+    }
+}
+
+/// State of a merged stream: `Reconnecting` while any source reconnects,
+/// `Disconnected` once all have ended.
+fn fold(states: &[watch::Receiver<ConnectionState>]) -> ConnectionState {
+    let states: Vec<ConnectionState> = states.iter().map(|state| *state.borrow()).collect();
+    if states.contains(&ConnectionState::Reconnecting) {
+        ConnectionState::Reconnecting
+    } else if states
+        .iter()
+        .all(|state| *state == ConnectionState::Disconnected)
+    {
+        ConnectionState::Disconnected
+    } else {
+        ConnectionState::Connected
+    }
+}
+
+/// Merge streams, yielding from those with a higher priority first.
+///
+/// Whenever several streams have an item ready, the one with the highest
+/// priority is served, so a worker handles `payments.>` before a backlog
+/// of `analytics.>`; streams of equal priority take turns. A stream with
+/// a lower priority is only read while every stream above it has nothing
+/// ready. The merged stream ends once all of them have.
+///
+/// # Example
+///
+/// ```no_run
+/// # use notifsh::{merge_prioritized, Notif};
+/// # use futures::StreamExt;
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let payments = client.subscribe(&["payments.>"]).await?;
+/// let analytics = client.subscribe(&["analytics.>"]).await?;
+///
+/// let mut stream = merge_prioritized(vec![(payments, 10), (analytics, 1)]);
+/// while let Some(event) = stream.next().await {
+///     let event = event?;
+///     println!("{}: {:?}", event.topic, event.data);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn merge_prioritized<S>(streams: Vec<(S, u32)>) -> Prioritized<S>
+where
+    S: Stream + Unpin,
+{
+    let mut sources = streams;
+    sources.sort_by_key(|(_, priority)| std::cmp::Reverse(*priority));
+    Prioritized { sources }
+}
+
+/// Streams merged by priority, from [`merge_prioritized`].
+pub struct Prioritized<S> {
+    /// Sources still open, highest priority first.
+    sources: Vec<(S, u32)>,
+}
+
+impl<S> Stream for Prioritized<S>
+where
+    S: Stream + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let sources = &mut self.sources;
+        let mut at = 0;
+        while at < sources.len() {
+            match sources[at].0.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => {
+                    // Let the next stream of the same priority go first next time
+                    let priority = sources[at].1;
+                    let end = sources[at..]
+                        .iter()
+                        .position(|(_, p)| *p != priority)
+                        .map_or(sources.len(), |len| at + len);
+                    sources[at..end].rotate_left(1);
+                    return Poll::Ready(Some(item));
+                }
+                Poll::Ready(None) => {
+                    sources.remove(at);
+                }
+                Poll::Pending => at += 1,
+            }
+        }
+        if sources.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<S> fmt::Debug for Prioritized<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let priorities: Vec<u32> = self.sources.iter().map(|(_, priority)| *priority).collect();
+        f.debug_struct("Prioritized")
+            .field("priorities", &priorities)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,51 @@
+//! MessagePack encoding, negotiated with the server (`msgpack` feature).
+//!
+//! Emits are sent as MessagePack with `Content-Type: application/msgpack`;
+//! a server that refuses it gets JSON from then on. Servers that negotiate
+//! answer `415 Unsupported Media Type`, while servers that only read JSON,
+//! as notif.sh does today, answer `400 invalid JSON payload`. Subscriptions
+//! ask for MessagePack and decode the binary frames a server agreeing to it
+//! sends, while still accepting JSON text frames.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{NotifError, Result};
+
+/// Media type of MessagePack request and response bodies.
+pub(crate) const CONTENT_TYPE: &str = "application/msgpack";
+
+/// Subscription encoding requested from the server.
+pub(crate) const ENCODING: &str = "msgpack";
+
+/// Error of the emit endpoint when it cannot decode the body as JSON.
+const JSON_ONLY: &str = "invalid JSON payload";
+
+/// Whether the server refused a MessagePack request body.
+pub(crate) fn rejected(error: &NotifError) -> bool {
+    match error {
+        NotifError::Api { status: 415, .. } => true,
+        NotifError::Api {
+            status: 400,
+            message,
+        } => message == JSON_ONLY,
+        _ => false,
+    }
+}
+
+/// Encode `value` as a MessagePack map, keeping field names.
+pub(crate) fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    rmp_serde::to_vec_named(value).map_err(|e| invalid(e.to_string()))
+}
+
+/// Decode a MessagePack body.
+pub(crate) fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    rmp_serde::from_slice(bytes).map_err(|e| invalid(e.to_string()))
+}
+
+fn invalid(message: String) -> NotifError {
+    NotifError::Serialization(serde::de::Error::custom(format!(
+        "MessagePack: {}",
+        message
+    )))
+}
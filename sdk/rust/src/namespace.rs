@@ -0,0 +1,154 @@
+//! A view of a client scoped to a topic prefix, for multi-tenant apps.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::subscribe::EventStream;
+use crate::topic::Topic;
+use crate::types::{EmitOptions, EmitResponse, SubscribeOptions};
+
+impl Notif {
+    /// A view of the client whose topics live under `prefix`.
+    ///
+    /// `client.namespaced("acme")` emits `orders.created` as
+    /// `acme.orders.created`, subscribes to `orders.*` as `acme.orders.*`,
+    /// and hands events back with the prefix stripped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use serde_json::json;
+    /// # use futures::StreamExt;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let acme = client.namespaced("acme");
+    ///
+    /// acme.emit("orders.created", json!({"order_id": "123"})).await?;
+    ///
+    /// let mut stream = acme.subscribe(&["orders.*"]).await?;
+    /// while let Some(event) = stream.next().await {
+    ///     let event = event?;
+    ///     assert!(event.topic.starts_with("orders."));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn namespaced(&self, prefix: impl Into<String>) -> Namespaced {
+        Namespaced {
+            client: self.clone(),
+            prefix: prefix.into(),
+        }
+    }
+}
+
+/// A client whose topics are prefixed with a namespace, created by
+/// [`Notif::namespaced`].
+///
+/// Cheap to clone; clones share the underlying client.
+#[derive(Clone)]
+pub struct Namespaced {
+    client: Notif,
+    prefix: String,
+}
+
+impl Namespaced {
+    /// The namespace prefix, without the trailing dot.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The underlying client, which does not prefix topics.
+    pub fn client(&self) -> &Notif {
+        &self.client
+    }
+
+    /// A nested namespace, e.g. `acme` then `eu` for `acme.eu`.
+    pub fn namespaced(&self, prefix: &str) -> Namespaced {
+        self.client
+            .namespaced(format!("{}.{}", self.prefix, prefix))
+    }
+
+    /// The full topic name or pattern of `topic` in this namespace.
+    pub fn topic(&self, topic: &str) -> String {
+        format!("{}.{}", self.prefix, topic)
+    }
+
+    /// Emit an event to `topic` in this namespace.
+    pub async fn emit<T: Serialize>(&self, topic: &str, data: T) -> Result<EmitResponse> {
+        self.emit_with_options(topic, data, EmitOptions::new())
+            .await
+    }
+
+    /// Emit an event to `topic` in this namespace with custom options.
+    ///
+    /// The response reports the topic without the prefix.
+    pub async fn emit_with_options<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        self.check()?;
+        let mut response = self
+            .client
+            .emit_with_options(&self.topic(topic), data, options)
+            .await?;
+        if let Some(topic) = strip(&self.prefix, &response.topic) {
+            response.topic = topic;
+        }
+        Ok(response)
+    }
+
+    /// Subscribe to topic patterns in this namespace.
+    pub async fn subscribe(&self, topics: &[&str]) -> Result<EventStream> {
+        self.subscribe_with_options(topics, SubscribeOptions::new())
+            .await
+    }
+
+    /// Subscribe to topic patterns in this namespace with custom options.
+    ///
+    /// Events are yielded with the prefix stripped from their topic.
+    pub async fn subscribe_with_options(
+        &self,
+        topics: &[&str],
+        options: SubscribeOptions,
+    ) -> Result<EventStream> {
+        self.check()?;
+        let topics: Vec<String> = topics.iter().map(|topic| self.topic(topic)).collect();
+        let topics: Vec<&str> = topics.iter().map(String::as_str).collect();
+        let stream = self.client.subscribe_with_options(&topics, options).await?;
+        Ok(stream.strip_topic_prefix(format!("{}.", self.prefix)))
+    }
+
+    /// The prefix must be a plain topic name, without wildcards.
+    fn check(&self) -> Result<()> {
+        Topic::name(&self.prefix).map_err(|e| match e {
+            NotifError::InvalidTopic { reason, .. } => NotifError::InvalidTopic {
+                topic: self.prefix.clone(),
+                reason: format!("invalid namespace: {reason}"),
+            },
+            e => e,
+        })?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Namespaced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Namespaced")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `topic` without `prefix` and its dot, if it is in the namespace.
+fn strip(prefix: &str, topic: &str) -> Option<String> {
+    topic
+        .strip_prefix(prefix)?
+        .strip_prefix('.')
+        .map(str::to_string)
+}
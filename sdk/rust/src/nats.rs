@@ -0,0 +1,356 @@
+//! Consuming events straight from the NATS subjects behind a self-hosted
+//! server (`nats` feature).
+//!
+//! The server publishes every event to JetStream on the subject
+//! `events.{org_id}.{project_id}.{topic}`. A [`NatsBridge`] subscribes to
+//! those subjects with [`async_nats`] and yields the events as an
+//! [`EventStream`], skipping the WebSocket hop for services running next
+//! to the NATS cluster.
+//!
+//! These are core NATS subscriptions: events published while the bridge
+//! is disconnected are not redelivered, and events cannot be acked,
+//! nacked or shared with a consumer group. Use `Notif::subscribe` where
+//! those matter. Addresses with a `tls://` scheme connect over TLS.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use notifsh::nats::NatsBridge;
+//!
+//! # async fn example() -> notifsh::Result<()> {
+//! let bridge = NatsBridge::new("nats://nats.internal:4222", "org_123", "prj_456")
+//!     .token("s3cret");
+//!
+//! let mut stream = bridge.subscribe(&["orders.*"]).await?;
+//! while let Some(event) = stream.next().await {
+//!     let event = event?;
+//!     println!("{}: {}", event.topic, event.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_nats::{Client, ConnectErrorKind, ConnectOptions, Subscriber};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_stream::StreamMap;
+
+use crate::error::{NotifError, Result};
+use crate::stats::StreamStats;
+use crate::subscribe::{Command, EventStream, CONTROL_CHANNEL_SIZE};
+use crate::topic::Topic;
+use crate::types::{Backoff, ConnectionState, ControlEvent, Event};
+
+/// Subscribes to the server's NATS subjects directly.
+///
+/// Cheap to clone; every `subscribe` opens its own connection.
+#[derive(Clone)]
+pub struct NatsBridge {
+    settings: Arc<Settings>,
+}
+
+#[derive(Clone)]
+struct Settings {
+    address: String,
+    prefix: String,
+    auth: Option<Auth>,
+    backoff: Backoff,
+}
+
+#[derive(Clone)]
+enum Auth {
+    Token(String),
+    User { user: String, password: String },
+}
+
+impl NatsBridge {
+    /// Bridge to the NATS server at `address` (`host:port`, optionally
+    /// with a `nats://` or `tls://` scheme), for the events of one project.
+    pub fn new(
+        address: impl Into<String>,
+        org_id: impl Into<String>,
+        project_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            settings: Arc::new(Settings {
+                address: address.into(),
+                prefix: format!("events.{}.{}", org_id.into(), project_id.into()),
+                auth: None,
+                backoff: Backoff::default(),
+            }),
+        }
+    }
+
+    /// Authenticate with a token.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.settings).auth = Some(Auth::Token(token.into()));
+        self
+    }
+
+    /// Authenticate with a user name and password.
+    pub fn user_password(mut self, user: impl Into<String>, password: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.settings).auth = Some(Auth::User {
+            user: user.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Set the delay between reconnection attempts after the first,
+    /// immediate one (default: 500ms doubling to 30s, retrying forever).
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        Arc::make_mut(&mut self.settings).backoff = backoff;
+        self
+    }
+
+    /// The NATS subject events of `topic` are published on.
+    pub fn subject(&self, topic: &str) -> String {
+        format!("{}.{}", self.settings.prefix, topic)
+    }
+
+    /// Subscribe to topic patterns, with the same syntax as
+    /// `Notif::subscribe`.
+    ///
+    /// Fails if the first connection cannot be made. After that, a lost
+    /// connection is re-established per the backoff, and the stream ends
+    /// with a `NotifError::Connection` once it gives up.
+    pub async fn subscribe(&self, topics: &[&str]) -> Result<EventStream> {
+        let topics = topics
+            .iter()
+            .map(|topic| Topic::parse(topic).map(String::from))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (state_tx, state_rx) = watch::channel(ConnectionState::Connected);
+        let state_tx = Arc::new(state_tx);
+        let client = self.connect(state_tx.clone()).await?;
+
+        let mut subscriptions = StreamMap::new();
+        for topic in &topics {
+            let subscriber = client
+                .subscribe(self.subject(topic))
+                .await
+                .map_err(|e| NotifError::connection(format!("NATS subscribe: {e}")))?;
+            subscriptions.insert(topic.clone(), subscriber);
+        }
+
+        let (event_tx, event_rx) = mpsc::channel(1);
+        // Core NATS has no acks; events carry no ack channel
+        let (ack_tx, _) = mpsc::channel(1);
+        let (control_tx, control_rx) = broadcast::channel(CONTROL_CHANNEL_SIZE);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let stats = StreamStats::default();
+
+        let _ = control_tx.send(ControlEvent::Assigned {
+            consumer_id: None,
+            topics,
+        });
+
+        let worker = NatsWorker {
+            client,
+            prefix: self.settings.prefix.clone(),
+            subscriptions,
+            event_tx,
+            command_rx,
+            state_tx,
+            stats: stats.clone(),
+            paused: false,
+        };
+        tokio::spawn(worker.run());
+
+        Ok(EventStream::from_parts(
+            event_rx, ack_tx, control_tx, control_rx, command_tx, state_rx, stats,
+        ))
+    }
+
+    /// Connect with the bridge's credentials and backoff, reporting
+    /// connection changes on `state`.
+    async fn connect(&self, state: Arc<watch::Sender<ConnectionState>>) -> Result<Client> {
+        let settings = &self.settings;
+        let options = match &settings.auth {
+            Some(Auth::Token(token)) => ConnectOptions::with_token(token.clone()),
+            Some(Auth::User { user, password }) => {
+                ConnectOptions::with_user_and_password(user.clone(), password.clone())
+            }
+            None => ConnectOptions::new(),
+        };
+        let backoff = settings.backoff.clone();
+        let options = options
+            .name("notifsh")
+            .max_reconnects(backoff.max_retries.map(|max| max as usize))
+            // Attempts count from 1, and the first one is made at once
+            .reconnect_delay_callback(move |attempt| match attempt {
+                0 | 1 => Duration::ZERO,
+                attempt => backoff.delay((attempt - 2).try_into().unwrap_or(u32::MAX)),
+            })
+            .event_callback(move |event| {
+                let state = state.clone();
+                async move {
+                    match event {
+                        async_nats::Event::Connected => {
+                            state.send_replace(ConnectionState::Connected);
+                        }
+                        async_nats::Event::Disconnected => {
+                            state.send_replace(ConnectionState::Reconnecting);
+                        }
+                        _ => {}
+                    }
+                }
+            });
+
+        options
+            .connect(settings.address.as_str())
+            .await
+            .map_err(|e| match e.kind() {
+                ConnectErrorKind::Authentication | ConnectErrorKind::AuthorizationViolation => {
+                    NotifError::auth(format!("NATS {}: {e}", settings.address))
+                }
+                _ => NotifError::connection(format!("NATS {}: {e}", settings.address)),
+            })
+    }
+}
+
+impl fmt::Debug for NatsBridge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NatsBridge")
+            .field("address", &self.settings.address)
+            .field("prefix", &self.settings.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An event as the server publishes it to JetStream.
+#[derive(Deserialize)]
+struct WireEvent {
+    id: String,
+    topic: String,
+    data: serde_json::Value,
+    timestamp: DateTime<Utc>,
+    #[serde(default)]
+    attempt: u32,
+}
+
+impl From<WireEvent> for Event {
+    fn from(wire: WireEvent) -> Self {
+        let attempt = wire.attempt.max(1);
+        Event {
+            id: wire.id,
+            topic: wire.topic,
+            data: wire.data,
+            headers: HashMap::new(),
+            timestamp: wire.timestamp,
+            attempt,
+            max_attempts: attempt,
+            seq: None,
+            ack_tx: None,
+        }
+    }
+}
+
+/// Background task feeding an `EventStream` from NATS subscriptions.
+///
+/// The client re-subscribes by itself after a reconnect; every
+/// subscription ends once it gives up.
+struct NatsWorker {
+    client: Client,
+    prefix: String,
+    /// Live subscriptions by topic pattern.
+    subscriptions: StreamMap<String, Subscriber>,
+    event_tx: mpsc::Sender<Result<Event>>,
+    command_rx: mpsc::UnboundedReceiver<Command>,
+    state_tx: Arc<watch::Sender<ConnectionState>>,
+    stats: StreamStats,
+    /// Set by `EventStream::pause`.
+    paused: bool,
+}
+
+impl NatsWorker {
+    async fn run(mut self) {
+        loop {
+            tokio::select! {
+                message = self.subscriptions.next(), if !self.paused => match message {
+                    Some((_, message)) => {
+                        let item = serde_json::from_slice::<WireEvent>(&message.payload)
+                            .map(Event::from)
+                            .map_err(NotifError::from);
+                        if let Ok(event) = &item {
+                            self.stats.record_received(event);
+                        }
+                        if self.event_tx.send(item).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => {
+                        let _ = self
+                            .event_tx
+                            .send(Err(NotifError::connection(
+                                "NATS connection lost; gave up reconnecting",
+                            )))
+                            .await;
+                        break;
+                    }
+                },
+                command = self.command_rx.recv() => match command {
+                    Some(Command::Pause(paused)) => self.paused = paused,
+                    Some(Command::ChangeTopics { add, remove, done }) => {
+                        let result = self.change_topics(add, remove).await;
+                        let _ = done.send(result);
+                    }
+                    Some(Command::Close { done, .. }) => {
+                        // Nothing to flush: events are never acked
+                        for (_, subscriber) in self.subscriptions.iter_mut() {
+                            let _ = subscriber.unsubscribe().await;
+                        }
+                        let _ = self.client.flush().await;
+                        if let Some(done) = done {
+                            let _ = done.send(());
+                        }
+                        break;
+                    }
+                    None => break,
+                },
+            }
+        }
+        self.state_tx.send_replace(ConnectionState::Disconnected);
+    }
+
+    /// Subscribe to `add` and unsubscribe from `remove`.
+    async fn change_topics(&mut self, add: Vec<String>, remove: Vec<String>) -> Result<()> {
+        let remaining = self
+            .subscriptions
+            .keys()
+            .filter(|topic| !remove.contains(topic))
+            .count();
+        if remaining == 0 && add.is_empty() {
+            return Err(NotifError::InvalidTopic {
+                topic: remove.join(","),
+                reason: "cannot remove every topic; close the stream instead".to_string(),
+            });
+        }
+
+        for topic in remove {
+            if let Some(mut subscriber) = self.subscriptions.remove(&topic) {
+                let _ = subscriber.unsubscribe().await;
+            }
+        }
+        for topic in add {
+            if self.subscriptions.contains_key(&topic) {
+                continue;
+            }
+            let subscriber = self
+                .client
+                .subscribe(format!("{}.{}", self.prefix, topic))
+                .await
+                .map_err(|e| NotifError::connection(format!("NATS subscribe: {e}")))?;
+            self.subscriptions.insert(topic, subscriber);
+        }
+        Ok(())
+    }
+}
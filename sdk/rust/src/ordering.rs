@@ -0,0 +1,66 @@
+//! Per-key ordered delivery, for `SubscribeOptions::ordering_key_path`.
+//!
+//! At most one event per key is handed to the consumer at a time. Later
+//! events with the same key are held until the one before them is settled,
+//! then released oldest first.
+
+use std::collections::HashMap;
+
+use crate::error::Result;
+use crate::filter::FieldPath;
+use crate::types::Event;
+
+/// Tracks which keys have an unsettled event out and what waits behind it.
+#[derive(Debug)]
+pub(crate) struct KeyOrder {
+    path: FieldPath,
+    /// Key of every event handed out and not yet settled, by event ID.
+    active: HashMap<String, String>,
+    /// Events waiting for their key, oldest first.
+    held: HashMap<String, Vec<Event>>,
+}
+
+impl KeyOrder {
+    pub(crate) fn new(path: &str) -> Result<Self> {
+        Ok(Self {
+            path: FieldPath::parse(path)?,
+            active: HashMap::new(),
+            held: HashMap::new(),
+        })
+    }
+
+    /// Return `event` if it can be handed out now, or hold it behind the
+    /// unsettled event with the same key.
+    pub(crate) fn admit(&mut self, event: Event) -> Option<Event> {
+        let Some(key) = self.path.key(&event) else {
+            return Some(event);
+        };
+        if self.active.values().any(|active| *active == key) {
+            let queue = self.held.entry(key).or_default();
+            // Keep arrival order among events with the same timestamp
+            let at = queue.partition_point(|held| held.timestamp <= event.timestamp);
+            queue.insert(at, event);
+            return None;
+        }
+        self.active.insert(event.id.clone(), key);
+        Some(event)
+    }
+
+    /// Mark the event `id` settled and return the next event for its key.
+    pub(crate) fn release(&mut self, id: &str) -> Option<Event> {
+        let key = self.active.remove(id)?;
+        let queue = self.held.get_mut(&key)?;
+        let next = queue.remove(0);
+        if queue.is_empty() {
+            self.held.remove(&key);
+        }
+        self.active.insert(next.id.clone(), key);
+        Some(next)
+    }
+
+    /// Forget every key, returning the held events.
+    pub(crate) fn clear(&mut self) -> Vec<Event> {
+        self.active.clear();
+        self.held.drain().flat_map(|(_, queue)| queue).collect()
+    }
+}
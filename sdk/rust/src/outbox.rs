@@ -0,0 +1,221 @@
+//! Transactional outbox for sqlx users (`sqlx` feature, PostgreSQL).
+//!
+//! [`stage`] inserts an event into the `notif_outbox` table inside the
+//! caller's transaction, so it is recorded if and only if the transaction
+//! commits. A [`Relay`] then emits staged rows in order and marks them
+//! published. Rows are emitted at least once: a relay that crashes after
+//! emitting but before marking a row emits it again, so each event carries
+//! its row ID in the `notif-outbox-id` header for consumers to deduplicate.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::outbox::{self, Relay};
+//! use notifsh::Notif;
+//! use serde_json::json;
+//! use sqlx::PgPool;
+//!
+//! # async fn example(client: Notif, pool: PgPool) -> notifsh::Result<()> {
+//! outbox::migrate(&pool).await?;
+//! tokio::spawn(Relay::new(client, pool.clone()).run());
+//!
+//! let mut tx = pool.begin().await?;
+//! sqlx::query("UPDATE orders SET status = 'paid' WHERE id = $1")
+//!     .bind(42)
+//!     .execute(&mut *tx)
+//!     .await?;
+//! outbox::stage(&mut tx, "orders.paid", json!({"order_id": 42})).await?;
+//! tx.commit().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::topic::Topic;
+use crate::types::EmitOptions;
+
+/// Header carrying the outbox row ID of a relayed event.
+pub const OUTBOX_ID_HEADER: &str = "notif-outbox-id";
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS notif_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    topic TEXT NOT NULL,
+    data JSONB NOT NULL,
+    headers JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    event_id TEXT,
+    published_at TIMESTAMPTZ,
+    failed_at TIMESTAMPTZ
+)";
+
+const CREATE_INDEX: &str = "CREATE INDEX IF NOT EXISTS notif_outbox_unsent
+    ON notif_outbox (id) WHERE published_at IS NULL AND failed_at IS NULL";
+
+const INSERT: &str =
+    "INSERT INTO notif_outbox (topic, data, headers) VALUES ($1, $2, $3) RETURNING id";
+
+const CLAIM: &str = "SELECT id, topic, data, headers FROM notif_outbox
+    WHERE published_at IS NULL AND failed_at IS NULL
+    ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED";
+
+const MARK_PUBLISHED: &str = "UPDATE notif_outbox
+    SET published_at = now(), event_id = $2, attempts = attempts + 1 WHERE id = $1";
+
+const MARK_RETRY: &str =
+    "UPDATE notif_outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1";
+
+const MARK_FAILED: &str = "UPDATE notif_outbox
+    SET failed_at = now(), attempts = attempts + 1, last_error = $2 WHERE id = $1";
+
+/// Create the `notif_outbox` table if it does not exist.
+///
+/// Run once at startup, or copy the statements into your own migrations.
+pub async fn migrate(pool: &PgPool) -> Result<()> {
+    sqlx::query(CREATE_TABLE).execute(pool).await?;
+    sqlx::query(CREATE_INDEX).execute(pool).await?;
+    Ok(())
+}
+
+/// Stage an event in `tx`, returning its outbox row ID.
+///
+/// The event is relayed once the transaction commits and discarded if
+/// it rolls back.
+pub async fn stage<T: Serialize>(
+    tx: &mut Transaction<'_, Postgres>,
+    topic: &str,
+    data: T,
+) -> Result<i64> {
+    stage_with_options(tx, topic, data, EmitOptions::new()).await
+}
+
+/// Stage an event with custom options such as headers.
+pub async fn stage_with_options<T: Serialize>(
+    tx: &mut Transaction<'_, Postgres>,
+    topic: &str,
+    data: T,
+    options: EmitOptions,
+) -> Result<i64> {
+    let topic = Topic::name(topic)?;
+    let data = serde_json::to_value(data)?;
+    let id = sqlx::query_scalar(INSERT)
+        .bind(topic.as_str())
+        .bind(Json(data))
+        .bind(Json(options.headers))
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(id)
+}
+
+/// Background task emitting staged outbox rows.
+///
+/// Each pass claims up to `batch_size` unsent rows with
+/// `FOR UPDATE SKIP LOCKED`, so several relays can run against the same
+/// table. Rows are emitted in ID order; a retryable failure ends the pass
+/// so later rows wait, while a row the server rejects for good is marked
+/// failed (`failed_at`, `last_error`) and skipped from then on.
+pub struct Relay {
+    client: Notif,
+    pool: PgPool,
+    batch_size: usize,
+    poll_interval: Duration,
+}
+
+impl Relay {
+    /// Relay rows from `pool` through `client`.
+    pub fn new(client: Notif, pool: PgPool) -> Self {
+        Self {
+            client,
+            pool,
+            batch_size: 100,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the most rows claimed per pass (default: 100).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set how long to wait when there is nothing to relay or a pass
+    /// failed (default: 1s).
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Relay rows until the task is dropped.
+    ///
+    /// Database and emit failures are retried after the poll interval.
+    pub async fn run(self) {
+        loop {
+            match self.relay_once().await {
+                // A full batch means more rows are likely waiting
+                Ok(relayed) if relayed == self.batch_size => continue,
+                // Failed rows stay unsent and are retried on the next pass
+                Ok(_) | Err(_) => {}
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Run one pass, returning how many rows were published or marked failed.
+    pub async fn relay_once(&self) -> Result<usize> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(CLAIM)
+            .bind(i64::try_from(self.batch_size).unwrap_or(i64::MAX))
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let mut relayed = 0;
+        for row in rows {
+            let id: i64 = row.try_get("id")?;
+            let topic: String = row.try_get("topic")?;
+            let Json(data): Json<serde_json::Value> = row.try_get("data")?;
+            let Json(headers): Json<HashMap<String, String>> = row.try_get("headers")?;
+
+            let options = EmitOptions::new()
+                .headers(headers)
+                .header(OUTBOX_ID_HEADER, id.to_string());
+            match self.client.emit_with_options(&topic, data, options).await {
+                Ok(response) => {
+                    sqlx::query(MARK_PUBLISHED)
+                        .bind(id)
+                        .bind(response.id)
+                        .execute(&mut *tx)
+                        .await?;
+                    relayed += 1;
+                }
+                Err(e) if e.is_retryable() => {
+                    sqlx::query(MARK_RETRY)
+                        .bind(id)
+                        .bind(e.to_string())
+                        .execute(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    return Err(e);
+                }
+                Err(e) => {
+                    sqlx::query(MARK_FAILED)
+                        .bind(id)
+                        .bind(e.to_string())
+                        .execute(&mut *tx)
+                        .await?;
+                    relayed += 1;
+                }
+            }
+        }
+        tx.commit().await?;
+        Ok(relayed)
+    }
+}
@@ -0,0 +1,181 @@
+//! Parking events that keep failing, for `Concurrency::park`.
+
+use std::fmt;
+use std::time::Duration;
+
+use chrono::Utc;
+use futures_util::StreamExt;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::types::{EmitOptions, Event, SubscribeOptions};
+
+/// Suffix of the topic a failing event is parked on.
+pub const PARKED_SUFFIX: &str = ".parked";
+
+/// Header of a parked event holding the topic it was published to.
+pub const PARKED_TOPIC_HEADER: &str = "notif-parked-topic";
+
+/// Header of a parked event holding its original ID.
+pub const PARKED_ID_HEADER: &str = "notif-parked-id";
+
+/// Header of a parked event holding the attempt that failed, as
+/// `<attempt>/<max_attempts>`.
+pub const PARKED_ATTEMPTS_HEADER: &str = "notif-parked-attempts";
+
+/// Header of a parked event holding when it was parked (RFC 3339).
+pub const PARKED_AT_HEADER: &str = "notif-parked-at";
+
+/// How long `Notif::unpark` waits for another parked event before it
+/// considers the backlog replayed.
+const UNPARK_IDLE: Duration = Duration::from_secs(2);
+
+/// Moves events out of the way before their delivery attempts run out.
+///
+/// When the handler fails on one of the last attempts of an event, the
+/// event is republished to `<topic>.parked` with its data and headers,
+/// plus [`PARKED_TOPIC_HEADER`], [`PARKED_ID_HEADER`],
+/// [`PARKED_ATTEMPTS_HEADER`] and [`PARKED_AT_HEADER`], and the original
+/// is acked instead of going to the dead letter queue. Once a fix ships,
+/// [`Notif::unpark`] publishes them again. If parking fails, the event is
+/// nacked as usual.
+///
+/// Events already on a `.parked` topic are never parked again. A handler
+/// subscribed with a `>` wildcard also receives parked events, so it
+/// should skip topics ending in [`PARKED_SUFFIX`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use notifsh::{Concurrency, Notif, ParkingPolicy, SubscribeOptions};
+/// # async fn import(event: notifsh::Event) -> Result<(), String> { Ok(()) }
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let stream = client
+///     .subscribe_with_options(&["imports.*"], SubscribeOptions::new().auto_ack(false))
+///     .await?;
+///
+/// let concurrency = Concurrency::new(4).park(ParkingPolicy::new(&client));
+/// stream.for_each_concurrent_with(concurrency, import).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ParkingPolicy {
+    client: Notif,
+    attempts_left: u32,
+}
+
+impl ParkingPolicy {
+    /// Park events through `client` when their last attempt fails.
+    pub fn new(client: &Notif) -> Self {
+        Self {
+            client: client.clone(),
+            attempts_left: 0,
+        }
+    }
+
+    /// Park events that fail with at most `attempts` delivery attempts
+    /// left (default: 0, on the last attempt).
+    pub fn attempts_left(mut self, attempts: u32) -> Self {
+        self.attempts_left = attempts;
+        self
+    }
+
+    /// Whether a failure of `event` parks it.
+    pub(crate) fn applies(&self, event: &Event) -> bool {
+        !event.topic.ends_with(PARKED_SUFFIX)
+            && event.max_attempts.saturating_sub(event.attempt) <= self.attempts_left
+    }
+
+    /// Republish `event` to its parked topic.
+    pub(crate) async fn park(&self, event: Event) -> Result<()> {
+        let options = EmitOptions::new()
+            .headers(&event.headers)
+            .header(PARKED_TOPIC_HEADER, event.topic.as_str())
+            .header(PARKED_ID_HEADER, event.id.as_str())
+            .header(
+                PARKED_ATTEMPTS_HEADER,
+                format!("{}/{}", event.attempt, event.max_attempts),
+            )
+            .header(PARKED_AT_HEADER, Utc::now().to_rfc3339());
+        let topic = format!("{}{PARKED_SUFFIX}", event.topic);
+        self.client
+            .emit_with_options(&topic, event.data, options)
+            .await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ParkingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParkingPolicy")
+            .field("attempts_left", &self.attempts_left)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Notif {
+    /// Publish the events parked from `topic` again, e.g. once a fix for
+    /// the handler that failed on them ships.
+    ///
+    /// `topic` may be a pattern such as `imports.*`, matching the parked
+    /// topics `imports.*.parked`. Each parked event is republished to its
+    /// original topic with its data and headers, without the parking
+    /// headers, and then acked, so running `unpark` again only replays
+    /// events parked since. Returns once no parked event arrived for two
+    /// seconds, with the number of events replayed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let replayed = client.unpark("imports.*").await?;
+    /// println!("replayed {replayed} parked imports");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn unpark(&self, topic: &str) -> Result<u64> {
+        let parked = format!("{topic}{PARKED_SUFFIX}");
+        // One durable consumer per pattern remembers what was replayed
+        let name: String = topic
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let options = SubscribeOptions::new()
+            .durable(format!("notif-unpark-{name}"))
+            .from("beginning")
+            .auto_ack(false);
+        let mut stream = self.subscribe_with_options(&[&parked], options).await?;
+
+        let mut replayed = 0;
+        while let Ok(Some(item)) = tokio::time::timeout(UNPARK_IDLE, stream.next()).await {
+            let event = item?;
+            let original = match event.headers.get(PARKED_TOPIC_HEADER) {
+                Some(original) => original.clone(),
+                None => event
+                    .topic
+                    .strip_suffix(PARKED_SUFFIX)
+                    .unwrap_or(&event.topic)
+                    .to_string(),
+            };
+            let headers = event
+                .headers
+                .iter()
+                .filter(|(name, _)| !name.starts_with("notif-parked-"));
+            let options = EmitOptions::new().headers(headers);
+            if let Err(e) = self
+                .emit_with_options(&original, &event.data, options)
+                .await
+            {
+                let _ = event.nack(None).await;
+                return Err(e);
+            }
+            event.ack().await?;
+            replayed += 1;
+        }
+        stream.close().await?;
+        Ok(replayed)
+    }
+}
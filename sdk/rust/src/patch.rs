@@ -0,0 +1,414 @@
+//! JSON Patch (RFC 6902) deltas against a JSON document.
+//!
+//! A patch is emitted as the event data and marked with the
+//! `notif-encoding: json-patch` header, so consumers such as `StateCache`
+//! can tell it from a full value and apply it to what they hold.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::binary::ENCODING_HEADER;
+use crate::error::{NotifError, Result};
+use crate::types::{EmitOptions, Event};
+
+pub(crate) const JSON_PATCH: &str = "json-patch";
+
+/// Mark `options` as carrying a JSON Patch.
+pub(crate) fn mark(options: EmitOptions) -> EmitOptions {
+    options.header(ENCODING_HEADER, JSON_PATCH)
+}
+
+/// One operation of a [`JsonPatch`].
+///
+/// Paths are JSON Pointers (RFC 6901) such as `/messages/0/text`; `-` as
+/// the last segment appends to an array.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum PatchOperation {
+    /// Insert `value` at `path`, replacing an object member.
+    Add { path: String, value: Value },
+    /// Remove the value at `path`.
+    Remove { path: String },
+    /// Replace the existing value at `path`.
+    Replace { path: String, value: Value },
+    /// Remove the value at `from` and add it at `path`.
+    Move { from: String, path: String },
+    /// Add a copy of the value at `from` at `path`.
+    Copy { from: String, path: String },
+    /// Fail the patch unless the value at `path` equals `value`.
+    Test { path: String, value: Value },
+}
+
+/// A JSON Patch (RFC 6902): a list of operations applied in order.
+///
+/// Emit one with `Notif::emit_patch` or `Notif::publish_patch` instead of
+/// the whole document when a large state changes a little at a time, and
+/// fold it in on the consuming side with `Event::apply_to`.
+///
+/// # Example
+///
+/// ```
+/// use notifsh::JsonPatch;
+/// use serde_json::json;
+///
+/// let before = json!({"status": "running", "messages": ["hi"]});
+/// let after = json!({"status": "running", "messages": ["hi", "hello"]});
+///
+/// let patch = JsonPatch::diff(&before, &after);
+/// assert_eq!(patch, JsonPatch::new().add("/messages/-", json!("hello")));
+///
+/// let mut doc = before;
+/// patch.apply(&mut doc)?;
+/// assert_eq!(doc, after);
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonPatch {
+    operations: Vec<PatchOperation>,
+}
+
+impl JsonPatch {
+    /// Create an empty patch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The operations turning `old` into `new`.
+    ///
+    /// Objects are compared member by member. Elements appended to or
+    /// removed from the end of an array become `add` and `remove`
+    /// operations; other array changes are compared element by element if
+    /// the length is unchanged, and replace the array otherwise.
+    pub fn diff(old: &Value, new: &Value) -> Self {
+        let mut patch = Self::new();
+        diff(&mut patch.operations, String::new(), old, new);
+        patch
+    }
+
+    /// Add `value` at `path`.
+    pub fn add(mut self, path: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.operations.push(PatchOperation::Add {
+            path: path.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Remove the value at `path`.
+    pub fn remove(mut self, path: impl Into<String>) -> Self {
+        self.operations
+            .push(PatchOperation::Remove { path: path.into() });
+        self
+    }
+
+    /// Replace the value at `path` with `value`.
+    pub fn replace(mut self, path: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.operations.push(PatchOperation::Replace {
+            path: path.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Move the value at `from` to `path`.
+    pub fn move_to(mut self, from: impl Into<String>, path: impl Into<String>) -> Self {
+        self.operations.push(PatchOperation::Move {
+            from: from.into(),
+            path: path.into(),
+        });
+        self
+    }
+
+    /// Copy the value at `from` to `path`.
+    pub fn copy_to(mut self, from: impl Into<String>, path: impl Into<String>) -> Self {
+        self.operations.push(PatchOperation::Copy {
+            from: from.into(),
+            path: path.into(),
+        });
+        self
+    }
+
+    /// Require the value at `path` to equal `value`.
+    pub fn test(mut self, path: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.operations.push(PatchOperation::Test {
+            path: path.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// The operations, in order.
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    /// Whether the patch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Apply the patch to `doc`.
+    ///
+    /// The patch is atomic: if any operation fails, `doc` is left
+    /// unchanged and `NotifError::PatchFailed` names the failing path.
+    pub fn apply(&self, doc: &mut Value) -> Result<()> {
+        let mut patched = doc.clone();
+        for operation in &self.operations {
+            apply(&mut patched, operation)?;
+        }
+        *doc = patched;
+        Ok(())
+    }
+}
+
+impl From<Vec<PatchOperation>> for JsonPatch {
+    fn from(operations: Vec<PatchOperation>) -> Self {
+        Self { operations }
+    }
+}
+
+impl Event {
+    /// The patch carried by an event emitted with `Notif::emit_patch` or
+    /// `Notif::publish_patch`.
+    ///
+    /// Returns `None` for other events and for data that is not a valid
+    /// patch.
+    pub fn json_patch(&self) -> Option<JsonPatch> {
+        if !self.is_patch() {
+            return None;
+        }
+        serde_json::from_value(self.data.clone()).ok()
+    }
+
+    /// Fold the event into `state`: apply it if it carries a JSON Patch,
+    /// otherwise replace `state` with its data.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use notifsh::{Notif, Projector};
+    ///
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let transcript = Projector::new(
+    ///     &client,
+    ///     &["sessions.abc.transcript"],
+    ///     serde_json::Value::Null,
+    ///     |transcript, event| {
+    ///         let _ = event.apply_to(transcript);
+    ///     },
+    /// )
+    /// .start()
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_to(&self, state: &mut Value) -> Result<()> {
+        if !self.is_patch() {
+            *state = self.data.clone();
+            return Ok(());
+        }
+        let patch: JsonPatch = serde_json::from_value(self.data.clone())?;
+        patch.apply(state)
+    }
+
+    pub(crate) fn is_patch(&self) -> bool {
+        self.headers.get(ENCODING_HEADER).map(String::as_str) == Some(JSON_PATCH)
+    }
+}
+
+fn apply(doc: &mut Value, operation: &PatchOperation) -> Result<()> {
+    match operation {
+        PatchOperation::Add { path, value } => add(doc, path, value.clone()),
+        PatchOperation::Remove { path } => remove(doc, path).map(drop),
+        PatchOperation::Replace { path, value } => {
+            *lookup_mut(doc, path)? = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if from == path {
+                return Ok(());
+            }
+            if path.starts_with(&format!("{from}/")) {
+                return Err(failed(path, "cannot move a value into itself"));
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = lookup_mut(doc, from)?.clone();
+            add(doc, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            if *lookup_mut(doc, path)? == *value {
+                Ok(())
+            } else {
+                Err(failed(path, "test failed: value differs"))
+            }
+        }
+    }
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<()> {
+    let (parent, last) = parent_mut(doc, path)?;
+    let Some(last) = last else {
+        *parent = value;
+        return Ok(());
+    };
+    match parent {
+        Value::Object(members) => {
+            members.insert(last, value);
+        }
+        Value::Array(items) => {
+            let index = if last == "-" {
+                items.len()
+            } else {
+                index(path, &last)?
+            };
+            if index > items.len() {
+                return Err(failed(path, "index out of bounds"));
+            }
+            items.insert(index, value);
+        }
+        _ => return Err(failed(path, "parent is not an object or array")),
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value> {
+    let (parent, last) = parent_mut(doc, path)?;
+    let Some(last) = last else {
+        return Err(failed(path, "cannot remove the whole document"));
+    };
+    match parent {
+        Value::Object(members) => members
+            .remove(&last)
+            .ok_or_else(|| failed(path, "no such member")),
+        Value::Array(items) => {
+            let index = index(path, &last)?;
+            if index >= items.len() {
+                return Err(failed(path, "index out of bounds"));
+            }
+            Ok(items.remove(index))
+        }
+        _ => Err(failed(path, "parent is not an object or array")),
+    }
+}
+
+/// The container holding `path` and the last token of `path`, or `doc`
+/// itself and `None` for the root.
+fn parent_mut<'a>(doc: &'a mut Value, path: &str) -> Result<(&'a mut Value, Option<String>)> {
+    let mut tokens = tokens(path)?;
+    let Some(last) = tokens.pop() else {
+        return Ok((doc, None));
+    };
+    let parent = descend(doc, path, &tokens)?;
+    Ok((parent, Some(last)))
+}
+
+fn lookup_mut<'a>(doc: &'a mut Value, path: &str) -> Result<&'a mut Value> {
+    let tokens = tokens(path)?;
+    descend(doc, path, &tokens)
+}
+
+fn descend<'a>(mut value: &'a mut Value, path: &str, tokens: &[String]) -> Result<&'a mut Value> {
+    for token in tokens {
+        value = match value {
+            Value::Object(members) => members.get_mut(token),
+            Value::Array(items) => items.get_mut(index(path, token)?),
+            _ => None,
+        }
+        .ok_or_else(|| failed(path, "no such value"))?;
+    }
+    Ok(value)
+}
+
+/// Split a JSON Pointer into unescaped reference tokens.
+fn tokens(path: &str) -> Result<Vec<String>> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = path.strip_prefix('/') else {
+        return Err(failed(path, "a JSON pointer must start with '/'"));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn index(path: &str, token: &str) -> Result<usize> {
+    let canonical = token == "0" || !token.starts_with('0');
+    match token.parse::<usize>() {
+        Ok(index) if canonical && token.bytes().all(|b| b.is_ascii_digit()) => Ok(index),
+        _ => Err(failed(path, "not an array index")),
+    }
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn diff(operations: &mut Vec<PatchOperation>, path: String, old: &Value, new: &Value) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_members(operations, &path, old, new),
+        (Value::Array(old), Value::Array(new)) if new.starts_with(old) => {
+            for value in &new[old.len()..] {
+                operations.push(PatchOperation::Add {
+                    path: format!("{path}/-"),
+                    value: value.clone(),
+                });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.starts_with(new) => {
+            for index in (new.len()..old.len()).rev() {
+                operations.push(PatchOperation::Remove {
+                    path: format!("{path}/{index}"),
+                });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                diff(operations, format!("{path}/{index}"), old, new);
+            }
+        }
+        _ => operations.push(PatchOperation::Replace {
+            path,
+            value: new.clone(),
+        }),
+    }
+}
+
+fn diff_members(
+    operations: &mut Vec<PatchOperation>,
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+) {
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        operations.push(PatchOperation::Remove {
+            path: format!("{path}/{}", escape(key)),
+        });
+    }
+    for (key, value) in new {
+        let member = format!("{path}/{}", escape(key));
+        match old.get(key) {
+            Some(previous) => diff(operations, member, previous, value),
+            None => operations.push(PatchOperation::Add {
+                path: member,
+                value: value.clone(),
+            }),
+        }
+    }
+}
+
+fn failed(path: &str, reason: &str) -> NotifError {
+    NotifError::PatchFailed {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,474 @@
+//! Folding a topic's history into local state, with snapshots.
+//!
+//! A [`Projector`] replays topics from the beginning into a state value
+//! and keeps it up to date with live events. With a [`SnapshotStore`] it
+//! periodically saves the state together with the cursor of the last
+//! applied event, and on the next start resumes from the snapshot instead
+//! of replaying everything.
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::sequence::{self, SequenceWatch};
+use crate::subscribe::EventStream;
+use crate::types::{Event, EventCursor, SubscribeOptions};
+
+type ApplyFn<S> = dyn FnMut(&mut S, Event) + Send;
+
+/// Saved state of a projection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The projected state, as JSON.
+    pub state: serde_json::Value,
+    /// Position of the last event folded into `state`.
+    pub cursor: EventCursor,
+    /// Events folded into `state` since the projection started from scratch.
+    pub events: u64,
+    /// When the snapshot was taken.
+    pub taken_at: DateTime<Utc>,
+}
+
+/// Where a [`Projector`] keeps its latest snapshot.
+///
+/// Only the latest snapshot is ever loaded, so `save` may overwrite the
+/// previous one.
+pub trait SnapshotStore: Send + Sync + 'static {
+    /// Return the latest snapshot, if one was saved.
+    fn load(&self) -> BoxFuture<'_, Result<Option<Snapshot>>>;
+
+    /// Save `snapshot` as the latest.
+    fn save(&self, snapshot: Snapshot) -> BoxFuture<'_, Result<()>>;
+}
+
+/// Keeps the latest snapshot as a JSON file.
+///
+/// The file is replaced atomically, so a crash while saving leaves the
+/// previous snapshot intact.
+#[derive(Debug, Clone)]
+pub struct FileSnapshotStore {
+    path: PathBuf,
+}
+
+impl FileSnapshotStore {
+    /// Store snapshots at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SnapshotStore for FileSnapshotStore {
+    fn load(&self) -> BoxFuture<'_, Result<Option<Snapshot>>> {
+        Box::pin(async move {
+            match fs::read(&self.path) {
+                Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    fn save(&self, snapshot: Snapshot) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let tmp = self.path.with_extension("tmp");
+            fs::write(&tmp, serde_json::to_vec(&snapshot)?)?;
+            fs::rename(&tmp, &self.path)?;
+            Ok(())
+        })
+    }
+}
+
+/// Keeps the latest snapshot in memory, e.g. for tests.
+///
+/// Clones share the same snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySnapshotStore {
+    latest: Arc<Mutex<Option<Snapshot>>>,
+}
+
+impl MemorySnapshotStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The latest saved snapshot.
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+impl SnapshotStore for MemorySnapshotStore {
+    fn load(&self) -> BoxFuture<'_, Result<Option<Snapshot>>> {
+        Box::pin(async move { Ok(self.latest()) })
+    }
+
+    fn save(&self, snapshot: Snapshot) -> BoxFuture<'_, Result<()>> {
+        *self.latest.lock().unwrap() = Some(snapshot);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Builds state by folding every event of some topics into it.
+///
+/// `apply` is called once per event, in delivery order, starting from
+/// the oldest retained event or from the last snapshot. Snapshots are
+/// taken every 1000 applied events by default, and when the projection
+/// is stopped.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// use notifsh::{FileSnapshotStore, Notif, Projector};
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// let projection = Projector::new(
+///     &client,
+///     &["agents.*"],
+///     HashMap::<String, String>::new(),
+///     |agents, event| {
+///         if let Some(name) = event.data["name"].as_str() {
+///             agents.insert(name.to_string(), event.topic.clone());
+///         }
+///     },
+/// )
+/// .store(FileSnapshotStore::new("agents.snapshot.json"))
+/// .start()
+/// .await?;
+///
+/// let online = projection.read(|agents| agents.len());
+/// projection.stop().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Projector<S> {
+    client: Notif,
+    topics: Vec<String>,
+    state: S,
+    apply: Box<ApplyFn<S>>,
+    store: Option<Arc<dyn SnapshotStore>>,
+    every: u64,
+    interval: Option<Duration>,
+}
+
+impl<S> Projector<S>
+where
+    S: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// Fold events of `topics` into `initial` with `apply`.
+    pub fn new<F>(client: &Notif, topics: &[&str], initial: S, apply: F) -> Self
+    where
+        F: FnMut(&mut S, Event) + Send + 'static,
+    {
+        Self {
+            client: client.clone(),
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+            state: initial,
+            apply: Box::new(apply),
+            store: None,
+            every: 1000,
+            interval: None,
+        }
+    }
+
+    /// Save snapshots to `store` and resume from its latest one.
+    pub fn store(mut self, store: impl SnapshotStore) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Snapshot after every `events` applied events (default: 1000).
+    pub fn snapshot_every(mut self, events: u64) -> Self {
+        self.every = events.max(1);
+        self
+    }
+
+    /// Also snapshot every `interval` if events were applied since the
+    /// last snapshot.
+    pub fn snapshot_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Restore the latest snapshot, subscribe after it and start folding.
+    ///
+    /// Fails if the snapshot cannot be loaded or no longer deserializes
+    /// into `S`, or if subscribing fails.
+    pub async fn start(self) -> Result<Projection<S>> {
+        let mut view = View {
+            state: self.state,
+            cursor: None,
+            events: 0,
+        };
+        if let Some(store) = &self.store {
+            if let Some(snapshot) = store.load().await? {
+                view.state = serde_json::from_value(snapshot.state)?;
+                view.cursor = Some(snapshot.cursor);
+                view.events = snapshot.events;
+            }
+        }
+
+        let options = match &view.cursor {
+            Some(cursor) => SubscribeOptions::new().from_event_id(cursor.clone()),
+            None => SubscribeOptions::new().from("beginning"),
+        };
+        let topics: Vec<&str> = self.topics.iter().map(String::as_str).collect();
+        let stream = self.client.subscribe_with_options(&topics, options).await?;
+
+        let view = Arc::new(RwLock::new(view));
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (seq_tx, seq_rx) = watch::channel(None);
+        let worker = Worker {
+            view: view.clone(),
+            seq_tx,
+            apply: self.apply,
+            store: self.store,
+            every: self.every,
+            unsaved: 0,
+        };
+        let task = tokio::spawn(worker.run(stream, command_rx, self.interval));
+
+        Ok(Projection {
+            view,
+            applied: SequenceWatch::new(seq_rx),
+            command_tx,
+            task,
+        })
+    }
+}
+
+impl<S> fmt::Debug for Projector<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Projector")
+            .field("topics", &self.topics)
+            .field("snapshot_every", &self.every)
+            .field("snapshot_interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+struct View<S> {
+    state: S,
+    cursor: Option<EventCursor>,
+    events: u64,
+}
+
+enum Command {
+    Snapshot(oneshot::Sender<Result<()>>),
+    Stop,
+}
+
+/// A running projection started by [`Projector::start`].
+///
+/// Dropping it stops folding without a final snapshot.
+pub struct Projection<S> {
+    view: Arc<RwLock<View<S>>>,
+    /// Highest sequence number folded into the state.
+    applied: SequenceWatch,
+    command_tx: mpsc::UnboundedSender<Command>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl<S> Projection<S> {
+    /// Read the current state.
+    pub fn read<R>(&self, f: impl FnOnce(&S) -> R) -> R {
+        f(&self.view.read().unwrap().state)
+    }
+
+    /// Position of the last event folded into the state.
+    pub fn cursor(&self) -> Option<EventCursor> {
+        self.view.read().unwrap().cursor.clone()
+    }
+
+    /// Events folded into the state, including those in the restored snapshot.
+    pub fn events(&self) -> u64 {
+        self.view.read().unwrap().events
+    }
+
+    /// Wait until the event with stream sequence number `seq` has been
+    /// folded into the state, e.g. the one just emitted (see
+    /// `EmitResponse::seq`).
+    ///
+    /// Fails with `NotifError::Timeout` after `timeout`, or with
+    /// `NotifError::Connection` if the projection stops first.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::collections::HashMap;
+    /// # use std::time::Duration;
+    /// # async fn example(
+    /// #     client: notifsh::Notif,
+    /// #     projection: notifsh::Projection<HashMap<String, String>>,
+    /// # ) -> notifsh::Result<()> {
+    /// let response = client
+    ///     .emit("agents.online", serde_json::json!({"name": "alice"}))
+    ///     .await?;
+    /// if let Some(seq) = response.seq {
+    ///     projection.wait_for_sequence(seq, Duration::from_secs(5)).await?;
+    /// }
+    /// assert!(projection.read(|agents| agents.contains_key("alice")));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_sequence(&self, seq: u64, timeout: Duration) -> Result<()> {
+        self.applied.wait_for_sequence(seq, timeout).await
+    }
+
+    /// Save a snapshot now.
+    ///
+    /// Does nothing without a store or before the first event.
+    pub async fn snapshot(&self) -> Result<()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.command_tx
+            .send(Command::Snapshot(done_tx))
+            .map_err(|_| stopped())?;
+        done_rx.await.map_err(|_| stopped())?
+    }
+
+    /// Stop folding, save a final snapshot and close the subscription.
+    ///
+    /// Returns the error that ended the projection early, if any: the
+    /// subscription giving up or a snapshot failing to save.
+    pub async fn stop(mut self) -> Result<()> {
+        let _ = self.command_tx.send(Command::Stop);
+        match (&mut self.task).await {
+            Ok(result) => result,
+            Err(e) => Err(NotifError::Connection(format!(
+                "projection task failed: {e}"
+            ))),
+        }
+    }
+}
+
+impl<S> Drop for Projection<S> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<S> fmt::Debug for Projection<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let view = self.view.read().unwrap();
+        f.debug_struct("Projection")
+            .field("cursor", &view.cursor)
+            .field("events", &view.events)
+            .finish_non_exhaustive()
+    }
+}
+
+fn stopped() -> NotifError {
+    NotifError::Connection("projection stopped".to_string())
+}
+
+struct Worker<S> {
+    view: Arc<RwLock<View<S>>>,
+    seq_tx: watch::Sender<Option<u64>>,
+    apply: Box<ApplyFn<S>>,
+    store: Option<Arc<dyn SnapshotStore>>,
+    every: u64,
+    /// Events applied since the last snapshot.
+    unsaved: u64,
+}
+
+impl<S: Serialize> Worker<S> {
+    async fn run(
+        mut self,
+        mut stream: EventStream,
+        mut commands: mpsc::UnboundedReceiver<Command>,
+        interval: Option<Duration>,
+    ) -> Result<()> {
+        let mut ticker = interval.map(tokio::time::interval);
+        let result = loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(Command::Snapshot(done)) => {
+                        let _ = done.send(self.save().await);
+                    }
+                    Some(Command::Stop) | None => break self.save().await,
+                },
+                event = stream.next() => match event {
+                    Some(Ok(event)) => {
+                        self.fold(event);
+                        if self.unsaved >= self.every {
+                            if let Err(e) = self.save().await {
+                                break Err(e);
+                            }
+                        }
+                    }
+                    Some(Err(e)) => break Err(e),
+                    None => break self.save().await,
+                },
+                _ = tick(&mut ticker) => {
+                    if let Err(e) = self.save().await {
+                        break Err(e);
+                    }
+                }
+            }
+        };
+        stream.close().await?;
+        result
+    }
+
+    fn fold(&mut self, event: Event) {
+        let cursor = EventCursor::from(&event);
+        let seq = event.seq;
+        {
+            let mut view = self.view.write().unwrap();
+            (self.apply)(&mut view.state, event);
+            view.cursor = Some(cursor);
+            view.events += 1;
+        }
+        self.unsaved += 1;
+        sequence::advance(&self.seq_tx, seq);
+    }
+
+    /// Save a snapshot if there is a store and anything new to save.
+    async fn save(&mut self) -> Result<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        if self.unsaved == 0 {
+            return Ok(());
+        }
+        let snapshot = {
+            let view = self.view.read().unwrap();
+            let Some(cursor) = view.cursor.clone() else {
+                return Ok(());
+            };
+            Snapshot {
+                state: serde_json::to_value(&view.state)?,
+                cursor,
+                events: view.events,
+                taken_at: Utc::now(),
+            }
+        };
+        store.save(snapshot).await?;
+        self.unsaved = 0;
+        Ok(())
+    }
+}
+
+/// Wait for the next tick, or forever without a ticker.
+async fn tick(ticker: &mut Option<tokio::time::Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
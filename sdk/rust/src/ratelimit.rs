@@ -0,0 +1,211 @@
+//! Server rate limits: what responses report, and optional emit throttling.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use tokio::time::Instant;
+
+use crate::client::Notif;
+
+const LIMIT_HEADER: &str = "x-ratelimit-limit";
+const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+const RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// Reset values above this are Unix timestamps rather than seconds to wait.
+const EPOCH_THRESHOLD: u64 = 1_000_000_000;
+
+/// The rate limit last reported by the server.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RateLimitStatus {
+    /// Requests allowed per second, from `X-RateLimit-Limit`.
+    pub limit: Option<u32>,
+    /// Requests left in the current window, from `X-RateLimit-Remaining`.
+    pub remaining: Option<u32>,
+    /// When the limit resets, from `X-RateLimit-Reset` or `Retry-After`.
+    pub reset_at: Option<DateTime<Utc>>,
+    /// When the response carrying these values arrived.
+    pub observed_at: DateTime<Utc>,
+}
+
+/// How `NotifBuilder::throttle_emits` paces emits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throttle {
+    /// Fixed rate, or `None` to follow the limit the server reports.
+    per_second: Option<u32>,
+}
+
+impl Throttle {
+    /// Pace emits at the rate the server reports in `X-RateLimit-Limit`.
+    ///
+    /// Emits are not delayed until the server has reported a limit, and
+    /// wait out any `Retry-After` the server sends with a 429.
+    pub fn server_quota() -> Self {
+        Self { per_second: None }
+    }
+
+    /// Pace emits at `per_second` (at least one), whatever the server reports.
+    pub fn per_second(per_second: u32) -> Self {
+        Self {
+            per_second: Some(per_second.max(1)),
+        }
+    }
+}
+
+/// Token bucket holding one second's worth of emits.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(rate: u32) -> Self {
+        Self {
+            rate: f64::from(rate),
+            tokens: f64::from(rate),
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return how long until one is available.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    status: Option<RateLimitStatus>,
+    bucket: Option<Bucket>,
+    /// No emits before this, after a 429 with `Retry-After`.
+    blocked_until: Option<Instant>,
+}
+
+/// Rate limit bookkeeping shared by a client's requests.
+#[derive(Debug, Default)]
+pub(crate) struct RateLimits {
+    throttle: Option<Throttle>,
+    state: Mutex<State>,
+}
+
+impl RateLimits {
+    pub(crate) fn new(throttle: Option<Throttle>) -> Self {
+        let bucket = throttle
+            .and_then(|throttle| throttle.per_second)
+            .map(Bucket::new);
+        Self {
+            throttle,
+            state: Mutex::new(State {
+                bucket,
+                ..State::default()
+            }),
+        }
+    }
+
+    /// Record the rate limit headers of a response.
+    pub(crate) fn observe(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        retry_after: Option<Duration>,
+    ) {
+        let retry_after = retry_after.filter(|_| status == StatusCode::TOO_MANY_REQUESTS);
+        let number =
+            |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.trim().parse().ok() };
+        let limit = number(LIMIT_HEADER).and_then(|value| u32::try_from(value).ok());
+        let remaining = number(REMAINING_HEADER).and_then(|value| u32::try_from(value).ok());
+        let now = Utc::now();
+        let reset_at = number(RESET_HEADER)
+            .and_then(|value| match value {
+                value if value > EPOCH_THRESHOLD => {
+                    DateTime::from_timestamp(i64::try_from(value).ok()?, 0)
+                }
+                value => Some(now + chrono::Duration::seconds(i64::try_from(value).ok()?)),
+            })
+            .or_else(|| Some(now + chrono::Duration::from_std(retry_after?).ok()?));
+        if limit.is_none() && remaining.is_none() && reset_at.is_none() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.status = Some(RateLimitStatus {
+            limit,
+            remaining,
+            reset_at,
+            observed_at: now,
+        });
+        if self.throttle.is_none() {
+            return;
+        }
+        if let Some(wait) = retry_after {
+            state.blocked_until = Some(Instant::now() + wait);
+        }
+        let follows_server = self
+            .throttle
+            .is_some_and(|throttle| throttle.per_second.is_none());
+        if let Some(limit) = limit.filter(|limit| *limit > 0 && follows_server) {
+            match &mut state.bucket {
+                Some(bucket) if bucket.rate == f64::from(limit) => {}
+                bucket => *bucket = Some(Bucket::new(limit)),
+            }
+        }
+    }
+
+    /// Wait until an emit may be sent under the throttle, if there is one.
+    pub(crate) async fn acquire(&self) {
+        if self.throttle.is_none() {
+            return;
+        }
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                match state.blocked_until {
+                    Some(until) if until > now => Some(until - now),
+                    _ => state.bucket.as_mut().and_then(|bucket| bucket.take(now)),
+                }
+            };
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    pub(crate) fn status(&self) -> Option<RateLimitStatus> {
+        self.state.lock().unwrap().status.clone()
+    }
+}
+
+impl Notif {
+    /// The rate limit the server last reported, if any response carried one.
+    ///
+    /// The server reports its limit on throttled requests, so this is
+    /// mostly useful after a `NotifError::RateLimited`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # fn example(client: Notif) {
+    /// if let Some(status) = client.rate_limit_status() {
+    ///     println!("{:?} of {:?} requests left", status.remaining, status.limit);
+    /// }
+    /// # }
+    /// ```
+    pub fn rate_limit_status(&self) -> Option<RateLimitStatus> {
+        self.inner.rate_limits.status()
+    }
+}
@@ -0,0 +1,297 @@
+//! Low-level access to every endpoint of the server's HTTP API.
+//!
+//! One function per route returns a [`RawRequest`], authenticated and
+//! retried like the rest of the client, whose query and body are left to
+//! the caller and whose response is plain JSON. This reaches endpoints the
+//! high-level client does not wrap yet; [`request`] reaches any other path,
+//! such as one a newer server just added.
+//!
+//! The endpoint functions are generated from the server's route table by
+//! `scripts/genraw.py`. Routes only one server mode serves say so; the
+//! WebSocket and Server-Sent Events routes are left to `Notif::subscribe`.
+//!
+//! Requests made here skip what the high-level methods add on top, such as
+//! validation, encryption, signing or chunking of emitted events.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::{raw, Notif};
+//! use serde_json::json;
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let overview = raw::stats_overview(&client).send().await?;
+//! println!("pending in the DLQ: {}", overview["dlq"]["pending"]);
+//!
+//! raw::update_schema(&client, "order")
+//!     .json(&json!({ "description": "Orders placed on the storefront" }))
+//!     .send()
+//!     .await?;
+//!
+//! // Any path, for routes added after this SDK was released
+//! let events = raw::request(&client, raw::Method::GET, "/api/v1/events")
+//!     .query(&[("topic", "orders.created"), ("limit", "10")])
+//!     .send()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::client::Notif;
+use crate::error::Result;
+
+pub use reqwest::Method;
+
+/// A request to the API, from one of the endpoint functions of [`raw`](self).
+pub struct RawRequest {
+    client: Notif,
+    method: Method,
+    path: String,
+    request: RequestBuilder,
+}
+
+impl RawRequest {
+    fn new(client: &Notif, method: Method, path: String) -> Self {
+        Self {
+            client: client.clone(),
+            request: client.http(method.clone(), &path),
+            method,
+            path,
+        }
+    }
+
+    /// Add query parameters, such as `&[("limit", "10")]`.
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        self.request = self.request.query(query);
+        self
+    }
+
+    /// Send `body` as JSON.
+    pub fn json<T: Serialize + ?Sized>(mut self, body: &T) -> Self {
+        self.request = self.request.json(body);
+        self
+    }
+
+    /// Add a header.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.request = self.request.header(name, value);
+        self
+    }
+
+    /// Send the request and return the JSON response, or `Value::Null` if
+    /// it has no body.
+    ///
+    /// Statuses other than 2xx are returned as errors, like the high-level
+    /// methods do; so is anything but a `GET` or `HEAD` from a read-only
+    /// client.
+    pub async fn send(self) -> Result<Value> {
+        self.send_as().await
+    }
+
+    /// Send the request and deserialize the JSON response into `T`.
+    pub async fn send_as<T: DeserializeOwned>(self) -> Result<T> {
+        if self.method != Method::GET && self.method != Method::HEAD {
+            self.client
+                .check_writable(&format!("{} {}", self.method, self.path))?;
+        }
+        let response = self.client.execute(self.request).await?;
+        let body = response.bytes().await?;
+        if body.is_empty() {
+            return Ok(serde_json::from_value(Value::Null)?);
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+impl fmt::Debug for RawRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawRequest")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A request to `path` (such as `/api/v1/events`), for routes without an
+/// endpoint function.
+pub fn request(client: &Notif, method: Method, path: &str) -> RawRequest {
+    RawRequest::new(client, method, path.to_string())
+}
+
+/// Percent-encode `value` as a single path segment.
+pub(crate) fn segment(value: impl fmt::Display) -> String {
+    let mut encoded = String::new();
+    for byte in value.to_string().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Declare one function per route, taking its path parameters in order.
+macro_rules! endpoints {
+    ($($(#[$doc:meta])* $name:ident($($param:ident),*) => $method:ident $path:literal;)*) => {
+        $(
+            $(#[$doc])*
+            #[doc = concat!("\n\n`", stringify!($method), " ", $path, "`")]
+            pub fn $name(client: &Notif $(, $param: impl fmt::Display)*) -> RawRequest {
+                let path = format!($path $(, $param = segment($param))*);
+                RawRequest::new(client, Method::$method, path)
+            }
+        )*
+    };
+}
+
+// Generated by scripts/genraw.py from internal/server/routes.go
+endpoints! {
+    /// Whether the server is up.
+    health() => GET "/health";
+    /// Whether the server is ready to serve requests.
+    ready() => GET "/ready";
+    /// Health of the server and its dependencies. Multi-account servers only.
+    healthz() => GET "/healthz";
+
+    /// Whether the server was bootstrapped.
+    bootstrap_status() => GET "/api/v1/bootstrap/status";
+    /// Create the first organization and project of a self-hosted server.
+    bootstrap() => POST "/api/v1/bootstrap";
+
+    /// Create an organization; requires a dashboard session. Multi-account servers only.
+    create_org() => POST "/api/v1/orgs";
+    /// List organizations; requires a dashboard session. Multi-account servers only.
+    list_orgs() => GET "/api/v1/orgs";
+    /// Delete an organization; requires a dashboard session. Multi-account servers only.
+    delete_org(id) => DELETE "/api/v1/orgs/{id}";
+    /// Limits of an organization; requires a dashboard session. Multi-account servers only.
+    get_org_limits(id) => GET "/api/v1/orgs/{id}/limits";
+    /// Change the limits of an organization; requires a dashboard session. Multi-account servers only.
+    update_org_limits(id) => PUT "/api/v1/orgs/{id}/limits";
+
+    /// The organization, project and key of the caller.
+    whoami() => GET "/api/v1/whoami";
+
+    /// Emit an event.
+    emit() => POST "/api/v1/emit";
+
+    /// List stored events.
+    list_events() => GET "/api/v1/events";
+    /// Number of events the project emitted.
+    event_stats() => GET "/api/v1/events/stats";
+    /// A stored event by sequence number.
+    get_event(seq) => GET "/api/v1/events/{seq}";
+    /// Deliveries of an event to consumers and webhooks.
+    list_event_deliveries(id) => GET "/api/v1/events/{id}/deliveries";
+
+    /// Topics with stored events.
+    list_topics() => GET "/api/v1/topics";
+    /// A topic with stored events.
+    get_topic(name) => GET "/api/v1/topics/{name}";
+
+    /// Backlog of a consumer group.
+    consumer_lag(group) => GET "/api/v1/consumers/{group}/lag";
+
+    /// Register a webhook.
+    create_webhook() => POST "/api/v1/webhooks";
+    /// List webhooks.
+    list_webhooks() => GET "/api/v1/webhooks";
+    /// A webhook.
+    get_webhook(id) => GET "/api/v1/webhooks/{id}";
+    /// Change a webhook.
+    update_webhook(id) => PUT "/api/v1/webhooks/{id}";
+    /// Delete a webhook.
+    delete_webhook(id) => DELETE "/api/v1/webhooks/{id}";
+    /// Deliveries of a webhook.
+    list_webhook_deliveries(id) => GET "/api/v1/webhooks/{id}/deliveries";
+
+    /// List the dead letter queue.
+    list_dlq() => GET "/api/v1/dlq";
+    /// An event of the dead letter queue.
+    get_dlq(seq) => GET "/api/v1/dlq/{seq}";
+    /// Emit an event of the dead letter queue again.
+    replay_dlq(seq) => POST "/api/v1/dlq/{seq}/replay";
+    /// Remove an event from the dead letter queue.
+    delete_dlq(seq) => DELETE "/api/v1/dlq/{seq}";
+    /// Emit every event of the dead letter queue again.
+    replay_all_dlq() => POST "/api/v1/dlq/replay-all";
+    /// Empty the dead letter queue.
+    purge_dlq() => DELETE "/api/v1/dlq/purge";
+
+    /// Schedule an event. Single-account servers only.
+    create_schedule() => POST "/api/v1/schedules";
+    /// List scheduled events. Single-account servers only.
+    list_schedules() => GET "/api/v1/schedules";
+    /// A scheduled event. Single-account servers only.
+    get_schedule(id) => GET "/api/v1/schedules/{id}";
+    /// Change a pending scheduled event. Single-account servers only.
+    update_schedule(id) => PATCH "/api/v1/schedules/{id}";
+    /// Cancel a scheduled event. Single-account servers only.
+    cancel_schedule(id) => DELETE "/api/v1/schedules/{id}";
+    /// Emit a scheduled event now. Single-account servers only.
+    run_schedule(id) => POST "/api/v1/schedules/{id}/run";
+
+    /// Scheduled event statistics. Single-account servers only.
+    stats_schedules() => GET "/api/v1/stats/schedules";
+
+    /// Create a schema.
+    create_schema() => POST "/api/v1/schemas";
+    /// List schemas.
+    list_schemas() => GET "/api/v1/schemas";
+    /// The schema that applies to a topic.
+    get_schema_for_topic(topic) => GET "/api/v1/schemas/for-topic/{topic}";
+    /// A schema.
+    get_schema(name) => GET "/api/v1/schemas/{name}";
+    /// Change a schema.
+    update_schema(name) => PUT "/api/v1/schemas/{name}";
+    /// Delete a schema.
+    delete_schema(name) => DELETE "/api/v1/schemas/{name}";
+    /// Add a version to a schema.
+    create_schema_version(name) => POST "/api/v1/schemas/{name}/versions";
+    /// List the versions of a schema.
+    list_schema_versions(name) => GET "/api/v1/schemas/{name}/versions";
+    /// A version of a schema.
+    get_schema_version(name, version) => GET "/api/v1/schemas/{name}/versions/{version}";
+    /// Validate a payload against a schema.
+    validate_schema(name) => POST "/api/v1/schemas/{name}/validate";
+
+    /// List the audit log.
+    list_audit() => GET "/api/v1/audit";
+
+    /// Overview of the project's activity.
+    stats_overview() => GET "/api/v1/stats/overview";
+    /// Event counts of the organization, in total and recently.
+    stats_events() => GET "/api/v1/stats/events";
+    /// Webhook delivery statistics.
+    stats_webhooks() => GET "/api/v1/stats/webhooks";
+    /// Dead letter queue statistics.
+    stats_dlq() => GET "/api/v1/stats/dlq";
+
+    /// Create an API key; requires a dashboard session.
+    create_api_key() => POST "/api/v1/api-keys";
+    /// List API keys; requires a dashboard session.
+    list_api_keys() => GET "/api/v1/api-keys";
+    /// Revoke an API key; requires a dashboard session.
+    revoke_api_key(id) => DELETE "/api/v1/api-keys/{id}";
+
+    /// Create a project; requires a dashboard session.
+    create_project() => POST "/api/v1/projects";
+    /// List projects; requires a dashboard session.
+    list_projects() => GET "/api/v1/projects";
+    /// A project; requires a dashboard session.
+    get_project(id) => GET "/api/v1/projects/{id}";
+    /// Change a project; requires a dashboard session.
+    update_project(id) => PUT "/api/v1/projects/{id}";
+    /// Delete a project; requires a dashboard session.
+    delete_project(id) => DELETE "/api/v1/projects/{id}";
+}
@@ -0,0 +1,303 @@
+//! Replicating topics into Redis Streams and back (`redis` feature).
+//!
+//! A [`RedisSink`] appends every event of some topics to a Redis Stream
+//! with `XADD`, acking each event once Redis has it. A [`RedisSource`]
+//! reads a stream through a Redis consumer group and emits each entry,
+//! acknowledging it with `XACK` once the server accepted the emit, so the
+//! group's pending list is the checkpoint: a source that restarts picks
+//! up its unacknowledged entries first. Both run on the [`redis`] crate
+//! and accept `redis://` and `rediss://` (TLS) URLs.
+//!
+//! Entries written by the sink have the fields `id`, `topic`, `data`
+//! (JSON) and `timestamp`; the source reads `topic` and `data`, so
+//! streams round-trip between the two.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::redis::{RedisSink, RedisSource};
+//! use notifsh::Notif;
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! // Buffer orders locally in Redis...
+//! let sink = RedisSink::new(&client, "redis://127.0.0.1:6379", &["orders.*"], "orders")
+//!     .max_len(100_000);
+//! tokio::spawn(sink.run());
+//!
+//! // ...and forward what local services append to `outgoing`
+//! RedisSource::new(&client, "redis://127.0.0.1:6379", "outgoing", "notif-forwarder")
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamId, StreamMaxlen, StreamReadOptions};
+use redis::{AsyncConnectionConfig, AsyncTypedCommands};
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::types::{EmitOptions, SubscribeOptions};
+
+/// Header carrying the stream key and entry ID of an event emitted by a
+/// [`RedisSource`], as `key:id`, for consumers to deduplicate.
+pub const REDIS_ID_HEADER: &str = "notif-redis-id";
+
+/// Wait before reading entries again after a retryable emit failure.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Connect to `url` (`redis://` or `rediss://`, with the credentials and
+/// database it names). `XREADGROUP ... BLOCK` waits longer than the
+/// default response timeout, so replies are awaited without one.
+async fn connect(url: &str) -> Result<MultiplexedConnection> {
+    let config = AsyncConnectionConfig::new().set_response_timeout(None);
+    let connection = redis::Client::open(url)?
+        .get_multiplexed_async_connection_with_config(&config)
+        .await?;
+    Ok(connection)
+}
+
+/// Appends the events of some topics to a Redis Stream.
+///
+/// Created with [`RedisSink::new`] and started with [`run`](RedisSink::run).
+/// Each event is acked once `XADD` succeeds, so events are replicated at
+/// least once: after a failure, unacked events are redelivered to the
+/// next run.
+pub struct RedisSink {
+    client: Notif,
+    url: String,
+    topics: Vec<String>,
+    key: String,
+    max_len: Option<u64>,
+    options: SubscribeOptions,
+}
+
+impl RedisSink {
+    /// Replicate events of `topics` into the stream `key` on the Redis
+    /// server at `url`.
+    pub fn new(
+        client: &Notif,
+        url: impl Into<String>,
+        topics: &[&str],
+        key: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            url: url.into(),
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+            key: key.into(),
+            max_len: None,
+            options: SubscribeOptions::new(),
+        }
+    }
+
+    /// Trim the stream to about `max_len` entries (`MAXLEN ~`) as it grows.
+    pub fn max_len(mut self, max_len: u64) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Subscribe with `options`, e.g. a consumer group to share the
+    /// topics between several sinks. Acks are always manual.
+    pub fn options(mut self, options: SubscribeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Replicate events until the subscription ends or Redis fails.
+    pub async fn run(self) -> Result<()> {
+        let mut redis = connect(&self.url).await?;
+        let topics: Vec<&str> = self.topics.iter().map(String::as_str).collect();
+        let mut stream = self
+            .client
+            .subscribe_with_options(&topics, self.options.clone().auto_ack(false))
+            .await?;
+
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            let fields = [
+                ("id", event.id.clone()),
+                ("topic", event.topic.clone()),
+                ("data", event.data.to_string()),
+                ("timestamp", event.timestamp.to_rfc3339()),
+            ];
+            match self.max_len {
+                Some(max_len) => {
+                    let max_len = StreamMaxlen::Approx(max_len.try_into().unwrap_or(usize::MAX));
+                    redis.xadd_maxlen(&self.key, max_len, "*", &fields).await?;
+                }
+                None => {
+                    redis.xadd(&self.key, "*", &fields).await?;
+                }
+            }
+            event.ack().await?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RedisSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisSink")
+            .field("topics", &self.topics)
+            .field("key", &self.key)
+            .field("max_len", &self.max_len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Emits the entries of a Redis Stream, read through a consumer group.
+///
+/// Created with [`RedisSource::new`] and started with
+/// [`run`](RedisSource::run). Each entry is emitted to its `topic` field
+/// (or the topic set with [`topic`](RedisSource::topic)) with its `data`
+/// field as payload, parsed as JSON when it is JSON and sent as a string
+/// otherwise. Entries are acknowledged once emitted; after a retryable
+/// emit failure the source waits and retries from its pending entries,
+/// keeping their order. Entries that can never be emitted (no topic, or
+/// rejected by the server) are acknowledged and skipped.
+pub struct RedisSource {
+    client: Notif,
+    url: String,
+    key: String,
+    group: String,
+    consumer: String,
+    topic: Option<String>,
+    batch_size: usize,
+    block: Duration,
+}
+
+impl RedisSource {
+    /// Emit the entries of the stream `key` on the Redis server at `url`,
+    /// tracked by the consumer group `group`.
+    ///
+    /// The group is created at the end of the stream if it does not exist,
+    /// so only entries added from then on are emitted.
+    pub fn new(
+        client: &Notif,
+        url: impl Into<String>,
+        key: impl Into<String>,
+        group: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            url: url.into(),
+            key: key.into(),
+            group: group.into(),
+            consumer: "notifsh".to_string(),
+            topic: None,
+            batch_size: 100,
+            block: Duration::from_secs(5),
+        }
+    }
+
+    /// Set the consumer name within the group (default: `notifsh`).
+    ///
+    /// Give each concurrently running source its own name; a source
+    /// resumes the pending entries of its name.
+    pub fn consumer(mut self, consumer: impl Into<String>) -> Self {
+        self.consumer = consumer.into();
+        self
+    }
+
+    /// Emit every entry to `topic`, ignoring their `topic` field.
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Set the most entries read at once (default: 100).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set how long each read waits for new entries (default: 5s).
+    pub fn block(mut self, block: Duration) -> Self {
+        self.block = block;
+        self
+    }
+
+    /// Emit entries until Redis fails.
+    pub async fn run(self) -> Result<()> {
+        let mut redis = connect(&self.url).await?;
+        match redis
+            .xgroup_create_mkstream(&self.key, &self.group, "$")
+            .await
+        {
+            Err(e) if e.code() == Some("BUSYGROUP") => {}
+            other => other?,
+        }
+
+        let block = self.block.as_millis().try_into().unwrap_or(usize::MAX);
+        // Entries delivered to this consumer but never acknowledged come first
+        let mut pending = true;
+        loop {
+            let mut options = StreamReadOptions::default()
+                .group(&self.group, &self.consumer)
+                .count(self.batch_size);
+            if !pending {
+                options = options.block(block);
+            }
+            let start = if pending { "0" } else { ">" };
+            let entries = redis
+                .xread_options(&[&self.key], &[start], &options)
+                .await?
+                .and_then(|reply| reply.keys.into_iter().next())
+                .map(|stream| stream.ids)
+                .unwrap_or_default();
+            if pending && entries.is_empty() {
+                pending = false;
+                continue;
+            }
+
+            for entry in entries {
+                if !self.forward(&entry).await? {
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    pending = true;
+                    break;
+                }
+                redis.xack(&self.key, &self.group, &[&entry.id]).await?;
+            }
+        }
+    }
+
+    /// Emit one entry. `false` if it should be retried later.
+    ///
+    /// Entries deleted while pending have no fields and are skipped.
+    async fn forward(&self, entry: &StreamId) -> Result<bool> {
+        if entry.map.is_empty() {
+            return Ok(true);
+        }
+        let field = |name: &str| entry.get::<String>(name);
+        let Some(topic) = self.topic.clone().or_else(|| field("topic")) else {
+            return Ok(true);
+        };
+        let data = field("data").map_or(serde_json::Value::Null, |data| {
+            serde_json::from_str(&data).unwrap_or_else(|_| data.into())
+        });
+        let options =
+            EmitOptions::new().header(REDIS_ID_HEADER, format!("{}:{}", self.key, entry.id));
+        match self.client.emit_with_options(&topic, data, options).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_retryable() => Ok(false),
+            // Rejected for good, e.g. an invalid topic
+            Err(_) => Ok(true),
+        }
+    }
+}
+
+impl fmt::Debug for RedisSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisSource")
+            .field("key", &self.key)
+            .field("group", &self.group)
+            .field("consumer", &self.consumer)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,177 @@
+//! Registry of clients for publishing on behalf of many tenants.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+
+use crate::client::{Notif, NotifBuilder};
+use crate::error::{NotifError, Result};
+use crate::types::{EmitOptions, EmitResponse};
+
+type Factory = dyn Fn(&str) -> Result<NotifBuilder> + Send + Sync;
+
+/// Manages one [`Notif`] client per tenant.
+///
+/// Clients are created on first use, either from a builder registered with
+/// [`NotifRegistry::register`] or from the factory passed to
+/// [`NotifRegistry::with_factory`]. All clients share a single HTTP
+/// connection pool. Cloning is cheap; clones share the same clients.
+///
+/// # Example
+///
+/// ```no_run
+/// # use notifsh::{Notif, NotifRegistry};
+/// # use serde_json::json;
+/// # fn lookup_api_key(tenant: &str) -> String { unimplemented!() }
+/// # async fn example() -> notifsh::Result<()> {
+/// let registry = NotifRegistry::with_factory(|tenant| Ok(Notif::builder(lookup_api_key(tenant))));
+///
+/// registry
+///     .emit("acme", "invoices.paid", json!({"invoice_id": "inv_42"}))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct NotifRegistry {
+    inner: Arc<RegistryInner>,
+}
+
+struct RegistryInner {
+    http_client: HttpClient,
+    factory: Option<Box<Factory>>,
+    builders: RwLock<HashMap<String, NotifBuilder>>,
+    clients: RwLock<HashMap<String, Notif>>,
+}
+
+impl Default for NotifRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotifRegistry {
+    /// Create a registry of explicitly registered tenants.
+    pub fn new() -> Self {
+        Self::build(None)
+    }
+
+    /// Create a registry that builds clients for unknown tenants with `factory`.
+    ///
+    /// The factory runs once per tenant, on first use. Its errors are
+    /// returned to the caller and the tenant is retried on the next call.
+    pub fn with_factory<F>(factory: F) -> Self
+    where
+        F: Fn(&str) -> Result<NotifBuilder> + Send + Sync + 'static,
+    {
+        Self::build(Some(Box::new(factory)))
+    }
+
+    fn build(factory: Option<Box<Factory>>) -> Self {
+        Self {
+            inner: Arc::new(RegistryInner {
+                http_client: HttpClient::new(),
+                factory,
+                builders: RwLock::new(HashMap::new()),
+                clients: RwLock::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Register (or replace) the client configuration for a tenant.
+    ///
+    /// The client is built on first use.
+    pub fn register(&self, tenant: impl Into<String>, builder: NotifBuilder) {
+        let tenant = tenant.into();
+        write(&self.inner.clients).remove(&tenant);
+        write(&self.inner.builders).insert(tenant, builder);
+    }
+
+    /// Get the client for a tenant, building it if needed.
+    ///
+    /// Fails with `NotifError::UnknownTenant` if the tenant is not registered
+    /// and there is no factory.
+    pub fn get(&self, tenant: &str) -> Result<Notif> {
+        if let Some(client) = read(&self.inner.clients).get(tenant) {
+            return Ok(client.clone());
+        }
+
+        let builder = match read(&self.inner.builders).get(tenant) {
+            Some(builder) => builder.clone(),
+            None => match &self.inner.factory {
+                Some(factory) => factory(tenant)?,
+                None => return Err(NotifError::UnknownTenant(tenant.to_string())),
+            },
+        };
+        let client = builder
+            .http_client(self.inner.http_client.clone())
+            .build()?;
+
+        // Another caller may have built it meanwhile; keep the first one
+        let mut clients = write(&self.inner.clients);
+        Ok(clients.entry(tenant.to_string()).or_insert(client).clone())
+    }
+
+    /// Forget a tenant's client and registration.
+    ///
+    /// Subscriptions already opened with the client keep running.
+    pub fn remove(&self, tenant: &str) {
+        write(&self.inner.builders).remove(tenant);
+        write(&self.inner.clients).remove(tenant);
+    }
+
+    /// Tenants with a registration or a built client.
+    pub fn tenants(&self) -> Vec<String> {
+        let mut tenants: Vec<String> = read(&self.inner.builders).keys().cloned().collect();
+        for tenant in read(&self.inner.clients).keys() {
+            if !tenants.contains(tenant) {
+                tenants.push(tenant.clone());
+            }
+        }
+        tenants.sort();
+        tenants
+    }
+
+    /// Emit an event on behalf of a tenant.
+    pub async fn emit<T: Serialize>(
+        &self,
+        tenant: &str,
+        topic: &str,
+        data: T,
+    ) -> Result<EmitResponse> {
+        self.get(tenant)?.emit(topic, data).await
+    }
+
+    /// Emit an event on behalf of a tenant with custom options.
+    pub async fn emit_with_options<T: Serialize>(
+        &self,
+        tenant: &str,
+        topic: &str,
+        data: T,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        self.get(tenant)?
+            .emit_with_options(topic, data, options)
+            .await
+    }
+}
+
+impl fmt::Debug for NotifRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotifRegistry")
+            .field("tenants", &self.tenants())
+            .field("factory", &self.inner.factory.is_some())
+            .finish()
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> std::sync::RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,127 @@
+//! Retry policy for HTTP operations.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Request, Response, StatusCode};
+
+use crate::types::Backoff;
+
+/// Retry policy for HTTP calls such as emit and schedule.
+///
+/// Requests are retried on 429, 5xx, timeouts and connection failures with
+/// jittered exponential backoff. A `Retry-After` header from the server takes
+/// precedence over the computed delay.
+///
+/// Requests that are not idempotent, such as creating a webhook, are only
+/// retried when the server cannot have acted on them: on 429 and connection
+/// failures. Emits carry an `Idempotency-Key` the server deduplicates on, so
+/// they are retried like any other request without publishing twice.
+/// The server remembers keys for its deduplication window (two minutes by
+/// default).
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{Notif, RetryPolicy};
+///
+/// let client = Notif::builder("nsh_your_api_key")
+///     .retries(RetryPolicy::exponential(3))
+///     .build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Delays between attempts; `max_retries` bounds the number of retries.
+    pub backoff: Backoff,
+    /// Randomize each delay to avoid synchronized retries (default: true).
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry.
+    pub fn none() -> Self {
+        Self::exponential(0)
+    }
+
+    /// Retry up to `max_retries` times with exponential backoff (200ms to 10s).
+    pub fn exponential(max_retries: u32) -> Self {
+        Self {
+            backoff: Backoff::new(Duration::from_millis(200), Duration::from_secs(10))
+                .max_retries(max_retries),
+            jitter: true,
+        }
+    }
+
+    /// Use custom backoff settings.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Enable or disable jitter.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub(crate) fn max_retries(&self) -> u32 {
+        self.backoff.max_retries.unwrap_or(u32::MAX)
+    }
+
+    /// Delay before the given retry (0-based).
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.backoff.delay(attempt);
+        if self.jitter {
+            delay.mul_f64(0.5 + fastrand::f64() * 0.5)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Header with a key the server deduplicates retried emits on.
+pub(crate) const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+/// Whether sending `request` again cannot repeat its effect.
+pub(crate) fn is_idempotent(request: &Request) -> bool {
+    matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    ) || request.headers().contains_key(IDEMPOTENCY_KEY)
+}
+
+/// Whether a response status is worth retrying.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Whether a transport error is worth retrying.
+pub(crate) fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    (at - Utc::now()).to_std().ok()
+}
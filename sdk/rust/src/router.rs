@@ -0,0 +1,235 @@
+//! Dispatching events to typed handlers by topic pattern.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use serde::de::DeserializeOwned;
+
+use crate::client::Notif;
+use crate::concurrent::Concurrency;
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::{Event, SubscribeOptions, TypedEvent};
+
+type HandlerFn = dyn Fn(Event) -> BoxFuture<'static, std::result::Result<(), String>> + Send + Sync;
+type ErrorFn = dyn Fn(&RouteFailure) + Send + Sync;
+
+/// A handler of an [`EventRouter`] that failed on an event.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RouteFailure {
+    /// ID of the failed event.
+    pub event_id: String,
+    /// Topic of the failed event.
+    pub topic: String,
+    /// Pattern of the route that handled it.
+    pub pattern: String,
+    /// Delivery attempt that failed.
+    pub attempt: u32,
+    /// The handler's error, or why the payload did not decode.
+    pub message: String,
+}
+
+impl fmt::Display for RouteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "handler for '{}' failed on event {} ({}): {}",
+            self.pattern, self.event_id, self.topic, self.message
+        )
+    }
+}
+
+struct Route {
+    pattern: Topic,
+    handler: Arc<HandlerFn>,
+}
+
+/// Routes events from one subscription to handlers by topic pattern.
+///
+/// Each route decodes the payload into its own type. An event goes to
+/// the first route, in registration order, whose pattern matches its
+/// topic. It is acked once the handler returns `Ok`; when the handler
+/// returns `Err` or the payload does not decode, it is nacked with the
+/// backoff of [`EventRouter::concurrency`] and the failure is reported to
+/// [`EventRouter::on_error`]. Handlers must not ack or nack themselves.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{EventRouter, Notif, TypedEvent};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct SessionCompleted {
+///     session_id: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct ChatMessage {
+///     text: String,
+/// }
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// EventRouter::new()
+///     .on("agents.*.session.completed", |event: TypedEvent<SessionCompleted>| async move {
+///         println!("session {} done", event.data.session_id);
+///         Ok::<_, String>(())
+///     })
+///     .on("agents.*.chat", |event: TypedEvent<ChatMessage>| async move {
+///         println!("{}: {}", event.topic, event.data.text);
+///         Ok::<_, String>(())
+///     })
+///     .on_error(|failure| eprintln!("{failure}"))
+///     .run(&client)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct EventRouter {
+    routes: Vec<Route>,
+    /// First invalid pattern passed to `on`, reported by `run`.
+    invalid: Option<NotifError>,
+    options: SubscribeOptions,
+    concurrency: Concurrency,
+    on_error: Option<Arc<ErrorFn>>,
+}
+
+impl EventRouter {
+    /// Create a router without routes.
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            invalid: None,
+            options: SubscribeOptions::new(),
+            concurrency: Concurrency::new(1),
+            on_error: None,
+        }
+    }
+
+    /// Handle events matching `pattern`, decoded into `T`.
+    ///
+    /// An invalid pattern makes `run` fail with `NotifError::InvalidTopic`.
+    pub fn on<T, F, Fut, E>(mut self, pattern: &str, handler: F) -> Self
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(TypedEvent<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<(), E>> + Send + 'static,
+        E: fmt::Display,
+    {
+        let pattern = match Topic::parse(pattern) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                self.invalid.get_or_insert(e);
+                return self;
+            }
+        };
+        let handler = Arc::new(handler);
+        let handler: Arc<HandlerFn> = Arc::new(move |event: Event| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                let event = event.into_typed::<T>().map_err(|e| e.to_string())?;
+                handler(event).await.map_err(|e| e.to_string())
+            })
+        });
+        self.routes.push(Route { pattern, handler });
+        self
+    }
+
+    /// Set the options of the subscription, e.g. a consumer group.
+    ///
+    /// `auto_ack` is always turned off, since the router settles events.
+    pub fn subscribe_options(mut self, options: SubscribeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set how many events are handled at once and the redelivery
+    /// backoff of failed ones (default: one at a time, 1s doubling to 5m).
+    pub fn concurrency(mut self, concurrency: Concurrency) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Report every failed event to `callback`, e.g. to log it.
+    pub fn on_error<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&RouteFailure) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(callback));
+        self
+    }
+
+    /// Subscribe to the patterns of every route and dispatch until the
+    /// subscription ends.
+    ///
+    /// Fails right away if a pattern is invalid or there are no routes;
+    /// otherwise returns like `EventStream::for_each_concurrent_with`.
+    pub async fn run(self, client: &Notif) -> Result<()> {
+        if let Some(e) = self.invalid {
+            return Err(e);
+        }
+        if self.routes.is_empty() {
+            return Err(NotifError::Config("event router has no routes".to_string()));
+        }
+
+        let mut patterns: Vec<&str> = Vec::new();
+        for route in &self.routes {
+            if !patterns.contains(&route.pattern.as_str()) {
+                patterns.push(route.pattern.as_str());
+            }
+        }
+        let stream = client
+            .subscribe_with_options(&patterns, self.options.auto_ack(false))
+            .await?;
+
+        let routes = self.routes;
+        let on_error = self.on_error;
+        stream
+            .for_each_concurrent_with(self.concurrency, |event| {
+                let route = routes
+                    .iter()
+                    .find(|route| route.pattern.matches(&event.topic))
+                    .map(|route| (route.pattern.to_string(), route.handler.clone()));
+                let on_error = on_error.clone();
+                async move {
+                    // Only possible if the server matches more broadly; settle it
+                    let Some((pattern, handler)) = route else {
+                        return Ok(());
+                    };
+                    let (event_id, topic, attempt) =
+                        (event.id.clone(), event.topic.clone(), event.attempt);
+                    let result = handler(event).await;
+                    if let (Err(message), Some(on_error)) = (&result, &on_error) {
+                        on_error(&RouteFailure {
+                            event_id,
+                            topic,
+                            pattern,
+                            attempt,
+                            message: message.clone(),
+                        });
+                    }
+                    result
+                }
+            })
+            .await
+    }
+}
+
+impl Default for EventRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EventRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let patterns: Vec<&str> = self.routes.iter().map(|r| r.pattern.as_str()).collect();
+        f.debug_struct("EventRouter")
+            .field("routes", &patterns)
+            .field("concurrency", &self.concurrency)
+            .finish_non_exhaustive()
+    }
+}
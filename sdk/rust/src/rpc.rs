@@ -0,0 +1,115 @@
+//! Request/reply over topics.
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::types::{EmitResponse, Event};
+
+/// Prefix for per-request reply topics.
+const REPLY_TOPIC_PREFIX: &str = "_reply";
+
+/// Payload wrapper for a request event.
+#[derive(Debug, Serialize, Deserialize)]
+struct RequestEnvelope<T> {
+    correlation_id: String,
+    reply_to: String,
+    data: T,
+}
+
+/// Payload wrapper for a reply event.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReplyEnvelope<T> {
+    correlation_id: String,
+    data: T,
+}
+
+impl Notif {
+    /// Send a request and wait for the first reply.
+    ///
+    /// The payload is wrapped with a correlation ID and a reply topic, the
+    /// reply topic is subscribed, and the first matching reply is returned
+    /// with its `data` set to the responder's payload.
+    ///
+    /// Responders receive the wrapped payload and answer with [`Notif::reply`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use serde_json::json;
+    /// # use std::time::Duration;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    ///
+    /// let reply = client
+    ///     .request("inventory.check", json!({"sku": "abc"}), Duration::from_secs(10))
+    ///     .await?;
+    /// println!("in stock: {}", reply.data["available"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        timeout: Duration,
+    ) -> Result<Event> {
+        let correlation_id = uuid::Uuid::new_v4().simple().to_string();
+        let reply_to = format!("{}.{}", REPLY_TOPIC_PREFIX, correlation_id);
+
+        // Subscribe before emitting so the reply cannot be missed.
+        let mut replies = self.subscribe(&[reply_to.as_str()]).await?;
+
+        self.emit(
+            topic,
+            RequestEnvelope {
+                correlation_id: correlation_id.clone(),
+                reply_to: reply_to.clone(),
+                data,
+            },
+        )
+        .await?;
+
+        let wait = async {
+            while let Some(event) = replies.next().await {
+                let event = event?;
+                let envelope: ReplyEnvelope<serde_json::Value> = match event.parse() {
+                    Ok(envelope) => envelope,
+                    Err(_) => continue,
+                };
+                if envelope.correlation_id == correlation_id {
+                    return Ok(Event {
+                        data: envelope.data,
+                        ..event
+                    });
+                }
+            }
+            Err(NotifError::connection("reply subscription closed"))
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| NotifError::timeout(format!("no reply to request on '{}'", topic)))?
+    }
+
+    /// Answer a request received from [`Notif::request`].
+    ///
+    /// Returns a `NotifError::Decode` error if `request` was not sent as a
+    /// request (it has no correlation ID or reply topic).
+    pub async fn reply<T: Serialize>(&self, request: &Event, data: T) -> Result<EmitResponse> {
+        let envelope: RequestEnvelope<serde_json::Value> = request.parse()?;
+
+        self.emit(
+            &envelope.reply_to,
+            ReplyEnvelope {
+                correlation_id: envelope.correlation_id,
+                data,
+            },
+        )
+        .await
+    }
+}
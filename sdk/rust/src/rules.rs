@@ -0,0 +1,246 @@
+//! Declarative rules republishing derived events.
+
+use std::fmt;
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::filter::Filter;
+use crate::topic::Topic;
+use crate::types::{EmitOptions, Event, SubscribeOptions};
+
+/// Header set on events emitted by [`Rules`] to the ID of the event they
+/// were derived from.
+pub const DERIVED_FROM_HEADER: &str = "notif-derived-from";
+
+type PredicateFn = dyn Fn(&Event) -> bool + Send + Sync;
+type TransformFn = dyn Fn(&Event) -> serde_json::Result<Value> + Send + Sync;
+
+/// When a rule applies to an event whose topic matches its pattern.
+///
+/// Built from a closure over the [`Event`], or from a filter expression
+/// such as `"data.total > 100 && headers.tenant == 'acme'"` (see
+/// `SubscribeOptions::filter` for the syntax).
+#[derive(Clone)]
+pub struct Condition(Arc<PredicateFn>);
+
+impl Condition {
+    /// Apply to every event matching the pattern.
+    pub fn always() -> Self {
+        Self(Arc::new(|_| true))
+    }
+
+    /// Apply to events satisfying the filter expression `expr`.
+    pub fn filter(expr: &str) -> Result<Self> {
+        let filter = Filter::parse(expr)?;
+        Ok(Self(Arc::new(move |event| filter.matches(event))))
+    }
+}
+
+impl<F> From<F> for Condition
+where
+    F: Fn(&Event) -> bool + Send + Sync + 'static,
+{
+    fn from(predicate: F) -> Self {
+        Self(Arc::new(predicate))
+    }
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condition").finish_non_exhaustive()
+    }
+}
+
+struct Output {
+    topic: String,
+    transform: Arc<TransformFn>,
+}
+
+struct Rule {
+    pattern: Topic,
+    condition: Condition,
+    outputs: Vec<Output>,
+}
+
+/// Rules that turn events of one subscription into new events.
+///
+/// Each rule is a topic pattern, a [`Condition`] and the events to emit
+/// when both match, each with a topic and a transform building its
+/// payload. Every rule that applies to an event fires, in registration
+/// order. Derived events carry [`DERIVED_FROM_HEADER`]; rules should not
+/// match their own outputs, or they would loop.
+///
+/// An event is acked once everything derived from it is emitted. If an
+/// emit or a transform fails, it is nacked and redelivered, so outputs
+/// emitted before the failure may be emitted again.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{Condition, Event, Notif, Rules};
+/// use serde_json::json;
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// Rules::new()
+///     .when("orders.created", |event: &Event| {
+///         event.data["total"].as_f64() > Some(1000.0)
+///     })
+///     .emit("orders.large", |event: &Event| {
+///         json!({ "order_id": event.data["id"], "total": event.data["total"] })
+///     })
+///     .when("payments.failed", Condition::filter("data.retries >= 3")?)
+///     .emit("alerts.payments", |event: &Event| event.data.clone())
+///     .run(&client)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Rules {
+    rules: Vec<Rule>,
+    /// First invalid pattern or misplaced `emit`, reported by `run`.
+    invalid: Option<NotifError>,
+    options: SubscribeOptions,
+}
+
+impl Rules {
+    /// Create an engine without rules.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            invalid: None,
+            options: SubscribeOptions::new(),
+        }
+    }
+
+    /// Start a rule applying to events whose topic matches `pattern` and
+    /// that satisfy `condition`.
+    ///
+    /// An invalid pattern makes `run` fail with `NotifError::InvalidTopic`.
+    pub fn when(mut self, pattern: &str, condition: impl Into<Condition>) -> Self {
+        match Topic::parse(pattern) {
+            Ok(pattern) => self.rules.push(Rule {
+                pattern,
+                condition: condition.into(),
+                outputs: Vec::new(),
+            }),
+            Err(e) => {
+                self.invalid.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Make the last rule emit the payload `transform` builds to `topic`;
+    /// a rule may emit several events.
+    ///
+    /// An invalid topic, or calling `emit` before any `when`, makes `run`
+    /// fail.
+    pub fn emit<T, F>(mut self, topic: &str, transform: F) -> Self
+    where
+        T: Serialize,
+        F: Fn(&Event) -> T + Send + Sync + 'static,
+    {
+        let Some(rule) = self.rules.last_mut() else {
+            self.invalid.get_or_insert(NotifError::Config(format!(
+                "rule output '{topic}' comes before any `when`"
+            )));
+            return self;
+        };
+        if let Err(e) = Topic::parse(topic) {
+            self.invalid.get_or_insert(e);
+            return self;
+        }
+        rule.outputs.push(Output {
+            topic: topic.to_string(),
+            transform: Arc::new(move |event| serde_json::to_value(transform(event))),
+        });
+        self
+    }
+
+    /// Set the options of the subscription, e.g. a consumer group so
+    /// several instances share the work.
+    ///
+    /// `auto_ack` is always turned off, since the engine settles events.
+    pub fn subscribe_options(mut self, options: SubscribeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Subscribe to the patterns of every rule and apply the rules until
+    /// the subscription ends.
+    ///
+    /// Fails right away if a pattern is invalid, an `emit` has no rule or
+    /// there are no rules.
+    pub async fn run(self, client: &Notif) -> Result<()> {
+        if let Some(e) = self.invalid {
+            return Err(e);
+        }
+        if self.rules.is_empty() {
+            return Err(NotifError::Config("rules engine has no rules".to_string()));
+        }
+
+        let mut patterns: Vec<&str> = Vec::new();
+        for rule in &self.rules {
+            if !patterns.contains(&rule.pattern.as_str()) {
+                patterns.push(rule.pattern.as_str());
+            }
+        }
+        let mut stream = client
+            .subscribe_with_options(&patterns, self.options.auto_ack(false))
+            .await?;
+
+        while let Some(event) = stream.next().await {
+            // The stream recovers from errors by itself
+            let Ok(event) = event else { continue };
+            let _ = match apply(&self.rules, client, &event).await {
+                Ok(()) => event.ack().await,
+                Err(_) => event.nack(None).await,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Emit everything the rules derive from `event`.
+async fn apply(rules: &[Rule], client: &Notif, event: &Event) -> Result<()> {
+    let applying = rules
+        .iter()
+        .filter(|rule| rule.pattern.matches(&event.topic) && (rule.condition.0)(event));
+    for rule in applying {
+        for output in &rule.outputs {
+            let data = (output.transform)(event)?;
+            let options = EmitOptions::new().header(DERIVED_FROM_HEADER, event.id.as_str());
+            client
+                .emit_with_options(&output.topic, data, options)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<(&str, Vec<&str>)> = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let outputs = rule.outputs.iter().map(|o| o.topic.as_str()).collect();
+                (rule.pattern.as_str(), outputs)
+            })
+            .collect();
+        f.debug_struct("Rules")
+            .field("rules", &rules)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,34 @@
+//! Deterministic sampling by event ID, for `SubscribeOptions::sample`.
+
+use crate::error::{NotifError, Result};
+
+/// Keeps the events whose ID hashes below a threshold.
+///
+/// The decision only depends on the ID, so redeliveries of an event are
+/// sampled the same way, and sampling again events a server already
+/// sampled with the same rule keeps all of them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sample {
+    ratio: f64,
+}
+
+impl Sample {
+    /// Keep `ratio` of events, between `0.0` and `1.0`.
+    pub(crate) fn new(ratio: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&ratio) {
+            return Err(NotifError::Config(format!(
+                "sample ratio must be between 0 and 1, got {ratio}"
+            )));
+        }
+        Ok(Self { ratio })
+    }
+
+    /// Whether the event `id` is part of the sample.
+    pub(crate) fn keeps(&self, id: &str) -> bool {
+        // FNV-1a, whose top 53 bits make a uniform fraction in [0, 1)
+        let hash = id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+        ((hash >> 11) as f64 / (1_u64 << 53) as f64) < self.ratio
+    }
+}
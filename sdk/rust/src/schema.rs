@@ -0,0 +1,118 @@
+//! Schema registry: JSON Schemas registered for topics, and checking
+//! payloads against them.
+//!
+//! The server validates emits against the schema registered for their
+//! topic and, in strict mode, rejects invalid ones with
+//! `NotifError::SchemaViolation`. With the `jsonschema` feature,
+//! `NotifBuilder::validate_schemas` runs the same check locally, before
+//! an emit leaves the process and optionally on received events.
+
+use reqwest::Method;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::{
+    CreateSchemaRequest, CreateSchemaVersionRequest, ListSchemaVersionsResponse, Schema,
+    SchemaVersion,
+};
+
+impl Notif {
+    /// Register `schema` (a JSON Schema document) for a topic or pattern.
+    ///
+    /// The schema is named after `topic` and created on first use; each
+    /// call with a different document adds a new numbered version, which
+    /// takes effect right away. Registering the current document again
+    /// returns its version without creating one.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use serde_json::json;
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let version = client
+    ///     .register_schema(
+    ///         "agents.*.message",
+    ///         json!({
+    ///             "type": "object",
+    ///             "required": ["agent_id", "text"],
+    ///             "properties": {
+    ///                 "agent_id": {"type": "string"},
+    ///                 "text": {"type": "string"}
+    ///             }
+    ///         }),
+    ///     )
+    ///     .await?;
+    /// println!("schema version {}", version.version);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn register_schema(
+        &self,
+        topic: &str,
+        schema: serde_json::Value,
+    ) -> Result<SchemaVersion> {
+        let pattern = Topic::parse(topic)?;
+        let name = pattern.as_str();
+        let path = format!("/api/v1/schemas/{}", name);
+
+        let existing = match self.execute(self.http(Method::GET, &path)).await {
+            Ok(response) => Some(response.json::<Schema>().await?),
+            Err(NotifError::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let version = match existing {
+            Some(existing) => {
+                if let Some(latest) = existing.latest_version {
+                    if latest.schema == schema {
+                        return Ok(latest);
+                    }
+                }
+                let versions_path = format!("{}/versions", path);
+                let response = self.execute(self.http(Method::GET, &versions_path)).await?;
+                let list: ListSchemaVersionsResponse = response.json().await?;
+                list.versions
+                    .iter()
+                    .filter_map(|version| version.version.parse::<u64>().ok())
+                    .max()
+                    .map_or(1, |latest| latest + 1)
+            }
+            None => {
+                let request = CreateSchemaRequest {
+                    name,
+                    topic_pattern: name,
+                };
+                self.execute(self.http(Method::POST, "/api/v1/schemas").json(&request))
+                    .await?;
+                1
+            }
+        };
+
+        let version = version.to_string();
+        let request = CreateSchemaVersionRequest {
+            version: &version,
+            schema: &schema,
+        };
+        let response = self
+            .execute(
+                self.http(Method::POST, &format!("{}/versions", path))
+                    .json(&request),
+            )
+            .await?;
+        #[cfg(feature = "jsonschema")]
+        self.inner.schemas.invalidate();
+        Ok(response.json().await?)
+    }
+
+    /// The schema that applies to a concrete topic, if one is registered.
+    pub async fn schema_for_topic(&self, topic: &str) -> Result<Option<Schema>> {
+        let topic = Topic::name(topic)?;
+        let path = format!("/api/v1/schemas/for-topic/{}", topic);
+        match self.execute(self.http(Method::GET, &path)).await {
+            Ok(response) => Ok(Some(response.json().await?)),
+            Err(NotifError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
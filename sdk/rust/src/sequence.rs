@@ -0,0 +1,82 @@
+//! Waiting for a stream to reach a sequence number, for read-your-writes.
+
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use crate::error::{NotifError, Result};
+
+/// The highest stream sequence number a subscription or projection has
+/// seen, returned by `EventStream::sequence_watch`.
+///
+/// Pair it with `EmitResponse::seq` to wait until an event you emitted has
+/// reached a consumer. For subscriptions the sequence advances when the
+/// event is yielded; for a `Projection`, once it has been applied.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use notifsh::Notif;
+/// # use futures::StreamExt;
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let mut stream = client.subscribe(&["orders.*"]).await?;
+/// let watch = stream.sequence_watch();
+/// tokio::spawn(async move { while let Some(_event) = stream.next().await {} });
+///
+/// let response = client.emit("orders.created", serde_json::json!({"id": 1})).await?;
+/// if let Some(seq) = response.seq {
+///     watch.wait_for_sequence(seq, Duration::from_secs(5)).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SequenceWatch {
+    rx: watch::Receiver<Option<u64>>,
+}
+
+impl SequenceWatch {
+    pub(crate) fn new(rx: watch::Receiver<Option<u64>>) -> Self {
+        Self { rx }
+    }
+
+    /// The highest sequence number seen so far, if the server reported any.
+    pub fn current(&self) -> Option<u64> {
+        *self.rx.borrow()
+    }
+
+    /// Wait until the sequence number reaches `seq`.
+    ///
+    /// Returns at once if it already has. Fails with `NotifError::Timeout`
+    /// after `timeout`, or with `NotifError::Connection` if the
+    /// subscription ends first.
+    pub async fn wait_for_sequence(&self, seq: u64, timeout: Duration) -> Result<()> {
+        let mut rx = self.rx.clone();
+        let reached = rx.wait_for(|current| current.is_some_and(|current| current >= seq));
+        let outcome = tokio::time::timeout(timeout, reached).await;
+        match outcome {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(_)) => Err(NotifError::connection(format!(
+                "stream ended before reaching sequence {seq}"
+            ))),
+            Err(_) => Err(NotifError::timeout(format!(
+                "sequence {seq} not reached within {timeout:?}"
+            ))),
+        }
+    }
+}
+
+/// Advance `tx` to `seq` if it is higher than what was seen.
+pub(crate) fn advance(tx: &watch::Sender<Option<u64>>, seq: Option<u64>) {
+    if let Some(seq) = seq {
+        tx.send_if_modified(|current| {
+            let higher = current.is_none_or(|current| seq > current);
+            if higher {
+                *current = Some(seq);
+            }
+            higher
+        });
+    }
+}
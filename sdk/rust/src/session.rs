@@ -0,0 +1,184 @@
+//! Sessions: correlated multi-message exchanges on per-session topics.
+
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use serde::Serialize;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::subscribe::EventStream;
+use crate::topic::Topic;
+use crate::types::{EmitResponse, Event, SubscribeOptions};
+
+/// Last topic segment of messages sent by [`Session::send`].
+const MESSAGE_KIND: &str = "message";
+
+/// Last topic segment of [`Session::cancel`].
+const CANCEL_KIND: &str = "cancel";
+
+/// A lifecycle event of a [`Session`], by the last segment of its topic.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum SessionEvent {
+    /// The other side picked up the session (`<prefix>.<id>.started`).
+    Started(Event),
+    /// Intermediate output (`<prefix>.<id>.output`).
+    Output(Event),
+    /// The session finished (`<prefix>.<id>.completed`).
+    Completed(Event),
+    /// The session failed (`<prefix>.<id>.failed`).
+    Failed(Event),
+}
+
+impl SessionEvent {
+    fn from_event(event: Event) -> Option<Self> {
+        let kind = event.topic.rsplit('.').next().unwrap_or_default();
+        match kind {
+            "started" => Some(Self::Started(event)),
+            "output" => Some(Self::Output(event)),
+            "completed" => Some(Self::Completed(event)),
+            "failed" => Some(Self::Failed(event)),
+            _ => None,
+        }
+    }
+
+    /// The underlying event.
+    pub fn event(&self) -> &Event {
+        match self {
+            Self::Started(event)
+            | Self::Output(event)
+            | Self::Completed(event)
+            | Self::Failed(event) => event,
+        }
+    }
+
+    /// Whether the session is over: completed or failed.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed(_) | Self::Failed(_))
+    }
+}
+
+/// One session opened by [`Notif::open_session`].
+///
+/// Messages go to `<prefix>.<id>.message` and a cancellation to
+/// `<prefix>.<id>.cancel`; whoever serves the session subscribes to
+/// `<prefix>.*.message` and reports back on `<prefix>.<id>.started`,
+/// `.output`, `.completed` and `.failed`. The session is a stream of those
+/// reports that ends after the first completed or failed one.
+pub struct Session {
+    client: Notif,
+    prefix: String,
+    id: String,
+    inner: EventStream,
+    done: bool,
+}
+
+impl Notif {
+    /// Open a session on `<prefix>.<id>.*` topics under a new session ID.
+    ///
+    /// Lifecycle events are subscribed before this returns, so none sent in
+    /// answer to the first message can be missed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SessionEvent};
+    /// # use futures::StreamExt;
+    /// # use serde_json::json;
+    /// # async fn example(client: Notif) -> notifsh::Result<()> {
+    /// let mut session = client.open_session("agents.reviewer.sessions").await?;
+    /// session.send(json!({"prompt": "Review the open PR"})).await?;
+    ///
+    /// while let Some(event) = session.next().await {
+    ///     match event? {
+    ///         SessionEvent::Output(event) => println!("{}", event.data["text"]),
+    ///         SessionEvent::Failed(event) => eprintln!("failed: {}", event.data),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn open_session(&self, prefix: &str) -> Result<Session> {
+        let id = format!("sess_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+        let pattern = Topic::parse(&format!("{}.{}.*", prefix, id))?;
+        let inner = self
+            .subscribe_with_options(&[pattern.as_str()], SubscribeOptions::new().from("latest"))
+            .await?;
+        Ok(Session {
+            client: self.clone(),
+            prefix: prefix.to_string(),
+            id,
+            inner,
+            done: false,
+        })
+    }
+}
+
+impl Session {
+    /// The session ID, the topic segment after the prefix.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The topic `<prefix>.<id>.<kind>` of this session.
+    pub fn topic(&self, kind: &str) -> String {
+        format!("{}.{}.{}", self.prefix, self.id, kind)
+    }
+
+    /// Send a message to whoever serves the session.
+    pub async fn send<T: Serialize>(&self, data: T) -> Result<EmitResponse> {
+        self.client.emit(&self.topic(MESSAGE_KIND), data).await
+    }
+
+    /// Ask whoever serves the session to stop.
+    ///
+    /// The session keeps yielding events until the other side reports it
+    /// completed or failed.
+    pub async fn cancel(&self) -> Result<EmitResponse> {
+        self.client
+            .emit(&self.topic(CANCEL_KIND), serde_json::json!({}))
+            .await
+    }
+
+    /// Close the subscription (see `EventStream::close`).
+    pub async fn close(self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+impl Stream for Session {
+    type Item = Result<SessionEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+            let event = match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => event,
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            // Our own messages and cancellations arrive on the same pattern
+            if let Some(event) = SessionEvent::from_event(event) {
+                self.done = event.is_terminal();
+                return Poll::Ready(Some(Ok(event)));
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("prefix", &self.prefix)
+            .field("id", &self.id)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,428 @@
+//! Several event streams sharing one WebSocket connection.
+//!
+//! The server holds a single subscription per connection, so a shared
+//! subscription subscribes once to the union of its topics and routes each
+//! event locally to every stream whose patterns match it.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::stats::StreamStats;
+use crate::subscribe::{Command, ControlEvents, EventStream, TypedEventStream};
+use crate::topic::Topic;
+use crate::types::{
+    AckMessage, AckRequest, ConnectionState, ControlEvent, Event, SubscribeOptions,
+};
+
+/// One WebSocket connection feeding several [`EventStream`]s.
+///
+/// Created with [`Notif::subscribe_shared`]. Each call to
+/// [`SharedSubscription::stream`] returns a stream of the shared events
+/// matching its own patterns.
+///
+/// An event matching several streams is delivered to each. With
+/// `auto_ack(false)` it is acked on the server once every copy is acked,
+/// or nacked if any copy is nacked; copies held by a stream that closes
+/// count as acked. Events matching no stream are acked right away.
+///
+/// Streams are fed in turn, so a stream that stops reading holds back the
+/// others once its buffer is full. The connection closes when the
+/// subscription and every stream are dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # use notifsh::Notif;
+/// # use futures::StreamExt;
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let shared = client.subscribe_shared(&["agents.>", "tasks.>"]).await?;
+///
+/// let mut status = shared.stream(&["agents.*.status"])?;
+/// let mut tasks = shared.stream(&["tasks.>"])?;
+///
+/// tokio::spawn(async move {
+///     while let Some(event) = status.next().await {
+///         println!("status: {:?}", event);
+///     }
+/// });
+/// while let Some(event) = tasks.next().await {
+///     println!("task: {:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SharedSubscription {
+    route_tx: mpsc::UnboundedSender<Route>,
+    buffer_size: usize,
+    /// State of the shared connection, reported by every stream.
+    state_rx: watch::Receiver<ConnectionState>,
+}
+
+impl Notif {
+    /// Open one connection subscribed to `topics` for several local streams.
+    pub async fn subscribe_shared(&self, topics: &[&str]) -> Result<SharedSubscription> {
+        self.subscribe_shared_with_options(topics, SubscribeOptions::new())
+            .await
+    }
+
+    /// Open one connection for several local streams, with custom options.
+    ///
+    /// The options apply to the shared connection, so every stream has the
+    /// same `auto_ack`, `group` and starting position.
+    pub async fn subscribe_shared_with_options(
+        &self,
+        topics: &[&str],
+        options: SubscribeOptions,
+    ) -> Result<SharedSubscription> {
+        let buffer_size = options.buffer_size;
+        let mut source = self.subscribe_with_options(topics, options).await?;
+        let control = source.control_events();
+        let state_rx = source.watch_state();
+        let (route_tx, route_rx) = mpsc::unbounded_channel();
+        let (inbox_tx, inbox_rx) = mpsc::unbounded_channel();
+
+        let router = Router {
+            source,
+            control,
+            route_rx,
+            inbox_tx,
+            inbox_rx,
+            routes: Vec::new(),
+            next_route: 0,
+            pending: HashMap::new(),
+        };
+        tokio::spawn(router.run());
+
+        Ok(SharedSubscription {
+            route_tx,
+            buffer_size,
+            state_rx,
+        })
+    }
+}
+
+impl SharedSubscription {
+    /// Open a stream of the shared events whose topics match `patterns`.
+    ///
+    /// Patterns outside the shared topics never match anything.
+    pub fn stream(&self, patterns: &[&str]) -> Result<EventStream> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Topic::parse(pattern))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (event_tx, event_rx) = mpsc::channel(self.buffer_size);
+        let (ack_tx, ack_rx) = mpsc::channel(100);
+        let (control_tx, control_rx) = broadcast::channel(16);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let stats = StreamStats::default();
+        let route = Route {
+            id: 0,
+            patterns,
+            event_tx,
+            ack_tx: ack_tx.clone(),
+            control_tx: control_tx.clone(),
+            channels: Some((ack_rx, command_rx)),
+            stats: stats.clone(),
+        };
+        self.route_tx
+            .send(route)
+            .map_err(|_| NotifError::connection("shared subscription has ended"))?;
+
+        Ok(EventStream::from_parts(
+            event_rx,
+            ack_tx,
+            control_tx,
+            control_rx,
+            command_tx,
+            self.state_rx.clone(),
+            stats,
+        ))
+    }
+
+    /// Open a stream of the shared events matching `patterns`, decoding
+    /// every payload into `T`.
+    pub fn stream_typed<T: DeserializeOwned>(
+        &self,
+        patterns: &[&str],
+    ) -> Result<TypedEventStream<T>> {
+        Ok(TypedEventStream::new(self.stream(patterns)?))
+    }
+}
+
+/// A local stream fed by the router.
+struct Route {
+    id: u64,
+    patterns: Vec<Topic>,
+    event_tx: mpsc::Sender<Result<Event>>,
+    /// Handed to the copies delivered to this stream.
+    ack_tx: mpsc::Sender<AckRequest>,
+    control_tx: broadcast::Sender<ControlEvent>,
+    /// Taken by the task relaying the stream's acks and close.
+    channels: Option<(mpsc::Receiver<AckRequest>, mpsc::UnboundedReceiver<Command>)>,
+    /// Counters behind the stream's `EventStream::stats`.
+    stats: StreamStats,
+}
+
+impl Route {
+    fn matches(&self, topic: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.matches(topic))
+    }
+}
+
+/// Messages from a route's relay task, tagged with the route id.
+enum RouteMessage {
+    Settle(u64, AckRequest),
+    Closed(u64),
+}
+
+/// A source event whose copies are not all settled yet.
+struct Pending {
+    event: Event,
+    /// Routes holding a copy that has not been settled.
+    awaiting: HashSet<u64>,
+    /// Still being handed out; not settled before every copy is out.
+    delivering: bool,
+    /// Set by the first nack, with its retry delay.
+    nack: Option<Option<Duration>>,
+}
+
+/// Background task fanning the shared stream out to the local streams.
+struct Router {
+    source: EventStream,
+    control: ControlEvents,
+    route_rx: mpsc::UnboundedReceiver<Route>,
+    inbox_tx: mpsc::UnboundedSender<RouteMessage>,
+    inbox_rx: mpsc::UnboundedReceiver<RouteMessage>,
+    routes: Vec<Route>,
+    next_route: u64,
+    pending: HashMap<String, Pending>,
+}
+
+impl Router {
+    async fn run(mut self) {
+        let mut accepting = true;
+
+        loop {
+            tokio::select! {
+                item = self.source.next() => match item {
+                    Some(item) => self.route(item).await,
+                    None => break,
+                },
+                route = self.route_rx.recv(), if accepting => match route {
+                    Some(route) => self.add(route),
+                    None => accepting = false,
+                },
+                Some(message) = self.inbox_rx.recv() => self.handle(message).await,
+                Some(change) = self.control.next() => {
+                    for route in &self.routes {
+                        let _ = route.control_tx.send(change.clone());
+                    }
+                }
+            }
+
+            // The handle is gone and so is the last stream
+            if !accepting && self.routes.is_empty() {
+                break;
+            }
+        }
+        // Dropping the routes ends the local streams
+        let _ = self.source.close().await;
+    }
+
+    fn add(&mut self, mut route: Route) {
+        self.next_route += 1;
+        route.id = self.next_route;
+
+        if let Some((ack_rx, command_rx)) = route.channels.take() {
+            let inbox = self.inbox_tx.clone();
+            let stats = route.stats.clone();
+            tokio::spawn(relay(route.id, ack_rx, command_rx, inbox, stats));
+        }
+        self.routes.push(route);
+    }
+
+    async fn route(&mut self, item: Result<Event>) {
+        let event = match item {
+            Ok(event) => event,
+            Err(e) => {
+                // Errors concern the shared connection, so every stream sees them
+                let message = e.to_string();
+                for index in 0..self.routes.len() {
+                    let error = NotifError::connection(message.clone());
+                    self.deliver(index, Err(error)).await;
+                }
+                return;
+            }
+        };
+
+        let manual = event.ack_tx.is_some();
+        let id = event.id.clone();
+        if manual {
+            self.pending.insert(
+                id.clone(),
+                Pending {
+                    event: event.clone(),
+                    awaiting: HashSet::new(),
+                    delivering: true,
+                    nack: None,
+                },
+            );
+        }
+
+        let mut index = 0;
+        while index < self.routes.len() {
+            let route = &self.routes[index];
+            if route.matches(&event.topic) {
+                let route_id = route.id;
+                let mut copy = event.clone();
+                copy.ack_tx = manual.then(|| route.ack_tx.clone());
+                route.stats.record_received(&copy);
+                if let Some(pending) = self.pending.get_mut(&id) {
+                    pending.awaiting.insert(route_id);
+                }
+                if !self.deliver(index, Ok(copy)).await {
+                    if let Some(pending) = self.pending.get_mut(&id) {
+                        pending.awaiting.remove(&route_id);
+                    }
+                }
+            }
+            index += 1;
+        }
+
+        if let Some(pending) = self.pending.get_mut(&id) {
+            pending.delivering = false;
+        }
+        self.finish(&id, None).await;
+    }
+
+    /// Hand `item` to the route at `index`, relaying acks while it is full
+    /// so a consumer awaiting an ack cannot stall the router.
+    ///
+    /// Returns whether the item was delivered.
+    async fn deliver(&mut self, index: usize, item: Result<Event>) -> bool {
+        let Some(route) = self.routes.get(index) else {
+            return false;
+        };
+        let event_tx = route.event_tx.clone();
+        loop {
+            tokio::select! {
+                permit = event_tx.reserve() => {
+                    let Ok(permit) = permit else { return false };
+                    permit.send(item);
+                    return true;
+                }
+                Some(message) = self.inbox_rx.recv() => self.handle(message).await,
+            }
+        }
+    }
+
+    async fn handle(&mut self, message: RouteMessage) {
+        match message {
+            RouteMessage::Settle(route_id, request) => {
+                let AckRequest { msg, reply } = request;
+                let Some(pending) = self.pending.get_mut(msg.id()) else {
+                    let _ = reply.send(Err(NotifError::connection(format!(
+                        "event {} was already settled",
+                        msg.id()
+                    ))));
+                    return;
+                };
+                if !pending.awaiting.remove(&route_id) {
+                    let _ = reply.send(Ok(()));
+                    return;
+                }
+                if let AckMessage::Nack { retry_in, .. } = &msg {
+                    pending.nack.get_or_insert(*retry_in);
+                }
+                self.finish(msg.id(), Some(reply)).await;
+            }
+            RouteMessage::Closed(route_id) => {
+                self.routes.retain(|route| route.id != route_id);
+                let ids: Vec<String> = self
+                    .pending
+                    .iter_mut()
+                    .filter_map(|(id, pending)| {
+                        pending.awaiting.remove(&route_id).then(|| id.clone())
+                    })
+                    .collect();
+                for id in ids {
+                    self.finish(&id, None).await;
+                }
+            }
+        }
+    }
+
+    /// Settle the source event once every copy is settled.
+    async fn finish(&mut self, id: &str, reply: Option<oneshot::Sender<Result<()>>>) {
+        let done = self
+            .pending
+            .get(id)
+            .is_some_and(|pending| !pending.delivering && pending.awaiting.is_empty());
+        let pending = if done { self.pending.remove(id) } else { None };
+
+        let result = match pending {
+            Some(Pending {
+                event,
+                nack: Some(retry_in),
+                ..
+            }) => event.nack(retry_in).await,
+            Some(pending) => pending.event.ack().await,
+            None => Ok(()),
+        };
+        if let Some(reply) = reply {
+            let _ = reply.send(result);
+        }
+    }
+}
+
+/// Forward a stream's acks and close to the router.
+async fn relay(
+    id: u64,
+    mut ack_rx: mpsc::Receiver<AckRequest>,
+    mut command_rx: mpsc::UnboundedReceiver<Command>,
+    inbox: mpsc::UnboundedSender<RouteMessage>,
+    stats: StreamStats,
+) {
+    let settle = |request: AckRequest| {
+        match request.msg {
+            AckMessage::Ack { .. } => stats.record_acked(),
+            AckMessage::Nack { .. } => stats.record_nacked(),
+        }
+        let _ = inbox.send(RouteMessage::Settle(id, request));
+    };
+    loop {
+        tokio::select! {
+            Some(request) = ack_rx.recv() => settle(request),
+            command = command_rx.recv() => {
+                // The connection is shared, so a single stream cannot change it
+                match command {
+                    Some(Command::Pause(_)) => continue,
+                    Some(Command::ChangeTopics { done, .. }) => {
+                        let _ = done.send(Err(NotifError::Forbidden(
+                            "topics of a shared subscription are fixed".to_string(),
+                        )));
+                        continue;
+                    }
+                    _ => {}
+                }
+                // Acks sent before the close still count
+                while let Ok(request) = ack_rx.try_recv() {
+                    settle(request);
+                }
+                let _ = inbox.send(RouteMessage::Closed(id));
+                if let Some(Command::Close { done: Some(done), .. }) = command {
+                    let _ = done.send(());
+                }
+                break;
+            }
+        }
+    }
+}
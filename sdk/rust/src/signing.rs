@@ -0,0 +1,254 @@
+//! Ed25519 signatures on emitted events (`signing` feature).
+//!
+//! A client built with `NotifBuilder::signing_key` signs the topic and
+//! payload of every emit and attaches the signature in headers.
+//! Consumers check it with `Event::verify` against [`TrustedKeys`],
+//! configured locally or loaded from the well-known [`KEYS_TOPIC`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde_json::{json, Value};
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::interceptor::OutgoingEvent;
+use crate::state_cache::StateCache;
+use crate::types::{EmitResponse, Event};
+
+/// Topic public keys are published on with `Notif::publish_signing_key`,
+/// keyed by key ID.
+pub const KEYS_TOPIC: &str = "_keys.signing";
+
+const SIGNATURE_HEADER: &str = "notif-signature";
+const SIGNER_HEADER: &str = "notif-signer";
+const SIGNED_AT_HEADER: &str = "notif-signed-at";
+
+/// Bumped if the signed bytes ever change.
+const SIGNATURE_VERSION: &str = "notif-signature-v1";
+
+/// An Ed25519 key events are signed with.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{Notif, SigningKey};
+///
+/// # fn load_pkcs8() -> Vec<u8> { unimplemented!() }
+/// let key = SigningKey::from_pkcs8("approver-1", &load_pkcs8())?;
+/// let client = Notif::builder("nsh_...").signing_key(key).build()?;
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+#[derive(Clone)]
+pub struct SigningKey {
+    key_id: String,
+    pair: Arc<Ed25519KeyPair>,
+}
+
+impl SigningKey {
+    /// Generate a new key pair, returned as a PKCS#8 document to store
+    /// and load with `from_pkcs8`.
+    pub fn generate_pkcs8() -> Result<Vec<u8>> {
+        Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map(|document| document.as_ref().to_vec())
+            .map_err(|_| NotifError::InvalidSignature("failed to generate a key".to_string()))
+    }
+
+    /// Load a key pair from a PKCS#8 document, identified as `key_id`.
+    pub fn from_pkcs8(key_id: impl Into<String>, pkcs8: &[u8]) -> Result<Self> {
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|e| NotifError::InvalidSignature(format!("invalid PKCS#8 key: {e}")))?;
+        Ok(Self {
+            key_id: key_id.into(),
+            pair: Arc::new(pair),
+        })
+    }
+
+    /// ID consumers look the public key up by.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// The 32-byte public key, to hand to consumers.
+    pub fn public_key(&self) -> &[u8] {
+        self.pair.public_key().as_ref()
+    }
+
+    /// Sign `event`, adding the signature headers.
+    pub(crate) fn sign(&self, event: &mut OutgoingEvent) -> Result<()> {
+        let signed_at = Utc::now().to_rfc3339();
+        let message = message(&event.topic, &signed_at, &event.data)?;
+        let signature = self.pair.sign(&message);
+        let headers = &mut event.headers;
+        headers.insert(SIGNER_HEADER.to_string(), self.key_id.clone());
+        headers.insert(SIGNED_AT_HEADER.to_string(), signed_at);
+        headers.insert(
+            SIGNATURE_HEADER.to_string(),
+            STANDARD.encode(signature.as_ref()),
+        );
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Public keys events may be signed with, by key ID.
+///
+/// Add keys from configuration with `key`, or follow the keys published
+/// on [`KEYS_TOPIC`] with `from_topic`. Anyone who may emit to that topic
+/// can add a trusted key, so restrict it to your signers' API keys.
+#[derive(Clone, Default)]
+pub struct TrustedKeys {
+    keys: HashMap<String, Vec<u8>>,
+    published: Option<Arc<StateCache>>,
+}
+
+impl TrustedKeys {
+    /// Trust no key yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust `public_key` for signatures by `key_id`.
+    pub fn key(mut self, key_id: impl Into<String>, public_key: impl Into<Vec<u8>>) -> Self {
+        self.keys.insert(key_id.into(), public_key.into());
+        self
+    }
+
+    /// Also trust the keys published on [`KEYS_TOPIC`], following new
+    /// ones as they are published.
+    pub async fn from_topic(mut self, client: &Notif) -> Result<Self> {
+        let cache = StateCache::builder(client, KEYS_TOPIC).start().await?;
+        self.published = Some(Arc::new(cache));
+        Ok(self)
+    }
+
+    fn get(&self, key_id: &str) -> Option<Vec<u8>> {
+        if let Some(key) = self.keys.get(key_id) {
+            return Some(key.clone());
+        }
+        let published = self.published.as_ref()?.get(key_id)?;
+        STANDARD.decode(published["public_key"].as_str()?).ok()
+    }
+}
+
+impl fmt::Debug for TrustedKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrustedKeys")
+            .field("keys", &self.keys.keys().collect::<Vec<_>>())
+            .field("from_topic", &self.published.is_some())
+            .finish()
+    }
+}
+
+impl Notif {
+    /// Publish the public half of `key` on [`KEYS_TOPIC`], for consumers
+    /// using `TrustedKeys::from_topic`.
+    pub async fn publish_signing_key(&self, key: &SigningKey) -> Result<EmitResponse> {
+        let public_key = STANDARD.encode(key.public_key());
+        self.publish_state(
+            KEYS_TOPIC,
+            key.key_id(),
+            json!({ "public_key": public_key }),
+        )
+        .await
+    }
+}
+
+impl Event {
+    /// Check that the event was signed by one of `keys` and that its topic
+    /// and payload were not changed since.
+    ///
+    /// Returns the ID of the key that signed it. Fails with
+    /// `NotifError::InvalidSignature` if the event is unsigned, signed by
+    /// an unknown key, or tampered with.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use futures::StreamExt;
+    /// use notifsh::{Notif, TrustedKeys};
+    ///
+    /// # async fn example(client: Notif, approver_key: Vec<u8>) -> notifsh::Result<()> {
+    /// let keys = TrustedKeys::new().key("approver-1", approver_key);
+    /// let mut responses = client.subscribe(&["claude.permission.response"]).await?;
+    /// while let Some(event) = responses.next().await {
+    ///     let event = event?;
+    ///     if event.verify(&keys).is_err() {
+    ///         continue; // not from a trusted approver
+    ///     }
+    ///     println!("decision: {:?}", event.data);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn verify(&self, keys: &TrustedKeys) -> Result<String> {
+        let header = |name: &str| {
+            self.headers
+                .get(name)
+                .ok_or_else(|| NotifError::InvalidSignature("event is not signed".to_string()))
+        };
+        let key_id = header(SIGNER_HEADER)?;
+        let signed_at = header(SIGNED_AT_HEADER)?;
+        let signature = STANDARD
+            .decode(header(SIGNATURE_HEADER)?)
+            .map_err(|e| NotifError::InvalidSignature(format!("malformed signature: {e}")))?;
+
+        let public_key = keys
+            .get(key_id)
+            .ok_or_else(|| NotifError::InvalidSignature(format!("unknown key '{key_id}'")))?;
+        let message = message(&self.topic, signed_at, &self.data)?;
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(&message, &signature)
+            .map_err(|_| {
+                NotifError::InvalidSignature(format!("signature by '{key_id}' does not match"))
+            })?;
+        Ok(key_id.clone())
+    }
+
+    /// When the event was signed, if it is.
+    pub fn signed_at(&self) -> Option<DateTime<Utc>> {
+        let signed_at = self.headers.get(SIGNED_AT_HEADER)?;
+        DateTime::parse_from_rfc3339(signed_at)
+            .ok()
+            .map(|signed_at| signed_at.with_timezone(&Utc))
+    }
+}
+
+/// The bytes signed for an event.
+fn message(topic: &str, signed_at: &str, data: &Value) -> Result<Vec<u8>> {
+    let mut message = format!("{SIGNATURE_VERSION}\n{topic}\n{signed_at}\n").into_bytes();
+    serde_json::to_writer(&mut message, &canonical(data))?;
+    Ok(message)
+}
+
+/// `value` with object members sorted by key, so the signed bytes do not
+/// depend on the order they were received in.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(members) => {
+            let mut sorted: Vec<_> = members.iter().collect();
+            sorted.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                sorted
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonical(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,143 @@
+//! Emitting through the `futures::Sink` interface.
+
+use std::fmt;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_util::future::BoxFuture;
+use futures_util::stream::FuturesUnordered;
+use futures_util::{Sink, StreamExt};
+use serde::Serialize;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+use crate::types::{EmitOptions, EmitResponse};
+
+impl Notif {
+    /// A [`Sink`] of `(topic, data)` pairs, each emitted as an event.
+    ///
+    /// Forward an existing stream of domain events into notif.sh with
+    /// `StreamExt::forward` or `SinkExt::send_all`. Emits are retried per
+    /// the client's `RetryPolicy`, and the sink applies backpressure: it
+    /// accepts a new item only while fewer than `concurrency` emits are
+    /// in flight (default: 1, which keeps them in order).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # use futures::StreamExt;
+    /// # use serde_json::json;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let orders = futures::stream::iter(1..=100)
+    ///     .map(|id| Ok(("orders.created".to_string(), json!({"order_id": id}))));
+    ///
+    /// orders.forward(client.emit_sink().concurrency(8)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn emit_sink(&self) -> EmitSink {
+        EmitSink {
+            client: self.clone(),
+            options: EmitOptions::new(),
+            concurrency: 1,
+            in_flight: FuturesUnordered::new(),
+            emitted: 0,
+        }
+    }
+}
+
+/// Emits every `(topic, data)` pair sent into it, created by
+/// [`Notif::emit_sink`].
+///
+/// A failed emit is reported by the next `poll_ready`, `poll_flush` or
+/// `poll_close`, and ends a `forward`. Flush or close the sink to wait
+/// for the emits still in flight.
+pub struct EmitSink {
+    client: Notif,
+    options: EmitOptions,
+    concurrency: usize,
+    in_flight: FuturesUnordered<BoxFuture<'static, Result<EmitResponse>>>,
+    emitted: u64,
+}
+
+impl EmitSink {
+    /// Allow up to `limit` emits in flight at once (at least one).
+    ///
+    /// Above one, events may reach the server out of order.
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Emit every item with `options`, e.g. common headers.
+    pub fn options(mut self, options: EmitOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Events the server has accepted so far.
+    pub fn emitted(&self) -> u64 {
+        self.emitted
+    }
+
+    /// Wait for one emit in flight to finish.
+    fn poll_one(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<()>>> {
+        match ready!(self.in_flight.poll_next_unpin(cx)) {
+            Some(Ok(_)) => {
+                self.emitted += 1;
+                Poll::Ready(Some(Ok(())))
+            }
+            Some(Err(e)) => Poll::Ready(Some(Err(e))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl<T: Serialize> Sink<(String, T)> for EmitSink {
+    type Error = NotifError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        while this.in_flight.len() >= this.concurrency {
+            if let Some(Err(e)) = ready!(this.poll_one(cx)) {
+                return Poll::Ready(Err(e));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, (topic, data): (String, T)) -> Result<()> {
+        let this = self.get_mut();
+        let data = serde_json::to_value(data)?;
+        let client = this.client.clone();
+        let options = this.options.clone();
+        this.in_flight.push(Box::pin(async move {
+            client.emit_with_options(&topic, data, options).await
+        }));
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        while let Some(done) = ready!(this.poll_one(cx)) {
+            done?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        <Self as Sink<(String, T)>>::poll_flush(self, cx)
+    }
+}
+
+impl fmt::Debug for EmitSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmitSink")
+            .field("concurrency", &self.concurrency)
+            .field("in_flight", &self.in_flight.len())
+            .field("emitted", &self.emitted)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,243 @@
+//! Posting formatted events to a chat incoming webhook, shared by the
+//! Slack and Discord sinks.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use reqwest::StatusCode;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::error::{NotifError, Result};
+use crate::retry::{self, RetryPolicy};
+use crate::topic::Topic;
+use crate::types::Event;
+
+/// Turns an event into the text of a message.
+pub(crate) type Formatter = Arc<dyn Fn(&Event) -> String + Send + Sync>;
+
+/// What differs between chat services.
+pub(crate) struct Service {
+    pub(crate) name: &'static str,
+    /// Longest message the service accepts, in characters.
+    pub(crate) max_len: usize,
+    /// The request body posting `text`.
+    pub(crate) body: fn(&str) -> Value,
+}
+
+/// Routes events to messages and posts them to one webhook.
+#[derive(Clone)]
+pub(crate) struct Chat {
+    service: &'static Service,
+    webhook_url: String,
+    routes: Vec<(String, Formatter)>,
+    interval: Duration,
+    max_batch: usize,
+    retry: RetryPolicy,
+    http: reqwest::Client,
+}
+
+impl Chat {
+    pub(crate) fn new(service: &'static Service, webhook_url: String) -> Self {
+        Self {
+            service,
+            webhook_url,
+            routes: Vec::new(),
+            interval: Duration::from_secs(1),
+            max_batch: 10,
+            retry: RetryPolicy::exponential(3),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub(crate) fn route(&mut self, pattern: &str, formatter: Formatter) {
+        self.routes.push((pattern.to_string(), formatter));
+    }
+
+    pub(crate) fn interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    pub(crate) fn max_batch(&mut self, max_batch: usize) {
+        self.max_batch = max_batch.max(1);
+    }
+
+    pub(crate) fn retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Post messages for the events of `stream` until it ends.
+    pub(crate) async fn run<S>(&self, mut stream: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        let routes = self
+            .routes
+            .iter()
+            .map(|(pattern, formatter)| Ok((Topic::parse(pattern)?, formatter)))
+            .collect::<Result<Vec<_>>>()?;
+        let format = |event: &Event| {
+            routes
+                .iter()
+                .find(|(pattern, _)| pattern.matches(&event.topic))
+                .map(|(_, formatter)| formatter(event))
+        };
+
+        let mut batch: Vec<(String, Event)> = Vec::new();
+        let mut next_post = Instant::now();
+        loop {
+            tokio::select! {
+                event = stream.next(), if batch.len() < self.max_batch => match event {
+                    Some(Ok(event)) => match format(&event) {
+                        Some(text) => batch.push((text, event)),
+                        None => {
+                            let _ = event.ack().await;
+                        }
+                    },
+                    // The stream recovers from these by itself
+                    Some(Err(_)) => {}
+                    None => break,
+                },
+                _ = tokio::time::sleep_until(next_post), if !batch.is_empty() => {
+                    self.flush(&mut batch).await?;
+                    next_post = Instant::now() + self.interval;
+                }
+            }
+        }
+        if !batch.is_empty() {
+            tokio::time::sleep_until(next_post).await;
+            self.flush(&mut batch).await?;
+        }
+        Ok(())
+    }
+
+    /// Post `batch` as few messages as fit, then settle its events.
+    async fn flush(&self, batch: &mut Vec<(String, Event)>) -> Result<()> {
+        let texts: Vec<&str> = batch.iter().map(|(text, _)| text.as_str()).collect();
+        let mut posted = true;
+        for message in join(&texts, self.service.max_len) {
+            match self.post(&message).await {
+                Ok(()) => {}
+                // Redelivered later; the rest of the batch with it
+                Err(e) if e.is_retryable() => {
+                    posted = false;
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        for (_, event) in batch.drain(..) {
+            let _ = if posted {
+                event.ack().await
+            } else {
+                event.nack(None).await
+            };
+        }
+        Ok(())
+    }
+
+    /// Post one message, retrying per the policy.
+    pub(crate) async fn post(&self, text: &str) -> Result<()> {
+        let body = (self.service.body)(text);
+        let mut attempt = 0;
+        loop {
+            let can_retry = attempt < self.retry.max_retries();
+            let delay = match self.http.post(&self.webhook_url).json(&body).send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) if can_retry && retry::is_retryable_status(response.status()) => {
+                    retry::retry_after(&response).unwrap_or_else(|| self.retry.delay(attempt))
+                }
+                Ok(response) => return Err(self.rejected(response).await),
+                Err(e) if can_retry && retry::is_retryable_error(&e) => self.retry.delay(attempt),
+                Err(e) if retry::is_retryable_error(&e) => {
+                    return Err(NotifError::connection(format!(
+                        "{}: {e}",
+                        self.service.name
+                    )));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// The error for a post the service did not accept.
+    async fn rejected(&self, response: reqwest::Response) -> NotifError {
+        let status = response.status();
+        let retry_after = retry::retry_after(&response);
+        let message = format!(
+            "{}: {}",
+            self.service.name,
+            response.text().await.unwrap_or_default()
+        );
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => NotifError::RateLimited {
+                retry_after,
+                message,
+            },
+            StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                NotifError::Config(format!("webhook rejected ({status}): {message}"))
+            }
+            _ => NotifError::Api {
+                status: status.as_u16(),
+                message,
+            },
+        }
+    }
+}
+
+impl fmt::Debug for Chat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(self.service.name)
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|(pattern, _)| pattern)
+                    .collect::<Vec<_>>(),
+            )
+            .field("interval", &self.interval)
+            .field("max_batch", &self.max_batch)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Join `texts` with newlines into as few messages of at most `max_len`
+/// characters as possible, truncating any text too long on its own.
+fn join(texts: &[&str], max_len: usize) -> Vec<String> {
+    let mut messages: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    for text in texts {
+        let text = truncate(text, max_len);
+        let len = text.chars().count();
+        if current_len > 0 && current_len + 1 + len > max_len {
+            messages.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        if current_len > 0 {
+            current.push('\n');
+            current_len += 1;
+        }
+        current.push_str(&text);
+        current_len += len;
+    }
+    if current_len > 0 {
+        messages.push(current);
+    }
+    messages
+}
+
+/// `text`, cut to `max_len` characters with an ellipsis if longer.
+fn truncate(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_len.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
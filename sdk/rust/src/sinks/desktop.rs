@@ -0,0 +1,279 @@
+//! Showing events as native desktop notifications (`desktop` feature).
+//!
+//! A [`DesktopSink`] shows a notification for every event of a stream,
+//! through the notifier of the OS with [`notify_rust`]: the D-Bus
+//! notification service on Linux and the BSDs, Notification Center on
+//! macOS and toasts on Windows.
+//!
+//! By default, events in the `{"title", "body", "level"}` shape the desktop
+//! hub emits are shown as is, with the level mapped to an [`Urgency`];
+//! routes format other events.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::sinks::desktop::{DesktopNotification, DesktopSink, Urgency};
+//! use notifsh::Notif;
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let sink = DesktopSink::new("deploys")
+//!     .route("deploys.failed", |event| {
+//!         DesktopNotification::new("Deploy failed", event.data["service"].to_string())
+//!             .urgency(Urgency::Critical)
+//!     })
+//!     .on_click(|event| println!("opening {}", event.data["url"]));
+//!
+//! let stream = client.subscribe(&["desktop.hub.notify", "deploys.*"]).await?;
+//! sink.run(stream).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use futures_util::{Stream, StreamExt};
+use serde_json::Value;
+
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::Event;
+
+/// Action the notification service reports when the notification is
+/// clicked.
+const CLICK_ACTION: &str = "default";
+
+type Formatter = Arc<dyn Fn(&Event) -> DesktopNotification + Send + Sync>;
+
+type ClickHandler = Arc<dyn Fn(Event) + Send + Sync>;
+
+/// How insistently a notification is shown.
+///
+/// Linux desktops keep critical notifications until dismissed and may
+/// skip the popup of low ones; Windows maps urgencies to toast scenarios.
+/// macOS ignores them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Urgency {
+    /// Informational.
+    Low,
+    /// The default.
+    #[default]
+    Normal,
+    /// Needs attention now.
+    Critical,
+}
+
+impl Urgency {
+    /// The urgency for a log-style level: `error`, `critical` and `fatal`
+    /// are critical; `warn` and `warning` normal; anything else low.
+    pub fn from_level(level: &str) -> Self {
+        match level.to_ascii_lowercase().as_str() {
+            "error" | "critical" | "fatal" => Self::Critical,
+            "warn" | "warning" => Self::Normal,
+            _ => Self::Low,
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn native(self) -> notify_rust::Urgency {
+        match self {
+            Self::Low => notify_rust::Urgency::Low,
+            Self::Normal => notify_rust::Urgency::Normal,
+            Self::Critical => notify_rust::Urgency::Critical,
+        }
+    }
+}
+
+/// A notification to show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DesktopNotification {
+    /// The bold first line.
+    pub title: String,
+    /// The text under the title.
+    pub body: String,
+    /// How insistently it is shown.
+    pub urgency: Urgency,
+}
+
+impl DesktopNotification {
+    /// A notification with normal urgency.
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            urgency: Urgency::Normal,
+        }
+    }
+
+    /// Set the urgency.
+    pub fn urgency(mut self, urgency: Urgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+}
+
+impl From<&Event> for DesktopNotification {
+    /// The event's `title`, `body` (or `message`) and `level` fields,
+    /// falling back to the topic as title and the payload as body.
+    fn from(event: &Event) -> Self {
+        let text = |key: &str| event.data.get(key).and_then(Value::as_str);
+        let body = match text("body").or_else(|| text("message")) {
+            Some(body) => body.to_string(),
+            None => event.data.to_string(),
+        };
+        Self {
+            title: text("title").unwrap_or(&event.topic).to_string(),
+            body,
+            urgency: text("level").map(Urgency::from_level).unwrap_or_default(),
+        }
+    }
+}
+
+/// Shows a desktop notification for every event of a stream.
+///
+/// With manual acks, events are acked once their notification is shown.
+#[derive(Clone)]
+pub struct DesktopSink {
+    app_name: String,
+    routes: Vec<(String, Formatter)>,
+    on_click: Option<ClickHandler>,
+}
+
+impl DesktopSink {
+    /// Show notifications as coming from the application `app_name`.
+    pub fn new(app_name: impl Into<String>) -> Self {
+        Self {
+            app_name: app_name.into(),
+            routes: Vec::new(),
+            on_click: None,
+        }
+    }
+
+    /// Format events whose topic matches `pattern` with `formatter`.
+    ///
+    /// Routes are tried in the order they were added; events no route
+    /// matches are shown with the default mapping, the `From<&Event>`
+    /// implementation of [`DesktopNotification`].
+    pub fn route<F>(mut self, pattern: &str, formatter: F) -> Self
+    where
+        F: Fn(&Event) -> DesktopNotification + Send + Sync + 'static,
+    {
+        self.routes.push((pattern.to_string(), Arc::new(formatter)));
+        self
+    }
+
+    /// Call `handler` with the event of a notification the user clicks.
+    ///
+    /// Clicks are reported by the D-Bus notification service of Linux and
+    /// the BSDs only; elsewhere the handler is never called.
+    pub fn on_click<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.on_click = Some(Arc::new(handler));
+        self
+    }
+
+    /// Show notifications for the events of `stream` until it ends.
+    ///
+    /// Fails if a route pattern is invalid or the OS notifier is
+    /// unavailable or fails.
+    pub async fn run<S>(&self, mut stream: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        let routes = self
+            .routes
+            .iter()
+            .map(|(pattern, formatter)| Ok((Topic::parse(pattern)?, formatter)))
+            .collect::<Result<Vec<_>>>()?;
+
+        while let Some(event) = stream.next().await {
+            // The stream recovers from errors by itself
+            let Ok(event) = event else { continue };
+            let notification = match routes
+                .iter()
+                .find(|(pattern, _)| pattern.matches(&event.topic))
+            {
+                Some((_, formatter)) => formatter(&event),
+                None => DesktopNotification::from(&event),
+            };
+            self.notify(&notification, Some(&event)).await?;
+            let _ = event.ack().await;
+        }
+        Ok(())
+    }
+
+    /// Show `notification` right away, outside of any stream.
+    pub async fn show(&self, notification: &DesktopNotification) -> Result<()> {
+        self.notify(notification, None).await
+    }
+
+    /// Show `notification`, watching for a click on it if `event` is
+    /// given and a click handler is set.
+    async fn notify(
+        &self,
+        notification: &DesktopNotification,
+        event: Option<&Event>,
+    ) -> Result<()> {
+        let mut native = notify_rust::Notification::new();
+        native
+            .appname(&self.app_name)
+            .summary(&notification.title)
+            .body(&notification.body);
+        #[cfg(not(target_os = "macos"))]
+        native.urgency(notification.urgency.native());
+
+        let clicks = match (event, &self.on_click) {
+            (Some(event), Some(handler)) if cfg!(all(unix, not(target_os = "macos"))) => {
+                native.action(CLICK_ACTION, "Open");
+                Some((event.clone(), handler.clone()))
+            }
+            _ => None,
+        };
+
+        // D-Bus and the OS notification APIs block
+        let handle = tokio::task::spawn_blocking(move || native.show())
+            .await
+            .map_err(|e| NotifError::Io(io::Error::other(e)))?
+            .map_err(|e| {
+                NotifError::Io(io::Error::other(format!(
+                    "desktop notification failed: {e}"
+                )))
+            })?;
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some((event, handler)) = clicks {
+            // Returns once the notification is clicked or dismissed
+            tokio::task::spawn_blocking(move || {
+                handle.wait_for_action(|action| {
+                    if action == CLICK_ACTION {
+                        handler(event);
+                    }
+                })
+            });
+        }
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        let _ = (handle, clicks);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DesktopSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DesktopSink")
+            .field("app_name", &self.app_name)
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|(pattern, _)| pattern)
+                    .collect::<Vec<_>>(),
+            )
+            .field("on_click", &self.on_click.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,389 @@
+//! Emailing events through an SMTP server (`smtp` feature).
+//!
+//! An [`EmailSink`] sends an email rendered from an [`EmailTemplate`] for
+//! every event matching one of its routes. With a digest window, the
+//! events of each route are collected instead and sent as one email per
+//! window, so a burst of failures is one email rather than hundreds.
+//! Messages are built and sent with [`lettre`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use notifsh::sinks::email::{EmailSink, EmailTemplate};
+//! use notifsh::{Notif, SubscribeOptions};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let sink = EmailSink::new("smtps://smtp.example.com", "alerts@example.com")
+//!     .credentials("alerts@example.com", "app-password")
+//!     .route(
+//!         "billing.failed",
+//!         EmailTemplate::new(
+//!             "Payment failed for {{data.customer}}",
+//!             "Invoice {{data.invoice}} could not be charged: {{data.reason}}",
+//!         )
+//!         .to("billing@example.com"),
+//!     )
+//!     .digest(Duration::from_secs(15 * 60));
+//!
+//! let options = SubscribeOptions::new().auto_ack(false);
+//! let stream = client
+//!     .subscribe_with_options(&["billing.failed"], options)
+//!     .await?;
+//! sink.run(stream).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tokio::time::Instant;
+use url::Url;
+
+use super::template::render;
+use crate::error::{NotifError, Result};
+use crate::retry::RetryPolicy;
+use crate::topic::Topic;
+use crate::types::Event;
+
+type Transport = AsyncSmtpTransport<Tokio1Executor>;
+
+/// Longest one delivery may take, from connecting to `QUIT`.
+const SEND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Subject and body of the emails for one route, with its recipients.
+///
+/// Both are plain text in which `{{topic}}`, `{{id}}`, `{{timestamp}}`,
+/// `{{data}}`, `{{data.path.to.field}}` and `{{headers.name}}` are
+/// replaced with the event's values. String fields are inserted as is,
+/// others as JSON, and missing ones as nothing.
+#[derive(Debug, Clone)]
+pub struct EmailTemplate {
+    to: Vec<String>,
+    subject: String,
+    body: String,
+}
+
+impl EmailTemplate {
+    /// A template with no recipients yet; add them with [`to`](Self::to).
+    pub fn new(subject: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            to: Vec::new(),
+            subject: subject.into(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a recipient.
+    pub fn to(mut self, address: impl Into<String>) -> Self {
+        self.to.push(address.into());
+        self
+    }
+}
+
+/// Sends an email for every event matching one of its routes.
+///
+/// With manual acks, events are acked once their email is accepted by the
+/// server, nacked if sending keeps failing, and acked without an email if
+/// no route matches them.
+#[derive(Clone)]
+pub struct EmailSink {
+    server: String,
+    from: String,
+    credentials: Option<(String, String)>,
+    routes: Vec<(String, EmailTemplate)>,
+    digest: Option<Duration>,
+    retry: RetryPolicy,
+}
+
+impl EmailSink {
+    /// Send through the server at `server_url`, from the address `from`.
+    ///
+    /// `smtps://host[:port]` connects over TLS (port 465 by default);
+    /// `smtp://host[:port]` upgrades with `STARTTLS` when the server
+    /// offers it (port 587 by default), so plain local test servers work
+    /// too. Credentials are only ever sent over TLS.
+    pub fn new(server_url: impl Into<String>, from: impl Into<String>) -> Self {
+        Self {
+            server: server_url.into(),
+            from: from.into(),
+            credentials: None,
+            routes: Vec::new(),
+            digest: None,
+            retry: RetryPolicy::exponential(3),
+        }
+    }
+
+    /// Authenticate with `AUTH PLAIN` or `AUTH LOGIN`.
+    ///
+    /// With credentials, `smtp://` servers must offer `STARTTLS`.
+    pub fn credentials(mut self, user: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((user.into(), password.into()));
+        self
+    }
+
+    /// Email events whose topic matches `pattern` with `template`.
+    ///
+    /// Routes are tried in the order they were added; the first match
+    /// wins.
+    pub fn route(mut self, pattern: &str, template: EmailTemplate) -> Self {
+        self.routes.push((pattern.to_string(), template));
+        self
+    }
+
+    /// Collect the events of each route for `window` after the first of
+    /// them, then send them as one email (default: one email per event).
+    ///
+    /// The subject is rendered from the first event, with the number of
+    /// others appended; the body lists every event's rendered body.
+    pub fn digest(mut self, window: Duration) -> Self {
+        self.digest = Some(window);
+        self
+    }
+
+    /// Set how failed deliveries are retried (default: 3 retries).
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send emails for the events of `stream` until it ends, sending any
+    /// pending digest right away when it does.
+    ///
+    /// Fails if the configuration is invalid or the server rejects the
+    /// credentials or an email permanently (a `5xx` reply).
+    pub async fn run<S>(&self, mut stream: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        self.validate()?;
+        let transport = self.transport()?;
+        let routes = self
+            .routes
+            .iter()
+            .map(|(pattern, template)| Ok((Topic::parse(pattern)?, template)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut digests: Vec<Digest> = routes.iter().map(|_| Digest::default()).collect();
+        loop {
+            let due = digests.iter().filter_map(|digest| digest.due).min();
+            tokio::select! {
+                event = stream.next() => match event {
+                    Some(Ok(event)) => {
+                        let route = routes
+                            .iter()
+                            .position(|(pattern, _)| pattern.matches(&event.topic));
+                        match (route, self.digest) {
+                            (Some(route), Some(window)) => digests[route].push(event, window),
+                            (Some(route), None) => self.send(&transport, routes[route].1, vec![event]).await?,
+                            (None, _) => {
+                                let _ = event.ack().await;
+                            }
+                        }
+                    }
+                    // The stream recovers from these by itself
+                    Some(Err(_)) => {}
+                    None => break,
+                },
+                _ = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                    let now = Instant::now();
+                    for (route, digest) in digests.iter_mut().enumerate() {
+                        if digest.due.is_some_and(|due| due <= now) {
+                            self.send(&transport, routes[route].1, digest.take()).await?;
+                        }
+                    }
+                }
+            }
+        }
+        for (route, digest) in digests.iter_mut().enumerate() {
+            if digest.due.is_some() {
+                self.send(&transport, routes[route].1, digest.take())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail early on settings that could only fail every delivery.
+    fn validate(&self) -> Result<()> {
+        let addresses = self.routes.iter().flat_map(|(_, template)| &template.to);
+        for address in std::iter::once(&self.from).chain(addresses) {
+            mailbox(address)?;
+        }
+        if let Some((pattern, _)) = self.routes.iter().find(|(_, t)| t.to.is_empty()) {
+            return Err(NotifError::Config(format!(
+                "email route '{pattern}' has no recipients"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The transport for `smtp://` or `smtps://` server URLs.
+    fn transport(&self) -> Result<Transport> {
+        let url = Url::parse(&self.server)?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| NotifError::Config("SMTP URL has no host".to_string()))?;
+        let parameters = TlsParameters::new(host.to_string())
+            .map_err(|e| NotifError::Config(format!("TLS setup failed: {e}")))?;
+        let (tls, port) = match url.scheme() {
+            "smtps" => (Tls::Wrapper(parameters), 465),
+            // Credentials are never sent in the clear
+            "smtp" if self.credentials.is_some() => (Tls::Required(parameters), 587),
+            "smtp" => (Tls::Opportunistic(parameters), 587),
+            scheme => {
+                return Err(NotifError::Config(format!(
+                    "unsupported SMTP URL scheme '{scheme}'"
+                )))
+            }
+        };
+
+        let mut transport = Transport::builder_dangerous(host)
+            .port(url.port().unwrap_or(port))
+            .tls(tls)
+            .timeout(Some(SEND_TIMEOUT));
+        if let Some((user, password)) = &self.credentials {
+            transport = transport.credentials(Credentials::new(user.clone(), password.clone()));
+        }
+        Ok(transport.build())
+    }
+
+    /// Email `events` with `template`, then settle them.
+    async fn send(
+        &self,
+        transport: &Transport,
+        template: &EmailTemplate,
+        events: Vec<Event>,
+    ) -> Result<()> {
+        let mut subject = render(&template.subject, &events[0]);
+        if events.len() > 1 {
+            subject.push_str(&format!(" (+{} more)", events.len() - 1));
+        }
+        let body = events
+            .iter()
+            .map(|event| render(&template.body, event))
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        let message = message(&self.from, &template.to, &subject, body)?;
+
+        let delivered = match self.deliver(transport, &message).await {
+            Ok(()) => true,
+            // Redelivered later
+            Err(e) if e.is_retryable() => false,
+            Err(e) => return Err(e),
+        };
+        for event in events {
+            let _ = if delivered {
+                event.ack().await
+            } else {
+                event.nack(None).await
+            };
+        }
+        Ok(())
+    }
+
+    /// Hand `message` to the server, retrying per the policy.
+    async fn deliver(&self, transport: &Transport, message: &Message) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            let result =
+                match tokio::time::timeout(SEND_TIMEOUT, transport.send(message.clone())).await {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err(e)) => Err(smtp_error(e)),
+                    Err(_) => Err(NotifError::timeout(format!(
+                        "SMTP delivery took over {SEND_TIMEOUT:?}"
+                    ))),
+                };
+            match result {
+                Err(e) if e.is_retryable() && attempt < self.retry.max_retries() => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for EmailSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmailSink")
+            .field("server", &self.server)
+            .field("from", &self.from)
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|(pattern, _)| pattern)
+                    .collect::<Vec<_>>(),
+            )
+            .field("digest", &self.digest)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The events of one route waiting for their digest.
+#[derive(Default)]
+struct Digest {
+    events: Vec<Event>,
+    due: Option<Instant>,
+}
+
+impl Digest {
+    fn push(&mut self, event: Event, window: Duration) {
+        // A redelivery of an event still waiting here
+        if self.events.iter().any(|pending| pending.id == event.id) {
+            return;
+        }
+        self.due.get_or_insert_with(|| Instant::now() + window);
+        self.events.push(event);
+    }
+
+    fn take(&mut self) -> Vec<Event> {
+        self.due = None;
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Parse an email address, with or without a display name.
+fn mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|_| NotifError::Config(format!("invalid email address '{address}'")))
+}
+
+/// A plain text message from `from` to `to`.
+fn message(from: &str, to: &[String], subject: &str, body: String) -> Result<Message> {
+    let from = mailbox(from)?;
+    let domain = from.email.domain().to_string();
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(subject)
+        .message_id(Some(format!("<{}@{domain}>", uuid::Uuid::new_v4())))
+        .header(ContentType::TEXT_PLAIN);
+    for address in to {
+        builder = builder.to(mailbox(address)?);
+    }
+    builder
+        .body(body)
+        .map_err(|e| NotifError::Config(format!("invalid email: {e}")))
+}
+
+/// A `4xx` reply or a failed connection is transient and retryable; a
+/// `5xx` reply or a client-side failure fails with `NotifError::Smtp`.
+fn smtp_error(e: lettre::transport::smtp::Error) -> NotifError {
+    if e.is_permanent() || e.is_client() || e.is_response() {
+        NotifError::Smtp(e.to_string())
+    } else {
+        NotifError::connection(format!("SMTP {e}"))
+    }
+}
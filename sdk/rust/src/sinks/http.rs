@@ -0,0 +1,285 @@
+//! Forwarding events to an HTTP endpoint.
+//!
+//! A [`Forwarder`] POSTs every matching event of a stream to one URL, as
+//! the same JSON body notif.sh webhooks deliver (`id`, `topic`, `data`
+//! and `timestamp`), with headers rendered per event. Failed requests are
+//! retried, and a circuit breaker stops calling an endpoint that keeps
+//! failing, nacking events until it has had time to recover.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::sinks::http::Forwarder;
+//! use notifsh::{Notif, SubscribeOptions};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let forwarder = Forwarder::new("https://billing.internal/hooks/orders")
+//!     .matching("orders.>")
+//!     .header("Authorization", "Bearer s3cr3t")
+//!     .header("X-Order-Id", "{{data.order_id}}");
+//!
+//! let options = SubscribeOptions::new().auto_ack(false);
+//! let stream = client.subscribe_with_options(&["orders.>"], options).await?;
+//! forwarder.run(stream).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::json;
+use tokio::time::Instant;
+
+use super::template::render;
+use crate::error::{NotifError, Result};
+use crate::retry::{self, RetryPolicy};
+use crate::topic::Topic;
+use crate::types::Event;
+
+/// POSTs events to an HTTP endpoint.
+///
+/// With manual acks, events are acked once the endpoint answers with a
+/// 2xx status and nacked otherwise, so the server redelivers them or
+/// moves them to the dead letter queue. Events matching none of the
+/// patterns are acked without a request.
+#[derive(Clone)]
+pub struct Forwarder {
+    url: String,
+    patterns: Vec<String>,
+    headers: Vec<(String, String)>,
+    data_only: bool,
+    timeout: Duration,
+    retry: RetryPolicy,
+    failure_threshold: u32,
+    cooldown: Duration,
+    http: reqwest::Client,
+}
+
+/// How one event's delivery ended.
+enum Outcome {
+    Delivered,
+    /// The endpoint refused the event itself, e.g. with `400`.
+    Rejected,
+    /// The endpoint is unreachable or failing, even after retries.
+    Failed,
+}
+
+impl Forwarder {
+    /// Forward events to `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            patterns: Vec::new(),
+            headers: Vec::new(),
+            data_only: false,
+            timeout: Duration::from_secs(10),
+            retry: RetryPolicy::exponential(3),
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Only forward events whose topic matches `pattern`; may be called
+    /// several times. Without patterns, every event is forwarded.
+    pub fn matching(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.to_string());
+        self
+    }
+
+    /// Send the header `name` with every request.
+    ///
+    /// `value` may hold `{{topic}}`, `{{id}}`, `{{timestamp}}`, `{{data}}`,
+    /// `{{data.path.to.field}}` and `{{headers.name}}` placeholders, filled
+    /// in from each event.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// POST only the payload of events instead of the full envelope.
+    pub fn data_only(mut self, data_only: bool) -> Self {
+        self.data_only = data_only;
+        self
+    }
+
+    /// Give up on a request after `timeout` (default: 10s).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how failed requests are retried (default: 3 retries, honoring
+    /// `Retry-After`).
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Stop calling the endpoint for `cooldown` after `failures` events in
+    /// a row failed (default: 5 failures, 30s).
+    ///
+    /// While the circuit is open, events are nacked to be redelivered
+    /// once it closes. The first event after the cooldown is a trial: if
+    /// it fails too, the circuit opens again right away.
+    pub fn circuit_breaker(mut self, failures: u32, cooldown: Duration) -> Self {
+        self.failure_threshold = failures.max(1);
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Forward the events of `stream` until it ends.
+    ///
+    /// Fails if the URL, a pattern or a header name is invalid; delivery
+    /// failures only nack events.
+    pub async fn run<S>(&self, mut stream: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        url::Url::parse(&self.url)?;
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|pattern| Topic::parse(pattern))
+            .collect::<Result<Vec<_>>>()?;
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| NotifError::Config(format!("invalid header name '{name}'")))?;
+                Ok((name, value.as_str()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut breaker = Breaker::new(self.failure_threshold, self.cooldown);
+        while let Some(event) = stream.next().await {
+            // The stream recovers from errors by itself
+            let Ok(event) = event else { continue };
+            if !patterns.is_empty() && !patterns.iter().any(|p| p.matches(&event.topic)) {
+                let _ = event.ack().await;
+                continue;
+            }
+            if let Some(remaining) = breaker.open_for() {
+                let _ = event.nack(Some(remaining)).await;
+                continue;
+            }
+
+            let rendered = headers
+                .iter()
+                .filter_map(|(name, template)| {
+                    let value = render(template, &event).replace(['\r', '\n'], " ");
+                    Some((name.clone(), HeaderValue::from_str(&value).ok()?))
+                })
+                .collect::<HeaderMap>();
+            let _ = match self.deliver(&event, rendered).await {
+                Outcome::Delivered => {
+                    breaker.record(true);
+                    event.ack().await
+                }
+                Outcome::Rejected => {
+                    breaker.record(true);
+                    event.nack(None).await
+                }
+                Outcome::Failed => {
+                    breaker.record(false);
+                    event.nack(breaker.open_for()).await
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// POST `event`, retrying per the policy.
+    async fn deliver(&self, event: &Event, headers: HeaderMap) -> Outcome {
+        let body = if self.data_only {
+            event.data.clone()
+        } else {
+            json!({
+                "id": event.id,
+                "topic": event.topic,
+                "data": event.data,
+                "timestamp": event.timestamp,
+            })
+        };
+        let mut attempt = 0;
+        loop {
+            let request = self
+                .http
+                .post(&self.url)
+                .headers(headers.clone())
+                .timeout(self.timeout)
+                .json(&body);
+            let retryable = match request.send().await {
+                Ok(response) if response.status().is_success() => return Outcome::Delivered,
+                Ok(response) if retry::is_retryable_status(response.status()) => {
+                    Some(retry::retry_after(&response))
+                }
+                Ok(_) => return Outcome::Rejected,
+                Err(e) if e.is_timeout() || retry::is_retryable_error(&e) => Some(None),
+                Err(_) => None,
+            };
+            let Some(retry_after) = retryable.filter(|_| attempt < self.retry.max_retries()) else {
+                return Outcome::Failed;
+            };
+            tokio::time::sleep(retry_after.unwrap_or_else(|| self.retry.delay(attempt))).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl fmt::Debug for Forwarder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Header values may hold credentials
+        f.debug_struct("Forwarder")
+            .field("url", &self.url)
+            .field("patterns", &self.patterns)
+            .field(
+                "headers",
+                &self.headers.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .field("data_only", &self.data_only)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Counts consecutive failures and opens after too many of them.
+struct Breaker {
+    threshold: u32,
+    cooldown: Duration,
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            failures: 0,
+            open_until: None,
+        }
+    }
+
+    /// How much longer the circuit stays open, if it is.
+    fn open_for(&self) -> Option<Duration> {
+        self.open_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn record(&mut self, success: bool) {
+        if success {
+            self.failures = 0;
+            self.open_until = None;
+        } else {
+            self.failures += 1;
+            if self.failures >= self.threshold {
+                self.open_until = Some(Instant::now() + self.cooldown);
+            }
+        }
+    }
+}
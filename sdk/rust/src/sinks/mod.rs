@@ -0,0 +1,14 @@
+//! Sending received events somewhere else: chat channels, email, the
+//! desktop, HTTP endpoints or local storage.
+
+mod chat;
+#[cfg(feature = "desktop")]
+pub mod desktop;
+pub mod discord;
+#[cfg(feature = "smtp")]
+pub mod email;
+pub mod http;
+pub mod slack;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+mod template;
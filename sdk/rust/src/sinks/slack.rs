@@ -0,0 +1,108 @@
+//! Posting events to a Slack channel through an incoming webhook.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::sinks::slack::SlackSink;
+//! use notifsh::{Notif, SubscribeOptions};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let sink = SlackSink::new("https://hooks.slack.com/services/T000/B000/XXXX")
+//!     .route("alerts.>", |event| {
+//!         format!(":rotating_light: *{}*: {}", event.topic, event.data["message"])
+//!     })
+//!     .route("deploys.*", |event| format!(":rocket: deployed {}", event.data["version"]));
+//!
+//! let options = SubscribeOptions::new().auto_ack(false);
+//! let stream = client
+//!     .subscribe_with_options(&["alerts.>", "deploys.*"], options)
+//!     .await?;
+//! sink.run(stream).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::Stream;
+use serde_json::json;
+
+use super::chat::{Chat, Service};
+use crate::error::Result;
+use crate::retry::RetryPolicy;
+use crate::types::Event;
+
+static SLACK: Service = Service {
+    name: "SlackSink",
+    max_len: 40_000,
+    body: |text| json!({ "text": text }),
+};
+
+/// Posts a message to Slack for every event matching one of its routes.
+///
+/// Messages are posted at most once per interval (default: 1s, Slack's
+/// limit for incoming webhooks); those that pile up meanwhile are
+/// batched into one post, one line each. With manual acks, events are
+/// acked once their message is posted, nacked if posting keeps failing,
+/// and acked without a message if no route matches them.
+#[derive(Debug, Clone)]
+pub struct SlackSink {
+    chat: Chat,
+}
+
+impl SlackSink {
+    /// Post to the incoming webhook at `webhook_url`.
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            chat: Chat::new(&SLACK, webhook_url.into()),
+        }
+    }
+
+    /// Format events whose topic matches `pattern` with `formatter`.
+    ///
+    /// Routes are tried in the order they were added; the first match
+    /// wins. Slack `mrkdwn` is supported in the text.
+    pub fn route<F>(mut self, pattern: &str, formatter: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        self.chat.route(pattern, Arc::new(formatter));
+        self
+    }
+
+    /// Post at most once per `interval` (default: 1s).
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.chat.interval(interval);
+        self
+    }
+
+    /// Combine up to `max_batch` events into one post (default: 10).
+    pub fn max_batch(mut self, max_batch: usize) -> Self {
+        self.chat.max_batch(max_batch);
+        self
+    }
+
+    /// Set how failed posts are retried (default: 3 retries, honoring
+    /// `Retry-After`).
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.chat.retry(retry);
+        self
+    }
+
+    /// Post messages for the events of `stream` until it ends.
+    ///
+    /// Fails if a route pattern is invalid or Slack rejects the webhook,
+    /// e.g. once it is revoked.
+    pub async fn run<S>(&self, stream: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        self.chat.run(stream).await
+    }
+
+    /// Post `text` right away, outside of any stream.
+    pub async fn post(&self, text: &str) -> Result<()> {
+        self.chat.post(text).await
+    }
+}
@@ -0,0 +1,299 @@
+//! Durable local history in SQLite (`sqlite` feature).
+//!
+//! An [`EventSink`] writes received events into the `notif_events` table
+//! in batches, one transaction per batch, with the database in WAL mode
+//! so readers are not blocked by writes. [`EventSink::query`] reads them
+//! back by topic and time range.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use notifsh::sinks::sqlite::{EventQuery, EventSink};
+//! use notifsh::{Notif, SubscribeOptions};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let sink = EventSink::open("history.db").await?;
+//!
+//! // Latest 50 agent events, e.g. to render at startup
+//! for event in sink.query(EventQuery::new().topic("agent.*").limit(50)).await? {
+//!     println!("{} {}: {}", event.timestamp, event.topic, event.data);
+//! }
+//!
+//! // Record everything from now on, acking events once they are stored
+//! let options = SubscribeOptions::new().auto_ack(false);
+//! let stream = client.subscribe_with_options(&["agent.>"], options).await?;
+//! sink.run(stream).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqliteSynchronous};
+use sqlx::Row;
+use tokio::time::MissedTickBehavior;
+
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::Event;
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS notif_events (
+    id TEXT PRIMARY KEY,
+    topic TEXT NOT NULL,
+    data TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    attempt INTEGER NOT NULL
+)";
+
+const CREATE_INDEX: &str =
+    "CREATE INDEX IF NOT EXISTS notif_events_timestamp ON notif_events (timestamp)";
+
+// A redelivered event keeps its row and records the latest attempt
+const INSERT: &str = "INSERT INTO notif_events (id, topic, data, timestamp, attempt)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+    ON CONFLICT (id) DO UPDATE SET attempt = excluded.attempt";
+
+const SELECT: &str = "SELECT id, topic, data, timestamp, attempt FROM notif_events
+    WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2)
+    ORDER BY timestamp DESC, rowid DESC";
+
+const PRUNE: &str = "DELETE FROM notif_events WHERE timestamp < ?1";
+
+/// Writes received events into a SQLite table.
+///
+/// Events are written by [`run`](EventSink::run), which consumes a
+/// stream, or directly with [`write`](EventSink::write). Cheap to share:
+/// the pool is reference-counted.
+#[derive(Debug, Clone)]
+pub struct EventSink {
+    pool: SqlitePool,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+impl EventSink {
+    /// Open or create the database at `path` in WAL mode and create the
+    /// `notif_events` table if needed.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal);
+        let pool = SqlitePool::connect_with(options).await?;
+        Self::new(pool).await
+    }
+
+    /// Use an existing pool, switching its database to WAL mode and
+    /// creating the `notif_events` table if needed.
+    pub async fn new(pool: SqlitePool) -> Result<Self> {
+        sqlx::query("PRAGMA journal_mode = WAL")
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_TABLE).execute(&pool).await?;
+        sqlx::query(CREATE_INDEX).execute(&pool).await?;
+        Ok(Self {
+            pool,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(1),
+        })
+    }
+
+    /// Set the most events written per transaction (default: 100).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set how long `run` holds a partial batch before writing it
+    /// (default: 1s).
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// The underlying pool, for queries of your own.
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Write `events` in one transaction.
+    pub async fn write(&self, events: &[Event]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for event in events {
+            sqlx::query(INSERT)
+                .bind(&event.id)
+                .bind(&event.topic)
+                .bind(serde_json::to_string(&event.data)?)
+                .bind(timestamp(event.timestamp))
+                .bind(event.attempt)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Write every event of `events` until it ends, returning how many
+    /// were written.
+    ///
+    /// Events are written once `batch_size` have arrived or the flush
+    /// interval passes, and acked after their batch commits, so with
+    /// `auto_ack(false)` an event is only settled once it is stored.
+    /// Errors the stream yields are skipped: it recovers from them by
+    /// itself. A failed write ends the run; its events are not acked.
+    pub async fn run<S>(&self, mut events: S) -> Result<u64>
+    where
+        S: Stream<Item = Result<Event>> + Unpin,
+    {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut written = 0;
+        let mut flush = tokio::time::interval(self.flush_interval);
+        flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(Ok(event)) => {
+                        batch.push(event);
+                        if batch.len() < self.batch_size {
+                            continue;
+                        }
+                    }
+                    Some(Err(_)) => continue,
+                    None => break,
+                },
+                _ = flush.tick() => {
+                    if batch.is_empty() {
+                        continue;
+                    }
+                }
+            }
+            written += self.commit(&mut batch).await?;
+        }
+        written += self.commit(&mut batch).await?;
+        Ok(written)
+    }
+
+    /// Write and ack `batch`, leaving it empty.
+    async fn commit(&self, batch: &mut Vec<Event>) -> Result<u64> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        self.write(batch).await?;
+        for event in batch.iter() {
+            // An event whose ack is lost is redelivered and overwrites its row
+            let _ = event.ack().await;
+        }
+        let written = batch.len() as u64;
+        batch.clear();
+        Ok(written)
+    }
+
+    /// Stored events matching `query`, oldest first.
+    ///
+    /// Events read back carry no headers and cannot be acked.
+    pub async fn query(&self, query: EventQuery) -> Result<Vec<Event>> {
+        let pattern = query.topic.as_deref().map(Topic::parse).transpose()?;
+        let limit = query.limit.unwrap_or(usize::MAX);
+        let mut rows = sqlx::query(SELECT)
+            .bind(query.since.map(timestamp))
+            .bind(query.until.map(timestamp))
+            .fetch(&self.pool);
+
+        let mut events = Vec::new();
+        while events.len() < limit {
+            let Some(row) = rows.try_next().await? else {
+                break;
+            };
+            let topic: String = row.try_get("topic")?;
+            if pattern
+                .as_ref()
+                .is_some_and(|pattern| !pattern.matches(&topic))
+            {
+                continue;
+            }
+            let data: String = row.try_get("data")?;
+            let stored: String = row.try_get("timestamp")?;
+            let attempt: u32 = row.try_get("attempt")?;
+            events.push(Event {
+                id: row.try_get("id")?,
+                topic,
+                data: serde_json::from_str(&data)?,
+                headers: HashMap::new(),
+                timestamp: DateTime::parse_from_rfc3339(&stored)
+                    .map_err(|e| NotifError::Config(format!("invalid stored timestamp: {e}")))?
+                    .with_timezone(&Utc),
+                attempt,
+                max_attempts: attempt,
+                seq: None,
+                ack_tx: None,
+            });
+        }
+        events.reverse();
+        Ok(events)
+    }
+
+    /// Delete events created before `before`, returning how many were
+    /// deleted.
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(PRUNE)
+            .bind(timestamp(before))
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Which stored events `EventSink::query` returns.
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    /// Only return events whose topic matches this pattern.
+    pub topic: Option<String>,
+    /// Only return events created at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only return events created at or before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Return only the most recent events, up to this many.
+    pub limit: Option<usize>,
+}
+
+impl EventQuery {
+    /// Match every stored event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match events on topics matching `pattern`, e.g. `agent.*`.
+    pub fn topic(mut self, pattern: impl Into<String>) -> Self {
+        self.topic = Some(pattern.into());
+        self
+    }
+
+    /// Set the start of the time range.
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Set the end of the time range.
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Keep only the `limit` most recent matches.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// `time` in a fixed-width form that sorts chronologically as text.
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
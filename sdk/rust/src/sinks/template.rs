@@ -0,0 +1,46 @@
+//! `{{...}}` placeholders filled in from an event.
+
+use serde_json::Value;
+
+use crate::types::Event;
+
+/// Replace the `{{...}}` placeholders of `template` with `event`'s values:
+/// `topic`, `id`, `timestamp`, `data`, `data.path.to.field` and
+/// `headers.name`. String fields are inserted as is, others as JSON, and
+/// missing ones as nothing.
+pub(crate) fn render(template: &str, event: &Event) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&field(event, rest[start + 2..start + end].trim()));
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// The value of the placeholder `path` for `event`.
+fn field(event: &Event, path: &str) -> String {
+    let value = match path {
+        "id" => return event.id.clone(),
+        "topic" => return event.topic.clone(),
+        "timestamp" => return event.timestamp.to_rfc3339(),
+        "data" => Some(&event.data),
+        _ => {
+            if let Some(name) = path.strip_prefix("headers.") {
+                return event.headers.get(name).cloned().unwrap_or_default();
+            }
+            path.strip_prefix("data.")
+                .and_then(|path| event.data.pointer(&format!("/{}", path.replace('.', "/"))))
+        }
+    };
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    }
+}
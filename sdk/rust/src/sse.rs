@@ -0,0 +1,169 @@
+//! Server-Sent Events transport for subscriptions.
+//!
+//! The subscribe message is POSTed to `/api/v1/sse` and the response
+//! streams server messages, one JSON object per event `data` field, the
+//! same ones a WebSocket carries in text frames. Client frames such as
+//! acks, nacks and pings are POSTed to `/api/v1/sse/{session}`, where the
+//! session is named by the stream's `X-Session-ID` response header.
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, ACCEPT, CONTENT_TYPE};
+use reqwest::{Client as HttpClient, Response};
+
+use crate::client::NotifInner;
+use crate::error::{NotifError, Result};
+use crate::types::SubscribeMessage;
+
+/// Response header naming the session client frames are sent to.
+const SESSION_HEADER: &str = "x-session-id";
+
+/// Address event streams are opened at.
+pub(crate) fn url(inner: &NotifInner) -> String {
+    format!("{}/api/v1/sse", inner.server)
+}
+
+/// Open an event stream for `message`.
+pub(crate) async fn open(
+    inner: &NotifInner,
+    api_key: &str,
+    message: &SubscribeMessage,
+) -> Result<SseReader> {
+    let request = inner
+        .stream_client
+        .post(url(inner))
+        .headers(inner.default_headers.clone())
+        .header(ACCEPT, "text/event-stream")
+        .bearer_auth(api_key)
+        .json(message);
+    let response = tokio::time::timeout(inner.timeout, request.send())
+        .await
+        .map_err(|_| NotifError::connection("timed out opening event stream"))??;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(NotifError::from_response(
+            status.as_u16(),
+            &body,
+            None,
+            None,
+        ));
+    }
+    let session = response
+        .headers()
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| NotifError::connection("event stream has no session ID"))?
+        .to_string();
+    Ok(SseReader {
+        response,
+        session,
+        buf: Vec::new(),
+        data: None,
+    })
+}
+
+/// Reads the messages of an event stream.
+pub(crate) struct SseReader {
+    response: Response,
+    /// Session the server assigned the stream.
+    session: String,
+    /// Bytes of a line not yet complete.
+    buf: Vec<u8>,
+    /// Data of the event being read.
+    data: Option<String>,
+}
+
+impl SseReader {
+    /// Session client frames for this stream are sent to.
+    pub(crate) fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// The data of the next event, or `None` once the stream ends.
+    pub(crate) async fn next(&mut self) -> Option<Result<String>> {
+        loop {
+            while let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches(['\n', '\r']);
+                if line.is_empty() {
+                    if let Some(data) = self.data.take() {
+                        return Some(Ok(data));
+                    }
+                    continue;
+                }
+                // Other fields and `:` keep-alive comments are ignored
+                let value = match line.strip_prefix("data") {
+                    Some("") => "",
+                    Some(rest) => match rest.strip_prefix(':') {
+                        Some(value) => value.strip_prefix(' ').unwrap_or(value),
+                        None => continue,
+                    },
+                    None => continue,
+                };
+                match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                }
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.buf.extend_from_slice(&chunk),
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+/// Sends client frames for an event stream.
+pub(crate) struct SseWriter {
+    http_client: HttpClient,
+    url: String,
+    api_key: String,
+    timeout: Duration,
+    headers: HeaderMap,
+}
+
+impl SseWriter {
+    /// Address frames to the session the server assigned the stream.
+    pub(crate) fn new(inner: &NotifInner, api_key: &str, session: &str) -> Self {
+        Self {
+            http_client: inner.http_client.clone(),
+            url: format!("{}/api/v1/sse/{}", inner.server, session),
+            api_key: api_key.to_string(),
+            timeout: inner.timeout,
+            headers: inner.default_headers.clone(),
+        }
+    }
+
+    /// Send one JSON frame.
+    pub(crate) async fn send(&self, json: String) -> Result<()> {
+        let response = self
+            .http_client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .bearer_auth(&self.api_key)
+            .timeout(self.timeout)
+            .body(json)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(NotifError::from_response(
+                status.as_u16(),
+                &body,
+                None,
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+//! Last-value caches of keyed state published on a topic.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::filter::FieldPath;
+use crate::patch::{self, JsonPatch};
+use crate::types::{EmitOptions, EmitResponse, SubscribeOptions};
+
+/// Header carrying the key of a value published with `Notif::publish_state`.
+const STATE_KEY_HEADER: &str = "notif-state-key";
+
+/// Key path of values published with `Notif::publish_state`.
+const DEFAULT_KEY_PATH: &str = "headers.notif-state-key";
+
+type Entries = Arc<Mutex<HashMap<String, watch::Sender<Option<Value>>>>>;
+
+impl Notif {
+    /// Publish `value` as the state of `key` on `topic`.
+    ///
+    /// The key travels in the `notif-state-key` header, which a
+    /// [`StateCache`] reads by default. Publishing `null` deletes the key.
+    pub async fn publish_state<T: Serialize>(
+        &self,
+        topic: &str,
+        key: &str,
+        value: T,
+    ) -> Result<EmitResponse> {
+        let options = EmitOptions::new().header(STATE_KEY_HEADER, key);
+        self.emit_with_options(topic, value, options).await
+    }
+
+    /// Publish a JSON Patch against the state of `key` on `topic`.
+    ///
+    /// A [`StateCache`] applies it to the key's current value; patches
+    /// that do not apply are ignored, leaving the value unchanged.
+    pub async fn publish_patch(
+        &self,
+        topic: &str,
+        key: &str,
+        patch: &JsonPatch,
+    ) -> Result<EmitResponse> {
+        let options = patch::mark(EmitOptions::new().header(STATE_KEY_HEADER, key));
+        self.emit_with_options(topic, patch, options).await
+    }
+}
+
+/// Builds a [`StateCache`], created by [`StateCache::builder`].
+pub struct StateCacheBuilder {
+    client: Notif,
+    topic: String,
+    key_path: String,
+}
+
+impl StateCache {
+    /// Cache the state published on `topic`.
+    pub fn builder(client: &Notif, topic: impl Into<String>) -> StateCacheBuilder {
+        StateCacheBuilder {
+            client: client.clone(),
+            topic: topic.into(),
+            key_path: DEFAULT_KEY_PATH.to_string(),
+        }
+    }
+}
+
+impl StateCacheBuilder {
+    /// Key values by another event field, named the way filter expressions
+    /// do, such as `data.name` (default: the `publish_state` key header).
+    ///
+    /// Events without the field are ignored. An invalid path makes `start`
+    /// fail with `NotifError::InvalidKeyPath`.
+    pub fn key_path(mut self, path: impl Into<String>) -> Self {
+        self.key_path = path.into();
+        self
+    }
+
+    /// Subscribe from the beginning of the topic and keep the latest value
+    /// of every key.
+    ///
+    /// The cache fills up as the history is delivered, so right after this
+    /// returns it may not have every key yet; use [`StateCache::watch`] to
+    /// wait for one.
+    pub async fn start(self) -> Result<StateCache> {
+        let path = FieldPath::parse(&self.key_path)?;
+        let mut stream = self
+            .client
+            .subscribe_with_options(
+                &[self.topic.as_str()],
+                SubscribeOptions::new().from("beginning"),
+            )
+            .await?;
+
+        let entries: Entries = Arc::default();
+        let cached = entries.clone();
+        let task = tokio::spawn(async move {
+            while let Some(event) = stream.next().await {
+                let Ok(event) = event else {
+                    continue;
+                };
+                let Some(key) = path.key(&event) else {
+                    continue;
+                };
+                let mut entries = cached.lock().unwrap();
+                let value = if event.is_patch() {
+                    // Patch a copy of the current value, or null for a new key
+                    let mut value = entries
+                        .get(&key)
+                        .and_then(|entry| entry.borrow().clone())
+                        .unwrap_or_default();
+                    if event.apply_to(&mut value).is_err() {
+                        continue;
+                    }
+                    value
+                } else {
+                    event.data
+                };
+                let value = Some(value).filter(|value| !value.is_null());
+                match entries.get(&key) {
+                    Some(entry) => {
+                        entry.send_replace(value);
+                    }
+                    None => {
+                        if value.is_some() {
+                            entries.insert(key, watch::channel(value).0);
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(StateCache {
+            topic: self.topic,
+            entries,
+            task,
+        })
+    }
+}
+
+impl fmt::Debug for StateCacheBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateCacheBuilder")
+            .field("topic", &self.topic)
+            .field("key_path", &self.key_path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The latest value of every key published on a topic.
+///
+/// Dropping it closes the subscription.
+///
+/// # Example
+///
+/// ```no_run
+/// use notifsh::{Notif, StateCache};
+/// use serde_json::json;
+///
+/// # async fn example(client: Notif) -> notifsh::Result<()> {
+/// client
+///     .publish_state("agents.status", "reviewer", json!({"status": "busy"}))
+///     .await?;
+///
+/// let cache = StateCache::builder(&client, "agents.status").start().await?;
+/// let mut reviewer = cache.watch("reviewer");
+/// while reviewer.changed().await.is_ok() {
+///     println!("reviewer is now {:?}", *reviewer.borrow());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct StateCache {
+    topic: String,
+    entries: Entries,
+    task: JoinHandle<()>,
+}
+
+impl StateCache {
+    /// The latest value of `key`, or `None` if it was never published or
+    /// was deleted.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(key).and_then(|entry| entry.borrow().clone())
+    }
+
+    /// Watch the value of `key` change, starting from the current one.
+    ///
+    /// The value is `None` while the key is unknown or deleted.
+    pub fn watch(&self, key: &str) -> watch::Receiver<Option<Value>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .entry(key.to_string())
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    /// Every key that currently has a value, with its value.
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .filter_map(|(key, entry)| Some((key.clone(), entry.borrow().clone()?)))
+            .collect()
+    }
+}
+
+impl Drop for StateCache {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for StateCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateCache")
+            .field("topic", &self.topic)
+            .field("keys", &self.snapshot().len())
+            .finish_non_exhaustive()
+    }
+}
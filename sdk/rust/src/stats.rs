@@ -0,0 +1,179 @@
+//! Per-subscription counters behind `EventStream::stats`.
+
+use std::fmt;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::types::Event;
+
+/// Marks `last_event_at` and `lag` as not yet known.
+const UNSET: i64 = i64::MIN;
+
+/// Live counters of a subscription, returned by `EventStream::stats`.
+///
+/// A cheap handle onto counters the subscription keeps updating: clone it
+/// into a UI task and read it whenever convenient, even after the stream
+/// has been moved or dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # use notifsh::Notif;
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::from_env()?;
+/// let stream = client.subscribe(&["orders.*"]).await?;
+///
+/// let stats = stream.stats();
+/// tokio::spawn(async move {
+///     loop {
+///         println!("{} received, lag {:?}", stats.received(), stats.lag());
+///         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct StreamStats {
+    /// Counters of each subscription behind the stream; records go to the
+    /// first.
+    sources: Arc<[Arc<Counters>]>,
+}
+
+impl Default for StreamStats {
+    fn default() -> Self {
+        Self {
+            sources: Arc::new([Arc::new(Counters::default())]),
+        }
+    }
+}
+
+struct Counters {
+    received: AtomicU64,
+    acked: AtomicU64,
+    nacked: AtomicU64,
+    redeliveries: AtomicU64,
+    /// Timestamp of the last event, in milliseconds since the epoch.
+    last_event_ms: AtomicI64,
+    /// Delay between the last event's creation and its receipt, in
+    /// milliseconds.
+    lag_ms: AtomicI64,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            received: AtomicU64::new(0),
+            acked: AtomicU64::new(0),
+            nacked: AtomicU64::new(0),
+            redeliveries: AtomicU64::new(0),
+            last_event_ms: AtomicI64::new(UNSET),
+            lag_ms: AtomicI64::new(UNSET),
+        }
+    }
+}
+
+impl StreamStats {
+    /// Sum the counters of several streams, as merged by
+    /// `Notif::subscribe_many`.
+    pub(crate) fn merged(parts: &[StreamStats]) -> Self {
+        Self {
+            sources: parts
+                .iter()
+                .flat_map(|part| part.sources.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// Events received, including redeliveries.
+    pub fn received(&self) -> u64 {
+        self.sum(|counters| &counters.received)
+    }
+
+    /// Acks sent, whether by the consumer or for events settled locally
+    /// (filtered out, duplicates).
+    pub fn acked(&self) -> u64 {
+        self.sum(|counters| &counters.acked)
+    }
+
+    /// Nacks sent.
+    pub fn nacked(&self) -> u64 {
+        self.sum(|counters| &counters.nacked)
+    }
+
+    /// Received events that were delivered before (`Event::attempt` above 1).
+    pub fn redeliveries(&self) -> u64 {
+        self.sum(|counters| &counters.redeliveries)
+    }
+
+    /// Creation time of the last event received.
+    pub fn last_event_at(&self) -> Option<DateTime<Utc>> {
+        let (ms, _) = self.latest()?;
+        DateTime::from_timestamp_millis(ms)
+    }
+
+    /// Estimated lag: how long the last event took from being created to
+    /// being received. Grows while replaying history or catching up after
+    /// a disconnect.
+    pub fn lag(&self) -> Option<Duration> {
+        let (_, lag_ms) = self.latest()?;
+        Some(Duration::from_millis(lag_ms.max(0) as u64))
+    }
+
+    fn sum(&self, counter: impl Fn(&Counters) -> &AtomicU64) -> u64 {
+        self.sources
+            .iter()
+            .map(|counters| counter(counters).load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Timestamp and lag of the newest event across sources.
+    fn latest(&self) -> Option<(i64, i64)> {
+        self.sources
+            .iter()
+            .map(|counters| {
+                (
+                    counters.last_event_ms.load(Ordering::Relaxed),
+                    counters.lag_ms.load(Ordering::Relaxed),
+                )
+            })
+            .filter(|(ms, lag_ms)| *ms != UNSET && *lag_ms != UNSET)
+            .max_by_key(|(ms, _)| *ms)
+    }
+
+    pub(crate) fn record_received(&self, event: &Event) {
+        let counters = &self.sources[0];
+        counters.received.fetch_add(1, Ordering::Relaxed);
+        if event.attempt > 1 {
+            counters.redeliveries.fetch_add(1, Ordering::Relaxed);
+        }
+        let created = event.timestamp.timestamp_millis();
+        counters.last_event_ms.store(created, Ordering::Relaxed);
+        let lag = Utc::now().timestamp_millis() - created;
+        counters.lag_ms.store(lag.max(0), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_acked(&self) {
+        self.sources[0].acked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_nacked(&self) {
+        self.sources[0].nacked.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for StreamStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamStats")
+            .field("received", &self.received())
+            .field("acked", &self.acked())
+            .field("nacked", &self.nacked())
+            .field("redeliveries", &self.redeliveries())
+            .field("last_event_at", &self.last_event_at())
+            .field("lag", &self.lag())
+            .finish()
+    }
+}
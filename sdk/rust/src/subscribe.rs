@@ -1,27 +1,218 @@
-//! WebSocket subscription implementation.
+//! Subscription implementation, over a WebSocket or Server-Sent Events.
 
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, Stream, StreamExt};
-use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use serde::de::DeserializeOwned;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::time::Instant;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
 
+use crate::chunk::Reassembler;
 use crate::client::NotifInner;
+use crate::dedupe::Dedupe;
+use crate::dropped::{DropHandler, DropReason, DroppedEvent};
 use crate::error::{NotifError, Result};
+use crate::filter::Filter;
+use crate::frames::{FrameDirection, Tap};
+use crate::journal::{self, EventJournal};
+#[cfg(feature = "msgpack")]
+use crate::msgpack;
+use crate::ordering::KeyOrder;
+use crate::sample::Sample;
+use crate::sequence::{self, SequenceWatch};
+use crate::sse::{self, SseReader, SseWriter};
+use crate::stats::StreamStats;
+use crate::telemetry;
+use crate::topic::Topic;
+use crate::transport::Socket;
 use crate::types::{
-    AckMessage, AckWireMessage, Event, NackWireMessage, ServerMessage, SubscribeMessage,
-    SubscribeOptions, SubscribeOptionsWire,
+    AckMessage, AckRequest, AckWireMessage, BackpressurePolicy, ConnectionState, ControlEvent,
+    Event, EventCursor, FilteredPolicy, NackWireMessage, PingMessage, ServerMessage,
+    SubscribeMessage, SubscribeOptions, SubscribeOptionsWire, SubscribeTransport, TypedEvent,
 };
 
+pub(crate) type WsStream = WebSocketStream<MaybeTlsStream<Socket>>;
+type WsSink = SplitSink<WsStream, Message>;
+
+/// An open subscription connection.
+struct Link {
+    conn: Conn,
+    /// Copies frames for `NotifBuilder::debug_frames`.
+    tap: Tap,
+}
+
+/// The connection of a `Link`, over either transport.
+enum Conn {
+    Ws(WsStream),
+    Sse(SseReader, SseWriter),
+}
+
+impl Link {
+    fn split(self) -> (LinkWriter, LinkReader) {
+        let (write, read) = match self.conn {
+            Conn::Ws(ws) => {
+                let (write, read) = ws.split();
+                (WriteHalf::Ws(write), ReadHalf::Ws(read))
+            }
+            Conn::Sse(read, write) => (WriteHalf::Sse(write), ReadHalf::Sse(read)),
+        };
+        let writer = LinkWriter {
+            half: write,
+            tap: self.tap.clone(),
+        };
+        let reader = LinkReader {
+            half: read,
+            tap: self.tap,
+        };
+        (writer, reader)
+    }
+}
+
+/// A frame received from the server.
+enum Frame {
+    Text(String),
+    #[cfg(feature = "msgpack")]
+    Binary(Vec<u8>),
+    /// Control frames and anything else without a server message.
+    Other,
+}
+
+struct LinkReader {
+    half: ReadHalf,
+    tap: Tap,
+}
+
+enum ReadHalf {
+    Ws(SplitStream<WsStream>),
+    Sse(SseReader),
+}
+
+impl LinkReader {
+    /// The next frame, or `None` once the server closed the WebSocket.
+    async fn next(&mut self) -> Option<Result<Frame>> {
+        let frame = match &mut self.half {
+            ReadHalf::Ws(read) => match read.next().await? {
+                Ok(Message::Text(text)) => Some(Ok(Frame::Text(text))),
+                #[cfg(feature = "msgpack")]
+                Ok(Message::Binary(bytes)) => Some(Ok(Frame::Binary(bytes))),
+                Ok(Message::Close(_)) => None,
+                Ok(_) => Some(Ok(Frame::Other)),
+                Err(e) => Some(Err(NotifError::websocket(e.to_string()))),
+            },
+            ReadHalf::Sse(read) => match read.next().await {
+                Some(data) => Some(data.map(Frame::Text)),
+                None => Some(Err(NotifError::connection("event stream closed by server"))),
+            },
+        };
+        match &frame {
+            Some(Ok(Frame::Text(text))) => self.tap.text(FrameDirection::Inbound, text),
+            #[cfg(feature = "msgpack")]
+            Some(Ok(Frame::Binary(bytes))) => self.tap.binary(FrameDirection::Inbound, bytes),
+            _ => {}
+        }
+        frame
+    }
+}
+
+struct LinkWriter {
+    half: WriteHalf,
+    tap: Tap,
+}
+
+enum WriteHalf {
+    Ws(WsSink),
+    Sse(SseWriter),
+}
+
+impl LinkWriter {
+    /// Send a JSON client frame.
+    async fn send(&mut self, json: String) -> Result<()> {
+        self.tap.text(FrameDirection::Outbound, &json);
+        match &mut self.half {
+            WriteHalf::Ws(write) => write
+                .send(Message::Text(json))
+                .await
+                .map_err(|e| NotifError::websocket(e.to_string())),
+            WriteHalf::Sse(write) => write.send(json).await,
+        }
+    }
+
+    /// Close the connection; an event stream closes once its reader drops.
+    async fn close(&mut self) {
+        if let WriteHalf::Ws(write) = &mut self.half {
+            let _ = write.send(Message::Close(None)).await;
+            let _ = write.close().await;
+        }
+    }
+}
+
+/// Capacity of the control event channel.
+pub(crate) const CONTROL_CHANNEL_SIZE: usize = 16;
+
+/// A local predicate added with `EventStream::filter_topics` or `filter_data`.
+type LocalFilter = Box<dyn Fn(&Event) -> bool + Send + Sync>;
+
 /// A stream of events from a subscription.
 ///
 /// Implements `futures::Stream<Item = Result<Event>>`.
+///
+/// If the connection drops, the stream reconnects with exponential backoff
+/// and resubscribes without ending (see `SubscribeOptions::reconnect`).
 pub struct EventStream {
     event_rx: mpsc::Receiver<Result<Event>>,
     #[allow(dead_code)]
-    ack_tx: mpsc::Sender<AckMessage>,
+    ack_tx: mpsc::Sender<AckRequest>,
+    control_tx: broadcast::Sender<ControlEvent>,
+    /// Receiver created before the first `Assigned`, handed out once.
+    control_rx: Option<broadcast::Receiver<ControlEvent>>,
+    command_tx: mpsc::UnboundedSender<Command>,
+    closed: bool,
+    /// Position of the last event handed to the consumer.
+    cursor: Option<EventCursor>,
+    /// Predicates every yielded event must pass.
+    filters: Vec<LocalFilter>,
+    /// How events rejected by `filters` are settled.
+    filtered: FilteredPolicy,
+    state_rx: watch::Receiver<ConnectionState>,
+    /// Told about events a typed stream cannot decode.
+    on_drop: DropHandler,
+    /// Counters shared with the background task.
+    stats: StreamStats,
+    /// Set by `EventStream::pause`.
+    paused: bool,
+    /// Highest sequence number yielded so far.
+    seq_tx: watch::Sender<Option<u64>>,
+    /// Stripped from event topics, for `Notif::namespaced`.
+    topic_prefix: Option<String>,
+    /// Records every yielded event, if set, through the journal's writer task.
+    journal: Option<mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+/// Requests from an `EventStream` to its background task.
+pub(crate) enum Command {
+    /// Flush acks, optionally wait for in-flight events, then close the socket.
+    Close {
+        grace: Option<Duration>,
+        done: Option<oneshot::Sender<()>>,
+    },
+    /// Stop or start reading from the connection again.
+    Pause(bool),
+    /// Subscribe again with `add` and without `remove`.
+    ChangeTopics {
+        add: Vec<String>,
+        remove: Vec<String>,
+        done: oneshot::Sender<Result<()>>,
+    },
 }
 
 impl EventStream {
@@ -31,163 +222,1392 @@ impl EventStream {
         topics: &[&str],
         options: SubscribeOptions,
     ) -> Result<Self> {
-        // Convert HTTP URL to WebSocket URL
-        let ws_url = inner
-            .server
-            .replace("https://", "wss://")
-            .replace("http://", "ws://");
-        let ws_url = format!("{}/ws?token={}", ws_url, inner.api_key);
-
-        // Connect to WebSocket
-        let (ws_stream, _) = connect_async(&ws_url)
-            .await
-            .map_err(|e| NotifError::websocket(format!("connection failed: {}", e)))?;
-
-        let (mut write, mut read) = ws_stream.split();
-
-        // Send subscribe message
-        let subscribe_msg = SubscribeMessage {
-            action: "subscribe".to_string(),
-            topics: topics.iter().map(|s| s.to_string()).collect(),
-            options: Some(SubscribeOptionsWire {
-                auto_ack: options.auto_ack,
-                from: options.from.clone(),
-                group: options.group.clone(),
-            }),
+        let topics = parse_topics(topics)?;
+        let filter = options.filter.as_deref().map(Filter::parse).transpose()?;
+        let sample = options.sample.map(Sample::new).transpose()?;
+        let order = match &options.ordering_key_path {
+            Some(path) if !options.auto_ack => Some(KeyOrder::new(path)?),
+            _ => None,
+        };
+        let (link, assigned) = open(&inner, &topics, &options, options.from.clone()).await?;
+
+        // Create channels for events, acks and lifecycle notifications.
+        // Events are buffered in the worker, which applies the backpressure
+        // policy, so the channel only holds the next one.
+        let (event_tx, event_rx) = mpsc::channel::<Result<Event>>(1);
+        let (ack_tx, ack_rx) = mpsc::channel::<AckRequest>(100);
+        let (control_tx, control_rx) = broadcast::channel(CONTROL_CHANNEL_SIZE);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let consumer_id = match &assigned {
+            ControlEvent::Assigned { consumer_id, .. } => consumer_id.clone(),
+            _ => None,
         };
+        let _ = control_tx.send(assigned);
+        let (state_tx, state_rx) = watch::channel(ConnectionState::Connected);
+        let connection_id = inner.connections.register();
+        inner
+            .connections
+            .update(connection_id, ConnectionState::Connected);
+        let stats = StreamStats::default();
+
+        let worker = Worker {
+            ack_tx_for_events: if options.auto_ack {
+                None
+            } else {
+                Some(ack_tx.clone())
+            },
+            pacer: Pacer::new(options.max_rate),
+            resume_after: options.resume_after.clone(),
+            filter,
+            sample,
+            order,
+            dedupe: options.dedupe_window.map(Dedupe::new),
+            state_tx,
+            connection_id,
+            stats: stats.clone(),
+            paused: false,
+            last_seen: options.from.is_none().then(Utc::now),
+            chunks: Reassembler::new(inner.max_chunks),
+            inner,
+            topics,
+            options,
+            event_tx,
+            ack_rx,
+            control_tx: control_tx.clone(),
+            consumer_id,
+            command_rx,
+            in_flight: HashMap::new(),
+            chunk_parts: HashMap::new(),
+            buffer: VecDeque::new(),
+        };
+
+        let on_drop = worker.inner.on_drop.clone();
+
+        // Spawn background task to handle server messages
+        tokio::spawn(worker.run(link));
+
+        let mut stream = Self::from_parts(
+            event_rx, ack_tx, control_tx, control_rx, command_tx, state_rx, stats,
+        );
+        stream.on_drop = on_drop;
+        Ok(stream)
+    }
 
-        let msg_json = serde_json::to_string(&subscribe_msg)?;
-        write
-            .send(Message::Text(msg_json))
+    /// Build a stream around the channels of its background task: the
+    /// WebSocket worker, a shared subscription's router or the in-memory
+    /// test broker.
+    pub(crate) fn from_parts(
+        event_rx: mpsc::Receiver<Result<Event>>,
+        ack_tx: mpsc::Sender<AckRequest>,
+        control_tx: broadcast::Sender<ControlEvent>,
+        control_rx: broadcast::Receiver<ControlEvent>,
+        command_tx: mpsc::UnboundedSender<Command>,
+        state_rx: watch::Receiver<ConnectionState>,
+        stats: StreamStats,
+    ) -> Self {
+        Self {
+            event_rx,
+            ack_tx,
+            control_tx,
+            control_rx: Some(control_rx),
+            command_tx,
+            closed: false,
+            cursor: None,
+            filters: Vec::new(),
+            filtered: FilteredPolicy::default(),
+            state_rx,
+            on_drop: DropHandler::default(),
+            stats,
+            paused: false,
+            seq_tx: watch::Sender::new(None),
+            topic_prefix: None,
+            journal: None,
+        }
+    }
+
+    /// Yield events with `prefix` removed from their topic.
+    pub(crate) fn strip_topic_prefix(mut self, prefix: String) -> Self {
+        self.topic_prefix = Some(prefix);
+        self
+    }
+
+    /// Current health of the subscription's connection.
+    pub fn state(&self) -> ConnectionState {
+        *self.state_rx.borrow()
+    }
+
+    /// Watch the connection state change, e.g. to drive a status indicator.
+    ///
+    /// Ends with `Disconnected` once the subscription is closed or gives up
+    /// reconnecting.
+    pub fn watch_state(&self) -> watch::Receiver<ConnectionState> {
+        self.state_rx.clone()
+    }
+
+    /// Stop yielding new events, keeping the subscription.
+    ///
+    /// The few events already handed to the stream are still yielded; newer
+    /// ones wait in its buffer, subject to `SubscribeOptions::backpressure`.
+    /// The connection keeps being read so pings are answered and no
+    /// reconnect happens. Has no effect on the streams of a
+    /// `SharedSubscription`, which share one connection.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let mut stream = client.subscribe(&["logs.>"]).await?;
+    ///
+    /// // While the user scrolls the log view
+    /// stream.pause();
+    /// // ...
+    /// stream.resume();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pause(&mut self) {
+        self.set_paused(true);
+    }
+
+    /// Start yielding events again after `EventStream::pause`.
+    pub fn resume(&mut self) {
+        self.set_paused(false);
+    }
+
+    /// Whether the stream is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if self.paused != paused {
+            self.paused = paused;
+            let _ = self.command_tx.send(Command::Pause(paused));
+        }
+    }
+
+    /// Subscribe to more topics.
+    ///
+    /// The stream reconnects with the new set of patterns and resumes where
+    /// it left off, like after a dropped connection: events it handed out
+    /// unsettled are redelivered. Without a consumer group the stream
+    /// resumes from the oldest of them, so events received after it come
+    /// again too. Returns once the server confirmed the new
+    /// subscription; if it refuses it, the stream goes back to the previous
+    /// topics. Patterns already subscribed are ignored. Not
+    /// supported by the streams of a `SharedSubscription` or
+    /// `Notif::subscribe_many`, which fail with `NotifError::Forbidden`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let stream = client.subscribe(&["agents.alpha.>"]).await?;
+    ///
+    /// // A new agent namespace appeared
+    /// stream.add_topics(&["agents.beta.>"]).await?;
+    /// stream.remove_topics(&["agents.alpha.>"]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_topics(&self, topics: &[&str]) -> Result<()> {
+        let add = parse_topics(topics)?;
+        self.send_topics(add, Vec::new()).await
+    }
+
+    /// Unsubscribe from some topics.
+    ///
+    /// Reconnects with the remaining patterns, like `EventStream::add_topics`.
+    /// Events for the removed ones already buffered may still be yielded.
+    /// Removing every topic fails; close the stream instead.
+    pub async fn remove_topics(&self, topics: &[&str]) -> Result<()> {
+        let remove = parse_topics(topics)?;
+        self.send_topics(Vec::new(), remove).await
+    }
+
+    async fn send_topics(&self, add: Vec<String>, remove: Vec<String>) -> Result<()> {
+        let (done, result) = oneshot::channel();
+        let change = Command::ChangeTopics { add, remove, done };
+        if self.command_tx.send(change).is_err() {
+            return Err(NotifError::connection("subscription has ended"));
+        }
+        result
             .await
-            .map_err(|e| NotifError::websocket(format!("failed to send subscribe: {}", e)))?;
-
-        // Wait for subscribed confirmation
-        match read.next().await {
-            Some(Ok(Message::Text(text))) => {
-                let msg: ServerMessage = serde_json::from_str(&text)?;
-                match msg.msg_type.as_str() {
-                    "subscribed" => {
-                        // Successfully subscribed
-                    }
-                    "error" => {
-                        return Err(NotifError::api(
-                            400,
-                            msg.message.unwrap_or_else(|| "subscription error".to_string()),
-                        ));
+            .unwrap_or_else(|_| Err(NotifError::connection("subscription has ended")))
+    }
+
+    /// Close the subscription cleanly.
+    ///
+    /// Acks already issued are flushed and the socket is closed, so the
+    /// server releases the consumer right away.
+    /// Dropping the stream does the same without waiting for completion.
+    pub async fn close(self) -> Result<()> {
+        self.shutdown(None).await
+    }
+
+    /// Close the subscription after in-flight events are settled.
+    ///
+    /// Stops reading new events, then waits up to `grace` for every event
+    /// handed out with `auto_ack(false)` to be acked or nacked before
+    /// closing as in [`EventStream::close`]. Events still unsettled after
+    /// `grace` are redelivered by the server.
+    pub async fn close_gracefully(self, grace: Duration) -> Result<()> {
+        self.shutdown(Some(grace)).await
+    }
+
+    /// Close, waiting up to `grace` for in-flight events if given.
+    pub(crate) async fn shutdown(mut self, grace: Option<Duration>) -> Result<()> {
+        self.closed = true;
+        let (done_tx, done_rx) = oneshot::channel();
+        let close = Command::Close {
+            grace,
+            done: Some(done_tx),
+        };
+        if self.command_tx.send(close).is_ok() {
+            // An error means the task had already stopped
+            let _ = done_rx.await;
+        }
+        Ok(())
+    }
+
+    /// Collect events until none arrives for `idle`.
+    ///
+    /// Returns what was received, possibly nothing, once the stream has
+    /// been quiet for `idle` or has ended: "everything currently queued",
+    /// as tests and batch jobs often want. Fails with the first error the
+    /// stream yields; events collected until then are dropped unsettled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SubscribeOptions};
+    /// # use std::time::Duration;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let mut stream = client
+    ///     .subscribe_with_options(&["reports.*"], SubscribeOptions::new().group("nightly"))
+    ///     .await?;
+    ///
+    /// let batch = stream.drain(Duration::from_secs(2)).await?;
+    /// println!("processing {} reports", batch.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn drain(&mut self, idle: Duration) -> Result<Vec<Event>> {
+        drain(self, idle).await
+    }
+
+    /// Counters of received, acked and nacked events, and the estimated lag.
+    ///
+    /// The handle is cheap to clone and stays up to date, e.g. for a UI to
+    /// poll.
+    pub fn stats(&self) -> StreamStats {
+        self.stats.clone()
+    }
+
+    /// Watch the highest stream sequence number this stream has yielded,
+    /// to wait until an emitted event has been received (see
+    /// `SequenceWatch::wait_for_sequence`).
+    pub fn sequence_watch(&self) -> SequenceWatch {
+        SequenceWatch::new(self.seq_tx.subscribe())
+    }
+
+    /// ID of the last event this stream yielded.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.cursor.as_ref().map(|cursor| cursor.id.as_str())
+    }
+
+    /// Position of the last event this stream yielded.
+    ///
+    /// Persist it once the event is processed and pass it to
+    /// `SubscribeOptions::from_event_id` to resume after a restart.
+    pub fn cursor(&self) -> Option<&EventCursor> {
+        self.cursor.as_ref()
+    }
+
+    /// Only yield events whose topic matches one of `patterns`.
+    ///
+    /// Rejected events are settled per `EventStream::on_filtered` instead
+    /// of being dropped unacknowledged, unlike a plain `StreamExt::filter`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{FilteredPolicy, Notif, SubscribeOptions};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// #[derive(serde::Deserialize)]
+    /// struct Order { total: f64 }
+    ///
+    /// let client = Notif::from_env()?;
+    /// let mut orders = client
+    ///     .subscribe_with_options(&["orders.>"], SubscribeOptions::new().auto_ack(false))
+    ///     .await?
+    ///     .filter_topics(&["orders.created", "orders.updated"])?
+    ///     .filter_data(|data| data["total"].as_f64().is_some_and(|total| total > 100.0))
+    ///     .on_filtered(FilteredPolicy::Ack)
+    ///     .map_typed::<Order>();
+    ///
+    /// while let Some(order) = orders.next().await {
+    ///     let order = order?;
+    ///     println!("large order: {}", order.data.total);
+    ///     order.ack().await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn filter_topics(mut self, patterns: &[&str]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Topic::parse(pattern))
+            .collect::<Result<Vec<_>>>()?;
+        self.filters.push(Box::new(move |event| {
+            patterns.iter().any(|pattern| pattern.matches(&event.topic))
+        }));
+        Ok(self)
+    }
+
+    /// Only yield events whose payload satisfies `predicate`.
+    ///
+    /// Rejected events are settled per `EventStream::on_filtered`.
+    pub fn filter_data<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&serde_json::Value) -> bool + Send + Sync + 'static,
+    {
+        self.filters
+            .push(Box::new(move |event| predicate(&event.data)));
+        self
+    }
+
+    /// Set how events rejected by local filters are settled (default: ack).
+    pub fn on_filtered(mut self, policy: FilteredPolicy) -> Self {
+        self.filtered = policy;
+        self
+    }
+
+    /// Append every event the stream yields to `journal`, to replay
+    /// after a restart.
+    ///
+    /// Events are recorded after local filters, by a background task so
+    /// disk writes never hold up delivery. A failed write leaves the event
+    /// missing from the journal.
+    pub fn journal(mut self, journal: EventJournal) -> Self {
+        self.journal = Some(journal.spawn_writer());
+        self
+    }
+
+    /// Decode every payload into `T`.
+    ///
+    /// Unlike `Notif::subscribe_typed`, events that fail to decode are also
+    /// settled per `EventStream::on_filtered` before the
+    /// `NotifError::Decode` error is yielded, so they are not left hanging.
+    pub fn map_typed<T: DeserializeOwned>(self) -> TypedEventStream<T> {
+        let mut typed = TypedEventStream::new(self);
+        typed.settle_undecodable = true;
+        typed
+    }
+
+    /// Observe subscription lifecycle changes (assigned, revoked, rebalanced).
+    ///
+    /// The first call also sees the initial `Assigned` notification; later
+    /// calls only see notifications sent after they were made.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{ControlEvent, Notif, SubscribeOptions};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let mut stream = client
+    ///     .subscribe_with_options(&["orders.*"], SubscribeOptions::new().group("workers"))
+    ///     .await?;
+    ///
+    /// let mut control = stream.control_events();
+    /// tokio::spawn(async move {
+    ///     while let Some(change) = control.next().await {
+    ///         if let ControlEvent::Revoked { .. } = change {
+    ///             // Flush in-flight state...
+    ///         }
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn control_events(&mut self) -> ControlEvents {
+        let rx = self
+            .control_rx
+            .take()
+            .unwrap_or_else(|| self.control_tx.subscribe());
+        ControlEvents {
+            inner: BroadcastStream::new(rx),
+        }
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        if !self.closed {
+            let _ = self.command_tx.send(Command::Close {
+                grace: None,
+                done: None,
+            });
+        }
+    }
+}
+
+/// A stream of subscription lifecycle notifications.
+///
+/// Implements `futures::Stream<Item = ControlEvent>`. Notifications missed
+/// by a slow reader are skipped.
+pub struct ControlEvents {
+    inner: BroadcastStream<ControlEvent>,
+}
+
+impl Stream for ControlEvents {
+    type Item = ControlEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => return Poll::Ready(Some(event)),
+                Poll::Ready(Some(Err(BroadcastStreamRecvError::Lagged(_)))) => continue,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Validate subscription patterns.
+fn parse_topics(topics: &[&str]) -> Result<Vec<String>> {
+    topics
+        .iter()
+        .map(|topic| Topic::parse(topic).map(String::from))
+        .collect()
+}
+
+/// The frame subscribing to `topics`, starting at `from`.
+#[cfg_attr(not(feature = "msgpack"), allow(unused_variables))]
+fn subscribe_message(
+    inner: &NotifInner,
+    topics: Vec<String>,
+    options: &SubscribeOptions,
+    from: Option<String>,
+) -> SubscribeMessage {
+    #[cfg(feature = "msgpack")]
+    let encoding = inner
+        .msgpack
+        .load(std::sync::atomic::Ordering::Relaxed)
+        .then(|| msgpack::ENCODING.to_string());
+    #[cfg(not(feature = "msgpack"))]
+    let encoding = None;
+
+    SubscribeMessage {
+        action: "subscribe".to_string(),
+        topics,
+        options: Some(SubscribeOptionsWire {
+            auto_ack: options.auto_ack,
+            from,
+            group: options.consumer_group(),
+            filter: options.filter.clone(),
+            sample: options.sample,
+            encoding,
+        }),
+    }
+}
+
+/// Open a WebSocket, send the subscribe message and wait for confirmation.
+///
+/// Returns the connection and the resulting `ControlEvent::Assigned`.
+async fn open(
+    inner: &NotifInner,
+    topics: &[String],
+    options: &SubscribeOptions,
+    from: Option<String>,
+) -> Result<(Link, ControlEvent)> {
+    let subscribe_msg = subscribe_message(inner, topics.to_vec(), options, from);
+
+    // Connect and subscribe, with a fresh key if the provider's was rejected
+    let api_key = inner.credentials.api_key().await?;
+    let transport = options.transport;
+    let (link, msg) = match subscribe(inner, &api_key, transport, &subscribe_msg).await {
+        Err(NotifError::Auth(_)) if inner.credentials.refreshes() => {
+            inner.credentials.invalidate(&api_key);
+            let api_key = inner.credentials.api_key().await?;
+            subscribe(inner, &api_key, transport, &subscribe_msg).await?
+        }
+        result => result?,
+    };
+
+    match msg.msg_type.as_str() {
+        "subscribed" => {
+            let assigned = ControlEvent::Assigned {
+                consumer_id: msg.consumer_id,
+                topics: msg.topics.unwrap_or_else(|| topics.to_vec()),
+            };
+            Ok((link, assigned))
+        }
+        "error" => Err(server_error(msg, "subscription error")),
+        _ => Err(NotifError::websocket(format!(
+            "unexpected message type: {}",
+            msg.msg_type
+        ))),
+    }
+}
+
+/// Connect over `transport` and send the subscribe message, returning the
+/// connection and the server's answer.
+async fn subscribe(
+    inner: &NotifInner,
+    api_key: &str,
+    transport: SubscribeTransport,
+    subscribe_msg: &SubscribeMessage,
+) -> Result<(Link, ServerMessage)> {
+    let msg_json = serde_json::to_string(subscribe_msg)?;
+    if transport == SubscribeTransport::Sse {
+        let tap = inner.frames.tap(&sse::url(inner), api_key);
+        tap.text(FrameDirection::Outbound, &msg_json);
+        let mut read = sse::open(inner, api_key, subscribe_msg).await?;
+        let msg: ServerMessage = match read.next().await {
+            Some(data) => {
+                let data = data?;
+                tap.text(FrameDirection::Inbound, &data);
+                serde_json::from_str(&data)?
+            }
+            None => return Err(NotifError::connection("event stream closed unexpectedly")),
+        };
+        let write = SseWriter::new(inner, api_key, read.session());
+        let conn = Conn::Sse(read, write);
+        return Ok((Link { conn, tap }, msg));
+    }
+
+    let url = ws_url(inner, api_key);
+    let tap = inner.frames.tap(&url, api_key);
+    let mut ws = dial(inner, &url).await?;
+    tap.text(FrameDirection::Outbound, &msg_json);
+    ws.send(Message::Text(msg_json))
+        .await
+        .map_err(|e| NotifError::websocket(format!("failed to send subscribe: {}", e)))?;
+
+    // Wait for subscribed confirmation
+    let msg: ServerMessage = match ws.next().await {
+        Some(Ok(Message::Text(text))) => {
+            tap.text(FrameDirection::Inbound, &text);
+            serde_json::from_str(&text)?
+        }
+        #[cfg(feature = "msgpack")]
+        Some(Ok(Message::Binary(bytes))) => {
+            tap.binary(FrameDirection::Inbound, &bytes);
+            msgpack::decode(&bytes)?
+        }
+        Some(Ok(_)) => return Err(NotifError::websocket("unexpected message format")),
+        Some(Err(e)) => return Err(NotifError::websocket(format!("WebSocket error: {}", e))),
+        None => return Err(NotifError::websocket("connection closed unexpectedly")),
+    };
+    let conn = Conn::Ws(ws);
+    Ok((Link { conn, tap }, msg))
+}
+
+/// The error reported by a server `error` frame, prefixed with its code.
+fn server_error(msg: ServerMessage, fallback: &str) -> NotifError {
+    let message = msg.message.unwrap_or_else(|| fallback.to_string());
+    match msg.code {
+        Some(code) => NotifError::api(400, format!("{code}: {message}")),
+        None => NotifError::api(400, message),
+    }
+}
+
+/// WebSocket URL authenticated with `api_key`.
+fn ws_url(inner: &NotifInner, api_key: &str) -> String {
+    // Convert HTTP URL to WebSocket URL
+    let ws_url = inner
+        .server
+        .replace("https://", "wss://")
+        .replace("http://", "ws://");
+    format!("{}/ws?token={}", ws_url, api_key)
+}
+
+/// Open a WebSocket to `ws_url`.
+async fn dial(inner: &NotifInner, ws_url: &str) -> Result<WsStream> {
+    inner.dialer.connect(ws_url, &inner.default_headers).await
+}
+
+/// Why a connection session ended.
+enum SessionEnd {
+    /// The consumer dropped the stream; stop for good.
+    Shutdown,
+    /// The connection was lost and may be re-established.
+    Disconnected(NotifError),
+    /// The topics changed; subscribe to them on a new connection.
+    ChangeTopics {
+        /// Topics to go back to if the new ones are refused.
+        previous: Vec<String>,
+        done: oneshot::Sender<Result<()>>,
+    },
+}
+
+/// Background task state, kept across reconnections.
+struct Worker {
+    inner: Arc<NotifInner>,
+    topics: Vec<String>,
+    options: SubscribeOptions,
+    event_tx: mpsc::Sender<Result<Event>>,
+    ack_rx: mpsc::Receiver<AckRequest>,
+    ack_tx_for_events: Option<mpsc::Sender<AckRequest>>,
+    pacer: Pacer,
+    /// Timestamp to resume from when resubscribing without a consumer group.
+    last_seen: Option<DateTime<Utc>>,
+    control_tx: broadcast::Sender<ControlEvent>,
+    /// Consumer ID of the current connection.
+    consumer_id: Option<String>,
+    command_rx: mpsc::UnboundedReceiver<Command>,
+    /// Events handed out for manual ack that are not yet settled, with
+    /// their timestamps.
+    in_flight: HashMap<String, DateTime<Utc>>,
+    /// Buffered parts of chunked events.
+    chunks: Reassembler,
+    /// Other part IDs of reassembled events, settled alongside them.
+    chunk_parts: HashMap<String, Vec<String>>,
+    /// Items waiting for the consumer, bounded by `SubscribeOptions::buffer_size`.
+    buffer: VecDeque<Result<Event>>,
+    /// Cursor from `SubscribeOptions::from_event_id` not yet passed.
+    resume_after: Option<EventCursor>,
+    /// Local evaluation of `SubscribeOptions::filter`.
+    filter: Option<Filter>,
+    /// Local evaluation of `SubscribeOptions::sample`.
+    sample: Option<Sample>,
+    /// Events held back by `SubscribeOptions::ordering_key_path`.
+    order: Option<KeyOrder>,
+    /// IDs already delivered, for `SubscribeOptions::dedupe_window`.
+    dedupe: Option<Dedupe>,
+    state_tx: watch::Sender<ConnectionState>,
+    /// Key of this subscription in the client's `Connections`.
+    connection_id: u64,
+    /// Counters behind `EventStream::stats`.
+    stats: StreamStats,
+    /// Delivery is held off by `EventStream::pause`.
+    paused: bool,
+}
+
+impl Worker {
+    async fn run(mut self, mut link: Link) {
+        loop {
+            let err = match self.session(link).await {
+                SessionEnd::Shutdown => break,
+                SessionEnd::Disconnected(err) => {
+                    self.revoke();
+                    err
+                }
+                SessionEnd::ChangeTopics { previous, done } => {
+                    self.revoke();
+                    let from = self.resume_from();
+                    match open(&self.inner, &self.topics, &self.options, from).await {
+                        Ok((new_link, assigned)) => {
+                            self.assign(assigned);
+                            let _ = done.send(Ok(()));
+                            link = new_link;
+                            continue;
+                        }
+                        Err(e) => {
+                            // Go back to the topics that were accepted
+                            self.topics = previous;
+                            let _ = done.send(Err(e));
+                            NotifError::connection("failed to subscribe to the new topics")
+                        }
                     }
-                    _ => {
-                        return Err(NotifError::websocket(format!(
-                            "unexpected message type: {}",
-                            msg.msg_type
-                        )));
+                }
+            };
+            if self.options.reconnect {
+                self.set_state(ConnectionState::Reconnecting);
+            }
+            match self.reconnect(err).await {
+                Some(new_link) => {
+                    self.set_state(ConnectionState::Connected);
+                    link = new_link;
+                }
+                None => break,
+            }
+        }
+
+        self.set_state(ConnectionState::Disconnected);
+
+        // Hand over what is left, such as a final error
+        while let Some(item) = self.buffer.pop_front() {
+            if self.event_tx.send(item).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Pump messages over a single connection until it ends.
+    async fn session(&mut self, link: Link) -> SessionEnd {
+        let (mut write, mut read) = link.split();
+        let mut heartbeat = self.options.heartbeat_interval.map(|period| {
+            let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval
+        });
+        // Heartbeat intervals in a row without any frame from the server
+        let mut missed = 0;
+        let mut heard = false;
+
+        loop {
+            let ready_at = self.pacer.ready_at();
+            let event_tx = self.event_tx.clone();
+            tokio::select! {
+                // Ping the server and give up on a silent connection
+                _ = async {
+                    match heartbeat.as_mut() {
+                        Some(interval) => interval.tick().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    if heard {
+                        missed = 0;
+                    } else {
+                        missed += 1;
+                        if missed >= self.options.max_missed_heartbeats {
+                            return SessionEnd::Disconnected(NotifError::ConnectionStale { missed });
+                        }
+                    }
+                    heard = false;
+                    let ping = PingMessage {
+                        action: "ping".to_string(),
+                    };
+                    if let Ok(json) = serde_json::to_string(&ping) {
+                        let _ = write.send(json).await;
+                    }
+                }
+                // Hand buffered items to the consumer, no faster than `max_rate`
+                permit = async {
+                    if let Some(at) = ready_at {
+                        tokio::time::sleep_until(at).await;
+                    }
+                    event_tx.reserve_owned().await
+                }, if !self.paused && !self.buffer.is_empty() => {
+                    match permit {
+                        Ok(permit) => self.forward(permit),
+                        Err(_) => return SessionEnd::Shutdown,
+                    }
+                }
+                // Handle incoming messages, also while paused so pings are answered
+                msg = read.next() => {
+                    heard = true;
+                    match msg {
+                        Some(Ok(Frame::Text(text))) => {
+                            let open = self.handle_text(&text).await;
+                            if !open {
+                                return SessionEnd::Shutdown;
+                            }
+                        }
+                        #[cfg(feature = "msgpack")]
+                        Some(Ok(Frame::Binary(bytes))) => {
+                            let open = match msgpack::decode::<ServerMessage>(&bytes) {
+                                Ok(server_msg) => self.handle_message(server_msg, None).await,
+                                Err(e) => {
+                                    self.inner.on_drop.report(|| {
+                                        DroppedEvent::new(DropReason::Malformed).error(&e)
+                                    });
+                                    self.deliver(Err(e)).await
+                                }
+                            };
+                            if !open {
+                                return SessionEnd::Shutdown;
+                            }
+                        }
+                        Some(Ok(Frame::Other)) => {}
+                        None => {
+                            return SessionEnd::Disconnected(NotifError::websocket(
+                                "connection closed by server",
+                            ));
+                        }
+                        Some(Err(e)) => return SessionEnd::Disconnected(e),
+                    }
+                }
+                // Handle outgoing ack/nack messages
+                ack_msg = self.ack_rx.recv() => {
+                    match ack_msg {
+                        Some(msg) => self.write_ack(&mut write, msg).await,
+                        None => return SessionEnd::Shutdown,
                     }
                 }
+                // Handle requests from the stream handle
+                command = self.command_rx.recv() => {
+                    match command {
+                        Some(Command::Pause(paused)) => {
+                            self.paused = paused;
+                            continue;
+                        }
+                        Some(Command::ChangeTopics { add, remove, done }) => {
+                            let previous = self.topics.clone();
+                            match self.update_topics(add, remove) {
+                                Ok(true) => {
+                                    self.flush_acks(&mut write).await;
+                                    write.close().await;
+                                    return SessionEnd::ChangeTopics { previous, done };
+                                }
+                                result => {
+                                    let _ = done.send(result.map(|_| ()));
+                                    continue;
+                                }
+                            }
+                        }
+                        Some(Command::Close { grace, done }) => {
+                            self.finish(&mut write, grace).await;
+                            if let Some(done) = done {
+                                let _ = done.send(());
+                            }
+                        }
+                        None => {}
+                    }
+                    return SessionEnd::Shutdown;
+                }
+            }
+        }
+    }
+
+    /// Publish a connection state change to the stream and the client.
+    fn set_state(&self, state: ConnectionState) {
+        self.state_tx.send_replace(state);
+        self.inner.connections.update(self.connection_id, state);
+    }
+
+    /// Write an ack or nack frame, mark the event settled and report the
+    /// outcome to the caller.
+    async fn write_ack(&mut self, write: &mut LinkWriter, request: AckRequest) {
+        let AckRequest { msg, reply } = request;
+        let id = msg.id().to_string();
+
+        // The server only knows about events delivered on this connection
+        if self.in_flight.remove(msg.id()).is_none() {
+            let _ = reply.send(Err(NotifError::connection(format!(
+                "event {} is not pending on the current connection",
+                msg.id()
+            ))));
+            return;
+        }
+        match &msg {
+            AckMessage::Ack { .. } => {
+                telemetry::event_acked();
+                self.stats.record_acked();
+            }
+            AckMessage::Nack { id, .. } => {
+                telemetry::event_nacked();
+                self.stats.record_nacked();
+                if let Some(dedupe) = &mut self.dedupe {
+                    dedupe.forget(id);
+                }
+            }
+        }
+
+        let mut result = Ok(());
+        for part in self.chunk_parts.remove(msg.id()).unwrap_or_default() {
+            result = result.and(write_settlement(write, msg.for_id(part)).await);
+        }
+        let result = result.and(write_settlement(write, msg).await);
+        let _ = reply.send(result);
+        self.release(&id);
+    }
+
+    /// Record a change of topics, returning whether the set changed.
+    fn update_topics(&mut self, add: Vec<String>, remove: Vec<String>) -> Result<bool> {
+        let mut topics = self.topics.clone();
+        let remove: Vec<String> = remove
+            .into_iter()
+            .filter(|topic| topics.contains(topic))
+            .collect();
+        topics.retain(|topic| !remove.contains(topic));
+        let mut changed = !remove.is_empty();
+        for topic in add {
+            if !topics.contains(&topic) {
+                topics.push(topic);
+                changed = true;
+            }
+        }
+        if topics.is_empty() {
+            return Err(NotifError::InvalidTopic {
+                topic: remove.join(", "),
+                reason: "cannot remove every topic of a subscription".to_string(),
+            });
+        }
+        self.topics = topics;
+        Ok(changed)
+    }
+
+    /// Write the acks and nacks already issued.
+    async fn flush_acks(&mut self, write: &mut LinkWriter) {
+        while let Ok(msg) = self.ack_rx.try_recv() {
+            self.write_ack(write, msg).await;
+        }
+    }
+
+    /// Forget what was unsettled on a connection that is going away; the
+    /// server redelivers it.
+    fn revoke(&mut self) {
+        // Ephemeral subscriptions resume by time, so go back to the oldest
+        // unsettled event for the new connection to deliver it again
+        if let Some(oldest) = self.in_flight.values().min() {
+            self.last_seen = Some(self.last_seen.map_or(*oldest, |seen| seen.min(*oldest)));
+        }
+        self.in_flight.clear();
+        self.chunk_parts.clear();
+        if let Some(order) = &mut self.order {
+            order.clear();
+        }
+        let _ = self.control_tx.send(ControlEvent::Revoked {
+            consumer_id: self.consumer_id.take(),
+        });
+    }
+
+    /// Where a new connection picks up the subscription.
+    ///
+    /// Groups and durable consumers resume from their server-side position;
+    /// ephemeral subscriptions resume from the last event we saw, or the
+    /// oldest one left unsettled.
+    fn resume_from(&self) -> Option<String> {
+        match (self.options.consumer_group(), self.last_seen) {
+            (None, Some(ts)) => Some(ts.to_rfc3339()),
+            _ => self.options.from.clone(),
+        }
+    }
+
+    /// Take over the consumer a new connection was assigned.
+    fn assign(&mut self, assigned: ControlEvent) {
+        if let ControlEvent::Assigned { consumer_id, .. } = &assigned {
+            self.consumer_id = consumer_id.clone();
+        }
+        let _ = self.control_tx.send(assigned);
+    }
+
+    /// Settle outstanding acks and close the socket.
+    async fn finish(&mut self, write: &mut LinkWriter, grace: Option<Duration>) {
+        // Buffered events will never reach the consumer; the server redelivers them
+        for event in self.buffer.drain(..).flatten() {
+            self.in_flight.remove(&event.id);
+        }
+        if let Some(mut order) = self.order.take() {
+            for event in order.clear() {
+                self.in_flight.remove(&event.id);
             }
-            Some(Ok(_)) => {
-                return Err(NotifError::websocket("unexpected message format"));
+        }
+
+        self.flush_acks(write).await;
+
+        // Optionally wait for events still being processed
+        if let Some(grace) = grace {
+            let deadline = Instant::now() + grace;
+            while !self.in_flight.is_empty() {
+                tokio::select! {
+                    msg = self.ack_rx.recv() => match msg {
+                        Some(msg) => self.write_ack(write, msg).await,
+                        None => break,
+                    },
+                    _ = tokio::time::sleep_until(deadline) => break,
+                }
             }
-            Some(Err(e)) => {
-                return Err(NotifError::websocket(format!("WebSocket error: {}", e)));
+        }
+
+        // Closing the socket removes the server's consumer
+        write.close().await;
+    }
+
+    /// Pass an item through the interceptors into the consumer's buffer.
+    ///
+    /// Returns false once the consumer has gone away or the buffer
+    /// overflowed under `BackpressurePolicy::Error`.
+    async fn deliver(&mut self, item: Result<Event>) -> bool {
+        if self.event_tx.is_closed() {
+            return false;
+        }
+        let item = self.inner.interceptors.on_event(item);
+
+        // Errors are always kept; only events are subject to the policy
+        if item.is_ok() && self.buffer.len() >= self.options.buffer_size {
+            match self.options.backpressure {
+                // Kept until the consumer catches up; reads go on so the
+                // connection stays alive
+                BackpressurePolicy::Block => {}
+                BackpressurePolicy::DropOldest => {
+                    if let Some(oldest) = self.buffer.iter().position(Result::is_ok) {
+                        if let Some(Ok(event)) = self.buffer.remove(oldest) {
+                            self.discard(&event);
+                            self.inner
+                                .on_drop
+                                .report(|| DroppedEvent::new(DropReason::BufferFull).event(event));
+                        }
+                    }
+                }
+                BackpressurePolicy::DropNewest => {
+                    if let Ok(event) = item {
+                        self.discard(&event);
+                        self.inner
+                            .on_drop
+                            .report(|| DroppedEvent::new(DropReason::BufferFull).event(event));
+                    }
+                    return true;
+                }
+                BackpressurePolicy::Error => {
+                    self.buffer.push_back(Err(NotifError::BufferOverflow {
+                        capacity: self.options.buffer_size,
+                    }));
+                    return false;
+                }
             }
-            None => {
-                return Err(NotifError::websocket("connection closed unexpectedly"));
+        }
+        self.buffer.push_back(item);
+        true
+    }
+
+    /// Send the oldest buffered item through a reserved channel slot.
+    fn forward(&mut self, permit: mpsc::OwnedPermit<Result<Event>>) {
+        if let Some(item) = self.buffer.pop_front() {
+            if item.is_ok() {
+                self.pacer.record();
             }
+            permit.send(item);
         }
+    }
+
+    /// Whether `event` is at or before the `from_event_id` cursor.
+    fn already_processed(&mut self, event: &Event) -> bool {
+        let Some(cursor) = &self.resume_after else {
+            return false;
+        };
+        if event.timestamp > cursor.timestamp {
+            self.resume_after = None;
+            return false;
+        }
+        if event.id == cursor.id {
+            self.resume_after = None;
+        }
+        true
+    }
 
-        // Create channels for events and acks
-        let (event_tx, event_rx) = mpsc::channel::<Result<Event>>(100);
-        let (ack_tx, mut ack_rx) = mpsc::channel::<AckMessage>(100);
+    /// Forget a dropped event, leaving it unsettled for redelivery.
+    fn discard(&mut self, event: &Event) {
+        self.in_flight.remove(&event.id);
+        self.chunk_parts.remove(&event.id);
+        self.release(&event.id);
+    }
+
+    /// Queue the event held behind a settled or dropped one, if any.
+    fn release(&mut self, id: &str) {
+        if let Some(next) = self.order.as_mut().and_then(|order| order.release(id)) {
+            let item = self.inner.interceptors.on_event(Ok(next));
+            self.buffer.push_back(item);
+        }
+    }
 
-        let ack_tx_for_events = if options.auto_ack { None } else { Some(ack_tx.clone()) };
+    /// Handle a text frame. Returns false once the consumer has gone away.
+    async fn handle_text(&mut self, text: &str) -> bool {
+        let server_msg = match serde_json::from_str::<ServerMessage>(text) {
+            Ok(server_msg) => server_msg,
+            Err(e) => {
+                self.inner.on_drop.report(|| {
+                    DroppedEvent::new(DropReason::Malformed)
+                        .frame(Some(text))
+                        .error(&e)
+                });
+                return self.deliver(Err(NotifError::Serialization(e))).await;
+            }
+        };
+        self.handle_message(server_msg, Some(text)).await
+    }
 
-        // Spawn background task to handle WebSocket messages
-        tokio::spawn(async move {
+    /// Handle a decoded server message, received as the text `frame` if it
+    /// was one. Returns false once the consumer has gone away.
+    async fn handle_message(&mut self, server_msg: ServerMessage, frame: Option<&str>) -> bool {
+        match server_msg.msg_type.as_str() {
+            "event" => {
+                // Validate required fields
+                let (id, topic) = match (server_msg.id, server_msg.topic) {
+                    (Some(id), Some(topic)) => (id, topic),
+                    _ => {
+                        let err = NotifError::websocket("malformed event: missing id or topic");
+                        self.inner.on_drop.report(|| {
+                            DroppedEvent::new(DropReason::Malformed)
+                                .frame(frame)
+                                .error(&err)
+                        });
+                        return self.deliver(Err(err)).await;
+                    }
+                };
+                let event = Event {
+                    id,
+                    topic,
+                    data: server_msg.data.unwrap_or(serde_json::Value::Null),
+                    headers: server_msg.headers.unwrap_or_default(),
+                    timestamp: server_msg.timestamp.unwrap_or_else(Utc::now),
+                    attempt: server_msg.attempt.unwrap_or(1),
+                    max_attempts: server_msg.max_attempts.unwrap_or(3),
+                    seq: server_msg.seq,
+                    ack_tx: self.ack_tx_for_events.clone(),
+                };
+                self.last_seen = Some(event.timestamp);
+                let event = match self.chunks.accept(event) {
+                    Ok(Some(assembled)) => {
+                        if assembled.event.ack_tx.is_some() && !assembled.part_ids.is_empty() {
+                            self.chunk_parts
+                                .insert(assembled.event.id.clone(), assembled.part_ids);
+                        }
+                        assembled.event
+                    }
+                    // Wait for the remaining parts
+                    Ok(None) => return true,
+                    Err(e) => {
+                        self.inner.on_drop.report(|| {
+                            DroppedEvent::new(DropReason::Malformed)
+                                .frame(frame)
+                                .error(&e)
+                        });
+                        return self.deliver(Err(e)).await;
+                    }
+                };
+                #[cfg(feature = "encryption")]
+                let event = match &self.inner.encryption {
+                    Some(encryption) => {
+                        let id = event.id.clone();
+                        match encryption.open(event).await {
+                            Ok(event) => event,
+                            // Left unsettled, for a consumer with the right key
+                            Err(e) => {
+                                self.chunk_parts.remove(&id);
+                                return self.deliver(Err(e)).await;
+                            }
+                        }
+                    }
+                    None => event,
+                };
+                telemetry::event_received();
+                self.stats.record_received(&event);
+                if self
+                    .dedupe
+                    .as_mut()
+                    .is_some_and(|dedupe| dedupe.is_duplicate(&event.id))
+                {
+                    telemetry::event_deduplicated();
+                    // Settle it unless the first copy is still being processed
+                    if let Some(tx) = &self.ack_tx_for_events {
+                        if self
+                            .in_flight
+                            .insert(event.id.clone(), event.timestamp)
+                            .is_none()
+                        {
+                            let (reply, _) = oneshot::channel();
+                            let msg = AckMessage::Ack { id: event.id };
+                            let _ = tx.try_send(AckRequest { msg, reply });
+                        }
+                    }
+                    return true;
+                }
+                if event.ack_tx.is_some() {
+                    self.in_flight.insert(event.id.clone(), event.timestamp);
+                }
+                let unwanted = self
+                    .filter
+                    .as_ref()
+                    .is_some_and(|filter| !filter.matches(&event))
+                    || self.sample.is_some_and(|sample| !sample.keeps(&event.id));
+                if self.already_processed(&event) || unwanted {
+                    // Settle it so the server does not redeliver it
+                    if let Some(tx) = &self.ack_tx_for_events {
+                        let (reply, _) = oneshot::channel();
+                        let msg = AckMessage::Ack { id: event.id };
+                        let _ = tx.try_send(AckRequest { msg, reply });
+                    }
+                    return true;
+                }
+                #[cfg(feature = "jsonschema")]
+                if self.inner.schemas.validates_events() {
+                    let client = crate::client::Notif {
+                        inner: self.inner.clone(),
+                    };
+                    let checked = self
+                        .inner
+                        .schemas
+                        .check(&client, &event.topic, &event.data)
+                        .await;
+                    if let Err(e) = checked {
+                        // Let the server's retry policy move it to the DLQ
+                        if let Some(tx) = &self.ack_tx_for_events {
+                            self.in_flight.remove(&event.id);
+                            let (reply, _) = oneshot::channel();
+                            let msg = AckMessage::Nack {
+                                id: event.id,
+                                retry_in: None,
+                            };
+                            let _ = tx.try_send(AckRequest { msg, reply });
+                        }
+                        return self.deliver(Err(e)).await;
+                    }
+                }
+                let event = match self.order.as_mut() {
+                    Some(order) => match order.admit(event) {
+                        Some(event) => event,
+                        // Released once the event before it is settled
+                        None => return true,
+                    },
+                    None => event,
+                };
+                self.deliver(Ok(event)).await
+            }
+            "rebalanced" => {
+                if server_msg.consumer_id.is_some() {
+                    self.consumer_id = server_msg.consumer_id;
+                }
+                let _ = self.control_tx.send(ControlEvent::Rebalanced {
+                    consumer_id: self.consumer_id.clone(),
+                    topics: server_msg.topics.unwrap_or_else(|| self.topics.clone()),
+                });
+                true
+            }
+            "revoked" => {
+                let _ = self.control_tx.send(ControlEvent::Revoked {
+                    consumer_id: self.consumer_id.clone(),
+                });
+                true
+            }
+            "error" => {
+                let err = server_error(server_msg, "unknown error");
+                self.deliver(Err(err)).await
+            }
+            _ => true,
+        }
+    }
+
+    /// Re-establish the connection with backoff.
+    ///
+    /// Returns `None` when reconnection is disabled, retries are exhausted
+    /// (the error is forwarded to the consumer) or the consumer went away.
+    async fn reconnect(&mut self, err: NotifError) -> Option<Link> {
+        if !self.options.reconnect {
+            self.deliver(Err(err)).await;
+            return None;
+        }
+
+        let from = self.resume_from();
+        // Topic changes made while disconnected, confirmed by the next subscribe
+        let mut changes = Vec::new();
+        let mut attempt = 0;
+        loop {
+            let wake = Instant::now() + self.options.backoff.delay(attempt);
             loop {
                 tokio::select! {
-                    // Handle incoming messages
-                    msg = read.next() => {
-                        match msg {
-                            Some(Ok(Message::Text(text))) => {
-                                match serde_json::from_str::<ServerMessage>(&text) {
-                                    Ok(server_msg) => {
-                                        if server_msg.msg_type == "event" {
-                                            // Validate required fields
-                                            let (id, topic) = match (server_msg.id, server_msg.topic) {
-                                                (Some(id), Some(topic)) => (id, topic),
-                                                _ => {
-                                                    let _ = event_tx.send(Err(NotifError::websocket(
-                                                        "malformed event: missing id or topic"
-                                                    ))).await;
-                                                    continue;
-                                                }
-                                            };
-                                            let event = Event {
-                                                id,
-                                                topic,
-                                                data: server_msg.data.unwrap_or(serde_json::Value::Null),
-                                                timestamp: server_msg.timestamp.unwrap_or_else(chrono::Utc::now),
-                                                attempt: server_msg.attempt.unwrap_or(1),
-                                                max_attempts: server_msg.max_attempts.unwrap_or(3),
-                                                ack_tx: ack_tx_for_events.clone(),
-                                            };
-                                            if event_tx.send(Ok(event)).await.is_err() {
-                                                break;
-                                            }
-                                        } else if server_msg.msg_type == "error" {
-                                            let err = NotifError::api(
-                                                400,
-                                                server_msg.message.unwrap_or_else(|| "unknown error".to_string()),
-                                            );
-                                            let _ = event_tx.send(Err(err)).await;
-                                        }
-                                    }
+                    _ = tokio::time::sleep_until(wake) => break,
+                    // Keep feeding the consumer what was buffered before the drop
+                    permit = self.event_tx.clone().reserve_owned(), if !self.paused && !self.buffer.is_empty() => {
+                        match permit {
+                            Ok(permit) => self.forward(permit),
+                            Err(_) => return None,
+                        }
+                    }
+                    // Events from the dropped connection are redelivered
+                    Some(request) = self.ack_rx.recv() => {
+                        let _ = request.reply.send(Err(NotifError::connection(
+                            "not connected; the event will be redelivered",
+                        )));
+                    }
+                    _ = self.event_tx.closed() => return None,
+                    command = self.command_rx.recv() => {
+                        match command {
+                            Some(Command::Pause(paused)) => {
+                                self.paused = paused;
+                                continue;
+                            }
+                            Some(Command::ChangeTopics { add, remove, done }) => {
+                                match self.update_topics(add, remove) {
+                                    Ok(_) => changes.push(done),
                                     Err(e) => {
-                                        let _ = event_tx.send(Err(NotifError::Serialization(e))).await;
+                                        let _ = done.send(Err(e));
                                     }
                                 }
+                                continue;
                             }
-                            Some(Ok(Message::Close(_))) => {
-                                break;
-                            }
-                            Some(Err(e)) => {
-                                let _ = event_tx.send(Err(NotifError::websocket(e.to_string()))).await;
-                                break;
-                            }
-                            None => break,
                             _ => {}
                         }
-                    }
-                    // Handle outgoing ack/nack messages
-                    ack_msg = ack_rx.recv() => {
-                        match ack_msg {
-                            Some(AckMessage::Ack { id }) => {
-                                let msg = AckWireMessage {
-                                    action: "ack".to_string(),
-                                    id,
-                                };
-                                if let Ok(json) = serde_json::to_string(&msg) {
-                                    let _ = write.send(Message::Text(json)).await;
-                                }
-                            }
-                            Some(AckMessage::Nack { id, retry_in }) => {
-                                let msg = NackWireMessage {
-                                    action: "nack".to_string(),
-                                    id,
-                                    retry_in,
-                                };
-                                if let Ok(json) = serde_json::to_string(&msg) {
-                                    let _ = write.send(Message::Text(json)).await;
-                                }
-                            }
-                            None => break,
+                        // Nothing to flush while disconnected
+                        if let Some(Command::Close { done: Some(done), .. }) = command {
+                            let _ = done.send(());
                         }
+                        return None;
                     }
                 }
             }
-        });
 
-        Ok(Self { event_rx, ack_tx })
+            match open(&self.inner, &self.topics, &self.options, from.clone()).await {
+                Ok((link, assigned)) => {
+                    self.assign(assigned);
+                    for done in changes {
+                        let _ = done.send(Ok(()));
+                    }
+                    telemetry::reconnected();
+                    return Some(link);
+                }
+                Err(e) => {
+                    attempt += 1;
+                    if self
+                        .options
+                        .backoff
+                        .max_retries
+                        .is_some_and(|max| attempt >= max)
+                    {
+                        self.deliver(Err(e)).await;
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Send an ack or nack frame.
+async fn write_settlement(write: &mut LinkWriter, msg: AckMessage) -> Result<()> {
+    let json = match msg {
+        AckMessage::Ack { id } => serde_json::to_string(&AckWireMessage {
+            action: "ack".to_string(),
+            id,
+        }),
+        AckMessage::Nack { id, retry_in } => serde_json::to_string(&NackWireMessage {
+            action: "nack".to_string(),
+            id,
+            retry_in: retry_in.map(crate::duration::format),
+        }),
+    };
+    write.send(json?).await
+}
+
+/// Spaces out event delivery to honor `SubscribeOptions::max_rate`.
+struct Pacer {
+    interval: Option<Duration>,
+    next: Instant,
+}
+
+impl Pacer {
+    fn new(max_rate: Option<f64>) -> Self {
+        let interval = max_rate
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .map(|rate| Duration::from_secs_f64(1.0 / rate));
+        Self {
+            interval,
+            next: Instant::now(),
+        }
+    }
+
+    /// When the next event may be handed out, or `None` if unthrottled.
+    fn ready_at(&self) -> Option<Instant> {
+        self.interval.map(|_| self.next)
+    }
+
+    /// Record a delivered event and push back the next slot.
+    fn record(&mut self) {
+        if let Some(interval) = self.interval {
+            self.next = self.next.max(Instant::now()) + interval;
+        }
     }
 }
 
@@ -195,6 +1615,193 @@ impl Stream for EventStream {
     type Item = Result<Event>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.event_rx).poll_recv(cx)
+        loop {
+            let mut item = Pin::new(&mut self.event_rx).poll_recv(cx);
+            if let Poll::Ready(Some(Ok(event))) = &mut item {
+                if let Some(topic) = self
+                    .topic_prefix
+                    .as_deref()
+                    .and_then(|prefix| event.topic.strip_prefix(prefix))
+                {
+                    event.topic = topic.to_string();
+                }
+                if !self.filters.iter().all(|filter| filter(event)) {
+                    if let Poll::Ready(Some(Ok(event))) = item {
+                        settle_filtered(self.filtered, event.id, event.ack_tx);
+                    }
+                    continue;
+                }
+                self.cursor = Some(EventCursor::from(&*event));
+                sequence::advance(&self.seq_tx, event.seq);
+                if let Some(journal) = &self.journal {
+                    if let Ok(record) = journal::encode(event) {
+                        let _ = journal.send(record);
+                    }
+                }
+            }
+            return item;
+        }
+    }
+}
+
+/// Collect items from `stream` until it is quiet for `idle` or ends.
+async fn drain<S, T>(stream: &mut S, idle: Duration) -> Result<Vec<T>>
+where
+    S: Stream<Item = Result<T>> + Unpin,
+{
+    let mut batch = Vec::new();
+    while let Ok(Some(item)) = tokio::time::timeout(idle, stream.next()).await {
+        batch.push(item?);
+    }
+    Ok(batch)
+}
+
+/// Settle an event the consumer will never see, without waiting.
+fn settle_filtered(policy: FilteredPolicy, id: String, ack_tx: Option<mpsc::Sender<AckRequest>>) {
+    let msg = match policy {
+        FilteredPolicy::Ack => AckMessage::Ack { id },
+        FilteredPolicy::Nack => AckMessage::Nack { id, retry_in: None },
+        FilteredPolicy::Ignore => return,
+    };
+    let Some(tx) = ack_tx else {
+        return;
+    };
+    tokio::spawn(async move {
+        let (reply, _) = oneshot::channel();
+        let _ = tx.send(AckRequest { msg, reply }).await;
+    });
+}
+
+/// A stream of events decoded into `T`.
+///
+/// Implements `futures::Stream<Item = Result<TypedEvent<T>>>`. Events whose
+/// payload does not match `T` are yielded as `NotifError::Decode` errors.
+pub struct TypedEventStream<T> {
+    inner: EventStream,
+    /// Settle events that fail to decode (see `EventStream::map_typed`).
+    settle_undecodable: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedEventStream<T> {
+    pub(crate) fn new(inner: EventStream) -> Self {
+        Self {
+            inner,
+            settle_undecodable: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Observe subscription lifecycle changes (see `EventStream::control_events`).
+    pub fn control_events(&mut self) -> ControlEvents {
+        self.inner.control_events()
+    }
+
+    /// Current health of the connection (see `EventStream::state`).
+    pub fn state(&self) -> ConnectionState {
+        self.inner.state()
+    }
+
+    /// Watch the connection state change (see `EventStream::watch_state`).
+    pub fn watch_state(&self) -> watch::Receiver<ConnectionState> {
+        self.inner.watch_state()
+    }
+
+    /// Stop yielding new events, keeping the subscription (see `EventStream::pause`).
+    pub fn pause(&mut self) {
+        self.inner.pause();
+    }
+
+    /// Start yielding events again (see `EventStream::resume`).
+    pub fn resume(&mut self) {
+        self.inner.resume();
+    }
+
+    /// Whether the stream is paused.
+    pub fn is_paused(&self) -> bool {
+        self.inner.is_paused()
+    }
+
+    /// Subscribe to more topics (see `EventStream::add_topics`).
+    pub async fn add_topics(&self, topics: &[&str]) -> Result<()> {
+        self.inner.add_topics(topics).await
+    }
+
+    /// Unsubscribe from some topics (see `EventStream::remove_topics`).
+    pub async fn remove_topics(&self, topics: &[&str]) -> Result<()> {
+        self.inner.remove_topics(topics).await
+    }
+
+    /// Close the subscription cleanly (see `EventStream::close`).
+    pub async fn close(self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    /// Close after in-flight events are settled (see `EventStream::close_gracefully`).
+    pub async fn close_gracefully(self, grace: Duration) -> Result<()> {
+        self.inner.close_gracefully(grace).await
+    }
+
+    /// Collect events until none arrives for `idle` (see `EventStream::drain`).
+    pub async fn drain(&mut self, idle: Duration) -> Result<Vec<TypedEvent<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        drain(self, idle).await
+    }
+
+    /// Counters of the subscription (see `EventStream::stats`).
+    pub fn stats(&self) -> StreamStats {
+        self.inner.stats()
+    }
+
+    /// Watch the highest sequence number yielded (see
+    /// `EventStream::sequence_watch`).
+    pub fn sequence_watch(&self) -> SequenceWatch {
+        self.inner.sequence_watch()
+    }
+
+    /// ID of the last event this stream yielded (see `EventStream::last_event_id`).
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.inner.last_event_id()
+    }
+
+    /// Position of the last event this stream yielded (see `EventStream::cursor`).
+    pub fn cursor(&self) -> Option<&EventCursor> {
+        self.inner.cursor()
+    }
+
+    /// Get back the underlying untyped stream.
+    pub fn into_inner(self) -> EventStream {
+        self.inner
+    }
+}
+
+impl<T: DeserializeOwned> Stream for TypedEventStream<T> {
+    type Item = Result<TypedEvent<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let settle = self.settle_undecodable;
+        let policy = self.inner.filtered;
+        let on_drop = self.inner.on_drop.clone();
+        Pin::new(&mut self.inner).poll_next(cx).map(|item| {
+            item.map(|event| {
+                let event = event?;
+                let settlement = settle.then(|| (event.id.clone(), event.ack_tx.clone()));
+                let reported = on_drop.is_set().then(|| event.clone());
+                let typed = event.into_typed();
+                if let (Err(e), Some(event)) = (&typed, reported) {
+                    on_drop.report(|| {
+                        DroppedEvent::new(DropReason::Undecodable)
+                            .event(event)
+                            .error(e)
+                    });
+                }
+                if let (Err(_), Some((id, ack_tx))) = (&typed, settlement) {
+                    settle_filtered(policy, id, ack_tx);
+                }
+                typed
+            })
+        })
     }
 }
@@ -0,0 +1,72 @@
+//! Metrics recorded through the `metrics` crate facade.
+//!
+//! With the `metrics` feature enabled, the SDK records the following, which
+//! any `metrics` exporter (Prometheus, statsd, ...) can publish:
+//!
+//! | Metric | Type | Description |
+//! |--------|------|-------------|
+//! | `notif_events_emitted_total` | counter | Events successfully emitted |
+//! | `notif_emit_errors_total` | counter | Emits that failed |
+//! | `notif_emit_duration_seconds` | histogram | Emit round-trip latency |
+//! | `notif_events_received_total` | counter | Events received by subscriptions |
+//! | `notif_events_deduplicated_total` | counter | Redelivered events suppressed by `dedupe_window` |
+//! | `notif_events_acked_total` | counter | Acks sent |
+//! | `notif_events_nacked_total` | counter | Nacks sent |
+//! | `notif_reconnects_total` | counter | Successful subscription reconnects |
+//!
+//! Without the feature every function here is a no-op.
+
+use std::time::Duration;
+
+#[cfg(feature = "metrics")]
+pub(crate) fn emit_finished(ok: bool, elapsed: Duration) {
+    if ok {
+        metrics::counter!("notif_events_emitted_total").increment(1);
+    } else {
+        metrics::counter!("notif_emit_errors_total").increment(1);
+    }
+    metrics::histogram!("notif_emit_duration_seconds").record(elapsed.as_secs_f64());
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn event_received() {
+    metrics::counter!("notif_events_received_total").increment(1);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn event_deduplicated() {
+    metrics::counter!("notif_events_deduplicated_total").increment(1);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn event_acked() {
+    metrics::counter!("notif_events_acked_total").increment(1);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn event_nacked() {
+    metrics::counter!("notif_events_nacked_total").increment(1);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn reconnected() {
+    metrics::counter!("notif_reconnects_total").increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn emit_finished(_ok: bool, _elapsed: Duration) {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn event_received() {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn event_deduplicated() {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn event_acked() {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn event_nacked() {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn reconnected() {}
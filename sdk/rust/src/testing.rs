@@ -0,0 +1,468 @@
+//! In-memory broker for testing event handlers.
+//!
+//! [`MockNotif`] mirrors the emit and subscribe API of [`crate::Notif`]
+//! without a server: emits are delivered to local subscriptions whose
+//! patterns match, and acks and nacks are recorded so tests can assert on
+//! them.
+//!
+//! Subscriptions sharing a `group` receive each event once, round-robin,
+//! like a durable consumer group. Nacked events are recorded but not
+//! redelivered. `from` replays are not supported; subscriptions only see
+//! events emitted after they were created.
+//!
+//! # Example
+//!
+//! ```
+//! use futures::StreamExt;
+//! use notifsh::testing::MockNotif;
+//! use notifsh::SubscribeOptions;
+//! use serde_json::json;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> notifsh::Result<()> {
+//! let notif = MockNotif::new();
+//! let mut stream = notif
+//!     .subscribe_with_options(&["orders.*"], SubscribeOptions::new().auto_ack(false))
+//!     .await?;
+//!
+//! notif.emit("orders.created", json!({"order_id": "123"})).await?;
+//!
+//! let event = stream.next().await.unwrap()?;
+//! assert_eq!(event.data["order_id"], "123");
+//! event.ack().await?;
+//!
+//! stream.close().await?;
+//! assert_eq!(notif.acked(), vec![event.id.clone()]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, watch};
+
+use crate::binary;
+use crate::error::{NotifError, Result};
+use crate::event_type::NotifEvent;
+use crate::filter::Filter;
+use crate::ordering::KeyOrder;
+use crate::sample::Sample;
+use crate::stats::StreamStats;
+use crate::subscribe::{Command, EventStream, TypedEventStream};
+use crate::topic::Topic;
+use crate::types::{
+    AckLevel, AckMessage, AckRequest, ConnectionState, ControlEvent, EmitOptions, EmitResponse, Event,
+    SubscribeOptions,
+};
+
+/// Delivery attempts reported on mock events, matching the server default.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// In-memory stand-in for [`crate::Notif`].
+///
+/// Cloning is cheap; clones share the same broker.
+#[derive(Debug, Clone, Default)]
+pub struct MockNotif {
+    broker: Arc<Mutex<Broker>>,
+}
+
+#[derive(Debug, Default)]
+struct Broker {
+    next_subscription: u64,
+    subscriptions: Vec<Subscription>,
+    /// Round-robin position per consumer group.
+    cursors: HashMap<String, usize>,
+    emitted: Vec<Event>,
+    acked: Vec<String>,
+    nacked: Vec<(String, Option<Duration>)>,
+}
+
+#[derive(Debug)]
+struct Subscription {
+    id: u64,
+    patterns: Vec<Topic>,
+    group: Option<String>,
+    auto_ack: bool,
+    filter: Option<Filter>,
+    sample: Option<Sample>,
+    inbox: mpsc::UnboundedSender<Event>,
+}
+
+impl Subscription {
+    fn matches(&self, event: &Event) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(&event.topic))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(event))
+            && self.sample.is_none_or(|sample| sample.keeps(&event.id))
+    }
+}
+
+impl MockNotif {
+    /// Create an empty broker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emit an event to matching local subscriptions.
+    pub async fn emit<T: Serialize>(&self, topic: &str, data: T) -> Result<EmitResponse> {
+        self.emit_with_options(topic, data, EmitOptions::new())
+            .await
+    }
+
+    /// Emit an event with custom options such as headers.
+    pub async fn emit_with_options<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        Topic::name(topic)?;
+
+        let mut broker = self.lock();
+        let event = Event {
+            id: format!("evt_{}", uuid::Uuid::new_v4().simple()),
+            topic: topic.to_string(),
+            data: serde_json::to_value(data)?,
+            headers: options.headers,
+            timestamp: Utc::now(),
+            attempt: 1,
+            max_attempts: MAX_ATTEMPTS,
+            seq: Some(broker.emitted.len() as u64 + 1),
+            ack_tx: None,
+        };
+
+        let delivered = broker.publish(&event) > 0;
+        broker.emitted.push(event.clone());
+
+        // Everything is stored at once; delivery is only reported when a
+        // subscription received the event
+        let level = options.ack_level;
+        Ok(EmitResponse {
+            id: event.id,
+            topic: event.topic,
+            created_at: event.timestamp,
+            seq: event.seq,
+            stored_at: (level >= AckLevel::Stored).then_some(event.timestamp),
+            delivered_at: (level == AckLevel::Delivered && delivered).then_some(event.timestamp),
+        })
+    }
+
+    /// Emit a binary payload tagged with `content_type`.
+    pub async fn emit_bytes(
+        &self,
+        topic: &str,
+        data: impl AsRef<[u8]>,
+        content_type: &str,
+    ) -> Result<EmitResponse> {
+        let (data, options) = binary::encode(data.as_ref(), content_type, EmitOptions::new());
+        self.emit_with_options(topic, data, options).await
+    }
+
+    /// Subscribe to one or more topics.
+    pub async fn subscribe(&self, topics: &[&str]) -> Result<EventStream> {
+        self.subscribe_with_options(topics, SubscribeOptions::new())
+            .await
+    }
+
+    /// Subscribe to topics with custom options.
+    ///
+    /// `auto_ack`, `group`, `filter`, `sample` and `ordering_key_path` are
+    /// honored; connection options such as `reconnect` and `backoff` have
+    /// no effect.
+    pub async fn subscribe_with_options(
+        &self,
+        topics: &[&str],
+        options: SubscribeOptions,
+    ) -> Result<EventStream> {
+        let patterns = topics
+            .iter()
+            .map(|topic| Topic::parse(topic))
+            .collect::<Result<Vec<_>>>()?;
+        let filter = options.filter.as_deref().map(Filter::parse).transpose()?;
+        let sample = options.sample.map(Sample::new).transpose()?;
+        let order = match &options.ordering_key_path {
+            Some(path) if !options.auto_ack => Some(KeyOrder::new(path)?),
+            _ => None,
+        };
+
+        let (inbox_tx, inbox_rx) = mpsc::unbounded_channel();
+        let (event_tx, event_rx) = mpsc::channel(options.buffer_size);
+        let (ack_tx, ack_rx) = mpsc::channel(100);
+        let (control_tx, control_rx) = broadcast::channel(16);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (state_tx, state_rx) = watch::channel(ConnectionState::Connected);
+
+        let _ = control_tx.send(ControlEvent::Assigned {
+            consumer_id: options.consumer_group(),
+            topics: patterns.iter().map(|topic| topic.to_string()).collect(),
+        });
+
+        let id = {
+            let mut broker = self.lock();
+            broker.next_subscription += 1;
+            let id = broker.next_subscription;
+            broker.subscriptions.push(Subscription {
+                id,
+                patterns,
+                group: options.consumer_group(),
+                auto_ack: options.auto_ack,
+                filter,
+                sample,
+                inbox: inbox_tx,
+            });
+            id
+        };
+
+        let stats = StreamStats::default();
+        let worker = MockWorker {
+            id,
+            broker: self.broker.clone(),
+            inbox: inbox_rx,
+            pending: VecDeque::new(),
+            order,
+            event_tx,
+            ack_tx: (!options.auto_ack).then(|| ack_tx.clone()),
+            ack_rx,
+            command_rx,
+            state_tx,
+            stats: stats.clone(),
+            paused: false,
+        };
+        tokio::spawn(worker.run());
+
+        Ok(EventStream::from_parts(
+            event_rx, ack_tx, control_tx, control_rx, command_tx, state_rx, stats,
+        ))
+    }
+
+    /// Subscribe to topics, decoding every payload into `T`.
+    pub async fn subscribe_typed<T: DeserializeOwned>(
+        &self,
+        topics: &[&str],
+    ) -> Result<TypedEventStream<T>> {
+        self.subscribe_typed_with_options(topics, SubscribeOptions::new())
+            .await
+    }
+
+    /// Subscribe to topics with custom options, decoding every payload into `T`.
+    pub async fn subscribe_typed_with_options<T: DeserializeOwned>(
+        &self,
+        topics: &[&str],
+        options: SubscribeOptions,
+    ) -> Result<TypedEventStream<T>> {
+        let stream = self.subscribe_with_options(topics, options).await?;
+        Ok(TypedEventStream::new(stream))
+    }
+
+    /// Emit `event` to the topic of its type.
+    pub async fn emit_event<E: NotifEvent>(&self, event: &E) -> Result<EmitResponse> {
+        self.emit(E::TOPIC, event).await
+    }
+
+    /// Subscribe to the topic of `E`, decoding every payload into it.
+    pub async fn subscribe_events<E: NotifEvent>(&self) -> Result<TypedEventStream<E>> {
+        self.subscribe_typed(&[E::TOPIC]).await
+    }
+
+    /// Every event emitted so far, in order.
+    pub fn emitted(&self) -> Vec<Event> {
+        self.lock().emitted.clone()
+    }
+
+    /// Events emitted to topics matching `pattern`, in order.
+    pub fn emitted_to(&self, pattern: &str) -> Result<Vec<Event>> {
+        let pattern = Topic::parse(pattern)?;
+        Ok(self
+            .lock()
+            .emitted
+            .iter()
+            .filter(|event| pattern.matches(&event.topic))
+            .cloned()
+            .collect())
+    }
+
+    /// IDs of acknowledged events, in ack order.
+    ///
+    /// Includes events delivered to `auto_ack` subscriptions.
+    pub fn acked(&self) -> Vec<String> {
+        self.lock().acked.clone()
+    }
+
+    /// IDs and retry delays of negatively acknowledged events, in nack order.
+    pub fn nacked(&self) -> Vec<(String, Option<Duration>)> {
+        self.lock().nacked.clone()
+    }
+
+    /// Forget recorded emits, acks and nacks. Subscriptions stay open.
+    pub fn clear(&self) {
+        let mut broker = self.lock();
+        broker.emitted.clear();
+        broker.acked.clear();
+        broker.nacked.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Broker> {
+        // A panicking test thread must not hide the broker from the others
+        self.broker.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Broker {
+    /// Queue `event` on every matching subscription, once per group.
+    /// Deliver `event` to matching subscriptions, returning how many got it.
+    fn publish(&mut self, event: &Event) -> usize {
+        self.subscriptions.retain(|sub| !sub.inbox.is_closed());
+
+        let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut targets = Vec::new();
+        for (index, sub) in self.subscriptions.iter().enumerate() {
+            if !sub.matches(event) {
+                continue;
+            }
+            match &sub.group {
+                Some(group) => groups.entry(group).or_default().push(index),
+                None => targets.push(index),
+            }
+        }
+        for (group, members) in groups {
+            let cursor = self.cursors.entry(group.to_string()).or_default();
+            targets.push(members[*cursor % members.len()]);
+            *cursor += 1;
+        }
+
+        let mut delivered = 0;
+        for index in targets {
+            let sub = &self.subscriptions[index];
+            if sub.inbox.send(event.clone()).is_ok() {
+                delivered += 1;
+                if sub.auto_ack {
+                    self.acked.push(event.id.clone());
+                }
+            }
+        }
+        delivered
+    }
+}
+
+/// Background task standing in for the WebSocket worker.
+struct MockWorker {
+    id: u64,
+    broker: Arc<Mutex<Broker>>,
+    inbox: mpsc::UnboundedReceiver<Event>,
+    pending: VecDeque<Event>,
+    order: Option<KeyOrder>,
+    event_tx: mpsc::Sender<Result<Event>>,
+    ack_tx: Option<mpsc::Sender<AckRequest>>,
+    ack_rx: mpsc::Receiver<AckRequest>,
+    command_rx: mpsc::UnboundedReceiver<Command>,
+    state_tx: watch::Sender<ConnectionState>,
+    stats: StreamStats,
+    paused: bool,
+}
+
+impl MockWorker {
+    async fn run(mut self) {
+        loop {
+            tokio::select! {
+                permit = self.event_tx.clone().reserve_owned(), if !self.paused && !self.pending.is_empty() => {
+                    let Ok(permit) = permit else { break };
+                    if let Some(mut event) = self.pending.pop_front() {
+                        event.ack_tx = self.ack_tx.clone();
+                        self.stats.record_received(&event);
+                        permit.send(Ok(event));
+                    }
+                }
+                Some(event) = self.inbox.recv() => {
+                    let event = match self.order.as_mut() {
+                        Some(order) => order.admit(event),
+                        None => Some(event),
+                    };
+                    self.pending.extend(event);
+                }
+                Some(ack) = self.ack_rx.recv() => self.record(ack),
+                command = self.command_rx.recv() => {
+                    match command {
+                        // Paused events wait in `pending` like on the server
+                        Some(Command::Pause(paused)) => {
+                            self.paused = paused;
+                            continue;
+                        }
+                        Some(Command::ChangeTopics { add, remove, done }) => {
+                            let _ = done.send(self.change_topics(add, remove));
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    // Settle acks already sent before reporting the close
+                    while let Ok(ack) = self.ack_rx.try_recv() {
+                        self.record(ack);
+                    }
+                    if let Some(Command::Close { done: Some(done), .. }) = command {
+                        self.unsubscribe();
+                        let _ = done.send(());
+                    }
+                    break;
+                }
+            }
+        }
+        self.unsubscribe();
+    }
+
+    fn record(&mut self, request: AckRequest) {
+        let id = request.msg.id().to_string();
+        {
+            let mut broker = self.broker.lock().unwrap_or_else(|e| e.into_inner());
+            match request.msg {
+                AckMessage::Ack { id } => {
+                    self.stats.record_acked();
+                    broker.acked.push(id);
+                }
+                AckMessage::Nack { id, retry_in } => {
+                    self.stats.record_nacked();
+                    broker.nacked.push((id, retry_in));
+                }
+            }
+        }
+        let _ = request.reply.send(Ok(()));
+        if let Some(next) = self.order.as_mut().and_then(|order| order.release(&id)) {
+            self.pending.push_back(next);
+        }
+    }
+
+    fn change_topics(&self, add: Vec<String>, remove: Vec<String>) -> Result<()> {
+        let mut broker = self.broker.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(sub) = broker.subscriptions.iter_mut().find(|sub| sub.id == self.id) else {
+            return Ok(());
+        };
+        let mut patterns = sub.patterns.clone();
+        patterns.retain(|pattern| !remove.iter().any(|topic| topic == pattern.as_str()));
+        for topic in add {
+            if !patterns.iter().any(|pattern| pattern.as_str() == topic) {
+                patterns.push(Topic::parse(&topic)?);
+            }
+        }
+        if patterns.is_empty() {
+            return Err(NotifError::InvalidTopic {
+                topic: remove.join(", "),
+                reason: "cannot remove every topic of a subscription".to_string(),
+            });
+        }
+        sub.patterns = patterns;
+        Ok(())
+    }
+
+    fn unsubscribe(&self) {
+        let mut broker = self.broker.lock().unwrap_or_else(|e| e.into_inner());
+        broker.subscriptions.retain(|sub| sub.id != self.id);
+        self.state_tx.send_replace(ConnectionState::Disconnected);
+    }
+}
@@ -0,0 +1,223 @@
+//! Topic names and subscription patterns.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{NotifError, Result};
+
+/// Maximum topic length accepted by the server.
+const MAX_TOPIC_LEN: usize = 255;
+
+/// Matches exactly one segment.
+const SINGLE_WILDCARD: &str = "*";
+
+/// Matches one or more trailing segments.
+const MULTI_WILDCARD: &str = ">";
+
+/// A validated topic name or subscription pattern.
+///
+/// Topics are dot-separated segments (`orders.created`). Patterns may use
+/// `*` to match exactly one segment and `>` as the last segment to match one
+/// or more segments. A standalone `*` means "all topics" and is normalized
+/// to `>`, matching the server's behavior.
+///
+/// # Example
+///
+/// ```
+/// use notifsh::Topic;
+///
+/// let pattern = Topic::parse("orders.*")?;
+/// assert!(pattern.matches("orders.created"));
+/// assert!(!pattern.matches("orders.eu.created"));
+///
+/// assert_eq!(Topic::parse("*")?.as_str(), ">");
+/// assert!(Topic::parse("orders.>.created").is_err());
+///
+/// let topic = Topic::builder().segment("agents").any().segment("status").build()?;
+/// assert_eq!(topic.as_str(), "agents.*.status");
+/// # Ok::<(), notifsh::NotifError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Topic(String);
+
+impl Topic {
+    /// Parse a topic name or subscription pattern.
+    pub fn parse(pattern: &str) -> Result<Self> {
+        if pattern == SINGLE_WILDCARD {
+            return Ok(Self(MULTI_WILDCARD.to_string()));
+        }
+
+        check_length(pattern)?;
+        let segments: Vec<&str> = pattern.split('.').collect();
+        for (i, segment) in segments.iter().enumerate() {
+            check_segment(pattern, segment)?;
+            if *segment == MULTI_WILDCARD && i != segments.len() - 1 {
+                return Err(invalid(pattern, "'>' is only allowed as the last segment"));
+            }
+        }
+
+        Ok(Self(pattern.to_string()))
+    }
+
+    /// Parse a concrete topic name that events can be emitted to.
+    ///
+    /// Unlike [`Topic::parse`], wildcards are rejected.
+    pub fn name(name: &str) -> Result<Self> {
+        check_length(name)?;
+        for segment in name.split('.') {
+            check_segment(name, segment)?;
+            if segment == SINGLE_WILDCARD || segment == MULTI_WILDCARD {
+                return Err(invalid(name, "wildcards are not allowed in topic names"));
+            }
+        }
+
+        Ok(Self(name.to_string()))
+    }
+
+    /// Start building a topic segment by segment.
+    pub fn builder() -> TopicBuilder {
+        TopicBuilder::default()
+    }
+
+    /// The normalized topic string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Iterate over the dot-separated segments.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split('.')
+    }
+
+    /// Whether this is a pattern containing wildcards.
+    pub fn is_pattern(&self) -> bool {
+        self.segments()
+            .any(|segment| segment == SINGLE_WILDCARD || segment == MULTI_WILDCARD)
+    }
+
+    /// Whether a concrete topic name matches this pattern.
+    pub fn matches(&self, topic: &str) -> bool {
+        let mut names = topic.split('.');
+        for segment in self.segments() {
+            match segment {
+                MULTI_WILDCARD => return names.next().is_some(),
+                SINGLE_WILDCARD => {
+                    if names.next().is_none() {
+                        return false;
+                    }
+                }
+                literal => {
+                    if names.next() != Some(literal) {
+                        return false;
+                    }
+                }
+            }
+        }
+        names.next().is_none()
+    }
+}
+
+fn invalid(topic: &str, reason: impl Into<String>) -> NotifError {
+    NotifError::InvalidTopic {
+        topic: topic.to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn check_length(topic: &str) -> Result<()> {
+    if topic.is_empty() {
+        return Err(invalid(topic, "topic is required"));
+    }
+    if topic.len() > MAX_TOPIC_LEN {
+        return Err(invalid(
+            topic,
+            format!("topic too long, max {} chars", MAX_TOPIC_LEN),
+        ));
+    }
+    if topic.starts_with('$') {
+        return Err(invalid(topic, "topic cannot start with $"));
+    }
+    Ok(())
+}
+
+fn check_segment(topic: &str, segment: &str) -> Result<()> {
+    if segment.is_empty() {
+        return Err(invalid(
+            topic,
+            "empty segment (topics cannot start or end with '.' or contain '..')",
+        ));
+    }
+    if segment.chars().any(char::is_whitespace) {
+        return Err(invalid(topic, "topic cannot contain whitespace"));
+    }
+    if segment != SINGLE_WILDCARD && segment != MULTI_WILDCARD && segment.contains(['*', '>']) {
+        return Err(invalid(
+            topic,
+            format!("wildcard must be a whole segment, got '{}'", segment),
+        ));
+    }
+    Ok(())
+}
+
+impl FromStr for Topic {
+    type Err = NotifError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for Topic {
+    type Error = NotifError;
+
+    fn try_from(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Topic {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<Topic> for String {
+    fn from(topic: Topic) -> Self {
+        topic.0
+    }
+}
+
+/// Builder for assembling a [`Topic`] from segments.
+#[derive(Debug, Clone, Default)]
+pub struct TopicBuilder {
+    segments: Vec<String>,
+}
+
+impl TopicBuilder {
+    /// Append a literal segment.
+    pub fn segment(mut self, segment: impl Into<String>) -> Self {
+        self.segments.push(segment.into());
+        self
+    }
+
+    /// Append a `*` wildcard matching exactly one segment.
+    pub fn any(self) -> Self {
+        self.segment(SINGLE_WILDCARD)
+    }
+
+    /// Append a `>` wildcard matching all remaining segments.
+    pub fn rest(self) -> Self {
+        self.segment(MULTI_WILDCARD)
+    }
+
+    /// Validate and build the topic.
+    pub fn build(self) -> Result<Topic> {
+        Topic::parse(&self.segments.join("."))
+    }
+}
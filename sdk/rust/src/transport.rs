@@ -0,0 +1,433 @@
+//! Proxy, TLS and socket settings shared by HTTP requests and WebSocket
+//! connections.
+//!
+//! reqwest applies them to HTTP calls on its own. WebSocket connections
+//! are dialed here instead: through the proxy (HTTP `CONNECT` or SOCKS5)
+//! when there is one, then over TLS with the same roots and identity. A
+//! `unix://` server replaces all of that with a local Unix socket.
+
+use std::env;
+use std::path::PathBuf;
+
+use base64::Engine;
+use reqwest::header::HeaderMap;
+use reqwest::{ClientBuilder, StatusCode};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::{client_async_tls_with_config, Connector};
+use url::Url;
+
+use crate::error::{NotifError, Result};
+use crate::subscribe::WsStream;
+
+/// Largest proxy `CONNECT` response head accepted.
+const MAX_CONNECT_RESPONSE: usize = 8 * 1024;
+
+/// Scheme of servers reached over a Unix socket, as in
+/// `unix:///var/run/notif.sock`.
+const UNIX_SCHEME: &str = "unix://";
+
+/// Base URL of requests sent over a Unix socket; the host only fills in
+/// the `Host` header.
+const UNIX_BASE_URL: &str = "http://localhost";
+
+/// A byte stream WebSockets run over.
+pub(crate) trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// A TCP connection, possibly tunneled through a proxy, or a Unix socket.
+pub(crate) type Socket = Box<dyn Io>;
+
+/// Which proxy connections go through.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum ProxySetting {
+    /// `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY`, minus `NO_PROXY`.
+    #[default]
+    System,
+    /// Always connect directly.
+    Disabled,
+    /// Always use this proxy.
+    Url(String),
+}
+
+/// Proxy and TLS options collected by `NotifBuilder`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TransportConfig {
+    pub(crate) proxy: ProxySetting,
+    /// PEM certificates trusted in addition to the system roots.
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    /// PEM certificate chain and PKCS#8 PEM key presented to the server.
+    pub(crate) identity: Option<(Vec<u8>, Vec<u8>)>,
+    /// Socket every connection goes through, from a `unix://` server.
+    pub(crate) unix_socket: Option<PathBuf>,
+}
+
+impl TransportConfig {
+    /// Take the socket path out of a `unix://` server, returning the base
+    /// URL requests are addressed to.
+    pub(crate) fn route(&mut self, server: &str) -> Result<String> {
+        let Some(path) = server.strip_prefix(UNIX_SCHEME) else {
+            return Ok(server.to_string());
+        };
+        if !cfg!(unix) {
+            return Err(NotifError::Config(
+                "Unix socket servers are not supported on this platform".to_string(),
+            ));
+        }
+        if path.is_empty() {
+            return Err(NotifError::Config(format!(
+                "no socket path in server '{}'",
+                server
+            )));
+        }
+        self.unix_socket = Some(PathBuf::from(path));
+        Ok(UNIX_BASE_URL.to_string())
+    }
+
+    /// Apply the settings to an HTTP client.
+    pub(crate) fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder> {
+        match &self.proxy {
+            ProxySetting::System => {}
+            ProxySetting::Disabled => builder = builder.no_proxy(),
+            ProxySetting::Url(url) => {
+                let proxy = reqwest::Proxy::all(url.as_str())
+                    .map_err(|e| NotifError::Config(format!("invalid proxy '{}': {}", url, e)))?;
+                builder = builder.proxy(proxy);
+            }
+        }
+        for pem in &self.root_certificates {
+            let certificate = reqwest::Certificate::from_pem(pem)
+                .map_err(|e| NotifError::Config(format!("invalid root certificate: {}", e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some((certificate, key)) = &self.identity {
+            let identity = reqwest::Identity::from_pkcs8_pem(certificate, key)
+                .map_err(|e| NotifError::Config(format!("invalid client identity: {}", e)))?;
+            builder = builder.identity(identity);
+        }
+        // Overrides the proxy, like every TCP option
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            builder = builder.unix_socket(path.clone());
+        }
+        Ok(builder)
+    }
+
+    /// Build the WebSocket dialer for these settings.
+    pub(crate) fn dialer(&self) -> Result<Dialer> {
+        let proxy = match &self.proxy {
+            ProxySetting::Url(url) => Some(Proxy::parse(url)?),
+            _ => None,
+        };
+
+        let tls = if self.root_certificates.is_empty() && self.identity.is_none() {
+            None
+        } else {
+            let mut tls = native_tls::TlsConnector::builder();
+            for pem in &self.root_certificates {
+                let certificate = native_tls::Certificate::from_pem(pem)
+                    .map_err(|e| NotifError::Config(format!("invalid root certificate: {}", e)))?;
+                tls.add_root_certificate(certificate);
+            }
+            if let Some((certificate, key)) = &self.identity {
+                let identity = native_tls::Identity::from_pkcs8(certificate, key)
+                    .map_err(|e| NotifError::Config(format!("invalid client identity: {}", e)))?;
+                tls.identity(identity);
+            }
+            Some(
+                tls.build()
+                    .map_err(|e| NotifError::Config(format!("invalid TLS settings: {}", e)))?,
+            )
+        };
+
+        Ok(Dialer {
+            proxy,
+            system_proxy: matches!(self.proxy, ProxySetting::System),
+            tls,
+            unix_socket: self.unix_socket.clone(),
+        })
+    }
+}
+
+/// Opens WebSocket connections with the client's proxy and TLS settings.
+pub(crate) struct Dialer {
+    proxy: Option<Proxy>,
+    system_proxy: bool,
+    tls: Option<native_tls::TlsConnector>,
+    unix_socket: Option<PathBuf>,
+}
+
+impl Dialer {
+    /// Connect and perform the WebSocket handshake for `url`, sending
+    /// `headers` along with it.
+    pub(crate) async fn connect(&self, url: &str, headers: &HeaderMap) -> Result<WsStream> {
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            let stream = UnixStream::connect(path).await.map_err(|e| {
+                NotifError::websocket(format!("connection to {} failed: {}", path.display(), e))
+            })?;
+            return handshake(url, Box::new(stream), None, headers).await;
+        }
+
+        let parsed = Url::parse(url)?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| NotifError::connection(format!("no host in '{}'", parsed)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let secure = parsed.scheme() == "wss";
+        let port = parsed
+            .port_or_known_default()
+            .unwrap_or(if secure { 443 } else { 80 });
+
+        let system;
+        let proxy = match &self.proxy {
+            Some(proxy) => Some(proxy),
+            None if self.system_proxy => {
+                system = Proxy::from_env(&host, secure)?;
+                system.as_ref()
+            }
+            None => None,
+        };
+        let stream = match proxy {
+            Some(proxy) => proxy.tunnel(&host, port).await?,
+            None => TcpStream::connect((host.as_str(), port))
+                .await
+                .map_err(|e| NotifError::websocket(format!("connection failed: {}", e)))?,
+        };
+        // Handshake latency matters more than batching for a long-lived socket
+        stream.set_nodelay(true).ok();
+
+        let connector = self.tls.clone().map(Connector::NativeTls);
+        handshake(url, Box::new(stream), connector, headers).await
+    }
+}
+
+/// Perform the WebSocket handshake for `url` over `stream`.
+async fn handshake(
+    url: &str,
+    stream: Socket,
+    connector: Option<Connector>,
+    headers: &HeaderMap,
+) -> Result<WsStream> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| NotifError::websocket(format!("invalid request: {}", e)))?;
+    request.headers_mut().extend(headers.clone());
+    let (ws, _) = client_async_tls_with_config(request, stream, None, connector)
+        .await
+        .map_err(|e| match e {
+            WsError::Http(response) if response.status() == StatusCode::UNAUTHORIZED => {
+                NotifError::auth("WebSocket handshake rejected the API key")
+            }
+            e => NotifError::websocket(format!("connection failed: {}", e)),
+        })?;
+    Ok(ws)
+}
+
+/// How a proxy is reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProxyKind {
+    /// HTTP proxy, tunneling with `CONNECT`.
+    Http,
+    /// SOCKS5 proxy, resolving names locally.
+    Socks5,
+    /// SOCKS5 proxy, resolving names on the proxy.
+    Socks5h,
+}
+
+#[derive(Debug, Clone)]
+struct Proxy {
+    kind: ProxyKind,
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+}
+
+impl Proxy {
+    fn parse(value: &str) -> Result<Self> {
+        let invalid =
+            |reason: &str| NotifError::Config(format!("invalid proxy '{}': {}", value, reason));
+        // Like curl, a bare `host:port` is an HTTP proxy
+        let url = if value.contains("://") {
+            Url::parse(value)
+        } else {
+            Url::parse(&format!("http://{}", value))
+        }
+        .map_err(|e| invalid(&e.to_string()))?;
+
+        let (kind, default_port) = match url.scheme() {
+            "http" => (ProxyKind::Http, 80),
+            "socks5" => (ProxyKind::Socks5, 1080),
+            "socks5h" => (ProxyKind::Socks5h, 1080),
+            other => return Err(invalid(&format!("unsupported scheme '{}'", other))),
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| invalid("no host"))?
+            .to_string();
+        let credentials = (!url.username().is_empty()).then(|| {
+            (
+                percent_decode(url.username()),
+                percent_decode(url.password().unwrap_or_default()),
+            )
+        });
+        Ok(Self {
+            kind,
+            host,
+            port: url.port().unwrap_or(default_port),
+            credentials,
+        })
+    }
+
+    /// The proxy the environment selects for `host`, if any.
+    fn from_env(host: &str, secure: bool) -> Result<Option<Self>> {
+        let names: &[&str] = if secure {
+            &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+        } else {
+            &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+        };
+        let Some(value) = names
+            .iter()
+            .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        else {
+            return Ok(None);
+        };
+        if bypasses_proxy(host) {
+            return Ok(None);
+        }
+        Self::parse(&value).map(Some)
+    }
+
+    /// Open a TCP connection to `host:port` through the proxy.
+    async fn tunnel(&self, host: &str, port: u16) -> Result<TcpStream> {
+        let failed = |e: &dyn std::fmt::Display| {
+            NotifError::connection(format!("proxy {}:{}: {}", self.host, self.port, e))
+        };
+        let proxy = (self.host.as_str(), self.port);
+
+        match self.kind {
+            ProxyKind::Http => {
+                let mut stream = TcpStream::connect(proxy).await.map_err(|e| failed(&e))?;
+                let authority = if host.contains(':') {
+                    format!("[{}]:{}", host, port)
+                } else {
+                    format!("{}:{}", host, port)
+                };
+                let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+                if let Some((user, password)) = &self.credentials {
+                    let token = base64::engine::general_purpose::STANDARD
+                        .encode(format!("{}:{}", user, password));
+                    request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+                }
+                request.push_str("\r\n");
+                stream
+                    .write_all(request.as_bytes())
+                    .await
+                    .map_err(|e| failed(&e))?;
+
+                let status = read_connect_response(&mut stream)
+                    .await
+                    .map_err(|e| failed(&e))?;
+                if !status.starts_with("HTTP/1.1 200") && !status.starts_with("HTTP/1.0 200") {
+                    return Err(failed(&format!("CONNECT refused: {}", status)));
+                }
+                Ok(stream)
+            }
+            ProxyKind::Socks5 | ProxyKind::Socks5h => {
+                let stream = if self.kind == ProxyKind::Socks5 {
+                    let target = tokio::net::lookup_host((host, port))
+                        .await
+                        .map_err(|e| failed(&e))?
+                        .next()
+                        .ok_or_else(|| failed(&format!("cannot resolve '{}'", host)))?;
+                    self.socks5(proxy, target).await
+                } else {
+                    self.socks5(proxy, (host, port)).await
+                };
+                stream.map_err(|e| failed(&e))
+            }
+        }
+    }
+
+    async fn socks5<'t>(
+        &self,
+        proxy: (&str, u16),
+        target: impl tokio_socks::IntoTargetAddr<'t>,
+    ) -> std::result::Result<TcpStream, tokio_socks::Error> {
+        let stream = match &self.credentials {
+            Some((user, password)) => {
+                Socks5Stream::connect_with_password(proxy, target, user, password).await?
+            }
+            None => Socks5Stream::connect(proxy, target).await?,
+        };
+        Ok(stream.into_inner())
+    }
+}
+
+/// Read a proxy's `CONNECT` response head and return its status line.
+async fn read_connect_response(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    // Byte by byte, so nothing past the head is consumed
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_CONNECT_RESPONSE || stream.read(&mut byte).await? == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "malformed CONNECT response",
+            ));
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    Ok(head.lines().next().unwrap_or_default().to_string())
+}
+
+/// Whether `NO_PROXY` exempts `host`.
+fn bypasses_proxy(host: &str) -> bool {
+    let Some(no_proxy) = env::var("NO_PROXY").or_else(|_| env::var("no_proxy")).ok() else {
+        return false;
+    };
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+}
+
+/// Decode `%XX` escapes in URL credentials.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
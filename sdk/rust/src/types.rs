@@ -1,10 +1,15 @@
 //! Data types for the notif.sh SDK.
 
+use std::collections::HashMap;
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
-use crate::error::Result;
+use crate::cloudevents;
+use crate::error::{NotifError, Result};
 
 /// Response from emitting an event.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +22,119 @@ pub struct EmitResponse {
     /// When the event was created.
     #[serde(rename = "created_at")]
     pub created_at: DateTime<Utc>,
+    /// Sequence number the server assigned the event in its topic's
+    /// stream, if it reports one. Wait for a subscription or projection to
+    /// reach it with `SequenceWatch::wait_for_sequence`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// When the event was durably stored, with `AckLevel::Stored` or
+    /// `AckLevel::Delivered`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored_at: Option<DateTime<Utc>>,
+    /// When the first consumer or webhook received the event, with
+    /// `AckLevel::Delivered`; `None` if none did while the server waited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// How far an emit must get before `emit` resolves, set with
+/// `EmitOptions::ack_level`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum AckLevel {
+    /// The server accepted the event.
+    #[default]
+    Accepted,
+    /// The event is durably stored and survives a server restart.
+    Stored,
+    /// At least one consumer or webhook received the event. The server
+    /// waits up to 25 seconds, then answers with `delivered_at` unset, so
+    /// emits to topics nobody subscribes to take that long.
+    Delivered,
+}
+
+impl AckLevel {
+    pub(crate) fn is_accepted(&self) -> bool {
+        *self == AckLevel::Accepted
+    }
+}
+
+/// Options for emitting an event.
+#[derive(Debug, Clone, Default)]
+pub struct EmitOptions {
+    /// Custom headers delivered alongside the payload.
+    pub headers: HashMap<String, String>,
+    /// How far the event must get before the emit resolves.
+    pub ack_level: AckLevel,
+}
+
+impl EmitOptions {
+    /// Create new emit options with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header (e.g. a trace ID or tenant ID).
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Add several headers at once.
+    pub fn headers<K, V>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers
+            .extend(headers.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Wait until the event is accepted, stored or delivered
+    /// (default: `AckLevel::Accepted`).
+    ///
+    /// The response's `stored_at` and `delivered_at` report the levels
+    /// reached.
+    pub fn ack_level(mut self, level: AckLevel) -> Self {
+        self.ack_level = level;
+        self
+    }
+
+    /// Mark the event as a CloudEvent (see `Event::to_cloudevent`).
+    ///
+    /// CloudEvents attributes travel as `ce-` headers; the payload is sent
+    /// unchanged. Set `source` and `subject` with their own builders;
+    /// `cloudevents(false)` removes every `ce-` header again.
+    pub fn cloudevents(mut self, enabled: bool) -> Self {
+        let name = format!("{}specversion", cloudevents::HEADER_PREFIX);
+        if enabled {
+            self.headers
+                .insert(name, cloudevents::SPEC_VERSION.to_string());
+        } else {
+            self.headers
+                .retain(|name, _| !name.starts_with(cloudevents::HEADER_PREFIX));
+        }
+        self
+    }
+
+    /// Set the CloudEvents `source` attribute (default: `/topics/<topic>`).
+    ///
+    /// Implies `cloudevents(true)`.
+    pub fn source(self, source: impl Into<String>) -> Self {
+        self.cloudevent_attribute("source", source.into())
+    }
+
+    /// Set the CloudEvents `subject` attribute. Implies `cloudevents(true)`.
+    pub fn subject(self, subject: impl Into<String>) -> Self {
+        self.cloudevent_attribute("subject", subject.into())
+    }
+
+    fn cloudevent_attribute(self, name: &str, value: String) -> Self {
+        let name = format!("{}{}", cloudevents::HEADER_PREFIX, name);
+        self.cloudevents(true).header(name, value)
+    }
 }
 
 /// Options for subscribing to topics.
@@ -28,6 +146,35 @@ pub struct SubscribeOptions {
     pub from: Option<String>,
     /// Consumer group name for load balancing.
     pub group: Option<String>,
+    /// Durable consumer name whose position the server retains.
+    pub durable: Option<String>,
+    /// Maximum events per second delivered to the consumer (default: unlimited).
+    pub max_rate: Option<f64>,
+    /// Reconnect and resubscribe automatically when the connection drops (default: true).
+    pub reconnect: bool,
+    /// Backoff between reconnection attempts.
+    pub backoff: Backoff,
+    /// Number of received events buffered for the consumer (default: 100).
+    pub buffer_size: usize,
+    /// What to do when the buffer is full (default: block).
+    pub backpressure: BackpressurePolicy,
+    /// Interval between heartbeat pings (default: 30s, `None` disables them).
+    pub heartbeat_interval: Option<Duration>,
+    /// Consecutive silent heartbeat intervals before the connection is
+    /// considered stale (default: 2).
+    pub max_missed_heartbeats: u32,
+    /// Resume just after this event (see `SubscribeOptions::from_event_id`).
+    pub resume_after: Option<EventCursor>,
+    /// Only deliver events matching this expression (see `SubscribeOptions::filter`).
+    pub filter: Option<String>,
+    /// Fraction of events delivered (see `SubscribeOptions::sample`).
+    pub sample: Option<f64>,
+    /// Event field whose value orders delivery (see `SubscribeOptions::ordering_key_path`).
+    pub ordering_key_path: Option<String>,
+    /// Suppress repeated event IDs seen within this window (see `SubscribeOptions::dedupe_window`).
+    pub dedupe_window: Option<Duration>,
+    /// How events reach the client (default: WebSocket).
+    pub transport: SubscribeTransport,
 }
 
 impl Default for SubscribeOptions {
@@ -43,6 +190,20 @@ impl SubscribeOptions {
             auto_ack: true,
             from: None,
             group: None,
+            durable: None,
+            max_rate: None,
+            reconnect: true,
+            backoff: Backoff::default(),
+            buffer_size: 100,
+            backpressure: BackpressurePolicy::Block,
+            heartbeat_interval: Some(Duration::from_secs(30)),
+            max_missed_heartbeats: 2,
+            resume_after: None,
+            filter: None,
+            sample: None,
+            ordering_key_path: None,
+            dedupe_window: None,
+            transport: SubscribeTransport::WebSocket,
         }
     }
 
@@ -58,11 +219,405 @@ impl SubscribeOptions {
         self
     }
 
+    /// Resume just after a previously delivered event.
+    ///
+    /// Persist `EventStream::cursor()` while consuming, then pass it here
+    /// after a restart. The subscription starts at the event's timestamp and
+    /// skips everything up to and including the event itself; skipped events
+    /// are acknowledged. Overrides `from`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{EventCursor, Notif, SubscribeOptions};
+    /// # async fn example(saved: String) -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let cursor: EventCursor = saved.parse()?;
+    /// let stream = client
+    ///     .subscribe_with_options(&["orders.*"], SubscribeOptions::new().from_event_id(cursor))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_event_id(mut self, cursor: EventCursor) -> Self {
+        self.from = Some(cursor.timestamp.to_rfc3339());
+        self.resume_after = Some(cursor);
+        self
+    }
+
     /// Set consumer group.
     pub fn group(mut self, group: impl Into<String>) -> Self {
         self.group = Some(group.into());
         self
     }
+
+    /// Name a durable consumer, so the server keeps its position across
+    /// disconnects and restarts.
+    ///
+    /// Reconnecting or subscribing again with the same name and topics
+    /// continues from the first unacknowledged event; `from` only applies
+    /// the first time the consumer is created. Durable names live in their
+    /// own namespace and never share a consumer with a `group` of the same
+    /// name. When `group` is also set, the group's consumer (itself durable)
+    /// is used and this is ignored.
+    pub fn durable(mut self, name: impl Into<String>) -> Self {
+        self.durable = Some(name.into());
+        self
+    }
+
+    /// Server-side consumer group backing this subscription, if durable.
+    pub(crate) fn consumer_group(&self) -> Option<String> {
+        self.group.clone().or_else(|| {
+            self.durable
+                .as_ref()
+                .map(|name| format!("durable-{}", name))
+        })
+    }
+
+    /// Limit delivery to at most `events_per_sec` events per second.
+    ///
+    /// Events beyond the rate wait in the stream's buffer, subject to
+    /// `SubscribeOptions::backpressure`, while the connection keeps being
+    /// read so heartbeats and errors are still handled.
+    pub fn max_rate(mut self, events_per_sec: f64) -> Self {
+        self.max_rate = Some(events_per_sec);
+        self
+    }
+
+    /// Enable or disable automatic reconnection.
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Set the backoff used between reconnection attempts.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set how many received events are buffered for a slow consumer.
+    pub fn buffer_size(mut self, events: usize) -> Self {
+        self.buffer_size = events.max(1);
+        self
+    }
+
+    /// Set what happens when the buffer is full.
+    pub fn backpressure(mut self, policy: BackpressurePolicy) -> Self {
+        self.backpressure = policy;
+        self
+    }
+
+    /// Ping the server every `interval` and treat the connection as stale
+    /// after `max_missed` intervals without any frame from the server.
+    ///
+    /// A stale connection is dropped and handled like any other disconnect:
+    /// it is reconnected, or the stream yields `NotifError::ConnectionStale`
+    /// when reconnection is disabled.
+    pub fn heartbeat(mut self, interval: Duration, max_missed: u32) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self.max_missed_heartbeats = max_missed.max(1);
+        self
+    }
+
+    /// Disable heartbeat pings and stale-connection detection.
+    pub fn no_heartbeat(mut self) -> Self {
+        self.heartbeat_interval = None;
+        self
+    }
+
+    /// Only deliver events matching a filter expression.
+    ///
+    /// Expressions compare `id`, `topic`, `attempt`, `headers.<name>` and
+    /// `data.<path>` with literals using `==`, `!=`, `<`, `<=`, `>`, `>=`,
+    /// combined with `&&`, `||`, `!` and parentheses. The expression is
+    /// sent to the server and also evaluated locally, so servers that do
+    /// not support filtering still only yield matching events. Events
+    /// filtered out locally are acknowledged.
+    ///
+    /// An invalid expression makes subscribing fail with
+    /// `NotifError::InvalidFilter`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SubscribeOptions};
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let stream = client
+    ///     .subscribe_with_options(
+    ///         &["notifications.>"],
+    ///         SubscribeOptions::new().filter("data.level == 'error' || data.urgent"),
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn filter(mut self, expr: impl Into<String>) -> Self {
+        self.filter = Some(expr.into());
+        self
+    }
+
+    /// Only deliver about `ratio` of the events, e.g. `0.01` for 1%.
+    ///
+    /// Events are picked by a hash of their ID, so a redelivered event is
+    /// sampled the same way as the first time. The ratio is sent to the
+    /// server, which then delivers only the sample, and also applied
+    /// locally, so servers that do not support sampling still only yield
+    /// the sample. Events left out locally are acknowledged.
+    ///
+    /// A ratio outside `0.0..=1.0` makes subscribing fail with
+    /// `NotifError::Config`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SubscribeOptions};
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let stream = client
+    ///     .subscribe_with_options(&["metrics.>"], SubscribeOptions::new().sample(0.01))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sample(mut self, ratio: f64) -> Self {
+        self.sample = Some(ratio);
+        self
+    }
+
+    /// Deliver events sharing a key in order, one at a time.
+    ///
+    /// `path` names an event field the way filter expressions do, such as
+    /// `data.order_id` or `headers.tenant`. While an event is unsettled,
+    /// later events with the same key are held back and released oldest
+    /// first as it is acked or nacked, so `EventStream::for_each_concurrent`
+    /// processes each key in order while other keys run in parallel. Events
+    /// without the field are not ordered.
+    ///
+    /// Only applies with `auto_ack(false)`, so held events are bounded by
+    /// the server's limit on unacknowledged events. A nacked event is
+    /// redelivered after the events held behind it.
+    ///
+    /// An invalid path makes subscribing fail with
+    /// `NotifError::InvalidKeyPath`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::{Notif, SubscribeOptions};
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    /// let options = SubscribeOptions::new()
+    ///     .auto_ack(false)
+    ///     .ordering_key_path("data.order_id");
+    /// let stream = client.subscribe_with_options(&["orders.>"], options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ordering_key_path(mut self, path: impl Into<String>) -> Self {
+        self.ordering_key_path = Some(path.into());
+        self
+    }
+
+    /// Suppress events whose ID was already delivered within `window`.
+    ///
+    /// At-least-once delivery can hand out the same event twice, for
+    /// instance when an ack is lost to a reconnect. Duplicates within the
+    /// window are not yielded; with `auto_ack(false)` they are acked unless
+    /// the first copy is still unsettled. Nacked events are forgotten so
+    /// their retry comes through. IDs are remembered for the window only,
+    /// so memory grows with the event rate times the window.
+    ///
+    /// Suppressed events are counted in `notif_events_deduplicated_total`
+    /// with the `metrics` feature.
+    pub fn dedupe_window(mut self, window: Duration) -> Self {
+        self.dedupe_window = Some(window);
+        self
+    }
+
+    /// Receive events over `transport` instead of a WebSocket.
+    ///
+    /// The stream behaves the same either way; use
+    /// [`SubscribeTransport::Sse`] where proxies or platforms block
+    /// WebSocket upgrades.
+    pub fn transport(mut self, transport: SubscribeTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+}
+
+/// Position of a delivered event, for resuming a subscription after it.
+///
+/// Round-trips through `Display`/`FromStr` (`<rfc3339>/<event id>`) and
+/// serde, so it can be persisted anywhere.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCursor {
+    /// ID of the last processed event.
+    pub id: String,
+    /// Timestamp of the last processed event.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl EventCursor {
+    /// Create a cursor from an event ID and its timestamp.
+    pub fn new(id: impl Into<String>, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            id: id.into(),
+            timestamp,
+        }
+    }
+}
+
+impl From<&Event> for EventCursor {
+    fn from(event: &Event) -> Self {
+        Self::new(event.id.clone(), event.timestamp)
+    }
+}
+
+impl std::fmt::Display for EventCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+            self.id
+        )
+    }
+}
+
+impl std::str::FromStr for EventCursor {
+    type Err = NotifError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || NotifError::InvalidCursor(s.to_string());
+        let (timestamp, id) = s.split_once('/').ok_or_else(invalid)?;
+        let timestamp = DateTime::parse_from_rfc3339(timestamp).map_err(|_| invalid())?;
+        if id.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self::new(id, timestamp.with_timezone(&Utc)))
+    }
+}
+
+/// Which operations a client may perform, set with `NotifBuilder::mode`.
+///
+/// Enforced locally, whatever the API key's own scope.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClientMode {
+    /// Every operation is allowed.
+    #[default]
+    ReadWrite,
+    /// Emitting and changing schedules fail with `NotifError::Forbidden`;
+    /// subscribing and queries still work.
+    ReadOnly,
+}
+
+/// Behavior of a full buffer: a subscription whose consumer falls behind,
+/// or a `BufferedEmitter` whose queue fills up while offline.
+///
+/// Events that a subscription drops with `auto_ack(false)` are left
+/// unacknowledged, so the server redelivers them after the ack timeout.
+/// With auto-ack they are lost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BackpressurePolicy {
+    /// Keep every event in the buffer until the consumer catches up.
+    ///
+    /// The connection keeps being read, so the buffer may grow past its
+    /// size. With `auto_ack(false)` it is bounded by the server's limit on
+    /// unacknowledged events; with auto-ack it is not.
+    #[default]
+    Block,
+    /// Discard the oldest buffered event to make room.
+    DropOldest,
+    /// Discard the newly received event.
+    DropNewest,
+    /// End the stream with `NotifError::BufferOverflow`.
+    Error,
+}
+
+/// How a subscription's events reach the client, set with
+/// `SubscribeOptions::transport`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SubscribeTransport {
+    /// A WebSocket connection to `/ws`.
+    #[default]
+    WebSocket,
+    /// Server-Sent Events over a long-lived HTTP response, with acks and
+    /// nacks sent as separate requests.
+    Sse,
+}
+
+/// What a stream does with events rejected by its local filters, set with
+/// `EventStream::on_filtered`.
+///
+/// Only matters with `auto_ack(false)`; auto-acked events are settled
+/// either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FilteredPolicy {
+    /// Acknowledge them, so they are never redelivered.
+    #[default]
+    Ack,
+    /// Negatively acknowledge them, so the server redelivers them after its
+    /// default delay (e.g. to another member of the group).
+    Nack,
+    /// Leave them unsettled until the server's ack timeout.
+    Ignore,
+}
+
+/// Exponential backoff settings for reconnecting a subscription.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// Delay before the first attempt.
+    pub initial: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max: Duration,
+    /// Factor applied to the delay after each failed attempt.
+    pub multiplier: f64,
+    /// Give up after this many consecutive failures (None = retry forever).
+    pub max_retries: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_secs(30))
+    }
+}
+
+impl Backoff {
+    /// Create a backoff starting at `initial` and capped at `max`.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            multiplier: 2.0,
+            max_retries: None,
+        }
+    }
+
+    /// Set the growth factor between attempts.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Give up after `max_retries` consecutive failures.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Delay before the given attempt (0-based).
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(attempt.min(64) as i32);
+        let secs = (self.initial.as_secs_f64() * factor).min(self.max.as_secs_f64());
+        Duration::from_secs_f64(secs)
+    }
 }
 
 /// An event received from a subscription.
@@ -75,55 +630,260 @@ pub struct Event {
     pub topic: String,
     /// Event payload.
     pub data: serde_json::Value,
+    /// Custom headers set by the producer.
+    pub headers: HashMap<String, String>,
     /// When the event was created.
     pub timestamp: DateTime<Utc>,
     /// Current delivery attempt number.
     pub attempt: u32,
     /// Maximum delivery attempts before DLQ.
     pub max_attempts: u32,
+    /// Stream sequence number, if the server reports one.
+    pub seq: Option<u64>,
     /// Internal sender for ack/nack (None if auto_ack is true).
-    pub(crate) ack_tx: Option<mpsc::Sender<AckMessage>>,
+    pub(crate) ack_tx: Option<mpsc::Sender<AckRequest>>,
 }
 
 impl Event {
+    /// Decode the payload into `T` without consuming the event.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn example(event: notifsh::Event) -> notifsh::Result<()> {
+    /// #[derive(serde::Deserialize)]
+    /// struct Order { order_id: String }
+    ///
+    /// let order: Order = event.parse()?;
+    /// println!("order {}", order.order_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T> {
+        T::deserialize(&self.data).map_err(|source| self.decode_error(source))
+    }
+
+    /// Convert into a [`TypedEvent`] by decoding the payload into `T`.
+    pub fn into_typed<T: DeserializeOwned>(self) -> Result<TypedEvent<T>> {
+        match serde_json::from_value(self.data) {
+            Ok(data) => Ok(TypedEvent {
+                id: self.id,
+                topic: self.topic,
+                data,
+                headers: self.headers,
+                timestamp: self.timestamp,
+                attempt: self.attempt,
+                max_attempts: self.max_attempts,
+                seq: self.seq,
+                ack_tx: self.ack_tx,
+            }),
+            Err(source) => Err(NotifError::Decode {
+                id: self.id,
+                topic: self.topic,
+                source,
+            }),
+        }
+    }
+
+    fn decode_error(&self, source: serde_json::Error) -> NotifError {
+        NotifError::Decode {
+            id: self.id.clone(),
+            topic: self.topic.clone(),
+            source,
+        }
+    }
+
     /// Acknowledge the event.
     ///
+    /// Resolves once the ack has been written to the connection. Fails if
+    /// the subscription is closed or the event was delivered on a connection
+    /// that has since dropped, in which case the server redelivers it.
+    ///
     /// This is a no-op if auto_ack is enabled.
     pub async fn ack(&self) -> Result<()> {
-        if let Some(tx) = &self.ack_tx {
-            let _ = tx
-                .send(AckMessage::Ack {
-                    id: self.id.clone(),
-                })
-                .await;
-        }
-        Ok(())
+        send_ack(&self.ack_tx, &self.id).await
     }
 
     /// Negatively acknowledge the event.
     ///
     /// The event will be redelivered after the specified delay.
-    /// Default delay is "5m" (5 minutes).
+    /// Default delay is 5 minutes, which is also used for a zero delay.
+    /// Fails like [`Event::ack`].
     ///
     /// This is a no-op if auto_ack is enabled.
-    pub async fn nack(&self, retry_in: Option<&str>) -> Result<()> {
-        if let Some(tx) = &self.ack_tx {
-            let _ = tx
-                .send(AckMessage::Nack {
-                    id: self.id.clone(),
-                    retry_in: retry_in.map(String::from),
-                })
-                .await;
-        }
-        Ok(())
+    pub async fn nack(&self, retry_in: Option<Duration>) -> Result<()> {
+        send_nack(&self.ack_tx, &self.id, retry_in).await
+    }
+
+    /// Negatively acknowledge the event with a delay such as `"5m"` or `"1h30m"`.
+    ///
+    /// Fails with `NotifError::InvalidDuration` if the delay is not in the
+    /// server's format, instead of silently falling back to the default.
+    pub async fn nack_str(&self, retry_in: &str) -> Result<()> {
+        let retry_in = crate::duration::parse(retry_in)?;
+        self.nack(Some(retry_in)).await
     }
 }
 
+/// An event whose payload has been decoded into `T`.
+///
+/// Obtained from [`Event::into_typed`] or `Notif::subscribe_typed`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TypedEvent<T> {
+    /// Event ID.
+    pub id: String,
+    /// Topic the event was received from.
+    pub topic: String,
+    /// Decoded event payload.
+    pub data: T,
+    /// Custom headers set by the producer.
+    pub headers: HashMap<String, String>,
+    /// When the event was created.
+    pub timestamp: DateTime<Utc>,
+    /// Current delivery attempt number.
+    pub attempt: u32,
+    /// Maximum delivery attempts before DLQ.
+    pub max_attempts: u32,
+    /// Stream sequence number, if the server reports one.
+    pub seq: Option<u64>,
+    pub(crate) ack_tx: Option<mpsc::Sender<AckRequest>>,
+}
+
+impl<T> TypedEvent<T> {
+    /// Acknowledge the event.
+    ///
+    /// This is a no-op if auto_ack is enabled.
+    pub async fn ack(&self) -> Result<()> {
+        send_ack(&self.ack_tx, &self.id).await
+    }
+
+    /// Negatively acknowledge the event.
+    ///
+    /// This is a no-op if auto_ack is enabled.
+    pub async fn nack(&self, retry_in: Option<Duration>) -> Result<()> {
+        send_nack(&self.ack_tx, &self.id, retry_in).await
+    }
+
+    /// Negatively acknowledge the event with a delay such as `"5m"`.
+    pub async fn nack_str(&self, retry_in: &str) -> Result<()> {
+        let retry_in = crate::duration::parse(retry_in)?;
+        self.nack(Some(retry_in)).await
+    }
+}
+
+async fn send_ack(ack_tx: &Option<mpsc::Sender<AckRequest>>, id: &str) -> Result<()> {
+    settle(ack_tx, AckMessage::Ack { id: id.to_string() }).await
+}
+
+async fn send_nack(
+    ack_tx: &Option<mpsc::Sender<AckRequest>>,
+    id: &str,
+    retry_in: Option<Duration>,
+) -> Result<()> {
+    let msg = AckMessage::Nack {
+        id: id.to_string(),
+        retry_in,
+    };
+    settle(ack_tx, msg).await
+}
+
+/// Hand an ack or nack to the subscription and wait until it is written.
+pub(crate) async fn settle(
+    ack_tx: &Option<mpsc::Sender<AckRequest>>,
+    msg: AckMessage,
+) -> Result<()> {
+    let Some(tx) = ack_tx else {
+        return Ok(());
+    };
+    let (reply, outcome) = oneshot::channel();
+    tx.send(AckRequest { msg, reply })
+        .await
+        .map_err(|_| NotifError::connection("subscription is closed"))?;
+    outcome.await.unwrap_or_else(|_| {
+        Err(NotifError::connection(
+            "subscription closed before the ack was sent",
+        ))
+    })
+}
+
+/// Lifecycle notification for a subscription, from `EventStream::control_events`.
+///
+/// Mostly useful with consumer groups, where unacked events move to another
+/// member when this one loses its connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ControlEvent {
+    /// The subscription was established (initially or after reconnecting).
+    Assigned {
+        /// Server-side consumer ID for this member.
+        consumer_id: Option<String>,
+        /// Topics the server confirmed.
+        topics: Vec<String>,
+    },
+    /// The connection was lost; in-flight events may be redelivered elsewhere.
+    Revoked {
+        /// Consumer ID that was active before the connection dropped.
+        consumer_id: Option<String>,
+    },
+    /// The server reported a change in group membership.
+    Rebalanced {
+        /// Current consumer ID for this member.
+        consumer_id: Option<String>,
+        /// Topics now assigned to this member.
+        topics: Vec<String>,
+    },
+}
+
+/// Health of a subscription's connection, or of all of a client's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionState {
+    /// Connected and receiving events.
+    Connected,
+    /// The connection was lost and is being re-established.
+    Reconnecting,
+    /// Closed, or reconnection gave up.
+    Disconnected,
+}
+
 /// Internal message for ack/nack operations.
 #[derive(Debug)]
 pub(crate) enum AckMessage {
-    Ack { id: String },
-    Nack { id: String, retry_in: Option<String> },
+    Ack {
+        id: String,
+    },
+    Nack {
+        id: String,
+        retry_in: Option<Duration>,
+    },
+}
+
+/// An ack or nack together with where to report whether it was sent.
+#[derive(Debug)]
+pub(crate) struct AckRequest {
+    pub msg: AckMessage,
+    pub reply: oneshot::Sender<Result<()>>,
+}
+
+impl AckMessage {
+    /// ID of the event being settled.
+    pub(crate) fn id(&self) -> &str {
+        match self {
+            AckMessage::Ack { id } | AckMessage::Nack { id, .. } => id,
+        }
+    }
+
+    /// The same settlement applied to another event.
+    pub(crate) fn for_id(&self, id: String) -> Self {
+        match self {
+            AckMessage::Ack { .. } => AckMessage::Ack { id },
+            AckMessage::Nack { retry_in, .. } => AckMessage::Nack {
+                id,
+                retry_in: *retry_in,
+            },
+        }
+    }
 }
 
 // WebSocket protocol messages
@@ -143,6 +903,17 @@ pub(crate) struct SubscribeOptionsWire {
     pub from: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PingMessage {
+    pub action: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -167,9 +938,11 @@ pub(crate) struct ServerMessage {
     pub id: Option<String>,
     pub topic: Option<String>,
     pub data: Option<serde_json::Value>,
+    pub headers: Option<HashMap<String, String>>,
     pub timestamp: Option<DateTime<Utc>>,
     pub attempt: Option<u32>,
     pub max_attempts: Option<u32>,
+    pub seq: Option<u64>,
     // Subscribed fields
     pub topics: Option<Vec<String>>,
     pub consumer_id: Option<String>,
@@ -184,6 +957,341 @@ pub(crate) struct ServerMessage {
 pub(crate) struct EmitRequest<'a, T: Serialize> {
     pub topic: &'a str,
     pub data: T,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub headers: &'a HashMap<String, String>,
+    #[serde(skip_serializing_if = "AckLevel::is_accepted")]
+    pub ack_level: AckLevel,
+}
+
+// History types
+
+/// Options for querying past events with `Notif::history`.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryOptions {
+    /// Only return events created at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only return events created at or before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Maximum number of events per page (server default: 100, max: 1000).
+    pub limit: Option<u32>,
+    /// Resume after this sequence number (from `HistoryPage::next_cursor`).
+    pub cursor: Option<u64>,
+}
+
+impl HistoryOptions {
+    /// Create new history options with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the start of the time range.
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Set the end of the time range.
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Set the page size.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Resume after a previous page.
+    pub fn cursor(mut self, cursor: u64) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+}
+
+/// How `Notif::replay` paces the events it redelivers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplaySpeed {
+    /// Deliver events one after another without waiting.
+    #[default]
+    Instant,
+    /// Keep the original gaps between events, measured from the first one.
+    RealTime,
+}
+
+/// Options for redelivering past events with `Notif::replay`.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    /// Start at events created at or after this time (default: the oldest retained).
+    pub from: Option<DateTime<Utc>>,
+    /// Stop after events created at or before this time (default: the newest).
+    pub until: Option<DateTime<Utc>>,
+    /// How events are paced.
+    pub speed: ReplaySpeed,
+}
+
+impl ReplayOptions {
+    /// Create new replay options with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the start of the time range.
+    pub fn from(mut self, from: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Set the end of the time range.
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Set how events are paced.
+    pub fn speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+/// A past event returned by `Notif::history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct HistoryEvent {
+    /// Stream sequence number.
+    pub seq: u64,
+    /// Event ID.
+    pub id: String,
+    /// Topic the event was published to.
+    pub topic: String,
+    /// Event payload.
+    pub data: serde_json::Value,
+    /// Custom headers set by the producer.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// When the event was created.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A page of past events.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HistoryPage {
+    /// Events in stream order.
+    pub events: Vec<HistoryEvent>,
+    /// Cursor for the next page, or `None` if this was the last one.
+    pub next_cursor: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StoredEventWire {
+    pub seq: u64,
+    pub event: StoredEventBody,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StoredEventBody {
+    pub id: String,
+    pub topic: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListEventsResponse {
+    #[serde(default)]
+    pub events: Vec<StoredEventWire>,
+}
+
+// Delivery types
+
+/// A delivery of an event to a subscriber or webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Delivery {
+    /// Delivery ID.
+    pub id: String,
+    /// ID of the delivered event.
+    pub event_id: String,
+    /// Receiver kind: "websocket" or "webhook".
+    pub receiver_type: String,
+    /// Delivery status, such as "pending", "delivered", "acked" or "dlq".
+    pub status: String,
+    /// Delivery attempt number.
+    pub attempt: u32,
+    /// Consumer that received the event (WebSocket deliveries).
+    #[serde(default)]
+    pub consumer_name: Option<String>,
+    /// Client connection that received the event (WebSocket deliveries).
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Webhook that received the event (webhook deliveries).
+    #[serde(default)]
+    pub receiver_id: Option<String>,
+    /// Target URL (webhook deliveries).
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// When the delivery was created.
+    pub created_at: DateTime<Utc>,
+    /// When the event reached the receiver.
+    #[serde(default)]
+    pub delivered_at: Option<DateTime<Utc>>,
+    /// When the receiver acknowledged the event.
+    #[serde(default)]
+    pub acked_at: Option<DateTime<Utc>>,
+    /// Last delivery error.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Delivery {
+    /// Whether the receiver acknowledged the event.
+    pub fn is_acked(&self) -> bool {
+        self.acked_at.is_some() || self.status == "acked"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DeliveriesResponse {
+    pub deliveries: Vec<Delivery>,
+}
+
+// Consumer types
+
+/// Backlog of a consumer group, from `Notif::consumer_lag`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConsumerLag {
+    /// Consumer group name.
+    pub group: String,
+    /// Topics the group subscribes with.
+    pub topics: Vec<String>,
+    /// Events not yet delivered to any member of the group.
+    pub pending: u64,
+    /// Events delivered to a member and not yet acked or nacked.
+    pub in_flight: u64,
+    /// When the oldest pending event was stored. Only reported for groups
+    /// subscribed to a single topic or pattern.
+    #[serde(default)]
+    pub oldest_pending_at: Option<DateTime<Utc>>,
+}
+
+impl ConsumerLag {
+    /// Events the group still has to process: pending plus in flight.
+    pub fn backlog(&self) -> u64 {
+        self.pending + self.in_flight
+    }
+}
+
+// Topic types
+
+/// A topic and what the server stores for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TopicInfo {
+    /// Topic name.
+    pub name: String,
+    /// Number of stored events.
+    pub messages: u64,
+    /// When the oldest stored event was emitted.
+    #[serde(default)]
+    pub first_event_at: Option<DateTime<Utc>>,
+    /// When the newest event was emitted.
+    #[serde(default)]
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// How long events are kept. The limits apply to the stream all
+    /// topics of the account share, not to this topic alone.
+    #[serde(default)]
+    pub retention: Option<TopicRetention>,
+}
+
+/// Retention limits of a topic. Unset limits are unbounded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TopicRetention {
+    /// Longest time an event is kept, in seconds.
+    #[serde(default)]
+    pub max_age_seconds: Option<u64>,
+    /// Most events kept before the oldest are discarded.
+    #[serde(default)]
+    pub max_messages: Option<u64>,
+    /// Most bytes kept before the oldest events are discarded.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+impl TopicRetention {
+    /// Longest time an event is kept.
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age_seconds.map(Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListTopicsResponse {
+    pub topics: Vec<TopicInfo>,
+}
+
+// Identity types
+
+/// The account, project and key an API key resolves to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Identity {
+    /// Organization owning the project.
+    pub org_id: String,
+    /// Project the key emits to and subscribes in.
+    pub project_id: String,
+    /// Display name of the project.
+    #[serde(default)]
+    pub project_name: Option<String>,
+    /// ID of the API key, unless authenticated another way.
+    #[serde(default)]
+    pub key_id: Option<String>,
+    /// Name given to the API key.
+    #[serde(default)]
+    pub key_name: Option<String>,
+    /// Visible start of the key, e.g. `nsh_ab12cd34ef56`.
+    #[serde(default)]
+    pub key_prefix: Option<String>,
+}
+
+// Webhook types
+
+/// An HTTP endpoint the server pushes events to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Webhook {
+    /// Webhook ID.
+    pub id: String,
+    /// Endpoint receiving the events.
+    pub url: String,
+    /// Topic patterns whose events are pushed.
+    pub topics: Vec<String>,
+    /// Signing secret; only returned when the webhook is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Whether deliveries are active.
+    pub enabled: bool,
+    /// When the webhook was created.
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CreateWebhookRequest<'a> {
+    pub url: &'a str,
+    pub topics: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListWebhooksResponse {
+    pub webhooks: Vec<Webhook>,
 }
 
 // Schedule types
@@ -224,6 +1332,52 @@ pub struct Schedule {
     pub executed_at: Option<DateTime<Utc>>,
 }
 
+/// Changes to a pending scheduled event, for `Notif::update_schedule`.
+///
+/// Fields left unset keep their current value.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSchedule {
+    /// New topic.
+    pub topic: Option<String>,
+    /// New payload.
+    pub data: Option<serde_json::Value>,
+    /// New absolute emit time.
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// New emit time, relative to now.
+    pub in_duration: Option<Duration>,
+}
+
+impl UpdateSchedule {
+    /// Create an empty update.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the topic.
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Set the payload.
+    pub fn data(mut self, data: impl Into<serde_json::Value>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// Set the absolute emit time.
+    pub fn scheduled_for(mut self, scheduled_for: DateTime<Utc>) -> Self {
+        self.scheduled_for = Some(scheduled_for);
+        self
+    }
+
+    /// Set the emit time relative to now.
+    pub fn in_duration(mut self, delay: Duration) -> Self {
+        self.in_duration = Some(delay);
+        self
+    }
+}
+
 /// Response from listing scheduled events.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -234,6 +1388,58 @@ pub struct ListSchedulesResponse {
     pub total: i64,
 }
 
+/// Filters for walking scheduled events with `Notif::schedules`.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleFilter {
+    /// Only schedules with this status (pending, completed, cancelled, failed).
+    pub status: Option<String>,
+    /// Only schedules whose topic matches this pattern.
+    pub topic: Option<String>,
+    /// Only schedules due at or after this time.
+    pub after: Option<DateTime<Utc>>,
+    /// Only schedules due at or before this time.
+    pub before: Option<DateTime<Utc>>,
+    /// Schedules fetched per request (default: 100).
+    pub page_size: Option<u32>,
+}
+
+impl ScheduleFilter {
+    /// Create a filter matching every schedule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return schedules with `status`.
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    /// Only return schedules whose topic matches `pattern`.
+    pub fn topic(mut self, pattern: impl Into<String>) -> Self {
+        self.topic = Some(pattern.into());
+        self
+    }
+
+    /// Only return schedules due at or after `after`.
+    pub fn after(mut self, after: DateTime<Utc>) -> Self {
+        self.after = Some(after);
+        self
+    }
+
+    /// Only return schedules due at or before `before`.
+    pub fn before(mut self, before: DateTime<Utc>) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    /// Set how many schedules each request fetches.
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+}
+
 /// Response from running a scheduled event immediately.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -253,3 +1459,88 @@ pub(crate) struct CreateScheduleRequest<'a, T: Serialize> {
     #[serde(skip_serializing_if = "Option::is_none", rename = "in")]
     pub in_duration: Option<&'a str>,
 }
+
+#[derive(Debug, Serialize)]
+pub(crate) struct UpdateScheduleRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<&'a serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_for: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "in")]
+    pub in_duration: Option<String>,
+}
+
+// Schema types
+
+/// A JSON Schema registered for a topic pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Schema {
+    /// Schema ID.
+    pub id: String,
+    /// Unique name within the project.
+    pub name: String,
+    /// Topic pattern the schema applies to.
+    pub topic_pattern: String,
+    /// Free-form description.
+    #[serde(default)]
+    pub description: String,
+    /// Free-form tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When the schema was registered.
+    pub created_at: DateTime<Utc>,
+    /// When the schema was last changed.
+    pub updated_at: DateTime<Utc>,
+    /// The version events are validated against, if any.
+    #[serde(default)]
+    pub latest_version: Option<SchemaVersion>,
+}
+
+/// An immutable version of a registered schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SchemaVersion {
+    /// Version ID.
+    pub id: String,
+    /// ID of the schema this version belongs to.
+    pub schema_id: String,
+    /// Version label, e.g. `"3"`.
+    pub version: String,
+    /// The JSON Schema document.
+    pub schema: serde_json::Value,
+    /// How the server handles invalid emits: `strict`, `warn` or `disabled`.
+    #[serde(default)]
+    pub validation_mode: String,
+    /// What a strict server does with invalid emits: `reject`, `log` or `dlq`.
+    #[serde(default)]
+    pub on_invalid: String,
+    /// Hash of the schema document.
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Whether this is the version currently in force.
+    #[serde(default)]
+    pub is_latest: bool,
+    /// When the version was created.
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CreateSchemaRequest<'a> {
+    pub name: &'a str,
+    pub topic_pattern: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CreateSchemaVersionRequest<'a> {
+    pub version: &'a str,
+    pub schema: &'a serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListSchemaVersionsResponse {
+    #[serde(default)]
+    pub versions: Vec<SchemaVersion>,
+}
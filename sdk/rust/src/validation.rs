@@ -0,0 +1,115 @@
+//! Checking payloads against registered schemas locally (`jsonschema` feature).
+//!
+//! Schemas are fetched per topic and cached for a minute, so a check
+//! costs one request per topic and minute at most.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::client::Notif;
+use crate::error::{NotifError, Result};
+
+/// How long a fetched schema, or its absence, is trusted.
+const SCHEMA_TTL: Duration = Duration::from_secs(60);
+
+/// Which payloads `NotifBuilder::validate_schemas` checks locally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaValidation {
+    /// Check emits before they are sent.
+    Emits,
+    /// Check emits, and received events before they are yielded.
+    EmitsAndEvents,
+}
+
+struct Cached {
+    validator: Option<Arc<jsonschema::Validator>>,
+    fetched: Instant,
+}
+
+/// Compiled schemas by topic, for local validation.
+#[derive(Default)]
+pub(crate) struct Schemas {
+    mode: Option<SchemaValidation>,
+    cache: Mutex<HashMap<String, Cached>>,
+}
+
+impl Schemas {
+    pub(crate) fn new(mode: Option<SchemaValidation>) -> Self {
+        Self {
+            mode,
+            cache: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn validates_emits(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    pub(crate) fn validates_events(&self) -> bool {
+        self.mode == Some(SchemaValidation::EmitsAndEvents)
+    }
+
+    /// Forget every fetched schema, e.g. after registering one.
+    pub(crate) fn invalidate(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Check `data` against the schema registered for `topic`, if any.
+    pub(crate) async fn check(
+        &self,
+        client: &Notif,
+        topic: &str,
+        data: &serde_json::Value,
+    ) -> Result<()> {
+        let Some(validator) = self.validator(client, topic).await? else {
+            return Ok(());
+        };
+        let violation =
+            validator
+                .iter_errors(data)
+                .next()
+                .map(|error| NotifError::SchemaViolation {
+                    path: error.instance_path().to_string(),
+                    message: error.to_string(),
+                });
+        violation.map_or(Ok(()), Err)
+    }
+
+    async fn validator(
+        &self,
+        client: &Notif,
+        topic: &str,
+    ) -> Result<Option<Arc<jsonschema::Validator>>> {
+        if let Some(cached) = self.cache.lock().unwrap().get(topic) {
+            if cached.fetched.elapsed() < SCHEMA_TTL {
+                return Ok(cached.validator.clone());
+            }
+        }
+
+        let schema = client
+            .schema_for_topic(topic)
+            .await?
+            .and_then(|schema| schema.latest_version);
+        let validator = match schema {
+            Some(version) => {
+                let validator = jsonschema::validator_for(&version.schema).map_err(|e| {
+                    NotifError::Config(format!("invalid schema for topic '{}': {}", topic, e))
+                })?;
+                Some(Arc::new(validator))
+            }
+            None => None,
+        };
+        self.cache.lock().unwrap().insert(
+            topic.to_string(),
+            Cached {
+                validator: validator.clone(),
+                fetched: Instant::now(),
+            },
+        );
+        Ok(validator)
+    }
+}
@@ -0,0 +1,429 @@
+//! Browser client for `wasm32-unknown-unknown`, built with the `wasm` feature.
+//!
+//! Emits go through `fetch` and subscriptions through the browser's
+//! `WebSocket`, with acks and nacks written from tasks on the page's event
+//! loop. It covers emitting and subscribing only, and a subscription ends
+//! when its connection closes instead of reconnecting.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use chrono::Utc;
+use futures_util::Stream;
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{CloseEvent, MessageEvent, WebSocket};
+
+use crate::error::{NotifError, Result};
+use crate::topic::Topic;
+use crate::types::{
+    AckMessage, AckRequest, AckWireMessage, EmitOptions, EmitRequest, EmitResponse, Event,
+    NackWireMessage, ServerMessage, SubscribeMessage, SubscribeOptions, SubscribeOptionsWire,
+};
+
+const DEFAULT_SERVER: &str = "https://api.notif.sh";
+const API_KEY_PREFIX: &str = "nsh_";
+
+/// Capacity of the ack channel of a subscription.
+const ACK_CHANNEL_SIZE: usize = 100;
+
+/// Builder for a browser [`Notif`] client.
+pub struct NotifBuilder {
+    api_key: String,
+    server: String,
+}
+
+impl NotifBuilder {
+    /// Create a new builder with the given API key.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            server: DEFAULT_SERVER.to_string(),
+        }
+    }
+
+    /// Set the server URL.
+    pub fn server(mut self, server: impl Into<String>) -> Self {
+        self.server = server.into();
+        self
+    }
+
+    /// Build the Notif client.
+    pub fn build(self) -> Result<Notif> {
+        if !self.api_key.starts_with(API_KEY_PREFIX) {
+            return Err(NotifError::auth(format!(
+                "API key must start with '{}'",
+                API_KEY_PREFIX
+            )));
+        }
+        Ok(Notif {
+            inner: Arc::new(NotifInner {
+                api_key: self.api_key,
+                server: self.server.trim_end_matches('/').to_string(),
+                http: reqwest::Client::new(),
+            }),
+        })
+    }
+}
+
+impl fmt::Debug for NotifBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotifBuilder")
+            .field("server", &self.server)
+            .finish_non_exhaustive()
+    }
+}
+
+struct NotifInner {
+    api_key: String,
+    server: String,
+    http: reqwest::Client,
+}
+
+/// Client for the notif.sh event hub, running in the browser.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use notifsh::Notif;
+/// use serde_json::json;
+///
+/// # async fn example() -> notifsh::Result<()> {
+/// let client = Notif::builder("nsh_...").build()?;
+/// client.emit("orders.created", json!({"order_id": "123"})).await?;
+///
+/// let mut stream = client.subscribe(&["orders.*"]).await?;
+/// while let Some(event) = stream.next().await {
+///     let event = event?;
+///     web_sys::console::log_1(&event.topic.into());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Notif {
+    inner: Arc<NotifInner>,
+}
+
+impl Notif {
+    /// Create a new builder with the given API key.
+    pub fn builder(api_key: impl Into<String>) -> NotifBuilder {
+        NotifBuilder::new(api_key)
+    }
+
+    /// Get the configured server URL.
+    pub fn server_url(&self) -> &str {
+        &self.inner.server
+    }
+
+    /// Emit an event to a topic.
+    pub async fn emit<T: Serialize>(&self, topic: &str, data: T) -> Result<EmitResponse> {
+        self.emit_with_options(topic, data, EmitOptions::new())
+            .await
+    }
+
+    /// Emit an event with custom options such as headers.
+    pub async fn emit_with_options<T: Serialize>(
+        &self,
+        topic: &str,
+        data: T,
+        options: EmitOptions,
+    ) -> Result<EmitResponse> {
+        Topic::name(topic)?;
+        let request = EmitRequest {
+            topic,
+            data,
+            headers: &options.headers,
+            ack_level: options.ack_level,
+        };
+        let response = self
+            .inner
+            .http
+            .post(format!("{}/api/v1/emit", self.inner.server))
+            .bearer_auth(&self.inner.api_key)
+            .json(&request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(NotifError::from_response(
+                status.as_u16(),
+                &body,
+                None,
+                None,
+            ));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Subscribe to one or more topics.
+    pub async fn subscribe(&self, topics: &[&str]) -> Result<EventStream> {
+        self.subscribe_with_options(topics, SubscribeOptions::default())
+            .await
+    }
+
+    /// Subscribe to topics with custom options.
+    ///
+    /// Only `auto_ack`, `from`, `group`, `durable`, `filter` and `sample`
+    /// apply in the browser; the filter and the sample are evaluated by the
+    /// server alone.
+    pub async fn subscribe_with_options(
+        &self,
+        topics: &[&str],
+        options: SubscribeOptions,
+    ) -> Result<EventStream> {
+        let topics = topics
+            .iter()
+            .map(|topic| Topic::parse(topic).map(String::from))
+            .collect::<Result<Vec<_>>>()?;
+        EventStream::connect(&self.inner, topics, options).await
+    }
+}
+
+impl fmt::Debug for Notif {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Notif")
+            .field("server", &self.inner.server)
+            .finish_non_exhaustive()
+    }
+}
+
+/// State shared by the socket's handlers.
+struct Connection {
+    /// Resolves `connect` on the server's answer to the subscribe message.
+    ready: Option<oneshot::Sender<Result<()>>>,
+    events: Option<mpsc::UnboundedSender<Result<Event>>>,
+    ack_tx: Option<mpsc::Sender<AckRequest>>,
+}
+
+impl Connection {
+    fn handle_text(&mut self, text: &str) {
+        let msg = match serde_json::from_str::<ServerMessage>(text) {
+            Ok(msg) => msg,
+            Err(e) => return self.deliver(Err(NotifError::Serialization(e))),
+        };
+        match msg.msg_type.as_str() {
+            "subscribed" => {
+                if let Some(ready) = self.ready.take() {
+                    let _ = ready.send(Ok(()));
+                }
+            }
+            "error" => {
+                let message = msg
+                    .message
+                    .unwrap_or_else(|| "subscription error".to_string());
+                let error = NotifError::api(400, message);
+                match self.ready.take() {
+                    Some(ready) => {
+                        let _ = ready.send(Err(error));
+                    }
+                    None => self.deliver(Err(error)),
+                }
+            }
+            "event" => {
+                let (Some(id), Some(topic)) = (msg.id, msg.topic) else {
+                    return self.deliver(Err(NotifError::websocket(
+                        "malformed event: missing id or topic",
+                    )));
+                };
+                self.deliver(Ok(Event {
+                    id,
+                    topic,
+                    data: msg.data.unwrap_or(serde_json::Value::Null),
+                    headers: msg.headers.unwrap_or_default(),
+                    timestamp: msg.timestamp.unwrap_or_else(Utc::now),
+                    attempt: msg.attempt.unwrap_or(1),
+                    max_attempts: msg.max_attempts.unwrap_or(3),
+                    seq: msg.seq,
+                    ack_tx: self.ack_tx.clone(),
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_close(&mut self, code: u16, reason: &str) {
+        let error = NotifError::websocket(format!("connection closed ({}): {}", code, reason));
+        match self.ready.take() {
+            Some(ready) => {
+                let _ = ready.send(Err(error));
+            }
+            // A normal closure just ends the stream
+            None if code != 1000 => self.deliver(Err(error)),
+            None => {}
+        }
+        self.events = None;
+        self.ack_tx = None;
+    }
+
+    fn deliver(&mut self, item: Result<Event>) {
+        if let Some(events) = &self.events {
+            let _ = events.send(item);
+        }
+    }
+}
+
+/// The socket's handlers, detached when the stream is dropped.
+struct Handlers {
+    on_open: Closure<dyn FnMut()>,
+    on_message: Closure<dyn FnMut(MessageEvent)>,
+    on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+/// A stream of events from a browser subscription.
+///
+/// Implements `futures::Stream<Item = Result<Event>>`. It ends when the
+/// connection closes, after yielding an error unless the server closed it
+/// normally. Dropping it closes the connection.
+pub struct EventStream {
+    socket: WebSocket,
+    events: mpsc::UnboundedReceiver<Result<Event>>,
+    _handlers: Handlers,
+}
+
+impl EventStream {
+    async fn connect(
+        inner: &NotifInner,
+        topics: Vec<String>,
+        options: SubscribeOptions,
+    ) -> Result<Self> {
+        let ws_url = inner
+            .server
+            .replace("https://", "wss://")
+            .replace("http://", "ws://");
+        let socket = WebSocket::new(&format!("{}/ws?token={}", ws_url, inner.api_key))
+            .map_err(|e| NotifError::websocket(format!("failed to open WebSocket: {:?}", e)))?;
+
+        let subscribe_msg = serde_json::to_string(&SubscribeMessage {
+            action: "subscribe".to_string(),
+            topics,
+            options: Some(SubscribeOptionsWire {
+                auto_ack: options.auto_ack,
+                from: options.from.clone(),
+                group: options.consumer_group(),
+                filter: options.filter.clone(),
+                sample: options.sample,
+                encoding: None,
+            }),
+        })?;
+
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (ack_tx, ack_rx) = mpsc::channel(ACK_CHANNEL_SIZE);
+        let connection = Rc::new(RefCell::new(Connection {
+            ready: Some(ready_tx),
+            events: Some(event_tx),
+            ack_tx: (!options.auto_ack).then_some(ack_tx),
+        }));
+
+        let on_open = {
+            let socket = socket.clone();
+            let connection = connection.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                if let Err(e) = socket.send_with_str(&subscribe_msg) {
+                    let error = NotifError::websocket(format!("failed to send subscribe: {:?}", e));
+                    if let Some(ready) = connection.borrow_mut().ready.take() {
+                        let _ = ready.send(Err(error));
+                    }
+                }
+            })
+        };
+        let on_message = {
+            let connection = connection.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |message: MessageEvent| {
+                if let Some(text) = message.data().as_string() {
+                    connection.borrow_mut().handle_text(&text);
+                }
+            })
+        };
+        let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |close: CloseEvent| {
+            connection
+                .borrow_mut()
+                .handle_close(close.code(), &close.reason());
+        });
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        let stream = EventStream {
+            socket: socket.clone(),
+            events: event_rx,
+            _handlers: Handlers {
+                on_open,
+                on_message,
+                on_close,
+            },
+        };
+        ready_rx
+            .await
+            .unwrap_or_else(|_| Err(NotifError::websocket("connection closed unexpectedly")))?;
+
+        wasm_bindgen_futures::spawn_local(write_settlements(socket, ack_rx));
+        Ok(stream)
+    }
+
+    /// Close the subscription.
+    ///
+    /// Dropping the stream does the same.
+    pub async fn close(self) -> Result<()> {
+        drop(self);
+        Ok(())
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        // The handlers are freed with the stream, so the socket must stop
+        // calling them first
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
+
+impl fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream").finish_non_exhaustive()
+    }
+}
+
+/// Write acks and nacks until every event of the subscription is gone.
+async fn write_settlements(socket: WebSocket, mut ack_rx: mpsc::Receiver<AckRequest>) {
+    while let Some(request) = ack_rx.recv().await {
+        let json = match request.msg {
+            AckMessage::Ack { id } => serde_json::to_string(&AckWireMessage {
+                action: "ack".to_string(),
+                id,
+            }),
+            AckMessage::Nack { id, retry_in } => serde_json::to_string(&NackWireMessage {
+                action: "nack".to_string(),
+                id,
+                retry_in: retry_in.map(crate::duration::format),
+            }),
+        };
+        let result = json.map_err(NotifError::from).and_then(|json| {
+            socket
+                .send_with_str(&json)
+                .map_err(|e| NotifError::websocket(format!("failed to send ack: {:?}", e)))
+        });
+        let _ = request.reply.send(result);
+    }
+}
@@ -0,0 +1,73 @@
+//! Management of outbound webhooks over HTTP.
+
+use reqwest::Method;
+
+use crate::client::Notif;
+use crate::error::Result;
+use crate::topic::Topic;
+use crate::types::{CreateWebhookRequest, ListWebhooksResponse, Webhook};
+
+impl Notif {
+    /// Register an HTTP endpoint that the server pushes matching events to.
+    ///
+    /// `secret` signs the deliveries and must be 16 to 256 characters;
+    /// pass `None` to let the server generate one. Either way the secret in use is only returned here,
+    /// in `Webhook::secret`, so store it to verify deliveries.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use notifsh::Notif;
+    /// # async fn example() -> notifsh::Result<()> {
+    /// let client = Notif::from_env()?;
+    ///
+    /// let webhook = client
+    ///     .create_webhook(&["orders.*"], "https://example.com/hooks/notif", None)
+    ///     .await?;
+    /// println!("signing secret: {:?}", webhook.secret);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_webhook(
+        &self,
+        topics: &[&str],
+        url: &str,
+        secret: Option<&str>,
+    ) -> Result<Webhook> {
+        self.check_writable("create webhooks")?;
+        let topics = topics
+            .iter()
+            .map(|topic| Topic::parse(topic).map(String::from))
+            .collect::<Result<Vec<_>>>()?;
+        url::Url::parse(url)?;
+
+        let request = CreateWebhookRequest {
+            url,
+            topics,
+            secret,
+        };
+        let response = self
+            .execute(self.http(Method::POST, "/api/v1/webhooks").json(&request))
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// List the webhooks registered for the project.
+    ///
+    /// Secrets are not included.
+    pub async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        let response = self
+            .execute(self.http(Method::GET, "/api/v1/webhooks"))
+            .await?;
+        let list: ListWebhooksResponse = response.json().await?;
+        Ok(list.webhooks)
+    }
+
+    /// Delete a webhook; the server stops pushing to it.
+    pub async fn delete_webhook(&self, id: &str) -> Result<()> {
+        self.check_writable("delete webhooks")?;
+        let path = format!("/api/v1/webhooks/{}", id);
+        self.execute(self.http(Method::DELETE, &path)).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+//! Aggregating events over time or count windows.
+//!
+//! [`WindowExt`] turns any stream of events into windows: tumbling time
+//! windows by event timestamp, aligned to the Unix epoch, or windows of a
+//! fixed number of events. Each window folds its events into a value and
+//! is yielded as a [`Window`] once it closes.
+//!
+//! Time windows close on a watermark: the latest event timestamp seen,
+//! minus the allowed lateness. A window ends once the watermark passes
+//! its end; events arriving for a window that already closed are late,
+//! and are acked and dropped. Since the watermark only moves with events,
+//! open windows are also closed after a stretch without any (by default,
+//! one window length) and when the stream ends.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use notifsh::{Notif, WindowExt};
+//!
+//! # async fn example(client: Notif) -> notifsh::Result<()> {
+//! let stream = client.subscribe(&["http.requests"]).await?;
+//! let mut per_minute = stream
+//!     .tumbling(Duration::from_secs(60))
+//!     .allowed_lateness(Duration::from_secs(5))
+//!     .group_by(|event| event.data["route"].as_str().unwrap_or("").to_string())
+//!     .aggregate(0.0, |total_ms, event| {
+//!         *total_ms += event.data["duration_ms"].as_f64().unwrap_or(0.0)
+//!     });
+//!
+//! while let Some(window) = per_minute.next().await {
+//!     let window = window?;
+//!     let mean = window.value / window.count as f64;
+//!     println!("{} {}: {} requests, {mean:.1}ms mean", window.start, window.key, window.count);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_util::stream::{self, BoxStream};
+use futures_util::{Stream, StreamExt};
+
+use crate::error::Result;
+use crate::types::Event;
+
+type KeyFn = dyn Fn(&Event) -> String + Send + Sync;
+
+/// The events of one window, folded into a value.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Window<A> {
+    /// Group of the window (see [`Windows::group_by`]); empty without
+    /// grouping.
+    pub key: String,
+    /// Start of a time window, or timestamp of the first event of a count
+    /// window.
+    pub start: DateTime<Utc>,
+    /// End of a time window (exclusive), or timestamp of the last event
+    /// of a count window.
+    pub end: DateTime<Utc>,
+    /// Number of events folded into `value`.
+    pub count: u64,
+    /// The aggregated value.
+    pub value: A,
+}
+
+/// Adds windowing to every stream of events.
+pub trait WindowExt: Stream<Item = Result<Event>> + Sized {
+    /// Tumbling windows of `size` by event timestamp.
+    fn tumbling(self, size: Duration) -> Windows<Self> {
+        let size = TimeDelta::from_std(size)
+            .unwrap_or(TimeDelta::MAX)
+            .max(TimeDelta::milliseconds(1));
+        Windows::new(self, Kind::Tumbling(size)).idle_timeout(size.to_std().unwrap_or_default())
+    }
+
+    /// Windows of `count` events each.
+    fn count_windows(self, count: usize) -> Windows<Self> {
+        Windows::new(self, Kind::Count(count.max(1) as u64))
+    }
+}
+
+impl<S: Stream<Item = Result<Event>>> WindowExt for S {}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Tumbling(TimeDelta),
+    Count(u64),
+}
+
+/// Windows over a stream of events, ready to [`aggregate`](Self::aggregate).
+pub struct Windows<S> {
+    source: S,
+    kind: Kind,
+    lateness: TimeDelta,
+    idle_timeout: Option<Duration>,
+    key: Option<Arc<KeyFn>>,
+}
+
+impl<S> Windows<S> {
+    fn new(source: S, kind: Kind) -> Self {
+        Self {
+            source,
+            kind,
+            lateness: TimeDelta::zero(),
+            idle_timeout: None,
+            key: None,
+        }
+    }
+
+    /// Keep time windows open for events up to `lateness` behind the
+    /// latest timestamp seen (default: none).
+    pub fn allowed_lateness(mut self, lateness: Duration) -> Self {
+        self.lateness = TimeDelta::from_std(lateness).unwrap_or(TimeDelta::MAX);
+        self
+    }
+
+    /// Close every open window once no event arrived for `timeout`
+    /// (default: one window length for time windows, never for count
+    /// windows, whose partial windows are then yielded too).
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Window the events of each key separately, e.g. per topic or per
+    /// tenant.
+    pub fn group_by<F>(mut self, key: F) -> Self
+    where
+        F: Fn(&Event) -> String + Send + Sync + 'static,
+    {
+        self.key = Some(Arc::new(key));
+        self
+    }
+
+    /// Fold the events of each window into a value starting at `initial`.
+    ///
+    /// With manual acks, events are acked once the consumer asks for the
+    /// window after theirs, so a consumer that stops before handling a
+    /// window gets its events redelivered.
+    pub fn aggregate<A, F>(self, initial: A, fold: F) -> WindowStream<A>
+    where
+        S: Stream<Item = Result<Event>> + Unpin + Send + 'static,
+        A: Clone + Send + 'static,
+        F: FnMut(&mut A, &Event) + Send + 'static,
+    {
+        let state = State {
+            source: self.source,
+            kind: self.kind,
+            lateness: self.lateness,
+            idle_timeout: self.idle_timeout,
+            key: self.key,
+            initial,
+            fold,
+            open: HashMap::new(),
+            watermark: None,
+            latest: None,
+            ready: VecDeque::new(),
+            settle: Vec::new(),
+            done: false,
+        };
+        WindowStream {
+            inner: stream::unfold(state, State::next).boxed(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Windows<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Windows")
+            .field("kind", &self.kind)
+            .field("lateness", &self.lateness)
+            .field("idle_timeout", &self.idle_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A stream of aggregated windows, from [`Windows::aggregate`].
+///
+/// Implements `futures::Stream<Item = Result<Window<A>>>`; errors of the
+/// underlying stream are passed through.
+pub struct WindowStream<A> {
+    inner: BoxStream<'static, Result<Window<A>>>,
+}
+
+impl<A> Stream for WindowStream<A> {
+    type Item = Result<Window<A>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl<A> fmt::Debug for WindowStream<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowStream").finish_non_exhaustive()
+    }
+}
+
+/// A window still collecting events.
+struct Open<A> {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    count: u64,
+    value: A,
+    /// Folded events, without their payload, to settle once yielded.
+    events: Vec<Event>,
+}
+
+struct State<S, A, F> {
+    source: S,
+    kind: Kind,
+    lateness: TimeDelta,
+    idle_timeout: Option<Duration>,
+    key: Option<Arc<KeyFn>>,
+    initial: A,
+    fold: F,
+    /// Open windows by key and, for time windows, start.
+    open: HashMap<(String, DateTime<Utc>), Open<A>>,
+    /// Time windows ending at or before this are closed.
+    watermark: Option<DateTime<Utc>>,
+    latest: Option<DateTime<Utc>>,
+    ready: VecDeque<(Window<A>, Vec<Event>)>,
+    /// Events of the window yielded last.
+    settle: Vec<Event>,
+    done: bool,
+}
+
+impl<S, A, F> State<S, A, F>
+where
+    S: Stream<Item = Result<Event>> + Unpin,
+    A: Clone,
+    F: FnMut(&mut A, &Event),
+{
+    async fn next(mut self) -> Option<(Result<Window<A>>, Self)> {
+        for event in self.settle.drain(..) {
+            let _ = event.ack().await;
+        }
+        loop {
+            if let Some((window, events)) = self.ready.pop_front() {
+                self.settle = events;
+                return Some((Ok(window), self));
+            }
+            if self.done {
+                return None;
+            }
+            let next = match self.idle_timeout.filter(|_| !self.open.is_empty()) {
+                Some(timeout) => match tokio::time::timeout(timeout, self.source.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        self.close(|_| true);
+                        continue;
+                    }
+                },
+                None => self.source.next().await,
+            };
+            match next {
+                Some(Ok(event)) => self.add(event).await,
+                Some(Err(e)) => return Some((Err(e), self)),
+                None => {
+                    self.close(|_| true);
+                    self.done = true;
+                }
+            }
+        }
+    }
+
+    /// Fold `event` into its window, closing the windows it completes.
+    async fn add(&mut self, mut event: Event) {
+        let key = self.key.as_ref().map(|key| key(&event)).unwrap_or_default();
+        let ts = event.timestamp;
+        let (start, end) = match self.kind {
+            Kind::Tumbling(size) => {
+                let size_ms = size.num_milliseconds();
+                let start_ms = ts.timestamp_millis().div_euclid(size_ms) * size_ms;
+                let start = DateTime::from_timestamp_millis(start_ms).unwrap_or(ts);
+                (start, start + size)
+            }
+            Kind::Count(_) => (DateTime::<Utc>::MIN_UTC, ts),
+        };
+        if matches!(self.kind, Kind::Tumbling(_)) && self.watermark.is_some_and(|w| end <= w) {
+            // Late for a window that already closed
+            let _ = event.ack().await;
+            return;
+        }
+
+        let window = self.open.entry((key, start)).or_insert_with(|| Open {
+            start: if start == DateTime::<Utc>::MIN_UTC {
+                ts
+            } else {
+                start
+            },
+            end,
+            count: 0,
+            value: self.initial.clone(),
+            events: Vec::new(),
+        });
+        (self.fold)(&mut window.value, &event);
+        window.count += 1;
+        if matches!(self.kind, Kind::Count(_)) {
+            window.end = ts;
+        }
+        event.data = serde_json::Value::Null;
+        window.events.push(event);
+
+        match self.kind {
+            Kind::Tumbling(_) => {
+                let latest = self.latest.map_or(ts, |latest| latest.max(ts));
+                self.latest = Some(latest);
+                let watermark = latest - self.lateness;
+                self.close(|window| window.end <= watermark);
+            }
+            Kind::Count(count) => self.close(|window| window.count >= count),
+        }
+    }
+
+    /// Close the open windows matching `done`, oldest first.
+    fn close(&mut self, done: impl Fn(&Open<A>) -> bool) {
+        let keys: Vec<_> = self
+            .open
+            .iter()
+            .filter(|(_, window)| done(window))
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut closed: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| self.open.remove_entry(&key))
+            .collect();
+        closed.sort_by(|((a_key, _), a), ((b_key, _), b)| (a.end, a_key).cmp(&(b.end, b_key)));
+        for ((key, _), window) in closed {
+            if matches!(self.kind, Kind::Tumbling(_)) {
+                self.watermark = Some(self.watermark.map_or(window.end, |w| w.max(window.end)));
+            }
+            let record = Window {
+                key,
+                start: window.start,
+                end: window.end,
+                count: window.count,
+                value: window.value,
+            };
+            self.ready.push_back((record, window.events));
+        }
+    }
+}